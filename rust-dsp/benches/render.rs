@@ -0,0 +1,45 @@
+// Render-time benchmarks of the whole engine, one 128-frame AudioWorklet
+// quantum per iteration:
+//
+//   cargo bench --no-default-features --bench render
+//
+// Each case holds its notes for the whole run so the voices stay in
+// sustain rather than releasing partway through.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_dsp::AudioEngine;
+use std::hint::black_box;
+
+const QUANTUM: usize = 128;
+
+fn bench_quantum(c: &mut Criterion, name: &str, setup: impl Fn(&mut AudioEngine)) {
+    let mut engine = AudioEngine::new();
+    setup(&mut engine);
+    let mut left = [0.0; QUANTUM];
+    let mut right = [0.0; QUANTUM];
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            engine.process_stereo(&mut left, &mut right);
+            black_box((&left, &right));
+        })
+    });
+}
+
+fn render(c: &mut Criterion) {
+    bench_quantum(c, "silence", |_| {});
+    bench_quantum(c, "triad", |engine| engine.play_chord(60, 0, 0, 0.8, None));
+    bench_quantum(c, "16 voices, 7 unison", |engine| {
+        engine.set_unison(1, 7, 20.0, 0.5);
+        for note in 48..64 {
+            engine.note_on(note, 0.8);
+        }
+    });
+    bench_quantum(c, "triad, reverb, 4x oversampling", |engine| {
+        engine.set_oversampling(4);
+        engine.set_reverb(1, true, 0.7, 0.5, 0.3);
+        engine.play_chord(60, 0, 0, 0.8, None);
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,188 @@
+use crate::sequencer::{NoteSource, SequencerEvent};
+
+// Arpeggiator for the live engine: held keys are collected here instead of
+// sounding directly, and played back one at a time on a tempo-synced grid.
+// The first key starts the pattern immediately; releasing every key lets
+// the last note finish its gate and stops.
+
+pub const ARP_UP: u8 = 0;
+pub const ARP_DOWN: u8 = 1;
+pub const ARP_UP_DOWN: u8 = 2;
+pub const ARP_RANDOM: u8 = 3;
+pub const ARP_AS_PLAYED: u8 = 4;
+
+const MAX_HELD: usize = 16;
+const MAX_OCTAVES: usize = 4;
+
+pub struct Arpeggiator {
+    mode: u8,
+    octaves: usize,
+    division: u32,
+    gate: f32,
+    // Held keys in the order they were pressed
+    held: Vec<(u8, f32)>,
+    // Scratch space for the note order of the current step
+    sequence: Vec<(u8, f32)>,
+    position: usize,
+    step_samples: f64,
+    until_step: f64,
+    playing: Option<(u8, f64)>,
+    random_state: u32,
+}
+
+impl Arpeggiator {
+    pub fn new(samples_per_beat: f64) -> Self {
+        Arpeggiator {
+            mode: ARP_UP,
+            octaves: 1,
+            division: 16,
+            gate: 0.5,
+            held: Vec::with_capacity(MAX_HELD),
+            sequence: Vec::with_capacity(MAX_HELD * MAX_OCTAVES),
+            position: 0,
+            step_samples: samples_per_beat / 4.0,
+            until_step: 0.0,
+            playing: None,
+            random_state: 0x9E37_79B9,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = mode.min(ARP_AS_PLAYED);
+    }
+
+    pub fn set_octaves(&mut self, octaves: u32) {
+        self.octaves = (octaves as usize).clamp(1, MAX_OCTAVES);
+    }
+
+    // Notes per whole note: 4 quarters, 8 eighths, 12 eighth triplets,
+    // 16 sixteenths...
+    pub fn set_division(&mut self, division: u32, samples_per_beat: f64) {
+        self.division = division.clamp(1, 64);
+        self.set_samples_per_beat(samples_per_beat);
+    }
+
+    // Fraction of a step each note sounds for, 0.05-1
+    pub fn set_gate(&mut self, gate: f32) {
+        self.gate = gate.clamp(0.05, 1.0);
+    }
+
+    pub fn set_samples_per_beat(&mut self, samples_per_beat: f64) {
+        let step_samples = samples_per_beat * 4.0 / self.division as f64;
+        if step_samples != self.step_samples {
+            let scale = step_samples / self.step_samples;
+            self.until_step *= scale;
+            if let Some((_, samples)) = &mut self.playing {
+                *samples *= scale;
+            }
+            self.step_samples = step_samples;
+        }
+    }
+
+    pub fn note_on(&mut self, note: u8, velocity: f32) {
+        if self.held.iter().any(|&(n, _)| n == note) || self.held.len() == MAX_HELD {
+            return;
+        }
+        if self.held.is_empty() {
+            self.position = 0;
+            self.until_step = 0.0;
+        }
+        self.held.push((note, velocity));
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        self.held.retain(|&(n, _)| n != note);
+    }
+
+    pub fn held_count(&self) -> usize {
+        self.held.len()
+    }
+
+    // Forgets the held keys and cuts the sounding note
+    pub fn reset(&mut self, events: &mut Vec<SequencerEvent>) {
+        self.held.clear();
+        if let Some((note, _)) = self.playing.take() {
+            events.push(SequencerEvent::NoteOff { note });
+        }
+    }
+
+    fn next_note(&mut self) -> (u8, f32) {
+        self.sequence.clear();
+        for octave in 0..self.octaves {
+            let shift = (octave * 12) as u8;
+            self.sequence.extend(
+                self.held
+                    .iter()
+                    .filter(|&&(n, _)| n as usize + octave * 12 <= 127)
+                    .map(|&(n, v)| (n + shift, v)),
+            );
+        }
+        // As-played keeps the press order within each octave
+        if self.mode != ARP_AS_PLAYED {
+            self.sequence.sort_by_key(|&(n, _)| n);
+        }
+
+        let len = self.sequence.len();
+        let index = match self.mode {
+            ARP_DOWN => len - 1 - self.position % len,
+            ARP_UP_DOWN if len > 1 => {
+                // Up then back down without repeating the top and bottom notes
+                let cycle = self.position % (len * 2 - 2);
+                if cycle < len { cycle } else { len * 2 - 2 - cycle }
+            }
+            ARP_RANDOM => {
+                self.random_state ^= self.random_state << 13;
+                self.random_state ^= self.random_state >> 17;
+                self.random_state ^= self.random_state << 5;
+                self.random_state as usize % len
+            }
+            _ => self.position % len,
+        };
+        self.position = self.position.wrapping_add(1);
+        self.sequence[index]
+    }
+}
+
+impl NoteSource for Arpeggiator {
+    fn samples_until_next_event(&self) -> Option<usize> {
+        let mut next = if self.held.is_empty() { f64::MAX } else { self.until_step };
+        if let Some((_, samples)) = self.playing {
+            next = next.min(samples);
+        }
+        if next == f64::MAX {
+            return None;
+        }
+        Some(next.max(0.0).ceil() as usize)
+    }
+
+    fn advance(&mut self, samples: usize) {
+        let samples = samples as f64;
+        if !self.held.is_empty() {
+            self.until_step -= samples;
+        }
+        if let Some((_, remaining)) = &mut self.playing {
+            *remaining -= samples;
+        }
+    }
+
+    fn take_due_events(&mut self, events: &mut Vec<SequencerEvent>) {
+        if let Some((note, remaining)) = self.playing {
+            if remaining <= 0.0 {
+                events.push(SequencerEvent::NoteOff { note });
+                self.playing = None;
+            }
+        }
+
+        if !self.held.is_empty() && self.until_step <= 0.0 {
+            // A full-length gate ends on this same sample; cut it first
+            if let Some((note, _)) = self.playing.take() {
+                events.push(SequencerEvent::NoteOff { note });
+            }
+            let (note, velocity) = self.next_note();
+            events.push(SequencerEvent::NoteOn { note, velocity });
+            let gate_samples = self.gate as f64 * self.step_samples + self.until_step;
+            self.playing = Some((note, gate_samples));
+            self.until_step += self.step_samples;
+        }
+    }
+}
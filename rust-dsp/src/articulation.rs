@@ -0,0 +1,153 @@
+// Chord articulations: turn one chord event into a timed note gesture so a
+// chord grid can sound musical without anyone playing the notes in.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Articulation {
+    Staccato = 0,
+    LegatoPad = 1,
+    Pulse8ths = 2,
+    Arpeggiated = 3,
+}
+
+impl Articulation {
+    pub fn from_u8(articulation: u8) -> Self {
+        match articulation {
+            0 => Articulation::Staccato,
+            1 => Articulation::LegatoPad,
+            2 => Articulation::Pulse8ths,
+            3 => Articulation::Arpeggiated,
+            _ => Articulation::LegatoPad,
+        }
+    }
+}
+
+// Note on (velocity > 0) or off (velocity 0) emitted by a gesture
+#[derive(Clone, Copy)]
+pub struct NoteEvent {
+    pub note: u8,
+    pub velocity: f32,
+}
+
+// Gate lengths and step sizes in beats
+const STACCATO_LENGTH: f64 = 0.25;
+const PULSE_STEP: f64 = 0.5;
+const PULSE_GATE: f64 = 0.3;
+const ARPEGGIO_STEP: f64 = 0.25;
+const ARPEGGIO_GATE: f64 = 0.2;
+
+// A chord being played with an articulation. The gesture keeps its own
+// clock in samples; the engine renders up to `samples_until_next` and then
+// asks it to `fire` the events that are due.
+#[derive(Clone)]
+pub struct ChordGesture {
+    notes: Vec<u8>,
+    velocity: f32,
+    articulation: Articulation,
+    position: u64,
+    next_event: Option<u64>,
+    step: usize,
+    sounding: Vec<u8>,
+}
+
+impl ChordGesture {
+    pub fn new(notes: &[u8], velocity: f32, articulation: Articulation) -> Self {
+        let mut notes = notes.to_vec();
+        notes.sort_unstable();
+        notes.dedup();
+        // An empty chord has nothing to schedule
+        let next_event = if notes.is_empty() { None } else { Some(0) };
+        ChordGesture {
+            notes,
+            velocity,
+            articulation,
+            position: 0,
+            next_event,
+            step: 0,
+            sounding: Vec::new(),
+        }
+    }
+
+    pub fn samples_until_next(&self) -> Option<u64> {
+        self.next_event.map(|at| at.saturating_sub(self.position))
+    }
+
+    pub fn advance(&mut self, samples: usize) {
+        self.position += samples as u64;
+    }
+
+    // Emits the events due at the current position and schedules the next step
+    pub fn fire(&mut self, samples_per_beat: f64) -> Vec<NoteEvent> {
+        let mut events = Vec::new();
+        if self.samples_until_next() != Some(0) {
+            return events;
+        }
+
+        let beats = |b: f64| (b * samples_per_beat) as u64;
+        let step = self.step;
+        self.step += 1;
+
+        match self.articulation {
+            Articulation::Staccato => {
+                if step == 0 {
+                    self.start_notes(&mut events, None);
+                    self.next_event = Some(self.position + beats(STACCATO_LENGTH));
+                } else {
+                    self.stop_notes(&mut events);
+                    self.next_event = None;
+                }
+            }
+            Articulation::LegatoPad => {
+                self.start_notes(&mut events, None);
+                self.next_event = None;
+            }
+            Articulation::Pulse8ths => {
+                // Even steps start a pulse, odd steps end it
+                if step.is_multiple_of(2) {
+                    self.start_notes(&mut events, None);
+                    self.next_event = Some(self.position + beats(PULSE_GATE));
+                } else {
+                    self.stop_notes(&mut events);
+                    self.next_event = Some(self.position + beats(PULSE_STEP - PULSE_GATE));
+                }
+            }
+            Articulation::Arpeggiated => {
+                if step.is_multiple_of(2) {
+                    let note = self.notes[(step / 2) % self.notes.len()];
+                    self.start_notes(&mut events, Some(note));
+                    self.next_event = Some(self.position + beats(ARPEGGIO_GATE));
+                } else {
+                    self.stop_notes(&mut events);
+                    self.next_event = Some(self.position + beats(ARPEGGIO_STEP - ARPEGGIO_GATE));
+                }
+            }
+        }
+        events
+    }
+
+    // Ends the gesture: everything still sounding is released
+    pub fn release(&mut self) -> Vec<NoteEvent> {
+        let mut events = Vec::new();
+        self.stop_notes(&mut events);
+        self.next_event = None;
+        events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_event.is_none() && self.sounding.is_empty()
+    }
+
+    fn start_notes(&mut self, events: &mut Vec<NoteEvent>, only: Option<u8>) {
+        for &note in &self.notes {
+            if only.is_none_or(|n| n == note) {
+                events.push(NoteEvent { note, velocity: self.velocity });
+                self.sounding.push(note);
+            }
+        }
+    }
+
+    fn stop_notes(&mut self, events: &mut Vec<NoteEvent>) {
+        for note in self.sounding.drain(..) {
+            events.push(NoteEvent { note, velocity: 0.0 });
+        }
+    }
+}
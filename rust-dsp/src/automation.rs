@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cc_map::{scale_to_range, CURVE_TOGGLE};
+
+// Automation lanes: breakpoints for a parameter over a pattern's beats,
+// played back against the timeline engine while the pattern runs. Each
+// point's curve shapes the move to the next one with the CC mapping curves:
+// linear, exponential (even in pitch, for cutoffs and times) or a step that
+// holds the value until the next point. Before the first point a lane holds
+// the first value and after the last the last.
+
+// Lanes per pattern and points per lane; more are refused
+const MAX_LANES: usize = 32;
+const MAX_POINTS: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutomationPoint {
+    pub beat: f64,
+    pub value: f32,
+    pub curve: u8,
+}
+
+struct Lane {
+    param: u32,
+    // Sorted by beat
+    points: Vec<AutomationPoint>,
+    // Value last handed out, so flat stretches don't set it again
+    last: Option<f32>,
+}
+
+impl Lane {
+    fn value_at(&self, beat: f64) -> f32 {
+        let next = self.points.partition_point(|p| p.beat <= beat);
+        match (next.checked_sub(1).map(|i| &self.points[i]), self.points.get(next)) {
+            (Some(from), Some(to)) => {
+                let t = ((beat - from.beat) / (to.beat - from.beat)) as f32;
+                scale_to_range(t, from.value, to.value, from.curve)
+            }
+            (Some(only), None) | (None, Some(only)) => only.value,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+pub struct Automation {
+    patterns: Vec<Vec<Lane>>,
+    // Pattern last played from; a different one sets every value afresh
+    playing: Option<usize>,
+}
+
+impl Automation {
+    pub fn new(patterns: usize) -> Self {
+        Automation { patterns: (0..patterns).map(|_| Vec::new()).collect(), playing: None }
+    }
+
+    // Adds a point, replacing one already at the same beat. False when the
+    // pattern doesn't exist or the pattern or lane is full.
+    pub fn add_point(&mut self, pattern: usize, param: u32, point: AutomationPoint) -> bool {
+        let Some(lanes) = self.patterns.get_mut(pattern) else {
+            return false;
+        };
+        let point = AutomationPoint { beat: point.beat.max(0.0), curve: point.curve.min(CURVE_TOGGLE), ..point };
+        let lane = match lanes.iter().position(|lane| lane.param == param) {
+            Some(i) => &mut lanes[i],
+            None if lanes.len() < MAX_LANES => {
+                lanes.push(Lane { param, points: Vec::new(), last: None });
+                lanes.last_mut().unwrap()
+            }
+            None => return false,
+        };
+        let index = lane.points.partition_point(|p| p.beat < point.beat);
+        if lane.points.get(index).is_some_and(|p| p.beat == point.beat) {
+            lane.points[index] = point;
+        } else if lane.points.len() < MAX_POINTS {
+            lane.points.insert(index, point);
+        } else {
+            return false;
+        }
+        lane.last = None;
+        true
+    }
+
+    // Removes the point at `beat`; a lane left empty goes with it
+    pub fn remove_point(&mut self, pattern: usize, param: u32, beat: f64) {
+        let Some(lanes) = self.patterns.get_mut(pattern) else {
+            return;
+        };
+        for lane in lanes.iter_mut().filter(|lane| lane.param == param) {
+            lane.points.retain(|p| p.beat != beat);
+            lane.last = None;
+        }
+        lanes.retain(|lane| !lane.points.is_empty());
+    }
+
+    pub fn clear_pattern(&mut self, pattern: usize) {
+        if let Some(lanes) = self.patterns.get_mut(pattern) {
+            lanes.clear();
+        }
+    }
+
+    pub fn clear_lane(&mut self, pattern: usize, param: u32) {
+        if let Some(lanes) = self.patterns.get_mut(pattern) {
+            lanes.retain(|lane| lane.param != param);
+        }
+    }
+
+    pub fn points(&self, pattern: usize, param: u32) -> &[AutomationPoint] {
+        self.patterns
+            .get(pattern)
+            .and_then(|lanes| lanes.iter().find(|lane| lane.param == param))
+            .map_or(&[], |lane| &lane.points)
+    }
+
+    // Parameters automated in `pattern`
+    pub fn params(&self, pattern: usize) -> impl Iterator<Item = u32> + '_ {
+        self.patterns.get(pattern).into_iter().flatten().map(|lane| lane.param)
+    }
+
+    pub fn lane_count(&self, pattern: usize) -> usize {
+        self.patterns.get(pattern).map_or(0, Vec::len)
+    }
+
+    // (param, value) of lane `index` at `beat`, or None while the value
+    // hasn't moved since last asked
+    pub fn value(&mut self, pattern: usize, index: usize, beat: f64) -> Option<(u32, f32)> {
+        if self.playing != Some(pattern) {
+            self.resync();
+            self.playing = Some(pattern);
+        }
+        let lane = self.patterns.get_mut(pattern)?.get_mut(index)?;
+        let value = lane.value_at(beat);
+        if lane.last == Some(value) {
+            return None;
+        }
+        lane.last = Some(value);
+        Some((lane.param, value))
+    }
+
+    // Every lane sets its value again on the next ask, e.g. when playback
+    // starts
+    pub fn resync(&mut self) {
+        for lane in self.patterns.iter_mut().flatten() {
+            lane.last = None;
+        }
+    }
+}
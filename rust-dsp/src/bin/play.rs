@@ -0,0 +1,86 @@
+// Plays the engine through the default audio device, outside the browser,
+// for trying out DSP changes and profiling them with native tools:
+//
+//   cargo run --release --no-default-features --features native --bin play -- [factory patch] [seconds]
+//
+// Loops a I-V-vi-IV progression in C on the live engine with the given
+// factory patch (default 0) for the given time (default 16 s), then prints
+// the engine's own DSP load.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rust_dsp::AudioEngine;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The engine renders at a fixed rate
+const SAMPLE_RATE: u32 = 48000;
+const CHORD_SECONDS: f32 = 2.0;
+// (root, chord type): C major, G major, A minor, F major
+const PROGRESSION: [(u8, u8); 4] = [(60, 0), (55, 0), (57, 1), (53, 0)];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let patch: u8 = args.next().map(|a| a.parse()).transpose()?.unwrap_or(0);
+    let seconds: f32 = args.next().map(|a| a.parse()).transpose()?.unwrap_or(16.0);
+
+    let host = cpal::default_host();
+    let device = host.default_output_device().ok_or("no output device")?;
+    let supported = device.supported_output_configs()?.any(|range| {
+        range.channels() == 2
+            && range.min_sample_rate().0 <= SAMPLE_RATE
+            && range.max_sample_rate().0 >= SAMPLE_RATE
+            && range.sample_format() == cpal::SampleFormat::F32
+    });
+    if !supported {
+        return Err(format!("{} has no {} Hz stereo float output", device.name()?, SAMPLE_RATE).into());
+    }
+    let config = cpal::StreamConfig {
+        channels: 2,
+        sample_rate: cpal::SampleRate(SAMPLE_RATE),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let mut engine = AudioEngine::new();
+    if !engine.load_factory_patch(1, patch) {
+        return Err(format!("no factory patch {}", patch).into());
+    }
+    // The main thread only holds the lock for a note change at a time
+    let engine = Arc::new(Mutex::new(engine));
+    let audio = Arc::clone(&engine);
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    let stream = device.build_output_stream(
+        &config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            let frames = data.len() / 2;
+            left.resize(frames, 0.0);
+            right.resize(frames, 0.0);
+            audio.lock().unwrap().process_stereo(&mut left, &mut right);
+            for (i, frame) in data.chunks_exact_mut(2).enumerate() {
+                frame[0] = left[i];
+                frame[1] = right[i];
+            }
+        },
+        |err| eprintln!("stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    println!("playing factory patch {} on {} for {} s", patch, device.name()?, seconds);
+
+    let start = Instant::now();
+    for &(root, chord_type) in PROGRESSION.iter().cycle() {
+        let elapsed = start.elapsed().as_secs_f32();
+        if elapsed >= seconds {
+            break;
+        }
+        engine.lock().unwrap().play_chord(root, chord_type, 0, 0.8, None);
+        thread::sleep(Duration::from_secs_f32(CHORD_SECONDS.min(seconds - elapsed)));
+        engine.lock().unwrap().release_chord();
+    }
+    // Let the release tails ring out
+    thread::sleep(Duration::from_secs(1));
+    println!("dsp load {:.1}%", engine.lock().unwrap().get_dsp_load() * 100.0);
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::f32::consts::PI;
+
+// Second-order IIR section with RBJ "Audio EQ Cookbook" coefficient designs,
+// processed in transposed direct form II.
+#[derive(Clone)]
+pub struct Biquad {
+    sample_rate: f32,
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    // Starts as a pass-through until one of the design methods is called
+    pub fn new(sample_rate: f32) -> Self {
+        Biquad {
+            sample_rate,
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    pub fn set_low_shelf(&mut self, freq: f32, gain_db: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, alpha) = self.omega(freq, std::f32::consts::FRAC_1_SQRT_2);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        self.set_coefficients(
+            a * ((a + 1.0) - (a - 1.0) * cos_w + sqrt_a_alpha),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w),
+            a * ((a + 1.0) - (a - 1.0) * cos_w - sqrt_a_alpha),
+            (a + 1.0) + (a - 1.0) * cos_w + sqrt_a_alpha,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_w),
+            (a + 1.0) + (a - 1.0) * cos_w - sqrt_a_alpha,
+        );
+    }
+
+    pub fn set_high_shelf(&mut self, freq: f32, gain_db: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, alpha) = self.omega(freq, std::f32::consts::FRAC_1_SQRT_2);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        self.set_coefficients(
+            a * ((a + 1.0) + (a - 1.0) * cos_w + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w),
+            a * ((a + 1.0) + (a - 1.0) * cos_w - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos_w + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_w),
+            (a + 1.0) - (a - 1.0) * cos_w - sqrt_a_alpha,
+        );
+    }
+
+    pub fn set_peak(&mut self, freq: f32, gain_db: f32, q: f32) {
+        let a = 10.0_f32.powf(gain_db / 40.0);
+        let (cos_w, alpha) = self.omega(freq, q);
+
+        self.set_coefficients(
+            1.0 + alpha * a,
+            -2.0 * cos_w,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_w,
+            1.0 - alpha / a,
+        );
+    }
+
+    pub fn set_lowpass(&mut self, freq: f32, q: f32) {
+        let (cos_w, alpha) = self.omega(freq, q);
+
+        self.set_coefficients(
+            (1.0 - cos_w) * 0.5,
+            1.0 - cos_w,
+            (1.0 - cos_w) * 0.5,
+            1.0 + alpha,
+            -2.0 * cos_w,
+            1.0 - alpha,
+        );
+    }
+
+    pub fn set_highpass(&mut self, freq: f32, q: f32) {
+        let (cos_w, alpha) = self.omega(freq, q);
+
+        self.set_coefficients(
+            (1.0 + cos_w) * 0.5,
+            -(1.0 + cos_w),
+            (1.0 + cos_w) * 0.5,
+            1.0 + alpha,
+            -2.0 * cos_w,
+            1.0 - alpha,
+        );
+    }
+
+    // Constant 0 dB peak gain at `freq`
+    pub fn set_bandpass(&mut self, freq: f32, q: f32) {
+        let (cos_w, alpha) = self.omega(freq, q);
+
+        self.set_coefficients(alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos_w, 1.0 - alpha);
+    }
+
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    fn omega(&self, freq: f32, q: f32) -> (f32, f32) {
+        let freq = freq.clamp(10.0, self.sample_rate * 0.49);
+        let w = 2.0 * PI * freq / self.sample_rate;
+        (w.cos(), w.sin() / (2.0 * q.max(0.1)))
+    }
+
+    fn set_coefficients(&mut self, b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) {
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+}
+
+// Lowest low cut and highest high cut; a filter set to either end of the
+// range is bypassed so leaving it open changes nothing
+pub const TONE_MIN_HZ: f32 = 20.0;
+pub const TONE_MAX_HZ: f32 = 20000.0;
+
+// Low-cut and high-cut pair for shaping effect tails, 12 dB/octave each
+#[derive(Clone)]
+pub struct ToneFilter {
+    low_cut: Biquad,
+    high_cut: Biquad,
+    low_cut_on: bool,
+    high_cut_on: bool,
+}
+
+impl ToneFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        ToneFilter {
+            low_cut: Biquad::new(sample_rate),
+            high_cut: Biquad::new(sample_rate),
+            low_cut_on: false,
+            high_cut_on: false,
+        }
+    }
+
+    pub fn set(&mut self, low_cut_hz: f32, high_cut_hz: f32) {
+        self.low_cut_on = low_cut_hz > TONE_MIN_HZ;
+        self.high_cut_on = high_cut_hz < TONE_MAX_HZ;
+        self.low_cut.set_highpass(low_cut_hz, std::f32::consts::FRAC_1_SQRT_2);
+        self.high_cut.set_lowpass(high_cut_hz, std::f32::consts::FRAC_1_SQRT_2);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mut output = input;
+        if self.low_cut_on {
+            output = self.low_cut.process(output);
+        }
+        if self.high_cut_on {
+            output = self.high_cut.process(output);
+        }
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.low_cut.reset();
+        self.high_cut.reset();
+    }
+}
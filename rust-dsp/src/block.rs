@@ -0,0 +1,65 @@
+// Whole-buffer arithmetic for the engine's voice mix and gain ramps. With
+// the `simd` feature the loops run four samples at a time; lane by lane
+// they are the scalar expressions, so both builds render the same samples.
+
+#[cfg(feature = "simd")]
+use crate::simd::F32x4;
+
+// output[i] += input[i]
+#[cfg(feature = "simd")]
+pub fn add_into(output: &mut [f32], input: &[f32]) {
+    let len = output.len().min(input.len());
+    let split = len - len % 4;
+    for i in (0..split).step_by(4) {
+        (F32x4::load(&output[i..]) + F32x4::load(&input[i..])).store(&mut output[i..]);
+    }
+    for (sample, value) in output[split..len].iter_mut().zip(&input[split..len]) {
+        *sample += value;
+    }
+}
+
+// Scales sample i by start + step * (i + 1), so a block ramped from the
+// last gain ends exactly on the new one
+#[cfg(not(feature = "simd"))]
+pub fn apply_gain_ramp(samples: &mut [f32], start: f32, step: f32) {
+    for (i, sample) in samples.iter_mut().enumerate() {
+        *sample *= start + step * (i + 1) as f32;
+    }
+}
+
+#[cfg(feature = "simd")]
+pub fn apply_gain_ramp(samples: &mut [f32], start: f32, step: f32) {
+    let split = samples.len() - samples.len() % 4;
+    let (start_lanes, step_lanes) = (F32x4::splat(start), F32x4::splat(step));
+    for i in (0..split).step_by(4) {
+        let gain = start_lanes + step_lanes * ramp_positions(i);
+        (F32x4::load(&samples[i..]) * gain).store(&mut samples[i..]);
+    }
+    for (i, sample) in samples.iter_mut().enumerate().skip(split) {
+        *sample *= start + step * (i + 1) as f32;
+    }
+}
+
+// The same ramp over both channels of a stereo block
+#[cfg(not(feature = "simd"))]
+pub fn apply_gain_ramp_stereo(left: &mut [f32], right: &mut [f32], start: f32, step: f32) {
+    for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+        let gain = start + step * (i + 1) as f32;
+        *l *= gain;
+        *r *= gain;
+    }
+}
+
+#[cfg(feature = "simd")]
+pub fn apply_gain_ramp_stereo(left: &mut [f32], right: &mut [f32], start: f32, step: f32) {
+    let len = left.len().min(right.len());
+    apply_gain_ramp(&mut left[..len], start, step);
+    apply_gain_ramp(&mut right[..len], start, step);
+}
+
+// Ramp positions i + 1 to i + 4, as the scalar loop converts them
+#[cfg(feature = "simd")]
+#[inline]
+fn ramp_positions(i: usize) -> F32x4 {
+    F32x4::new((i + 1) as f32, (i + 2) as f32, (i + 3) as f32, (i + 4) as f32)
+}
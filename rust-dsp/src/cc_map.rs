@@ -0,0 +1,101 @@
+// MIDI CC mappings: each routes one controller number to a parameter id on
+// an engine, scaled into a range along a curve. Several mappings may share
+// a controller, so one knob can sweep a few parameters at once.
+
+// Curve 0 (and anything unknown) is linear.
+// Equal ratios per step, for frequencies and times; falls back to linear
+// when the range isn't strictly positive
+pub const CURVE_EXPONENTIAL: u8 = 1;
+// Minimum below CC 64 (half way), maximum from 64 up, for switches and
+// pedals
+pub const CURVE_TOGGLE: u8 = 2;
+// Mappings kept at once; more are ignored
+const MAX_MAPPINGS: usize = 128;
+
+#[derive(Clone, Copy)]
+pub struct CcMapping {
+    pub controller: u8,
+    pub engine: u32,
+    pub param: u32,
+    min: f32,
+    max: f32,
+    curve: u8,
+}
+
+impl CcMapping {
+    pub fn new(controller: u8, engine: u32, param: u32, min: f32, max: f32, curve: u8) -> Self {
+        CcMapping { controller: controller.min(127), engine, param, min, max, curve: curve.min(CURVE_TOGGLE) }
+    }
+
+    // Parameter value for a 7-bit controller value
+    pub fn scale(&self, value: u8) -> f32 {
+        scale_to_range(value.min(127) as f32 / 127.0, self.min, self.max, self.curve)
+    }
+}
+
+// Maps 0-1 into min..max along a curve; shared with the macro knobs
+pub fn scale_to_range(x: f32, min: f32, max: f32, curve: u8) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    match curve {
+        CURVE_EXPONENTIAL if min > 0.0 && max > 0.0 => min * (max / min).powf(x),
+        CURVE_TOGGLE => {
+            if x >= 0.5 { max } else { min }
+        }
+        _ => min + (max - min) * x,
+    }
+}
+
+pub struct CcMap {
+    mappings: Vec<CcMapping>,
+    // Mapping waiting for the next controller to arrive (MIDI learn)
+    learning: Option<CcMapping>,
+}
+
+impl CcMap {
+    pub fn new() -> Self {
+        CcMap { mappings: Vec::new(), learning: None }
+    }
+
+    // Replaces any mapping of the same controller to the same parameter
+    pub fn map(&mut self, mapping: CcMapping) {
+        self.mappings.retain(|m| {
+            (m.controller, m.engine, m.param) != (mapping.controller, mapping.engine, mapping.param)
+        });
+        if self.mappings.len() < MAX_MAPPINGS {
+            self.mappings.push(mapping);
+        }
+    }
+
+    pub fn unmap(&mut self, controller: u8) {
+        self.mappings.retain(|m| m.controller != controller);
+    }
+
+    pub fn clear(&mut self) {
+        self.mappings.clear();
+        self.learning = None;
+    }
+
+    pub fn mappings(&self) -> &[CcMapping] {
+        &self.mappings
+    }
+
+    pub fn start_learn(&mut self, mapping: CcMapping) {
+        self.learning = Some(mapping);
+    }
+
+    pub fn cancel_learn(&mut self) {
+        self.learning = None;
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.learning.is_some()
+    }
+
+    // Completes a pending learn with the controller that just moved
+    pub fn learn(&mut self, controller: u8) {
+        if let Some(mut mapping) = self.learning.take() {
+            mapping.controller = controller.min(127);
+            self.map(mapping);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Chord dictionary for the chord-symbol API. Ids 0-11 follow the UI's
+// ChordType list in src/music/chords.ts so both sides agree on numbering;
+// the extended chords come after.
+pub const CHORD_TYPE_COUNT: usize = 16;
+
+pub const MAJOR: u8 = 0;
+pub const MINOR: u8 = 1;
+pub const DOM7: u8 = 2;
+pub const MAJ7: u8 = 3;
+pub const MIN7: u8 = 4;
+pub const AUG: u8 = 7;
+pub const DIM: u8 = 8;
+
+const INTERVALS: [&[u8]; CHORD_TYPE_COUNT] = [
+    &[0, 4, 7],                 // major
+    &[0, 3, 7],                 // minor
+    &[0, 4, 7, 10],             // dom7
+    &[0, 4, 7, 11],             // maj7
+    &[0, 3, 7, 10],             // min7
+    &[0, 2, 7],                 // sus2
+    &[0, 5, 7],                 // sus4
+    &[0, 4, 8],                 // aug
+    &[0, 3, 6],                 // dim
+    &[0, 4, 7, 11, 14],         // maj9
+    &[0, 3, 7, 10, 14],         // min9
+    &[0, 4, 7, 9],              // maj6
+    &[0, 4, 7, 10, 14],         // dom9
+    // 13ths leave out the 11th, as voiced on keys
+    &[0, 4, 7, 10, 14, 21],     // dom13
+    &[0, 4, 7, 11, 14, 21],     // maj13
+    &[0, 3, 7, 10, 14, 17, 21], // min13
+];
+
+const NAMES: [&str; CHORD_TYPE_COUNT] = [
+    "Major", "Minor", "7", "Maj7", "Min7", "Sus2", "Sus4", "Aug", "Dim",
+    "Maj9", "Min9", "Maj6", "9", "13", "Maj13", "Min13",
+];
+
+pub fn chord_type_name(chord_type: u8) -> Option<&'static str> {
+    NAMES.get(chord_type as usize).copied()
+}
+
+// MIDI notes of a chord on `root`. Each inversion moves the lowest note up
+// an octave; inversions past the last one wrap around. Notes that would
+// land above 127 are dropped.
+pub fn chord_notes(root: u8, chord_type: u8, inversion: u8) -> Vec<u8> {
+    let Some(intervals) = INTERVALS.get(chord_type as usize) else {
+        return Vec::new();
+    };
+    let mut notes: Vec<u16> = intervals.iter().map(|&i| root as u16 + i as u16).collect();
+    let inversion = inversion as usize % notes.len();
+    notes.rotate_left(inversion);
+    let len = notes.len();
+    for note in &mut notes[len - inversion..] {
+        *note += 12;
+    }
+    notes.into_iter().filter(|&n| n <= 127).map(|n| n as u8).collect()
+}
+
+// A chord symbol placed at a particular octave and inversion
+#[derive(Clone, Copy)]
+pub struct Voicing {
+    pub root: u8,
+    pub chord_type: u8,
+    pub inversion: u8,
+}
+
+impl Voicing {
+    pub fn notes(&self) -> Vec<u8> {
+        chord_notes(self.root, self.chord_type, self.inversion)
+    }
+}
+
+// Lowest note the voice leading drifts to, and how far it may move a root
+const VOICE_LEADING_FLOOR: i32 = 36;
+const VOICE_LEADING_OCTAVES: i32 = 2;
+// Cost per semitone the voicing's centre drifts from the first chord's, so
+// a long progression doesn't creep up or down the keyboard
+const DRIFT_COST: f32 = 0.5;
+
+// Voices each (root, chord_type) like the chord before it: the first keeps
+// its root position, every later one takes the inversion and octave whose
+// notes move least from the previous voicing's.
+pub fn voice_lead(chords: &[(u8, u8)]) -> Vec<Voicing> {
+    let mut voicings: Vec<Voicing> = Vec::with_capacity(chords.len());
+    let mut centre = 0.0;
+    for &(root, chord_type) in chords {
+        let Some(previous) = voicings.last() else {
+            let first = Voicing { root, chord_type, inversion: 0 };
+            centre = mean(&first.notes());
+            voicings.push(first);
+            continue;
+        };
+        let previous_notes = previous.notes();
+        let size = INTERVALS.get(chord_type as usize).map_or(0, |i| i.len());
+        let mut best = Voicing { root, chord_type, inversion: 0 };
+        let mut best_cost = f32::MAX;
+        for octave in -VOICE_LEADING_OCTAVES..=VOICE_LEADING_OCTAVES {
+            let shifted = root as i32 + 12 * octave;
+            if !(VOICE_LEADING_FLOOR..=127).contains(&shifted) {
+                continue;
+            }
+            for inversion in 0..size as u8 {
+                let candidate = Voicing { root: shifted as u8, chord_type, inversion };
+                let notes = candidate.notes();
+                if notes.len() < size {
+                    continue;
+                }
+                let cost = movement(&previous_notes, &notes) + DRIFT_COST * (mean(&notes) - centre).abs();
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = candidate;
+                }
+            }
+        }
+        voicings.push(best);
+    }
+    voicings
+}
+
+// Semitones every note of each chord is from the nearest note of the other
+fn movement(from: &[u8], to: &[u8]) -> f32 {
+    let nearest = |note: u8, chord: &[u8]| chord.iter().map(|&n| (n as i32 - note as i32).abs()).min().unwrap_or(0);
+    let there: i32 = from.iter().map(|&n| nearest(n, to)).sum();
+    let back: i32 = to.iter().map(|&n| nearest(n, from)).sum();
+    (there + back) as f32
+}
+
+fn mean(notes: &[u8]) -> f32 {
+    notes.iter().map(|&n| n as f32).sum::<f32>() / notes.len().max(1) as f32
+}
+
+// Each positive inversion moves the lowest note up an octave, each negative
+// one the highest note down. Notes come back sorted; any pushed outside the
+// MIDI range are dropped.
+pub fn invert(notes: &[u8], inversions: i32) -> Vec<u8> {
+    let mut notes: Vec<i32> = notes.iter().map(|&n| n as i32).collect();
+    notes.sort_unstable();
+    if !notes.is_empty() {
+        for _ in 0..inversions.unsigned_abs().min(notes.len() as u32 * 4) {
+            if inversions > 0 {
+                let lowest = notes.remove(0);
+                notes.push(lowest + 12);
+            } else {
+                let highest = notes.pop().unwrap_or_default();
+                notes.insert(0, highest - 12);
+            }
+        }
+    }
+    to_midi(notes)
+}
+
+// Negative harmony: mirrors the notes around the axis halfway between the
+// key's root and fifth (pitch class `key_root`), so in C major G becomes C
+// and E becomes Eb. The axis is placed to keep the chord in about the same
+// register.
+pub fn negative_harmony(notes: &[u8], key_root: u8) -> Vec<u8> {
+    if notes.is_empty() {
+        return Vec::new();
+    }
+    // Twice the axis, in semitones, so the half-step axis stays whole. Axes
+    // a tritone apart map the same pitch classes, so the doubled axis can
+    // move in steps of 12.
+    let base = 2 * (key_root % 12) as i32 + 7;
+    let centre = mean(notes);
+    let octaves = ((2.0 * centre - base as f32) / 12.0).round() as i32;
+    let axis = base + 12 * octaves;
+    let mut mirrored: Vec<i32> = notes.iter().map(|&n| axis - n as i32).collect();
+    mirrored.sort_unstable();
+    to_midi(mirrored)
+}
+
+fn to_midi(notes: Vec<i32>) -> Vec<u8> {
+    notes.into_iter().filter(|n| (0..=127).contains(n)).map(|n| n as u8).collect()
+}
@@ -0,0 +1,54 @@
+// Monotonic wall-clock milliseconds for measuring render time. In the
+// browser this is performance.now() where the global scope has one (older
+// AudioWorklet scopes don't) and Date.now() otherwise; native builds use a
+// process-local Instant. A WASM build without the JS bindings has no clock,
+// so the load it measures reads zero.
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod js {
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::JsCast;
+
+    #[wasm_bindgen]
+    extern "C" {
+        pub type Performance;
+
+        #[wasm_bindgen(method)]
+        pub fn now(this: &Performance) -> f64;
+    }
+
+    thread_local! {
+        // Looked up once, so a render only pays for the call itself rather
+        // than the global lookups and the strings naming them
+        pub static PERFORMANCE: Option<Performance> = lookup();
+    }
+
+    fn lookup() -> Option<Performance> {
+        let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance")).ok()?;
+        let now = Reflect::get(&performance, &JsValue::from_str("now")).ok()?;
+        now.is_instance_of::<Function>().then(|| performance.unchecked_into())
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub fn now_ms() -> f64 {
+    js::PERFORMANCE.with(|performance| match performance {
+        Some(performance) => performance.now(),
+        None => js_sys::Date::now(),
+    })
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+pub fn now_ms() -> f64 {
+    0.0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
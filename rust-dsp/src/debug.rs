@@ -0,0 +1,37 @@
+// Short history of control events for the diagnostic dump. The log is a
+// fixed ring so recording from the note and transport methods never
+// allocates; once full, the oldest entry is overwritten.
+
+pub const EVENT_LOG_SIZE: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct LoggedEvent {
+    pub sample_time: u64,
+    pub kind: &'static str,
+    pub args: [f32; 2],
+}
+
+pub struct EventLog {
+    entries: [Option<LoggedEvent>; EVENT_LOG_SIZE],
+    next: usize,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog {
+            entries: [None; EVENT_LOG_SIZE],
+            next: 0,
+        }
+    }
+
+    pub fn record(&mut self, sample_time: u64, kind: &'static str, args: [f32; 2]) {
+        self.entries[self.next] = Some(LoggedEvent { sample_time, kind, args });
+        self.next = (self.next + 1) % EVENT_LOG_SIZE;
+    }
+
+    // Oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedEvent> {
+        let (newer, older) = self.entries.split_at(self.next);
+        older.iter().chain(newer).flatten()
+    }
+}
@@ -0,0 +1,10 @@
+// Recursive filters and feedback loops decay towards zero through the
+// subnormal range, where many CPUs slow down by an order of magnitude or
+// more. WebAssembly has no flush-to-zero mode, so long tails flush their
+// state themselves once it is far below anything audible.
+pub const THRESHOLD: f32 = 1e-15;
+
+#[inline]
+pub fn flush(value: f32) -> f32 {
+    if value.abs() < THRESHOLD { 0.0 } else { value }
+}
@@ -0,0 +1,84 @@
+// Timeline and live setters from before engines were addressed by index.
+// Each is the engine-indexed setter of the same name with engine 0 (or 1 for
+// the live volume); new code should call that directly, which also reaches
+// created engines. They live in their own module so the bindings generated
+// for them can call deprecated items without the lint firing for the whole
+// crate.
+#![allow(deprecated)]
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{AudioEngine, ENGINE_LIVE, ENGINE_TIMELINE};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AudioEngine {
+    #[deprecated(note = "use set_engine_volume with engine 0")]
+    pub fn set_timeline_volume(&mut self, volume: f32) {
+        self.set_engine_volume(ENGINE_TIMELINE, volume);
+    }
+
+    #[deprecated(note = "use set_engine_volume with engine 1")]
+    pub fn set_live_volume(&mut self, volume: f32) {
+        self.set_engine_volume(ENGINE_LIVE, volume);
+    }
+
+    #[deprecated(note = "use set_waveform with engine 0")]
+    pub fn set_timeline_waveform(&mut self, waveform: u8) {
+        self.set_waveform(ENGINE_TIMELINE, waveform);
+    }
+
+    #[deprecated(note = "use set_adsr with engine 0")]
+    pub fn set_timeline_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        self.set_adsr(ENGINE_TIMELINE, attack, decay, sustain, release);
+    }
+
+    #[deprecated(note = "use set_lfo_rate with engine 0")]
+    pub fn set_timeline_lfo_rate(&mut self, rate: f32) {
+        self.set_lfo_rate(ENGINE_TIMELINE, rate);
+    }
+
+    #[deprecated(note = "use set_lfo_depth with engine 0")]
+    pub fn set_timeline_lfo_depth(&mut self, depth: f32) {
+        self.set_lfo_depth(ENGINE_TIMELINE, depth);
+    }
+
+    #[deprecated(note = "use set_lfo_waveform with engine 0")]
+    pub fn set_timeline_lfo_waveform(&mut self, waveform: u8) {
+        self.set_lfo_waveform(ENGINE_TIMELINE, waveform);
+    }
+
+    #[deprecated(note = "use set_detune with engine 0")]
+    pub fn set_timeline_detune(&mut self, cents: f32) {
+        self.set_detune(ENGINE_TIMELINE, cents);
+    }
+
+    #[deprecated(note = "use set_glide_time with engine 0")]
+    pub fn set_timeline_glide_time(&mut self, time_ms: f32) {
+        self.set_glide_time(ENGINE_TIMELINE, time_ms);
+    }
+
+    #[deprecated(note = "use set_delay with engine 0")]
+    pub fn set_timeline_delay(&mut self, enabled: bool, time_ms: f32, feedback: f32, mix: f32) {
+        self.set_delay(ENGINE_TIMELINE, enabled, time_ms, feedback, mix);
+    }
+
+    // The old setter had no mix; the patch's stays as it is
+    #[deprecated(note = "use set_reverb with engine 0")]
+    pub fn set_timeline_reverb(&mut self, enabled: bool, room_size: f32, damping: f32) {
+        let mix = self.timeline_engine.patch.reverb_mix;
+        self.set_reverb(ENGINE_TIMELINE, enabled, room_size, damping, mix);
+    }
+
+    // The old setter had no mix; the patch's stays as it is
+    #[deprecated(note = "use set_tremolo with engine 0")]
+    pub fn set_timeline_tremolo(&mut self, enabled: bool, rate: f32, depth: f32) {
+        let mix = self.timeline_engine.patch.tremolo_mix;
+        self.set_tremolo(ENGINE_TIMELINE, enabled, rate, depth, mix);
+    }
+
+    #[deprecated(note = "use set_flanger with engine 0")]
+    pub fn set_timeline_flanger(&mut self, enabled: bool, rate: f32, depth: f32, feedback: f32, mix: f32) {
+        self.set_flanger(ENGINE_TIMELINE, enabled, rate, depth, feedback, mix);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::filter::StateVariableFilter;
+use crate::voice::CONTROL_BLOCK;
+use super::Effect;
+
+const BASE_FREQ: f32 = 200.0;
+const MAX_SWEEP_OCTAVES: f32 = 6.0;
+
+// Envelope-follower filter: the louder the input, the higher a resonant
+// band-pass sweeps. Both channels share one follower and one cutoff.
+#[derive(Clone)]
+pub struct AutoWah {
+    filters: [StateVariableFilter; 2],
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    sensitivity: f32,
+    range: f32,
+    damping: f32,
+    mix: f32,
+}
+
+impl AutoWah {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut filters = [StateVariableFilter::new(sample_rate), StateVariableFilter::new(sample_rate)];
+        for filter in &mut filters {
+            filter.set_mode(2); // Bandpass
+        }
+
+        let mut auto_wah = AutoWah {
+            filters,
+            envelope: 0.0,
+            // Fast enough to catch stabs, slow enough not to ripple at audio rate
+            attack_coeff: 1.0 - (-1.0 / (0.005 * sample_rate)).exp(),
+            release_coeff: 1.0 - (-1.0 / (0.12 * sample_rate)).exp(),
+            sensitivity: 0.5,
+            range: 0.5,
+            damping: 0.0,
+            mix: 1.0,
+        };
+        auto_wah.set_q(3.0);
+        auto_wah
+    }
+
+    // Gain applied to the envelope before it moves the filter
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity.clamp(0.0, 1.0);
+    }
+
+    // 0..1 maps to a sweep of 0..6 octaves above 200 Hz
+    pub fn set_range(&mut self, range: f32) {
+        self.range = range.clamp(0.0, 1.0);
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        let q = q.clamp(0.7, 10.0);
+        self.damping = 1.0 / q;
+        // Same damping curve as the state variable filter's resonance control
+        let resonance = (1.0 - self.damping / 0.707) / 0.9;
+        for filter in &mut self.filters {
+            filter.set_resonance(resonance);
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    fn follow(&mut self, left: f32, right: f32) {
+        let level = ((left + right) * 0.5).abs();
+        let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+        self.envelope += (level - self.envelope) * coeff;
+    }
+
+    // Moves the filters to where the envelope has them
+    fn sweep(&mut self) {
+        let sweep = (self.envelope * self.sensitivity * 4.0).min(1.0);
+        let cutoff = BASE_FREQ * 2.0_f32.powf(sweep * self.range * MAX_SWEEP_OCTAVES);
+        for filter in &mut self.filters {
+            filter.set_cutoff(cutoff);
+        }
+    }
+
+    fn filter(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // The band-pass peaks at roughly Q, so scale back to unity at the centre
+        let wet_left = self.filters[0].process(left) * self.damping;
+        let wet_right = self.filters[1].process(right) * self.damping;
+        let dry = 1.0 - self.mix;
+        (left * dry + wet_left * self.mix, right * dry + wet_right * self.mix)
+    }
+}
+
+impl Effect for AutoWah {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.follow(left, right);
+        self.sweep();
+        self.filter(left, right)
+    }
+
+    // The envelope follows every frame but the cutoff only moves once per
+    // control block
+    fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (left, right) in left.chunks_mut(CONTROL_BLOCK).zip(right.chunks_mut(CONTROL_BLOCK)) {
+            self.sweep();
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                self.follow(*l, *r);
+                (*l, *r) = self.filter(*l, *r);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.envelope = 0.0;
+    }
+}
@@ -0,0 +1,35 @@
+use crate::biquad::Biquad;
+use super::Effect;
+
+// Elliptical EQ for the master bus: the side signal is high-passed so
+// everything below the crossover plays in mono while the mids and highs keep
+// their width. Two cascaded Butterworth sections give a 24 dB/octave slope.
+pub struct BassMono {
+    side_filters: [Biquad; 2],
+}
+
+impl BassMono {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut bass_mono = BassMono {
+            side_filters: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+        };
+        bass_mono.set_frequency(120.0);
+        bass_mono
+    }
+
+    pub fn set_frequency(&mut self, freq: f32) {
+        for filter in &mut self.side_filters {
+            filter.set_highpass(freq.clamp(20.0, 500.0), std::f32::consts::FRAC_1_SQRT_2);
+        }
+    }
+}
+
+impl Effect for BassMono {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let mid = (left + right) * 0.5;
+        let side = self.side_filters
+            .iter_mut()
+            .fold((left - right) * 0.5, |sample, filter| filter.process(sample));
+        (mid + side, mid - side)
+    }
+}
@@ -0,0 +1,61 @@
+use super::Effect;
+
+#[derive(Clone)]
+pub struct Bitcrusher {
+    bits: f32,
+    downsample: f32,
+    mix: f32,
+    held_left: f32,
+    held_right: f32,
+    hold_counter: f32,
+}
+
+impl Bitcrusher {
+    pub fn new() -> Self {
+        Bitcrusher {
+            bits: 8.0,
+            downsample: 4.0,
+            mix: 1.0,
+            held_left: 0.0,
+            held_right: 0.0,
+            hold_counter: 0.0,
+        }
+    }
+
+    pub fn set_bits(&mut self, bits: f32) {
+        self.bits = bits.clamp(1.0, 16.0);
+    }
+
+    pub fn set_downsample(&mut self, factor: f32) {
+        self.downsample = factor.clamp(1.0, 64.0);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl Effect for Bitcrusher {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Sample-rate reduction: hold each captured frame for `downsample` samples
+        self.hold_counter += 1.0;
+        if self.hold_counter >= self.downsample {
+            self.hold_counter -= self.downsample;
+            self.held_left = left;
+            self.held_right = right;
+        }
+
+        // Bit depth reduction: quantize to 2^(bits-1) levels per polarity
+        let levels = 2.0_f32.powf(self.bits - 1.0);
+        let crushed_left = (self.held_left * levels).round() / levels;
+        let crushed_right = (self.held_right * levels).round() / levels;
+
+        let dry = 1.0 - self.mix;
+        (left * dry + crushed_left * self.mix, right * dry + crushed_right * self.mix)
+    }
+
+    fn reset(&mut self) {
+        self.held_left = 0.0;
+        self.held_right = 0.0;
+    }
+}
@@ -0,0 +1,159 @@
+use crate::biquad::{ToneFilter, TONE_MAX_HZ, TONE_MIN_HZ};
+use crate::fft::{Complex, Fft};
+use super::Effect;
+
+const BLOCK_SIZE: usize = 128;
+const MAX_IR_SECONDS: f32 = 3.0;
+
+// Uniformly partitioned overlap-save convolution. The impulse response is
+// split into BLOCK_SIZE partitions whose spectra are multiplied against a
+// frequency-domain delay line of past input blocks, so cost per sample stays
+// flat regardless of where energy sits in the IR. Adds BLOCK_SIZE samples
+// of latency.
+#[derive(Clone)]
+pub struct ConvolutionReverb {
+    fft: Fft,
+    max_ir_samples: usize,
+    partitions: Vec<Vec<Complex>>,
+    delay_line: Vec<Vec<Complex>>,
+    delay_pos: usize,
+    input_block: Vec<f32>,
+    output_block: Vec<f32>,
+    fill: usize,
+    spectrum: Vec<Complex>,
+    accumulator: Vec<Complex>,
+    mix: f32,
+    frozen: bool,
+    low_cut: ToneFilter,
+}
+
+impl ConvolutionReverb {
+    pub fn new(sample_rate: f32) -> Self {
+        ConvolutionReverb {
+            fft: Fft::new(BLOCK_SIZE * 2),
+            max_ir_samples: (sample_rate * MAX_IR_SECONDS) as usize,
+            partitions: Vec::new(),
+            delay_line: Vec::new(),
+            delay_pos: 0,
+            input_block: vec![0.0; BLOCK_SIZE * 2],
+            output_block: vec![0.0; BLOCK_SIZE],
+            fill: 0,
+            spectrum: vec![Complex::default(); BLOCK_SIZE * 2],
+            accumulator: vec![Complex::default(); BLOCK_SIZE + 1],
+            mix: 0.2,
+            frozen: false,
+            low_cut: ToneFilter::new(sample_rate),
+        }
+    }
+
+    // Replaces the impulse response. The IR is truncated to MAX_IR_SECONDS
+    // and normalized to unit energy so any IR sits at a sensible wet level.
+    pub fn load_impulse_response(&mut self, ir: &[f32]) {
+        let ir = &ir[..ir.len().min(self.max_ir_samples)];
+        let energy: f32 = ir.iter().map(|s| s * s).sum();
+        let gain = if energy > 0.0 { 1.0 / energy.sqrt() } else { 0.0 };
+
+        let fft_size = self.fft.size();
+        self.partitions = ir
+            .chunks(BLOCK_SIZE)
+            .map(|chunk| {
+                let mut partition = vec![Complex::default(); fft_size];
+                for (bin, sample) in partition.iter_mut().zip(chunk) {
+                    bin.re = sample * gain;
+                }
+                self.fft.forward(&mut partition);
+                partition
+            })
+            .collect();
+
+        self.delay_line = vec![vec![Complex::default(); fft_size]; self.partitions.len()];
+        self.delay_pos = 0;
+        self.input_block.iter_mut().for_each(|s| *s = 0.0);
+        self.output_block.iter_mut().for_each(|s| *s = 0.0);
+        self.fill = 0;
+    }
+
+    fn process_block(&mut self) {
+        // Spectrum of the last two input blocks (overlap-save window)
+        for (bin, sample) in self.spectrum.iter_mut().zip(&self.input_block) {
+            *bin = Complex::new(*sample, 0.0);
+        }
+        self.fft.forward(&mut self.spectrum);
+        self.delay_line[self.delay_pos].copy_from_slice(&self.spectrum);
+
+        // Real signals have conjugate-symmetric spectra, so only the lower
+        // half (plus Nyquist) needs to be accumulated
+        let count = self.partitions.len();
+        self.accumulator.iter_mut().for_each(|bin| *bin = Complex::default());
+        for (p, partition) in self.partitions.iter().enumerate() {
+            let input = &self.delay_line[(self.delay_pos + count - p) % count];
+            for (k, acc) in self.accumulator.iter_mut().enumerate() {
+                let product = input[k].mul(partition[k]);
+                acc.re += product.re;
+                acc.im += product.im;
+            }
+        }
+
+        let fft_size = self.fft.size();
+        for k in 0..=BLOCK_SIZE {
+            self.spectrum[k] = self.accumulator[k];
+        }
+        for k in 1..BLOCK_SIZE {
+            self.spectrum[fft_size - k] = self.accumulator[k].conj();
+        }
+        self.fft.inverse(&mut self.spectrum);
+
+        // The second half of the circular result is free of wrap-around
+        for (out, bin) in self.output_block.iter_mut().zip(&self.spectrum[BLOCK_SIZE..]) {
+            *out = bin.re;
+        }
+
+        self.input_block.copy_within(BLOCK_SIZE.., 0);
+        self.delay_pos = (self.delay_pos + 1) % count;
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    // High-pass on what goes into the tail, in Hz (20 leaves it open), so
+    // long tails don't pile up low-end mud
+    pub fn set_low_cut(&mut self, freq: f32) {
+        self.low_cut.set(freq.clamp(TONE_MIN_HZ, 1000.0), TONE_MAX_HZ);
+    }
+
+    // A measured IR has a fixed length, so freezing can only stop new input:
+    // the tail still runs out after at most MAX_IR_SECONDS
+    pub fn set_freeze(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+}
+
+impl Effect for ConvolutionReverb {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.partitions.is_empty() {
+            return (left, right);
+        }
+
+        let input = self.low_cut.process((left + right) * 0.5);
+        self.input_block[BLOCK_SIZE + self.fill] = if self.frozen { 0.0 } else { input };
+        let wet = self.output_block[self.fill];
+        self.fill += 1;
+        if self.fill == BLOCK_SIZE {
+            self.process_block();
+            self.fill = 0;
+        }
+
+        let dry = 1.0 - self.mix;
+        (left * dry + wet * self.mix, right * dry + wet * self.mix)
+    }
+
+    fn reset(&mut self) {
+        for spectrum in &mut self.delay_line {
+            spectrum.fill(Complex::default());
+        }
+        self.input_block.fill(0.0);
+        self.output_block.fill(0.0);
+        self.low_cut.reset();
+    }
+}
@@ -0,0 +1,43 @@
+use std::f32::consts::PI;
+use super::Effect;
+use crate::denormal::flush;
+
+const CUTOFF_HZ: f32 = 10.0;
+
+// One-pole DC blocker (y = x - x[n-1] + r * y[n-1]) on each engine's output.
+// Asymmetric waveforms, drive and pulse widths leave a small offset that
+// would otherwise eat headroom in the limiter and thump on start/stop.
+#[derive(Clone)]
+pub struct DcBlocker {
+    coefficient: f32,
+    previous_input: [f32; 2],
+    previous_output: [f32; 2],
+}
+
+impl DcBlocker {
+    pub fn new(sample_rate: f32) -> Self {
+        DcBlocker {
+            coefficient: 1.0 - 2.0 * PI * CUTOFF_HZ / sample_rate,
+            previous_input: [0.0; 2],
+            previous_output: [0.0; 2],
+        }
+    }
+
+    fn process_channel(&mut self, channel: usize, input: f32) -> f32 {
+        let output = input - self.previous_input[channel] + self.coefficient * self.previous_output[channel];
+        self.previous_input[channel] = input;
+        self.previous_output[channel] = flush(output);
+        output
+    }
+}
+
+impl Effect for DcBlocker {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (self.process_channel(0, left), self.process_channel(1, right))
+    }
+
+    fn reset(&mut self) {
+        self.previous_input = [0.0; 2];
+        self.previous_output = [0.0; 2];
+    }
+}
@@ -1,28 +1,99 @@
+use super::Effect;
+use crate::biquad::{ToneFilter, TONE_MAX_HZ, TONE_MIN_HZ};
+use crate::denormal::flush;
+use std::f32::consts::TAU;
+
+// Time constant the modulated time offset eases toward its target with, so
+// stepped control updates bend the echoes instead of clicking
+const OFFSET_SMOOTHING_MS: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DelayMode {
+    Mono = 0,
+    Stereo = 1,
+    PingPong = 2,
+}
+
+#[derive(Clone)]
 pub struct Delay {
-    buffer: Vec<f32>,
+    buffer_left: Vec<f32>,
+    buffer_right: Vec<f32>,
     write_pos: usize,
     delay_samples: usize,
+    // Right line's own time in stereo and ping-pong modes, None to follow
+    // the left
+    right_delay_samples: Option<usize>,
     feedback: f32,
+    // Share of each stereo line's feedback taken from the other side
+    cross_feedback: f32,
+    // Tape-style wobble: the times swing up to this far and back at
+    // mod_rate Hz, the right line a quarter cycle behind the left
+    mod_depth_samples: f32,
+    mod_rate: f32,
+    mod_phase: f32,
+    // Offset of both times in samples from outside modulation, eased toward
+    // its target every sample
+    time_offset: f32,
+    time_offset_target: f32,
+    offset_coefficient: f32,
     mix: f32,
     sample_rate: f32,
+    mode: DelayMode,
+    // In the feedback loop, so every repeat comes back a little thinner
+    // and darker than the last
+    tone: [ToneFilter; 2],
 }
 
 impl Delay {
     pub fn new(sample_rate: f32, max_delay_ms: f32) -> Self {
         let max_samples = (max_delay_ms * sample_rate / 1000.0) as usize;
         Delay {
-            buffer: vec![0.0; max_samples],
+            buffer_left: vec![0.0; max_samples],
+            buffer_right: vec![0.0; max_samples],
             write_pos: 0,
             delay_samples: (sample_rate * 0.5 / 1000.0) as usize, // 0.5ms default
+            right_delay_samples: None,
             feedback: 0.3,
+            cross_feedback: 0.0,
+            mod_depth_samples: 0.0,
+            mod_rate: 0.5,
+            mod_phase: 0.0,
+            time_offset: 0.0,
+            time_offset_target: 0.0,
+            offset_coefficient: 1.0 - (-1000.0 / (OFFSET_SMOOTHING_MS * sample_rate)).exp(),
             mix: 0.3,
             sample_rate,
+            mode: DelayMode::Mono,
+            tone: [ToneFilter::new(sample_rate), ToneFilter::new(sample_rate)],
         }
     }
 
     pub fn set_delay_time(&mut self, time_ms: f32) {
         self.delay_samples = ((time_ms * self.sample_rate) / 1000.0) as usize;
-        self.delay_samples = self.delay_samples.min(self.buffer.len());
+        self.delay_samples = self.delay_samples.min(self.buffer_left.len());
+    }
+
+    // Right line time for stereo and ping-pong, 0 to follow the left
+    pub fn set_right_delay_time(&mut self, time_ms: f32) {
+        self.right_delay_samples = (time_ms > 0.0)
+            .then(|| (((time_ms * self.sample_rate) / 1000.0) as usize).min(self.buffer_right.len()));
+    }
+
+    // 0-1, stereo mode only: how much of each side's repeats feed the other
+    pub fn set_cross_feedback(&mut self, amount: f32) {
+        self.cross_feedback = amount.clamp(0.0, 1.0);
+    }
+
+    // Depth in ms (up to 10) and rate in Hz of the delay time wobble
+    pub fn set_modulation(&mut self, depth_ms: f32, rate_hz: f32) {
+        self.mod_depth_samples = depth_ms.clamp(0.0, 10.0) * self.sample_rate / 1000.0;
+        self.mod_rate = rate_hz.clamp(0.05, 10.0);
+    }
+
+    // Moves both times by `offset_ms` (either way) from what they're set to,
+    // gliding there rather than jumping; for modulation from the engine
+    pub fn set_time_offset(&mut self, offset_ms: f32) {
+        self.time_offset_target = offset_ms * self.sample_rate / 1000.0;
     }
 
     pub fn set_feedback(&mut self, feedback: f32) {
@@ -33,23 +104,111 @@ impl Delay {
         self.mix = mix.clamp(0.0, 1.0);
     }
 
-    pub fn process(&mut self, input: f32) -> f32 {
-        let read_pos = if self.write_pos >= self.delay_samples {
-            self.write_pos - self.delay_samples
-        } else {
-            self.buffer.len() - (self.delay_samples - self.write_pos)
+    // Low cut and high cut of the repeats in Hz, open at 20 and 20000
+    pub fn set_tone(&mut self, low_cut_hz: f32, high_cut_hz: f32) {
+        let low_cut = low_cut_hz.clamp(TONE_MIN_HZ, 2000.0);
+        let high_cut = high_cut_hz.clamp(1000.0, TONE_MAX_HZ);
+        for tone in &mut self.tone {
+            tone.set(low_cut, high_cut);
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = match mode {
+            0 => DelayMode::Mono,
+            1 => DelayMode::Stereo,
+            2 => DelayMode::PingPong,
+            _ => DelayMode::Mono,
         };
+    }
 
-        let delayed = self.buffer[read_pos];
-        let output = input + delayed * self.mix;
-        self.buffer[self.write_pos] = input + delayed * self.feedback;
+    // Echoes only, without the dry signal - used when the delay sits on a
+    // return bus
+    pub fn process_wet(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let right_delay_samples = match self.mode {
+            DelayMode::Mono => self.delay_samples,
+            _ => self.right_delay_samples.unwrap_or(self.delay_samples),
+        };
+        let (mut left_delay, mut right_delay) = (self.delay_samples as f32, right_delay_samples as f32);
+        let limit = (self.buffer_left.len() - 2) as f32;
+        if self.time_offset != self.time_offset_target {
+            self.time_offset += (self.time_offset_target - self.time_offset) * self.offset_coefficient;
+            if (self.time_offset_target - self.time_offset).abs() < 1e-3 {
+                self.time_offset = self.time_offset_target;
+            }
+        }
+        if self.time_offset != 0.0 {
+            // Kept a sample behind the write so the read never wraps
+            left_delay = (left_delay + self.time_offset).clamp(1.0, limit);
+            right_delay = (right_delay + self.time_offset).clamp(1.0, limit);
+        }
+        if self.mod_depth_samples > 0.0 {
+            // Only ever lengthened, so the read stays behind the write
+            let swing = |phase: f32| self.mod_depth_samples * 0.5 * (1.0 - (phase * TAU).cos());
+            left_delay = (left_delay + swing(self.mod_phase)).min(limit);
+            right_delay = (right_delay + swing(self.mod_phase + 0.25)).min(limit);
+            self.mod_phase = (self.mod_phase + self.mod_rate / self.sample_rate).fract();
+        }
+
+        let delayed_left = self.tone[0].process(read(&self.buffer_left, self.write_pos, left_delay));
+        let delayed_right = self.tone[1].process(read(&self.buffer_right, self.write_pos, right_delay));
+
+        let wet = match self.mode {
+            DelayMode::Mono => {
+                // Single line fed by the channel sum, echoes centered
+                self.buffer_left[self.write_pos] = flush((left + right) * 0.5 + delayed_left * self.feedback);
+                (delayed_left, delayed_left)
+            }
+            DelayMode::Stereo => {
+                let cross = self.cross_feedback;
+                let feedback_left = delayed_left * (1.0 - cross) + delayed_right * cross;
+                let feedback_right = delayed_right * (1.0 - cross) + delayed_left * cross;
+                self.buffer_left[self.write_pos] = flush(left + feedback_left * self.feedback);
+                self.buffer_right[self.write_pos] = flush(right + feedback_right * self.feedback);
+                (delayed_left, delayed_right)
+            }
+            DelayMode::PingPong => {
+                // Input enters on the left, feedback crosses sides so echoes alternate L/R
+                self.buffer_left[self.write_pos] = flush((left + right) * 0.5 + delayed_right * self.feedback);
+                self.buffer_right[self.write_pos] = flush(delayed_left * self.feedback);
+                (delayed_left, delayed_right)
+            }
+        };
 
         self.write_pos += 1;
-        if self.write_pos >= self.buffer.len() {
+        if self.write_pos >= self.buffer_left.len() {
             self.write_pos = 0;
         }
 
-        output
+        wet
     }
 }
 
+// Sample `delay` (fractional) samples behind `write_pos`, linearly
+// interpolated. A delay of 0 reads the oldest sample, a full buffer back.
+fn read(buffer: &[f32], write_pos: usize, delay: f32) -> f32 {
+    let len = buffer.len();
+    let whole = delay as usize;
+    let fraction = delay - whole as f32;
+    let newer = (write_pos + len - whole.min(len)) % len;
+    let older = (newer + len - 1) % len;
+    if fraction == 0.0 {
+        return buffer[newer];
+    }
+    buffer[newer] + (buffer[older] - buffer[newer]) * fraction
+}
+
+impl Effect for Delay {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (wet_left, wet_right) = self.process_wet(left, right);
+        (left + wet_left * self.mix, right + wet_right * self.mix)
+    }
+
+    fn reset(&mut self) {
+        self.buffer_left.fill(0.0);
+        self.buffer_right.fill(0.0);
+        for tone in &mut self.tone {
+            tone.reset();
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::biquad::Biquad;
+use super::Effect;
+
+// Three-band EQ: low shelf, peaking mid and high shelf. Each band keeps one
+// filter per channel so the stereo image passes through untouched.
+#[derive(Clone)]
+pub struct Eq {
+    low: [Biquad; 2],
+    mid: [Biquad; 2],
+    high: [Biquad; 2],
+}
+
+impl Eq {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut eq = Eq {
+            low: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+            mid: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+            high: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+        };
+        eq.set_low(0.0, 200.0);
+        eq.set_mid(0.0, 1000.0);
+        eq.set_high(0.0, 5000.0);
+        eq
+    }
+
+    pub fn set_low(&mut self, gain_db: f32, freq: f32) {
+        for filter in &mut self.low {
+            filter.set_low_shelf(freq.clamp(20.0, 1000.0), gain_db.clamp(-24.0, 24.0));
+        }
+    }
+
+    pub fn set_mid(&mut self, gain_db: f32, freq: f32) {
+        for filter in &mut self.mid {
+            filter.set_peak(freq.clamp(100.0, 10000.0), gain_db.clamp(-24.0, 24.0), 1.0);
+        }
+    }
+
+    pub fn set_high(&mut self, gain_db: f32, freq: f32) {
+        for filter in &mut self.high {
+            filter.set_high_shelf(freq.clamp(1000.0, 20000.0), gain_db.clamp(-24.0, 24.0));
+        }
+    }
+
+    fn process_channel(&mut self, channel: usize, input: f32) -> f32 {
+        let sample = self.low[channel].process(input);
+        let sample = self.mid[channel].process(sample);
+        self.high[channel].process(sample)
+    }
+}
+
+impl Effect for Eq {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (self.process_channel(0, left), self.process_channel(1, right))
+    }
+
+    fn reset(&mut self) {
+        for filter in self.low.iter_mut().chain(&mut self.mid).chain(&mut self.high) {
+            filter.reset();
+        }
+    }
+}
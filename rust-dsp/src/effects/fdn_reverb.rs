@@ -0,0 +1,379 @@
+use std::f32::consts::PI;
+use super::Effect;
+use crate::biquad::{ToneFilter, TONE_MAX_HZ, TONE_MIN_HZ};
+use crate::grain_shifter::GrainShifter;
+use crate::denormal::flush;
+#[cfg(feature = "simd")]
+use crate::simd::F32x4;
+
+const LINES: usize = 8;
+const DIFFUSERS: usize = 6;
+const MAX_PRE_DELAY_MS: f32 = 200.0;
+const MAX_MOD_SAMPLES: f32 = 12.0;
+const SHIMMER_WINDOW_MS: f32 = 60.0;
+// At sparse density the line modulation is worked out once per this many
+// samples rather than every sample
+const SPARSE_MOD_INTERVAL: usize = 32;
+
+// Density levels: how many input diffusers run, and how often the line
+// modulation is updated
+pub const DENSITY_SPARSE: u8 = 0;
+pub const DENSITY_NORMAL: u8 = 1;
+pub const DENSITY_DENSE: u8 = 2;
+const DENSITY_DIFFUSERS: [usize; 3] = [2, 4, 6];
+
+// Mutually prime line lengths (in samples at 48kHz) keep the modes spread
+// evenly so long tails don't ring at a handful of frequencies
+const LINE_DELAYS: [usize; LINES] = [1433, 1601, 1867, 2053, 2251, 2399, 2617, 2797];
+const DIFFUSER_DELAYS: [usize; DIFFUSERS] = [142, 107, 379, 277, 211, 163];
+const MOD_RATES: [f32; LINES] = [0.31, 0.43, 0.53, 0.61, 0.73, 0.83, 0.97, 1.09];
+
+// Eight-line feedback delay network with a Hadamard feedback matrix. Input
+// runs through a pre-delay and a chain of allpass diffusers before entering
+// the network; slow modulation of the line lengths smears the resonances
+// that make comb-based designs sound metallic on long tails.
+#[derive(Clone)]
+pub struct FdnReverb {
+    sample_rate: f32,
+    pre_delay: Vec<f32>,
+    pre_delay_pos: usize,
+    pre_delay_samples: usize,
+    diffusers: Vec<Diffuser>,
+    // Diffusers in use and modulation update interval for the density
+    active_diffusers: usize,
+    mod_interval: usize,
+    mod_countdown: usize,
+    lines: Vec<DelayLine>,
+    size: f32,
+    damping: f32,
+    diffusion: f32,
+    modulation: f32,
+    mix: f32,
+    frozen: bool,
+    shimmer: f32,
+    shifter: GrainShifter,
+    low_cut: ToneFilter,
+}
+
+#[derive(Clone)]
+struct DelayLine {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    length: f32,
+    gain: f32,
+    damping_state: f32,
+    mod_phase: f32,
+    mod_increment: f32,
+    // Modulation LFO output as last worked out, -1 to 1
+    mod_value: f32,
+}
+
+#[derive(Clone)]
+struct Diffuser {
+    buffer: Vec<f32>,
+    write_pos: usize,
+}
+
+impl FdnReverb {
+    pub fn new(sample_rate: f32) -> Self {
+        let scale = sample_rate / 48000.0;
+
+        let lines = LINE_DELAYS
+            .iter()
+            .zip(MOD_RATES)
+            .enumerate()
+            .map(|(i, (&delay, rate))| {
+                let mut line = DelayLine::new(delay as f32 * scale, MAX_MOD_SAMPLES as usize + 2);
+                line.mod_phase = i as f32 / LINES as f32;
+                line.mod_increment = rate / sample_rate;
+                line
+            })
+            .collect();
+        let diffusers = DIFFUSER_DELAYS
+            .iter()
+            .map(|&delay| Diffuser::new(((delay as f32 * scale) as usize).max(1)))
+            .collect();
+
+        let mut reverb = FdnReverb {
+            sample_rate,
+            pre_delay: vec![0.0; (sample_rate * MAX_PRE_DELAY_MS / 1000.0) as usize + 1],
+            pre_delay_pos: 0,
+            pre_delay_samples: 0,
+            diffusers,
+            active_diffusers: DENSITY_DIFFUSERS[DENSITY_NORMAL as usize],
+            mod_interval: 1,
+            mod_countdown: 0,
+            lines,
+            size: 0.5,
+            damping: 0.5,
+            diffusion: 0.7,
+            modulation: 0.3,
+            mix: 0.2,
+            frozen: false,
+            shimmer: 0.0,
+            shifter: GrainShifter::new(sample_rate, SHIMMER_WINDOW_MS),
+            low_cut: ToneFilter::new(sample_rate),
+        };
+        reverb.shifter.set_ratio(2.0);
+        reverb.update_gains();
+        reverb
+    }
+
+    pub fn set_pre_delay(&mut self, time_ms: f32) {
+        let time_ms = time_ms.clamp(0.0, MAX_PRE_DELAY_MS);
+        self.pre_delay_samples = ((time_ms / 1000.0) * self.sample_rate) as usize;
+    }
+
+    // 0..1 maps to a decay time of 0.3s..8s
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size.clamp(0.0, 1.0);
+        self.update_gains();
+    }
+
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.clamp(0.0, 1.0);
+    }
+
+    pub fn set_diffusion(&mut self, diffusion: f32) {
+        self.diffusion = diffusion.clamp(0.0, 1.0);
+    }
+
+    // DENSITY_SPARSE trades a little smoothness for CPU, DENSITY_DENSE
+    // smears the attack further. Safe to change while the tail rings: only
+    // the input diffusion and the modulation update rate change.
+    pub fn set_density(&mut self, density: u8) {
+        let density = density.min(DENSITY_DENSE);
+        let active = DENSITY_DIFFUSERS[density as usize];
+        // Diffusers coming back in start empty rather than replaying what
+        // they held when they were dropped
+        for diffuser in self.diffusers.iter_mut().take(active).skip(self.active_diffusers) {
+            diffuser.buffer.fill(0.0);
+        }
+        self.active_diffusers = active;
+        self.mod_interval = if density == DENSITY_SPARSE { SPARSE_MOD_INTERVAL } else { 1 };
+    }
+
+    pub fn set_modulation(&mut self, modulation: f32) {
+        self.modulation = modulation.clamp(0.0, 1.0);
+    }
+
+    // Amount of octave-up tail fed back into the network, 0-1. Each pass
+    // through the loop climbs another octave for the classic shimmer bed.
+    pub fn set_shimmer(&mut self, amount: f32) {
+        self.shimmer = amount.clamp(0.0, 1.0);
+    }
+
+    // Frozen, the network becomes lossless (unity gains, no damping or
+    // modulation smearing) and stops taking input: the tail holds forever
+    pub fn set_freeze(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.update_gains();
+    }
+
+    // Per-line gains so every line decays by 60dB over the same RT60
+    fn update_gains(&mut self) {
+        let rt60 = 0.3 + self.size * 7.7;
+        for line in &mut self.lines {
+            line.gain = if self.frozen {
+                1.0
+            } else {
+                10.0_f32.powf(-3.0 * line.length / (rt60 * self.sample_rate))
+            };
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    // High-pass on what goes into the tail, in Hz (20 leaves it open), so
+    // long tails don't pile up low-end mud
+    pub fn set_low_cut(&mut self, freq: f32) {
+        self.low_cut.set(freq.clamp(TONE_MIN_HZ, 1000.0), TONE_MAX_HZ);
+    }
+}
+
+impl Effect for FdnReverb {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Pre-delay
+        let len = self.pre_delay.len();
+        self.pre_delay[self.pre_delay_pos] = self.low_cut.process((left + right) * 0.5);
+        let read_pos = (self.pre_delay_pos + len - self.pre_delay_samples) % len;
+        let mut input = if self.frozen { 0.0 } else { self.pre_delay[read_pos] };
+        self.pre_delay_pos = (self.pre_delay_pos + 1) % len;
+
+        // Input diffusion
+        let coefficient = self.diffusion * 0.7;
+        for diffuser in &mut self.diffusers[..self.active_diffusers] {
+            input = diffuser.process(input, coefficient);
+        }
+
+        // Read the (modulated) line outputs and damp them
+        let (depth, damping) = if self.frozen {
+            (0.0, 0.0)
+        } else {
+            (self.modulation * MAX_MOD_SAMPLES, self.damping * 0.7)
+        };
+        let mod_steps = if self.mod_countdown == 0 {
+            self.mod_countdown = self.mod_interval;
+            self.mod_interval
+        } else {
+            0
+        };
+        self.mod_countdown -= 1;
+        let mut outputs = [0.0; LINES];
+        for (output, line) in outputs.iter_mut().zip(&mut self.lines) {
+            *output = line.read_damped(depth, damping, mod_steps);
+        }
+
+        // Even lines feed the left output, odd lines the right, with
+        // alternating signs to decorrelate the two sides
+        let mut wet_left = 0.0;
+        let mut wet_right = 0.0;
+        for i in (0..LINES).step_by(2) {
+            let sign = if i % 4 == 0 { 1.0 } else { -1.0 };
+            wet_left += outputs[i] * sign;
+            wet_right += outputs[i + 1] * sign;
+        }
+        let output_gain = 1.0 / (LINES as f32 / 2.0).sqrt();
+
+        // Mix through the Hadamard matrix
+        hadamard(&mut outputs);
+
+        // Shimmer: one direction of the recirculating signal is swapped for
+        // an octave-up copy of itself. Swapping rather than adding keeps the
+        // loop gain at or below the decay gains, so the octaves keep
+        // climbing but never run away.
+        let share = if self.frozen { 0.0 } else { self.shimmer };
+        let spread = 1.0 / (LINES as f32).sqrt();
+        let mut correction = 0.0;
+        if share > 0.0 {
+            let projected: f32 = outputs.iter().enumerate().map(|(i, o)| o * sign(i)).sum::<f32>() * spread;
+            correction = (self.shifter.process(projected) - projected) * share * spread;
+        }
+
+        // Write back with decay gains
+        for (i, (output, line)) in outputs.iter().zip(&mut self.lines).enumerate() {
+            line.write(input + (output + correction * sign(i)) * line.gain);
+        }
+
+        let dry = 1.0 - self.mix;
+        let wet = self.mix * output_gain;
+        (left * dry + wet_left * wet, right * dry + wet_right * wet)
+    }
+
+    fn reset(&mut self) {
+        self.pre_delay.fill(0.0);
+        for diffuser in &mut self.diffusers {
+            diffuser.buffer.fill(0.0);
+        }
+        for line in &mut self.lines {
+            line.buffer.fill(0.0);
+            line.damping_state = 0.0;
+        }
+        self.shifter.reset();
+        self.low_cut.reset();
+    }
+}
+
+// Alternating signs of the direction the shimmer works along
+fn sign(line: usize) -> f32 {
+    if line.is_multiple_of(2) { 1.0 } else { -1.0 }
+}
+
+// Normalized fast Walsh-Hadamard transform: lossless, maximally mixing
+#[cfg(not(feature = "simd"))]
+fn hadamard(values: &mut [f32; LINES]) {
+    let mut half = 1;
+    while half < LINES {
+        for start in (0..LINES).step_by(half * 2) {
+            for i in start..start + half {
+                let a = values[i];
+                let b = values[i + half];
+                values[i] = a + b;
+                values[i + half] = a - b;
+            }
+        }
+        half *= 2;
+    }
+    let scale = 1.0 / (LINES as f32).sqrt();
+    for value in values.iter_mut() {
+        *value *= scale;
+    }
+}
+
+// The same transform on two four-lane halves: each half in place, then
+// the butterfly between them
+#[cfg(feature = "simd")]
+fn hadamard(values: &mut [f32; LINES]) {
+    let low = F32x4::load(&values[..4]).hadamard();
+    let high = F32x4::load(&values[4..]).hadamard();
+    let scale = F32x4::splat(1.0 / (LINES as f32).sqrt());
+    ((low + high) * scale).store(&mut values[..4]);
+    ((low - high) * scale).store(&mut values[4..]);
+}
+
+impl DelayLine {
+    fn new(length: f32, headroom: usize) -> Self {
+        DelayLine {
+            buffer: vec![0.0; length as usize + headroom],
+            write_pos: 0,
+            length,
+            gain: 0.0,
+            damping_state: 0.0,
+            mod_phase: 0.0,
+            mod_increment: 0.0,
+            mod_value: 0.0,
+        }
+    }
+
+    // Advances the modulation LFO by `mod_steps` samples (0 holds it where
+    // it is), reads the line and runs the loop damping
+    fn read_damped(&mut self, depth: f32, damping: f32, mod_steps: usize) -> f32 {
+        if mod_steps > 0 {
+            self.mod_value = (self.mod_phase * 2.0 * PI).sin();
+            self.mod_phase += self.mod_increment * mod_steps as f32;
+            if self.mod_phase >= 1.0 {
+                self.mod_phase -= 1.0;
+            }
+        }
+
+        let delayed = self.read(self.mod_value * depth);
+        self.damping_state = flush(delayed * (1.0 - damping) + self.damping_state * damping);
+        self.damping_state
+    }
+
+    // Linear-interpolated read `length + offset` samples behind the write head
+    fn read(&self, offset: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = (self.length + offset).clamp(1.0, (len - 2) as f32);
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = self.buffer[(self.write_pos + len - whole) % len];
+        let b = self.buffer[(self.write_pos + len - whole - 1) % len];
+        a + (b - a) * frac
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.write_pos] = flush(sample);
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+    }
+}
+
+impl Diffuser {
+    fn new(delay_samples: usize) -> Self {
+        Diffuser {
+            buffer: vec![0.0; delay_samples],
+            write_pos: 0,
+        }
+    }
+
+    // Schroeder allpass
+    fn process(&mut self, input: f32, coefficient: f32) -> f32 {
+        let delayed = self.buffer[self.write_pos];
+        let v = flush(input + delayed * coefficient);
+        self.buffer[self.write_pos] = v;
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+        delayed - v * coefficient
+    }
+}
@@ -1,28 +1,39 @@
 use crate::lfo::Lfo;
+use crate::voice::CONTROL_BLOCK;
+use super::Effect;
 
+#[derive(Clone)]
 pub struct Flanger {
-    buffer: Vec<f32>,
+    buffer_left: Vec<f32>,
+    buffer_right: Vec<f32>,
     write_pos: usize,
     lfo: Lfo,
     delay_range: f32,
     feedback: f32,
     mix: f32,
     sample_rate: f32,
+    // Where the sweep has the delay, in samples
+    delay: f32,
 }
 
 impl Flanger {
     pub fn new(sample_rate: f32) -> Self {
         let max_delay_ms = 10.0;
         let max_samples = (max_delay_ms * sample_rate / 1000.0) as usize;
-        
+        // The LFO sweeps the whole range; the depth setting is the range
+        let mut lfo = Lfo::new(sample_rate);
+        lfo.set_depth(1.0);
+
         Flanger {
-            buffer: vec![0.0; max_samples],
+            buffer_left: vec![0.0; max_samples],
+            buffer_right: vec![0.0; max_samples],
             write_pos: 0,
-            lfo: Lfo::new(sample_rate),
+            lfo,
             delay_range: 5.0, // 0.5ms to 5ms
             feedback: 0.3,
             mix: 0.5,
             sample_rate,
+            delay: 0.0,
         }
     }
 
@@ -42,28 +53,58 @@ impl Flanger {
         self.lfo.set_rate(rate);
     }
 
-    pub fn process(&mut self, input: f32) -> f32 {
-        let lfo_value = self.lfo.process();
+    fn sweep_delay(&self, lfo_value: f32) -> f32 {
         let delay_ms = 0.5 + (self.delay_range - 0.5) * (lfo_value * 0.5 + 0.5);
-        let delay_samples = ((delay_ms * self.sample_rate) / 1000.0) as usize;
-        let delay_samples = delay_samples.min(self.buffer.len() - 1);
+        (delay_ms * self.sample_rate) / 1000.0
+    }
+
+    fn tick(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let delay_samples = (self.delay as usize).min(self.buffer_left.len() - 1);
 
         let read_pos = if self.write_pos >= delay_samples {
             self.write_pos - delay_samples
         } else {
-            self.buffer.len() - (delay_samples - self.write_pos)
+            self.buffer_left.len() - (delay_samples - self.write_pos)
         };
 
-        let delayed = self.buffer[read_pos];
-        let output = input + delayed * self.mix;
-        self.buffer[self.write_pos] = input + delayed * self.feedback;
+        let delayed_left = self.buffer_left[read_pos];
+        let delayed_right = self.buffer_right[read_pos];
+        self.buffer_left[self.write_pos] = left + delayed_left * self.feedback;
+        self.buffer_right[self.write_pos] = right + delayed_right * self.feedback;
 
         self.write_pos += 1;
-        if self.write_pos >= self.buffer.len() {
+        if self.write_pos >= self.buffer_left.len() {
             self.write_pos = 0;
         }
 
-        output
+        (left + delayed_left * self.mix, right + delayed_right * self.mix)
+    }
+}
+
+impl Effect for Flanger {
+    // Both channels share one sweep so the image doesn't wobble
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let lfo_value = self.lfo.process();
+        self.delay = self.sweep_delay(lfo_value);
+        self.tick(left, right)
+    }
+
+    // The sweep is read once per control block and the delay ramped to it
+    // across the block
+    fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (left, right) in left.chunks_mut(CONTROL_BLOCK).zip(right.chunks_mut(CONTROL_BLOCK)) {
+            self.lfo.advance(left.len());
+            let step = (self.sweep_delay(self.lfo.value()) - self.delay) / left.len() as f32;
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                self.delay += step;
+                (*l, *r) = self.tick(*l, *r);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer_left.fill(0.0);
+        self.buffer_right.fill(0.0);
     }
 }
 
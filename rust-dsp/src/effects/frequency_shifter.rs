@@ -0,0 +1,132 @@
+use super::Effect;
+use crate::denormal::flush;
+use std::f32::consts::TAU;
+
+// Furthest shift either way in Hz
+const MAX_SHIFT_HZ: f32 = 2000.0;
+
+// Allpass coefficients of the two paths of the Hilbert transformer, whose
+// outputs stay 90 degrees apart from about 20 Hz to within a few hundred Hz
+// of Nyquist (Olli Niemitalo's design)
+const PATH_A: [f32; 4] = [0.692_387_8, 0.936_065_4, 0.988_229_5, 0.998_748_8];
+const PATH_B: [f32; 4] = [0.402_192_1, 0.856_171_1, 0.972_290_9, 0.995_288_5];
+
+// Chain of second-order allpasses y[n] = c * (x[n] + y[n-2]) - x[n-2], with
+// c the squared coefficient
+#[derive(Clone)]
+struct AllpassChain {
+    coefficients: [f32; 4],
+    x: [[f32; 2]; 4],
+    y: [[f32; 2]; 4],
+}
+
+impl AllpassChain {
+    fn new(coefficients: [f32; 4]) -> Self {
+        AllpassChain { coefficients: coefficients.map(|a| a * a), x: [[0.0; 2]; 4], y: [[0.0; 2]; 4] }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let mut signal = input;
+        for (i, &c) in self.coefficients.iter().enumerate() {
+            let out = flush(c * (signal + self.y[i][1]) - self.x[i][1]);
+            self.x[i] = [signal, self.x[i][0]];
+            self.y[i] = [out, self.y[i][0]];
+            signal = out;
+        }
+        signal
+    }
+
+    fn reset(&mut self) {
+        self.x = [[0.0; 2]; 4];
+        self.y = [[0.0; 2]; 4];
+    }
+}
+
+// Splits a signal into a pair 90 degrees apart (in-phase, quadrature)
+#[derive(Clone)]
+struct Hilbert {
+    a: AllpassChain,
+    b: AllpassChain,
+    // Path a runs a sample behind
+    a_delayed: f32,
+}
+
+impl Hilbert {
+    fn new() -> Self {
+        Hilbert { a: AllpassChain::new(PATH_A), b: AllpassChain::new(PATH_B), a_delayed: 0.0 }
+    }
+
+    fn process(&mut self, input: f32) -> (f32, f32) {
+        let in_phase = self.a_delayed;
+        self.a_delayed = self.a.process(input);
+        (in_phase, self.b.process(input))
+    }
+
+    fn reset(&mut self) {
+        self.a.reset();
+        self.b.reset();
+        self.a_delayed = 0.0;
+    }
+}
+
+// Bode-style frequency shifter: every partial moves by the same number of
+// Hz, up or down, rather than by the same ratio as in the pitch shifter, so
+// harmonic sounds turn metallic and inharmonic. Small shifts of a few Hz
+// give a slow phasing swirl.
+#[derive(Clone)]
+pub struct FrequencyShifter {
+    left: Hilbert,
+    right: Hilbert,
+    phase: f32,
+    increment: f32,
+    shift_hz: f32,
+    mix: f32,
+    sample_rate: f32,
+}
+
+impl FrequencyShifter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut shifter = FrequencyShifter {
+            left: Hilbert::new(),
+            right: Hilbert::new(),
+            phase: 0.0,
+            increment: 0.0,
+            shift_hz: 0.0,
+            mix: 0.5,
+            sample_rate,
+        };
+        shifter.set_shift(100.0);
+        shifter
+    }
+
+    // -2000 to 2000 Hz, negative shifts down
+    pub fn set_shift(&mut self, shift_hz: f32) {
+        self.shift_hz = shift_hz.clamp(-MAX_SHIFT_HZ, MAX_SHIFT_HZ);
+        self.increment = self.shift_hz / self.sample_rate;
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl Effect for FrequencyShifter {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (sin, cos) = (self.phase * TAU).sin_cos();
+        // A negative increment runs the carrier backwards, which shifts down
+        self.phase = (self.phase + self.increment).rem_euclid(1.0);
+        let (left_i, left_q) = self.left.process(left);
+        let (right_i, right_q) = self.right.process(right);
+        let shifted_left = left_i * cos + left_q * sin;
+        let shifted_right = right_i * cos + right_q * sin;
+        (
+            left * (1.0 - self.mix) + shifted_left * self.mix,
+            right * (1.0 - self.mix) + shifted_right * self.mix,
+        )
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+}
@@ -1,7 +1,15 @@
+// Glide mode flags. Legato: only glide into a note played while another
+// is held, fresh notes start at pitch. Constant rate: the glide time is per
+// octave, so wider intervals take proportionally longer.
+pub const GLIDE_LEGATO: u8 = 1;
+pub const GLIDE_CONSTANT_RATE: u8 = 2;
+
+#[derive(Clone)]
 pub struct Glide {
     current_freq: f32,
     target_freq: f32,
     glide_time: f32,
+    mode: u8,
     sample_rate: f32,
     increment: f32,
 }
@@ -12,6 +20,7 @@ impl Glide {
             current_freq: 440.0,
             target_freq: 440.0,
             glide_time: 0.0,
+            mode: 0,
             sample_rate,
             increment: 0.0,
         }
@@ -21,10 +30,22 @@ impl Glide {
         self.glide_time = time_ms;
     }
 
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = mode & (GLIDE_LEGATO | GLIDE_CONSTANT_RATE);
+    }
+
+    pub fn is_legato_only(&self) -> bool {
+        self.mode & GLIDE_LEGATO != 0
+    }
+
     pub fn set_target(&mut self, target_freq: f32) {
         self.target_freq = target_freq;
-        if self.glide_time > 0.0 {
-            let samples = (self.glide_time * self.sample_rate / 1000.0) as f32;
+        let mut glide_time = self.glide_time;
+        if self.mode & GLIDE_CONSTANT_RATE != 0 && self.current_freq > 0.0 {
+            glide_time *= (target_freq / self.current_freq).log2().abs();
+        }
+        if glide_time > 0.0 && self.current_freq > 0.0 {
+            let samples = glide_time * self.sample_rate / 1000.0;
             self.increment = (target_freq - self.current_freq) / samples;
         } else {
             self.current_freq = target_freq;
@@ -32,8 +53,35 @@ impl Glide {
         }
     }
 
+    // Glides to `target_freq` over `time_ms` whatever the glide time and
+    // mode are set to
+    pub fn glide_over(&mut self, target_freq: f32, time_ms: f32) {
+        if time_ms > 0.0 && self.current_freq > 0.0 {
+            self.target_freq = target_freq;
+            self.increment = (target_freq - self.current_freq) / (time_ms * self.sample_rate / 1000.0);
+        } else {
+            self.jump_to(target_freq);
+        }
+    }
+
+    // Straight to `target_freq` without gliding
+    pub fn jump_to(&mut self, target_freq: f32) {
+        self.target_freq = target_freq;
+        self.current_freq = target_freq;
+        self.increment = 0.0;
+    }
+
+    // Forgets the last pitch, so the next note starts at its own instead of
+    // gliding in
+    pub fn reset(&mut self) {
+        self.current_freq = 0.0;
+        self.target_freq = 0.0;
+        self.increment = 0.0;
+    }
+
     pub fn process(&mut self) -> f32 {
-        if (self.current_freq - self.target_freq).abs() < 0.1 {
+        // Snap once within a step so large increments can't overshoot
+        if (self.current_freq - self.target_freq).abs() <= self.increment.abs().max(0.1) {
             self.current_freq = self.target_freq;
             self.increment = 0.0;
         } else {
@@ -42,6 +90,14 @@ impl Glide {
         self.current_freq
     }
 
+    // Runs the glide on by a block of samples for block-rate control use
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        for _ in 0..samples {
+            self.process();
+        }
+        self.current_freq
+    }
+
     pub fn get_frequency(&self) -> f32 {
         self.current_freq
     }
@@ -0,0 +1,87 @@
+// Lookahead brickwall limiter for the master output. The signal is delayed by
+// the lookahead window so gain reduction can ramp in before a peak arrives.
+pub struct Limiter {
+    buffer_left: Vec<f32>,
+    buffer_right: Vec<f32>,
+    write_pos: usize,
+    ceiling: f32,
+    gain: f32,
+    held_target: f32,
+    hold_counter: usize,
+    attack_coeff: f32,
+    release_coeff: f32,
+    sample_rate: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f32, lookahead_ms: f32) -> Self {
+        let lookahead = ((lookahead_ms * sample_rate / 1000.0) as usize).max(1);
+        let mut limiter = Limiter {
+            buffer_left: vec![0.0; lookahead],
+            buffer_right: vec![0.0; lookahead],
+            write_pos: 0,
+            ceiling: 1.0,
+            gain: 1.0,
+            held_target: 1.0,
+            hold_counter: 0,
+            // Reach ~99% of the required reduction within the lookahead window
+            attack_coeff: 1.0 - (-5.0 / lookahead as f32).exp(),
+            release_coeff: 0.0,
+            sample_rate,
+        };
+        limiter.set_ceiling_db(-0.3);
+        limiter.set_release(100.0);
+        limiter
+    }
+
+    // The lookahead delay, in samples
+    pub fn latency(&self) -> usize {
+        self.buffer_left.len()
+    }
+
+    pub fn set_ceiling_db(&mut self, ceiling_db: f32) {
+        self.ceiling = 10.0_f32.powf(ceiling_db.clamp(-24.0, 0.0) / 20.0);
+    }
+
+    pub fn set_release(&mut self, release_ms: f32) {
+        let release_samples = release_ms.clamp(1.0, 2000.0) * self.sample_rate / 1000.0;
+        self.release_coeff = 1.0 - (-1.0 / release_samples).exp();
+    }
+
+    // Both channels share one gain so the stereo image doesn't shift under reduction
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let delayed_left = self.buffer_left[self.write_pos];
+        let delayed_right = self.buffer_right[self.write_pos];
+        self.buffer_left[self.write_pos] = left;
+        self.buffer_right[self.write_pos] = right;
+        self.write_pos += 1;
+        if self.write_pos >= self.buffer_left.len() {
+            self.write_pos = 0;
+        }
+
+        // Hold the strongest reduction seen until its peak has left the delay line
+        let peak = left.abs().max(right.abs());
+        let target = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+        if target <= self.held_target {
+            self.held_target = target;
+            self.hold_counter = self.buffer_left.len();
+        } else if self.hold_counter > 0 {
+            self.hold_counter -= 1;
+        } else {
+            self.held_target = target;
+        }
+
+        let coeff = if self.held_target < self.gain {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.gain += (self.held_target - self.gain) * coeff;
+
+        // The final clamp makes the ceiling a hard guarantee
+        (
+            (delayed_left * self.gain).clamp(-self.ceiling, self.ceiling),
+            (delayed_right * self.gain).clamp(-self.ceiling, self.ceiling),
+        )
+    }
+}
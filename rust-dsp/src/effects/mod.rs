@@ -3,6 +3,99 @@ pub mod reverb;
 pub mod flanger;
 pub mod tremolo;
 pub mod glide;
+pub mod bitcrusher;
+pub mod limiter;
+pub mod eq;
+pub mod convolution;
+pub mod fdn_reverb;
+pub mod rumble_filter;
+pub mod bass_mono;
+pub mod multiband_compressor;
+pub mod auto_wah;
+pub mod vintage;
+pub mod rotary;
+pub mod tape;
+pub mod pitch_shifter;
+pub mod frequency_shifter;
+pub mod soft_clipper;
+pub mod dc_blocker;
+pub mod vocoder;
+
+// Common interface for every effect that can sit in an engine's chain. All
+// effects process stereo frames so they can be placed anywhere in the order.
+pub trait Effect {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32);
+
+    // Runs a block of frames in place. Effects with control-rate work can
+    // override this to do it once per block.
+    fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.process(*l, *r);
+        }
+    }
+
+    // Drops whatever signal the effect still holds (delay lines, reverb
+    // tails), so it falls silent at once
+    fn reset(&mut self) {}
+}
+
+// Effect ids used by the reorderable chain
+pub const EFFECT_BITCRUSHER: u8 = 0;
+pub const EFFECT_FLANGER: u8 = 1;
+pub const EFFECT_TREMOLO: u8 = 2;
+pub const EFFECT_EQ: u8 = 3;
+pub const EFFECT_DELAY: u8 = 4;
+pub const EFFECT_REVERB: u8 = 5;
+pub const EFFECT_AUTO_WAH: u8 = 6;
+pub const EFFECT_ROTARY: u8 = 7;
+pub const EFFECT_PITCH_SHIFTER: u8 = 8;
+pub const EFFECT_FREQUENCY_SHIFTER: u8 = 9;
+pub const EFFECT_COUNT: usize = 10;
+
+pub const DEFAULT_EFFECT_ORDER: [u8; EFFECT_COUNT] = [
+    EFFECT_AUTO_WAH,
+    EFFECT_BITCRUSHER,
+    EFFECT_FLANGER,
+    EFFECT_TREMOLO,
+    EFFECT_EQ,
+    EFFECT_PITCH_SHIFTER,
+    EFFECT_FREQUENCY_SHIFTER,
+    EFFECT_ROTARY,
+    EFFECT_DELAY,
+    EFFECT_REVERB,
+];
+
+pub fn effect_name(id: u8) -> &'static str {
+    match id {
+        EFFECT_BITCRUSHER => "bitcrusher",
+        EFFECT_FLANGER => "flanger",
+        EFFECT_TREMOLO => "tremolo",
+        EFFECT_EQ => "eq",
+        EFFECT_DELAY => "delay",
+        EFFECT_REVERB => "reverb",
+        EFFECT_AUTO_WAH => "auto-wah",
+        EFFECT_ROTARY => "rotary",
+        EFFECT_PITCH_SHIFTER => "pitch shifter",
+        EFFECT_FREQUENCY_SHIFTER => "frequency shifter",
+        _ => "unknown",
+    }
+}
+
+// Turns a user-supplied order into a complete one: unknown and repeated ids
+// are dropped and any effect left out keeps its default relative position
+// at the end of the chain
+pub fn effect_order(order: &[u8]) -> [u8; EFFECT_COUNT] {
+    let mut result = DEFAULT_EFFECT_ORDER;
+    let mut count = 0;
+    let requested = order.iter().copied().filter(|&id| (id as usize) < EFFECT_COUNT);
+    for id in requested.chain(DEFAULT_EFFECT_ORDER) {
+        if !result[..count].contains(&id) {
+            result[count] = id;
+            count += 1;
+        }
+    }
+    result
+}
 
 // Effects will be integrated into the audio engine in future updates
 // pub use delay::Delay;
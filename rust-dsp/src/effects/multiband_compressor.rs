@@ -0,0 +1,209 @@
+use crate::biquad::Biquad;
+use crate::denormal::flush;
+use super::Effect;
+use std::f32::consts::FRAC_1_SQRT_2;
+
+pub const BAND_COUNT: usize = 3;
+
+// Ratios at full amount: downward above each band's upper threshold, upward
+// below its lower one
+const MAX_DOWNWARD_RATIO: f32 = 20.0;
+const MAX_UPWARD_RATIO: f32 = 4.0;
+// Most an upward band is lifted, and the level below which the lift fades
+// out over 20 dB so silence and hiss aren't pumped up
+const MAX_UPWARD_DB: f32 = 24.0;
+const UPWARD_FLOOR_DB: f32 = -60.0;
+// Attack and release in ms, faster for the higher bands
+const ATTACK_MS: [f32; BAND_COUNT] = [12.0, 6.0, 2.0];
+const RELEASE_MS: [f32; BAND_COUNT] = [180.0, 120.0, 70.0];
+
+// Fourth-order Linkwitz-Riley split (two Butterworth sections per side), so
+// the low and high outputs sum back to a flat allpass response
+#[derive(Clone)]
+struct LinkwitzRiley {
+    low: [Biquad; 2],
+    high: [Biquad; 2],
+}
+
+impl LinkwitzRiley {
+    fn new(sample_rate: f32) -> Self {
+        LinkwitzRiley {
+            low: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+            high: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+        }
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        for filter in &mut self.low {
+            filter.set_lowpass(freq, FRAC_1_SQRT_2);
+        }
+        for filter in &mut self.high {
+            filter.set_highpass(freq, FRAC_1_SQRT_2);
+        }
+    }
+
+    fn split(&mut self, input: f32) -> (f32, f32) {
+        let low = self.low.iter_mut().fold(input, |sample, filter| filter.process(sample));
+        let high = self.high.iter_mut().fold(input, |sample, filter| filter.process(sample));
+        (low, high)
+    }
+
+    fn reset(&mut self) {
+        for filter in self.low.iter_mut().chain(self.high.iter_mut()) {
+            filter.reset();
+        }
+    }
+}
+
+// One channel's three-way split. The low band also runs through the upper
+// crossover's allpass so all three stay in phase when summed.
+#[derive(Clone)]
+struct BandSplitter {
+    lower: LinkwitzRiley,
+    upper: LinkwitzRiley,
+    low_phase: LinkwitzRiley,
+}
+
+impl BandSplitter {
+    fn new(sample_rate: f32) -> Self {
+        BandSplitter {
+            lower: LinkwitzRiley::new(sample_rate),
+            upper: LinkwitzRiley::new(sample_rate),
+            low_phase: LinkwitzRiley::new(sample_rate),
+        }
+    }
+
+    fn set_crossovers(&mut self, low_freq: f32, high_freq: f32) {
+        self.lower.set_frequency(low_freq);
+        self.upper.set_frequency(high_freq);
+        self.low_phase.set_frequency(high_freq);
+    }
+
+    fn split(&mut self, input: f32) -> [f32; BAND_COUNT] {
+        let (low, rest) = self.lower.split(input);
+        let (mid, high) = self.upper.split(rest);
+        let (low_low, low_high) = self.low_phase.split(low);
+        [low_low + low_high, mid, high]
+    }
+
+    fn reset(&mut self) {
+        self.lower.reset();
+        self.upper.reset();
+        self.low_phase.reset();
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Band {
+    // Levels in dBFS: compressed down above `downward`, lifted below `upward`
+    downward_threshold: f32,
+    upward_threshold: f32,
+    gain_db: f32,
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+}
+
+// OTT-style three-band compressor for the master bus: each band is pushed
+// down above its upper threshold and pulled up below its lower one, which
+// evens out and thickens pads and chord stacks. `amount` scales both
+// ratios from 1:1 (no effect) to the full squash.
+#[derive(Clone)]
+pub struct MultibandCompressor {
+    left: BandSplitter,
+    right: BandSplitter,
+    bands: [Band; BAND_COUNT],
+    amount: f32,
+    low_crossover: f32,
+    high_crossover: f32,
+}
+
+impl MultibandCompressor {
+    pub fn new(sample_rate: f32) -> Self {
+        let coeff = |ms: f32| 1.0 - (-1000.0 / (ms * sample_rate)).exp();
+        let band = |i: usize| Band {
+            downward_threshold: -18.0,
+            upward_threshold: -40.0,
+            gain_db: 0.0,
+            envelope: 0.0,
+            attack_coeff: coeff(ATTACK_MS[i]),
+            release_coeff: coeff(RELEASE_MS[i]),
+        };
+        let mut compressor = MultibandCompressor {
+            left: BandSplitter::new(sample_rate),
+            right: BandSplitter::new(sample_rate),
+            bands: [band(0), band(1), band(2)],
+            amount: 0.5,
+            low_crossover: 120.0,
+            high_crossover: 2500.0,
+        };
+        compressor.set_crossovers(120.0, 2500.0);
+        compressor
+    }
+
+    // 0-1 depth of both the downward and upward compression
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount.clamp(0.0, 1.0);
+    }
+
+    // Crossovers in Hz: low 40-1000, high 500-12000 and at least an octave
+    // above the low one
+    pub fn set_crossovers(&mut self, low_freq: f32, high_freq: f32) {
+        self.low_crossover = low_freq.clamp(40.0, 1000.0);
+        self.high_crossover = high_freq.clamp(500.0, 12000.0).max(self.low_crossover * 2.0);
+        self.left.set_crossovers(self.low_crossover, self.high_crossover);
+        self.right.set_crossovers(self.low_crossover, self.high_crossover);
+    }
+
+    // Band 0 low, 1 mid, 2 high. Thresholds in dBFS (-60 to 0, the upward
+    // one no higher than the downward one), makeup gain -24 to 24 dB.
+    pub fn set_band(&mut self, band: usize, downward_threshold_db: f32, upward_threshold_db: f32, gain_db: f32) {
+        if let Some(band) = self.bands.get_mut(band) {
+            band.downward_threshold = downward_threshold_db.clamp(-60.0, 0.0);
+            band.upward_threshold = upward_threshold_db.clamp(-60.0, band.downward_threshold);
+            band.gain_db = gain_db.clamp(-24.0, 24.0);
+        }
+    }
+
+    // Gain in dB for a band at `level_db`
+    fn band_gain_db(&self, band: &Band, level_db: f32) -> f32 {
+        let downward = 1.0 - 1.0 / (1.0 + self.amount * (MAX_DOWNWARD_RATIO - 1.0));
+        let upward = 1.0 - 1.0 / (1.0 + self.amount * (MAX_UPWARD_RATIO - 1.0));
+        let mut gain = band.gain_db;
+        if level_db > band.downward_threshold {
+            gain -= (level_db - band.downward_threshold) * downward;
+        } else if level_db < band.upward_threshold {
+            let floor = ((level_db - UPWARD_FLOOR_DB) / 20.0 + 1.0).clamp(0.0, 1.0);
+            gain += ((band.upward_threshold - level_db) * upward).min(MAX_UPWARD_DB) * floor;
+        }
+        gain
+    }
+}
+
+impl Effect for MultibandCompressor {
+    // Stereo-linked per band so the image doesn't wander under compression
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left_bands = self.left.split(left);
+        let right_bands = self.right.split(right);
+        let mut out = (0.0, 0.0);
+        for i in 0..BAND_COUNT {
+            let peak = left_bands[i].abs().max(right_bands[i].abs());
+            let band = &mut self.bands[i];
+            let coeff = if peak > band.envelope { band.attack_coeff } else { band.release_coeff };
+            band.envelope = flush(band.envelope + (peak - band.envelope) * coeff);
+            let level_db = 20.0 * band.envelope.max(1e-6).log10();
+            let gain = 10.0_f32.powf(self.band_gain_db(&self.bands[i], level_db) / 20.0);
+            out.0 += left_bands[i] * gain;
+            out.1 += right_bands[i] * gain;
+        }
+        out
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+        for band in &mut self.bands {
+            band.envelope = 0.0;
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::grain_shifter::GrainShifter;
+use super::Effect;
+
+// Longer than the shimmer window: chords smear less audibly than they grain
+const WINDOW_MS: f32 = 50.0;
+
+// Pitch shifter for octave and fifth doubling: the shifted copy is blended
+// with the dry signal, so at mix 0.5 a chord plays alongside itself an
+// interval up or down.
+#[derive(Clone)]
+pub struct PitchShifter {
+    left: GrainShifter,
+    right: GrainShifter,
+    semitones: f32,
+    mix: f32,
+}
+
+impl PitchShifter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut shifter = PitchShifter {
+            left: GrainShifter::new(sample_rate, WINDOW_MS),
+            right: GrainShifter::new(sample_rate, WINDOW_MS),
+            semitones: 12.0,
+            mix: 0.5,
+        };
+        shifter.set_semitones(12.0);
+        shifter
+    }
+
+    // -12 to +12 semitones, fractional values detune
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.semitones = semitones.clamp(-12.0, 12.0);
+        let ratio = 2.0_f32.powf(self.semitones / 12.0);
+        self.left.set_ratio(ratio);
+        self.right.set_ratio(ratio);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl Effect for PitchShifter {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let shifted_left = self.left.process(left);
+        let shifted_right = self.right.process(right);
+        (
+            left * (1.0 - self.mix) + shifted_left * self.mix,
+            right * (1.0 - self.mix) + shifted_right * self.mix,
+        )
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+}
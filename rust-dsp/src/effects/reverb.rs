@@ -1,10 +1,19 @@
+use super::Effect;
+use crate::biquad::{ToneFilter, TONE_MAX_HZ, TONE_MIN_HZ};
+use crate::denormal::flush;
+
+#[derive(Clone)]
 pub struct Reverb {
     comb_filters: Vec<CombFilter>,
     allpass_filters: Vec<AllpassFilter>,
     room_size: f32,
     damping: f32,
+    mix: f32,
+    frozen: bool,
+    low_cut: ToneFilter,
 }
 
+#[derive(Clone)]
 struct CombFilter {
     buffer: Vec<f32>,
     write_pos: usize,
@@ -12,6 +21,7 @@ struct CombFilter {
     filter_state: f32,
 }
 
+#[derive(Clone)]
 struct AllpassFilter {
     buffer: Vec<f32>,
     write_pos: usize,
@@ -42,13 +52,16 @@ impl Reverb {
             allpass_filters,
             room_size: 0.5,
             damping: 0.5,
+            mix: 0.2,
+            frozen: false,
+            low_cut: ToneFilter::new(sample_rate),
         }
     }
 
     pub fn set_room_size(&mut self, size: f32) {
         self.room_size = size.clamp(0.0, 1.0);
         // Further reduced feedback to prevent distortion (0.35 to 0.5 range)
-        let feedback = self.room_size * 0.15 + 0.35;
+        let feedback = if self.frozen { 1.0 } else { self.room_size * 0.15 + 0.35 };
         for comb in &mut self.comb_filters {
             comb.set_feedback(feedback);
         }
@@ -58,12 +71,34 @@ impl Reverb {
         self.damping = damping.clamp(0.0, 1.0);
     }
 
-    pub fn process(&mut self, input: f32) -> f32 {
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    // High-pass on what goes into the tail, in Hz (20 leaves it open), so
+    // long tails don't pile up low-end mud
+    pub fn set_low_cut(&mut self, freq: f32) {
+        self.low_cut.set(freq.clamp(TONE_MIN_HZ, 1000.0), TONE_MAX_HZ);
+    }
+
+    // Frozen combs loop without loss or damping and take no new input, so
+    // the current tail sustains indefinitely
+    pub fn set_freeze(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.set_room_size(self.room_size);
+    }
+}
+
+impl Effect for Reverb {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let input = self.low_cut.process((left + right) * 0.5);
+        let input = if self.frozen { 0.0 } else { input };
+        let damping = if self.frozen { 0.0 } else { self.damping };
         let mut output = 0.0;
 
         // Process through comb filters and AVERAGE instead of sum
         for comb in &mut self.comb_filters {
-            output += comb.process(input, self.damping);
+            output += comb.process(input, damping);
         }
         output /= self.comb_filters.len() as f32; // Average the comb outputs
         output *= 0.4; // Additional gain reduction to prevent distortion
@@ -73,11 +108,24 @@ impl Reverb {
             output = allpass.process(output);
         }
 
-        // Wet/dry mix: 6% wet, 94% dry - very conservative to prevent volume spikes
-        let wet = output * 0.06; // Much more reduced wet signal
-        let dry = input * 0.94;  // More preserved dry signal
+        // The tail is trimmed so it sits at the same level as the other
+        // algorithms for a given mix - still conservative to prevent volume spikes
+        let wet = output * self.mix * 0.3;
+        let dry = 1.0 - self.mix;
         
-        wet + dry
+        // Dry signal keeps its stereo image, the tail is shared by both sides
+        (wet + left * dry, wet + right * dry)
+    }
+
+    fn reset(&mut self) {
+        for comb in &mut self.comb_filters {
+            comb.buffer.fill(0.0);
+            comb.filter_state = 0.0;
+        }
+        for allpass in &mut self.allpass_filters {
+            allpass.buffer.fill(0.0);
+        }
+        self.low_cut.reset();
     }
 }
 
@@ -103,8 +151,8 @@ impl CombFilter {
         };
 
         let delayed = self.buffer[read_pos];
-        self.filter_state = delayed * (1.0 - damping) + self.filter_state * damping;
-        let output = input + self.filter_state * self.feedback;
+        self.filter_state = flush(delayed * (1.0 - damping) + self.filter_state * damping);
+        let output = flush(input + self.filter_state * self.feedback);
         self.buffer[self.write_pos] = output;
 
         self.write_pos += 1;
@@ -134,7 +182,7 @@ impl AllpassFilter {
         let delayed = self.buffer[read_pos];
         // Further reduced allpass feedback to 0.15 for cleaner sound
         let output = delayed + input * 0.15;
-        self.buffer[self.write_pos] = input + delayed * 0.15;
+        self.buffer[self.write_pos] = flush(input + delayed * 0.15);
 
         self.write_pos += 1;
         if self.write_pos >= self.buffer.len() {
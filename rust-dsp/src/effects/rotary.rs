@@ -0,0 +1,225 @@
+use std::f32::consts::PI;
+use crate::voice::CONTROL_BLOCK;
+use super::Effect;
+
+const CROSSOVER_HZ: f32 = 800.0;
+// Rotor speeds in Hz (chorale / tremolo) for the horn and the drum
+const HORN_SLOW: f32 = 0.83;
+const HORN_FAST: f32 = 6.7;
+const DRUM_SLOW: f32 = 0.67;
+const DRUM_FAST: f32 = 5.8;
+// The heavy drum takes this many times longer than the horn to change speed
+const DRUM_INERTIA: f32 = 3.0;
+const DOPPLER_CENTER_MS: f32 = 2.0;
+const DOPPLER_DEPTH_MS: f32 = 0.35;
+
+// Rotary speaker: a crossover splits the signal into a horn (highs) and a
+// drum (lows) that spin independently. Each rotor adds amplitude modulation
+// and left/right movement; the horn also adds Doppler pitch shift.
+#[derive(Clone)]
+pub struct Rotary {
+    sample_rate: f32,
+    crossover_coeff: f32,
+    crossover_state: f32,
+    doppler_buffer: Vec<f32>,
+    write_pos: usize,
+    horn: Rotor,
+    drum: Rotor,
+    acceleration: f32,
+    balance: f32,
+    spread: f32,
+    mix: f32,
+    // What the rotors are doing to the sound at their current angles
+    modulation: Modulation,
+}
+
+#[derive(Clone)]
+struct Rotor {
+    phase: f32,
+    speed: f32,
+    target: f32,
+    ramp_coeff: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Modulation {
+    doppler_ms: f32,
+    horn_level: f32,
+    drum_level: f32,
+    horn_pan: f32,
+    drum_pan: f32,
+}
+
+impl Rotary {
+    pub fn new(sample_rate: f32) -> Self {
+        let doppler_samples = ((DOPPLER_CENTER_MS + DOPPLER_DEPTH_MS) * sample_rate / 1000.0) as usize + 2;
+        let mut rotary = Rotary {
+            sample_rate,
+            crossover_coeff: 1.0 - (-2.0 * PI * CROSSOVER_HZ / sample_rate).exp(),
+            crossover_state: 0.0,
+            doppler_buffer: vec![0.0; doppler_samples],
+            write_pos: 0,
+            horn: Rotor::new(HORN_SLOW),
+            drum: Rotor::new(DRUM_SLOW),
+            acceleration: 1.0,
+            balance: 0.5,
+            spread: 0.8,
+            mix: 1.0,
+            modulation: Modulation {
+                doppler_ms: DOPPLER_CENTER_MS,
+                horn_level: 1.0,
+                drum_level: 1.0,
+                horn_pan: 0.0,
+                drum_pan: 0.0,
+            },
+        };
+        // Start the drum a quarter turn away so the rotors don't line up
+        rotary.drum.phase = 0.25;
+        rotary.set_acceleration(1.0);
+        rotary.modulation = rotary.rotor_modulation();
+        rotary
+    }
+
+    // Switches between chorale (slow) and tremolo (fast); the rotors ramp
+    // to the new speed over the acceleration time
+    pub fn set_fast(&mut self, fast: bool) {
+        self.horn.target = if fast { HORN_FAST } else { HORN_SLOW };
+        self.drum.target = if fast { DRUM_FAST } else { DRUM_SLOW };
+    }
+
+    // Time in seconds for the horn to (mostly) reach a new speed, 0.1 to 10
+    pub fn set_acceleration(&mut self, seconds: f32) {
+        self.acceleration = seconds.clamp(0.1, 10.0);
+        let horn_samples = self.acceleration * self.sample_rate / 3.0;
+        self.horn.ramp_coeff = 1.0 - (-1.0 / horn_samples).exp();
+        self.drum.ramp_coeff = 1.0 - (-1.0 / (horn_samples * DRUM_INERTIA)).exp();
+    }
+
+    // 0 = drum only, 1 = horn only
+    pub fn set_balance(&mut self, balance: f32) {
+        self.balance = balance.clamp(0.0, 1.0);
+    }
+
+    // How far the rotors swing across the stereo field
+    pub fn set_spread(&mut self, spread: f32) {
+        self.spread = spread.clamp(0.0, 1.0);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    fn doppler_read(&self, delay_ms: f32) -> f32 {
+        let len = self.doppler_buffer.len();
+        let delay = (delay_ms * self.sample_rate / 1000.0).clamp(1.0, (len - 2) as f32);
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = self.doppler_buffer[(self.write_pos + len - whole) % len];
+        let b = self.doppler_buffer[(self.write_pos + len - whole - 1) % len];
+        a + (b - a) * frac
+    }
+
+    fn rotor_modulation(&self) -> Modulation {
+        let horn_angle = self.horn.angle();
+        let drum_angle = self.drum.angle();
+        Modulation {
+            doppler_ms: DOPPLER_CENTER_MS + DOPPLER_DEPTH_MS * horn_angle.sin(),
+            horn_level: 1.0 - 0.35 * (1.0 - horn_angle.cos()) * 0.5,
+            drum_level: 1.0 - 0.2 * (1.0 - drum_angle.cos()) * 0.5,
+            horn_pan: horn_angle.sin() * self.spread,
+            drum_pan: drum_angle.sin() * self.spread * 0.5,
+        }
+    }
+
+    fn tick(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let input = (left + right) * 0.5;
+        let m = self.modulation;
+
+        // Crossover: lows to the drum, the remainder to the horn
+        self.crossover_state += (input - self.crossover_state) * self.crossover_coeff;
+        let low = self.crossover_state;
+        let high = input - low;
+
+        // Horn: Doppler from the modulated delay, plus facing/away amplitude
+        self.doppler_buffer[self.write_pos] = high;
+        self.write_pos = (self.write_pos + 1) % self.doppler_buffer.len();
+        let horn = self.doppler_read(m.doppler_ms) * m.horn_level;
+        let drum = low * m.drum_level;
+
+        let horn_gain = self.balance * 2.0_f32.min(1.0 / self.balance.max(0.5));
+        let drum_gain = (1.0 - self.balance) * 2.0_f32.min(1.0 / (1.0 - self.balance).max(0.5));
+
+        let wet_left = horn * horn_gain * (1.0 - m.horn_pan) * 0.5 + drum * drum_gain * (1.0 - m.drum_pan) * 0.5;
+        let wet_right = horn * horn_gain * (1.0 + m.horn_pan) * 0.5 + drum * drum_gain * (1.0 + m.drum_pan) * 0.5;
+
+        let dry = 1.0 - self.mix;
+        (left * dry + wet_left * self.mix, right * dry + wet_right * self.mix)
+    }
+}
+
+impl Effect for Rotary {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.horn.advance(1, self.sample_rate);
+        self.drum.advance(1, self.sample_rate);
+        self.modulation = self.rotor_modulation();
+        self.tick(left, right)
+    }
+
+    // The rotors move once per control block and the modulation they cause
+    // is ramped across the block
+    fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (left, right) in left.chunks_mut(CONTROL_BLOCK).zip(right.chunks_mut(CONTROL_BLOCK)) {
+            self.horn.advance(left.len(), self.sample_rate);
+            self.drum.advance(left.len(), self.sample_rate);
+            let from = self.modulation;
+            let to = self.rotor_modulation();
+            let steps = left.len() as f32;
+            for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+                self.modulation = from.lerp(&to, (i + 1) as f32 / steps);
+                (*l, *r) = self.tick(*l, *r);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.doppler_buffer.fill(0.0);
+        self.crossover_state = 0.0;
+    }
+}
+
+impl Rotor {
+    fn new(speed: f32) -> Self {
+        Rotor {
+            phase: 0.0,
+            speed,
+            target: speed,
+            ramp_coeff: 0.0,
+        }
+    }
+
+    // Ramps the speed towards its target over `samples` and turns the rotor
+    // by as much
+    fn advance(&mut self, samples: usize, sample_rate: f32) {
+        let ramp = 1.0 - (1.0 - self.ramp_coeff).powi(samples as i32);
+        self.speed += (self.target - self.speed) * ramp;
+        self.phase = (self.phase + self.speed * samples as f32 / sample_rate).fract();
+    }
+
+    // Rotor angle in radians
+    fn angle(&self) -> f32 {
+        self.phase * 2.0 * PI
+    }
+}
+
+impl Modulation {
+    fn lerp(&self, to: &Modulation, t: f32) -> Modulation {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Modulation {
+            doppler_ms: mix(self.doppler_ms, to.doppler_ms),
+            horn_level: mix(self.horn_level, to.horn_level),
+            drum_level: mix(self.drum_level, to.drum_level),
+            horn_pan: mix(self.horn_pan, to.horn_pan),
+            drum_pan: mix(self.drum_pan, to.drum_pan),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::biquad::Biquad;
+use super::Effect;
+
+// 12 dB/octave Butterworth high-pass that clears low-end build-up from a
+// track before it reaches the rest of its chain
+#[derive(Clone)]
+pub struct RumbleFilter {
+    filters: [Biquad; 2],
+}
+
+impl RumbleFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut rumble = RumbleFilter {
+            filters: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+        };
+        rumble.set_cutoff(80.0);
+        rumble
+    }
+
+    pub fn set_cutoff(&mut self, freq: f32) {
+        for filter in &mut self.filters {
+            filter.set_highpass(freq.clamp(20.0, 300.0), std::f32::consts::FRAC_1_SQRT_2);
+        }
+    }
+}
+
+impl Effect for RumbleFilter {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (self.filters[0].process(left), self.filters[1].process(right))
+    }
+
+    fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Final master stage: a tanh soft-clipper that rounds off anything the
+// limiter lets through (or everything, with the limiter off) instead of
+// leaving the browser to hard-clip it.
+//
+//   y = sign(x) * tanh(|x|^k)^(1/k)
+//
+// k = 1 is a plain tanh, which starts bending early; higher k keeps the
+// curve linear for longer and turns the knee sharper close to full scale.
+use crate::oversampler::Oversampler;
+
+pub struct SoftClipper {
+    input_gain: f32,
+    knee: f32,
+    oversamplers: [Oversampler; 2],
+}
+
+impl SoftClipper {
+    pub fn new() -> Self {
+        SoftClipper {
+            input_gain: 1.0,
+            knee: 1.0,
+            oversamplers: [Oversampler::new(), Oversampler::new()],
+        }
+    }
+
+    // Drive into the curve, 0 to +24 dB
+    pub fn set_input_gain_db(&mut self, gain_db: f32) {
+        self.input_gain = 10.0_f32.powf(gain_db.clamp(0.0, 24.0) / 20.0);
+    }
+
+    // 0 = gentle tanh, 1 = hard knee just below full scale
+    pub fn set_shape(&mut self, shape: f32) {
+        self.knee = 1.0 + shape.clamp(0.0, 1.0) * 7.0;
+    }
+
+    // 1, 2 or 4 times oversampled clipping
+    pub fn set_oversampling(&mut self, factor: u32) {
+        for oversampler in &mut self.oversamplers {
+            oversampler.set_factor(factor);
+        }
+    }
+
+    // In samples, from oversampling
+    pub fn latency(&self) -> f32 {
+        self.oversamplers[0].latency()
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (input_gain, knee) = (self.input_gain, self.knee);
+        let clip = |x: f32| {
+            let x = x * input_gain;
+            let magnitude = x.abs().powf(knee).tanh().powf(1.0 / knee);
+            magnitude.copysign(x)
+        };
+        (self.oversamplers[0].process(left, clip), self.oversamplers[1].process(right, clip))
+    }
+}
@@ -0,0 +1,146 @@
+use std::f32::consts::PI;
+use super::Effect;
+use crate::biquad::Biquad;
+use crate::oversampler::Oversampler;
+
+const BASE_DELAY_MS: f32 = 5.0;
+const WOW_RATE: f32 = 0.6;
+const WOW_DEPTH_MS: f32 = 1.2;
+const FLUTTER_RATE: f32 = 7.3;
+const FLUTTER_DEPTH_MS: f32 = 0.08;
+// Slow random drift on top of the wow, as the speed of a real transport wanders
+const DRIFT_DEPTH_MS: f32 = 0.4;
+// Small bias makes the saturation asymmetric for some even harmonics
+const BIAS: f32 = 0.15;
+
+// Tape emulation for the master bus: soft saturation, a gentle high
+// roll-off and wow/flutter from a modulated delay shared by both channels.
+pub struct Tape {
+    sample_rate: f32,
+    drive: f32,
+    wow_flutter: f32,
+    rolloff: [Biquad; 2],
+    oversamplers: [Oversampler; 2],
+    buffer_left: Vec<f32>,
+    buffer_right: Vec<f32>,
+    write_pos: usize,
+    wow_phase: f32,
+    flutter_phase: f32,
+    drift: f32,
+    drift_target: f32,
+    drift_counter: usize,
+    seed: u32,
+}
+
+impl Tape {
+    pub fn new(sample_rate: f32) -> Self {
+        let max_delay_ms = BASE_DELAY_MS + WOW_DEPTH_MS + FLUTTER_DEPTH_MS + DRIFT_DEPTH_MS;
+        let buffer_size = (max_delay_ms * sample_rate / 1000.0) as usize + 4;
+        let mut tape = Tape {
+            sample_rate,
+            drive: 0.3,
+            wow_flutter: 0.3,
+            rolloff: [Biquad::new(sample_rate), Biquad::new(sample_rate)],
+            oversamplers: [Oversampler::new(), Oversampler::new()],
+            buffer_left: vec![0.0; buffer_size],
+            buffer_right: vec![0.0; buffer_size],
+            write_pos: 0,
+            wow_phase: 0.0,
+            flutter_phase: 0.0,
+            drift: 0.0,
+            drift_target: 0.0,
+            drift_counter: 0,
+            seed: 0x9E37_79B9,
+        };
+        tape.set_rolloff(12000.0);
+        tape
+    }
+
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+    }
+
+    // Amount of pitch wobble, 0-1
+    pub fn set_wow_flutter(&mut self, amount: f32) {
+        self.wow_flutter = amount.clamp(0.0, 1.0);
+    }
+
+    // High roll-off corner, 2-20 kHz
+    pub fn set_rolloff(&mut self, freq: f32) {
+        for filter in &mut self.rolloff {
+            filter.set_lowpass(freq.clamp(2000.0, 20000.0), std::f32::consts::FRAC_1_SQRT_2);
+        }
+    }
+
+    // 1, 2 or 4 times oversampled saturation
+    pub fn set_oversampling(&mut self, factor: u32) {
+        for oversampler in &mut self.oversamplers {
+            oversampler.set_factor(factor);
+        }
+    }
+
+    // The centre of the wow/flutter delay plus oversampling, in samples
+    pub fn latency(&self) -> f32 {
+        BASE_DELAY_MS * self.sample_rate / 1000.0 + self.oversamplers[0].latency()
+    }
+
+    fn saturate(&mut self, channel: usize, sample: f32) -> f32 {
+        let gain = 1.0 + self.drive * 4.0;
+        self.oversamplers[channel].process(sample, |x| ((x * gain + BIAS).tanh() - BIAS.tanh()) / gain)
+    }
+
+    // xorshift32 for the drift targets
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    fn delay_ms(&mut self) -> f32 {
+        self.wow_phase = (self.wow_phase + WOW_RATE / self.sample_rate).fract();
+        self.flutter_phase = (self.flutter_phase + FLUTTER_RATE / self.sample_rate).fract();
+
+        // New drift target every quarter second, approached smoothly
+        if self.drift_counter == 0 {
+            self.drift_target = self.random();
+            self.drift_counter = (self.sample_rate * 0.25) as usize;
+        }
+        self.drift_counter -= 1;
+        self.drift += (self.drift_target - self.drift) * (2.0 / self.sample_rate);
+
+        let wobble = (self.wow_phase * 2.0 * PI).sin() * WOW_DEPTH_MS
+            + (self.flutter_phase * 2.0 * PI).sin() * FLUTTER_DEPTH_MS
+            + self.drift * DRIFT_DEPTH_MS;
+        BASE_DELAY_MS + wobble * self.wow_flutter
+    }
+
+    fn read(buffer: &[f32], write_pos: usize, delay: f32) -> f32 {
+        let len = buffer.len();
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = buffer[(write_pos + len - whole) % len];
+        let b = buffer[(write_pos + len - whole - 1) % len];
+        a + (b - a) * frac
+    }
+}
+
+impl Effect for Tape {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left = self.saturate(0, left);
+        let right = self.saturate(1, right);
+        let left = self.rolloff[0].process(left);
+        let right = self.rolloff[1].process(right);
+
+        self.buffer_left[self.write_pos] = left;
+        self.buffer_right[self.write_pos] = right;
+        let delay = (self.delay_ms() * self.sample_rate / 1000.0)
+            .clamp(1.0, (self.buffer_left.len() - 2) as f32);
+        let output = (
+            Self::read(&self.buffer_left, self.write_pos, delay),
+            Self::read(&self.buffer_right, self.write_pos, delay),
+        );
+        self.write_pos = (self.write_pos + 1) % self.buffer_left.len();
+        output
+    }
+}
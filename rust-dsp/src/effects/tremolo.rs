@@ -1,19 +1,35 @@
 use crate::lfo::Lfo;
+use crate::pan::PanLaw;
+use crate::voice::CONTROL_BLOCK;
+use super::Effect;
 
+#[derive(Clone)]
 pub struct Tremolo {
     lfo: Lfo,
     depth: f32,
     rate: f32,
+    mix: f32,
+    // Left and right swing half a cycle apart, panning instead of pulsing
+    auto_pan: bool,
+    pan_law: PanLaw,
+    // Left and right gains at the current LFO position, before the mix
+    gains: (f32, f32),
 }
 
 impl Tremolo {
     pub fn new(sample_rate: f32) -> Self {
         let mut lfo = Lfo::new(sample_rate);
         lfo.set_rate(5.0);
+        // Full swing; depth is applied to the gain, not the LFO
+        lfo.set_depth(1.0);
         Tremolo {
             lfo,
             depth: 0.5,
             rate: 5.0,
+            mix: 1.0,
+            auto_pan: false,
+            pan_law: PanLaw::EqualPower,
+            gains: (1.0, 1.0),
         }
     }
 
@@ -24,13 +40,60 @@ impl Tremolo {
 
     pub fn set_depth(&mut self, depth: f32) {
         self.depth = depth.clamp(0.0, 1.0);
-        self.lfo.set_depth(depth);
     }
 
-    pub fn process(&mut self, input: f32) -> f32 {
-        let lfo_value = self.lfo.process();
-        let modulation = 1.0 - (lfo_value * 0.5 + 0.5) * self.depth;
-        input * modulation
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    // 0 = sine, 1 = triangle, 2 = square
+    pub fn set_waveform(&mut self, waveform: u8) {
+        self.lfo.set_waveform(waveform.min(2));
+    }
+
+    pub fn set_auto_pan(&mut self, enabled: bool) {
+        self.auto_pan = enabled;
+    }
+
+    pub fn set_pan_law(&mut self, law: PanLaw) {
+        self.pan_law = law;
+    }
+
+    fn lfo_gains(&self, lfo_value: f32) -> (f32, f32) {
+        if self.auto_pan {
+            // Sweeps the source across the field, as far as the depth goes
+            return self.pan_law.gains(lfo_value * self.depth);
+        }
+        let gain = 1.0 - (lfo_value * 0.5 + 0.5) * self.depth;
+        (gain, gain)
+    }
+
+    fn apply(&self, left: f32, right: f32) -> (f32, f32) {
+        let dry = 1.0 - self.mix;
+        (left * (dry + self.gains.0 * self.mix), right * (dry + self.gains.1 * self.mix))
     }
 }
 
+impl Effect for Tremolo {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let lfo_value = self.lfo.process();
+        self.gains = self.lfo_gains(lfo_value);
+        self.apply(left, right)
+    }
+
+    // The LFO is read once per control block and the gains ramped to it
+    // across the block
+    fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (left, right) in left.chunks_mut(CONTROL_BLOCK).zip(right.chunks_mut(CONTROL_BLOCK)) {
+            self.lfo.advance(left.len());
+            let (target_left, target_right) = self.lfo_gains(self.lfo.value());
+            let steps = left.len() as f32;
+            let step = ((target_left - self.gains.0) / steps, (target_right - self.gains.1) / steps);
+            for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                self.gains.0 += step.0;
+                self.gains.1 += step.1;
+                (*l, *r) = self.apply(*l, *r);
+            }
+        }
+    }
+}
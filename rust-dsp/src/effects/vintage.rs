@@ -0,0 +1,78 @@
+use super::Effect;
+use crate::oversampler::Oversampler;
+
+// "Vintage" master colour: a very low-level hiss bed plus gentle tape-style
+// saturation, so the output feels less sterile. Small signals pass at unity;
+// only peaks are rounded off.
+pub struct Vintage {
+    noise_level: f32,
+    drive: f32,
+    hiss_coeff: f32,
+    hiss_state: [f32; 2],
+    seed: u32,
+    oversamplers: [Oversampler; 2],
+}
+
+impl Vintage {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut vintage = Vintage {
+            noise_level: 0.0,
+            drive: 0.3,
+            // One-pole low-pass around 6 kHz takes the fizz off the white noise
+            hiss_coeff: 1.0 - (-2.0 * std::f32::consts::PI * 6000.0 / sample_rate).exp(),
+            hiss_state: [0.0; 2],
+            seed: 0x1234_5678,
+            oversamplers: [Oversampler::new(), Oversampler::new()],
+        };
+        vintage.set_noise_level_db(-72.0);
+        vintage
+    }
+
+    // Hiss level in dBFS, -96 to -48
+    pub fn set_noise_level_db(&mut self, level_db: f32) {
+        self.noise_level = 10.0_f32.powf(level_db.clamp(-96.0, -48.0) / 20.0);
+    }
+
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+    }
+
+    // xorshift32: cheap, and independent of the other random sources
+    fn white_noise(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    // 1, 2 or 4 times oversampled saturation
+    pub fn set_oversampling(&mut self, factor: u32) {
+        for oversampler in &mut self.oversamplers {
+            oversampler.set_factor(factor);
+        }
+    }
+
+    // In samples, from oversampling
+    pub fn latency(&self) -> f32 {
+        self.oversamplers[0].latency()
+    }
+
+    fn saturate(&mut self, channel: usize, sample: f32) -> f32 {
+        let gain = 1.0 + self.drive * 3.0;
+        self.oversamplers[channel].process(sample, |x| (x * gain).tanh() / gain)
+    }
+}
+
+impl Effect for Vintage {
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let noise_left = self.white_noise();
+        let noise_right = self.white_noise();
+        self.hiss_state[0] += (noise_left - self.hiss_state[0]) * self.hiss_coeff;
+        self.hiss_state[1] += (noise_right - self.hiss_state[1]) * self.hiss_coeff;
+
+        (
+            self.saturate(0, left) + self.hiss_state[0] * self.noise_level,
+            self.saturate(1, right) + self.hiss_state[1] * self.noise_level,
+        )
+    }
+}
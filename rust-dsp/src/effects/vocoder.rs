@@ -0,0 +1,119 @@
+use crate::biquad::Biquad;
+use crate::denormal::flush;
+
+const LOWEST_BAND: f32 = 120.0;
+const HIGHEST_BAND: f32 = 7000.0;
+const MIN_BANDS: usize = 4;
+const MAX_BANDS: usize = 32;
+const ATTACK_SECONDS: f32 = 0.002;
+// Brings the band envelopes of a speaking-level input back up to roughly
+// the carrier's own level. Scaled by the square root of the band count,
+// since a broadband input spreads its level over every band.
+const MAKEUP_GAIN: f32 = 20.0;
+const SIBILANCE_FREQ: f32 = 5000.0;
+
+// Each band filters the modulator and carrier through matching fourth-order
+// band-passes; the modulator band's envelope sets the carrier band's level
+#[derive(Clone)]
+struct Band {
+    modulator: [Biquad; 2],
+    carrier: [Biquad; 2],
+    envelope: f32,
+}
+
+impl Band {
+    fn new(sample_rate: f32, freq: f32, q: f32) -> Self {
+        let filter = || {
+            let mut filter = Biquad::new(sample_rate);
+            filter.set_bandpass(freq, q);
+            filter
+        };
+        Band { modulator: [filter(), filter()], carrier: [filter(), filter()], envelope: 0.0 }
+    }
+}
+
+// Channel vocoder: the spectral envelope of the modulator (external audio,
+// usually a voice) is imposed on the carrier (the engine's voices), so held
+// chords "sing" what's spoken. High sibilants are mostly noise the carrier
+// can't reproduce, so a high-passed copy of the modulator can be mixed back in
+// to keep consonants intelligible.
+#[derive(Clone)]
+pub struct Vocoder {
+    sample_rate: f32,
+    bands: Vec<Band>,
+    gain: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    sibilance_filter: Biquad,
+    sibilance: f32,
+}
+
+impl Vocoder {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut sibilance_filter = Biquad::new(sample_rate);
+        sibilance_filter.set_highpass(SIBILANCE_FREQ, std::f32::consts::FRAC_1_SQRT_2);
+        let mut vocoder = Vocoder {
+            sample_rate,
+            bands: Vec::with_capacity(MAX_BANDS),
+            gain: 0.0,
+            attack_coeff: 1.0 - (-1.0 / (ATTACK_SECONDS * sample_rate)).exp(),
+            release_coeff: 0.0,
+            sibilance_filter,
+            sibilance: 0.0,
+        };
+        vocoder.set_bands(16);
+        vocoder.set_release(30.0);
+        vocoder
+    }
+
+    // Number of bands (4-32), spaced evenly in pitch from 120 Hz to 7 kHz.
+    // More bands follow the voice more closely.
+    pub fn set_bands(&mut self, count: usize) {
+        let count = count.clamp(MIN_BANDS, MAX_BANDS);
+        if count == self.bands.len() {
+            return;
+        }
+        let ratio = (HIGHEST_BAND / LOWEST_BAND).powf(1.0 / (count - 1) as f32);
+        // Neighbouring bands cross about where each falls 3 dB
+        let q = ratio.sqrt() / (ratio - 1.0);
+        self.bands.clear();
+        for i in 0..count {
+            let freq = LOWEST_BAND * ratio.powi(i as i32);
+            self.bands.push(Band::new(self.sample_rate, freq, q));
+        }
+        self.gain = MAKEUP_GAIN * (count as f32).sqrt();
+    }
+
+    // How long each band takes to fall silent once the modulator stops
+    pub fn set_release(&mut self, release_ms: f32) {
+        let seconds = release_ms.clamp(5.0, 500.0) / 1000.0;
+        self.release_coeff = 1.0 - (-1.0 / (seconds * self.sample_rate)).exp();
+    }
+
+    // 0-1 of the high-passed modulator mixed into the output
+    pub fn set_sibilance(&mut self, amount: f32) {
+        self.sibilance = amount.clamp(0.0, 1.0);
+    }
+
+    pub fn process(&mut self, carrier: f32, modulator: f32) -> f32 {
+        let mut output = 0.0;
+        for band in &mut self.bands {
+            let level = band.modulator.iter_mut().fold(modulator, |x, filter| filter.process(x)).abs();
+            let coeff = if level > band.envelope { self.attack_coeff } else { self.release_coeff };
+            band.envelope = flush(band.envelope + (level - band.envelope) * coeff);
+            output += band.carrier.iter_mut().fold(carrier, |x, filter| filter.process(x)) * band.envelope;
+        }
+        let sibilance = self.sibilance_filter.process(modulator) * self.sibilance;
+        output * self.gain + sibilance
+    }
+
+    pub fn reset(&mut self) {
+        for band in &mut self.bands {
+            for filter in band.modulator.iter_mut().chain(&mut band.carrier) {
+                filter.reset();
+            }
+            band.envelope = 0.0;
+        }
+        self.sibilance_filter.reset();
+    }
+}
@@ -0,0 +1,118 @@
+// Outgoing queue of engine activity for the UI: notes starting and ending,
+// voices stolen, patterns looping and outputs clipping, so keyboards and
+// clip lights can be driven from one drain per animation frame instead of
+// polling voice states. Storage is allocated up front; once the queue is
+// full, further events are dropped and counted until the UI drains it.
+//
+// Each event drains as EVENT_WORDS f64 words:
+//   [kind, engine, a, b, sample_time]
+// with the EVENT_* kinds below, engine -1 for the master output, and
+// sample_time the frame the render segment that raised it began at.
+
+pub const EVENT_QUEUE_CAPACITY: usize = 1024;
+pub const EVENT_WORDS: usize = 5;
+// Events one engine can hold between collections, which happen every
+// render segment
+const ENGINE_EVENT_CAPACITY: usize = 128;
+
+// a: note, b: velocity
+pub const EVENT_NOTE_STARTED: u32 = 0;
+// a: note
+pub const EVENT_NOTE_ENDED: u32 = 1;
+// a: note cut off, b: note taking its voice
+pub const EVENT_VOICE_STOLEN: u32 = 2;
+// a: pattern starting over
+pub const EVENT_PATTERN_LOOPED: u32 = 3;
+// a: peak level; raised once as an output reaches full scale, then not
+// again until its meter's peak has fallen back below it
+pub const EVENT_CLIP: u32 = 4;
+
+#[derive(Clone, Copy)]
+pub struct EngineEvent {
+    pub kind: u32,
+    pub a: f32,
+    pub b: f32,
+}
+
+// What a single engine raised since the audio engine last collected from
+// it. Capped, so an engine nobody collects from (the outgoing side of a
+// hot swap) stays bounded.
+#[derive(Clone)]
+pub struct EngineEvents {
+    events: Vec<EngineEvent>,
+}
+
+impl EngineEvents {
+    pub fn new() -> Self {
+        EngineEvents {
+            events: Vec::with_capacity(ENGINE_EVENT_CAPACITY),
+        }
+    }
+
+    pub fn push(&mut self, kind: u32, a: f32, b: f32) {
+        if self.events.len() < ENGINE_EVENT_CAPACITY {
+            self.events.push(EngineEvent { kind, a, b });
+        }
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, EngineEvent> {
+        self.events.drain(..)
+    }
+}
+
+pub struct EventQueue {
+    enabled: bool,
+    words: Vec<f64>,
+    dropped: u32,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue {
+            enabled: true,
+            words: Vec::with_capacity(EVENT_QUEUE_CAPACITY * EVENT_WORDS),
+            dropped: 0,
+        }
+    }
+
+    // Disabling empties the queue and stops collecting
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.words.clear();
+            self.dropped = 0;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // `engine` is None for the master output
+    pub fn push(&mut self, engine: Option<u32>, sample_time: u64, event: EngineEvent) {
+        if !self.enabled {
+            return;
+        }
+        if self.words.len() >= EVENT_QUEUE_CAPACITY * EVENT_WORDS {
+            self.dropped = self.dropped.saturating_add(1);
+            return;
+        }
+        let engine = engine.map_or(-1.0, |e| e as f64);
+        self.words.extend([event.kind as f64, engine, event.a as f64, event.b as f64, sample_time as f64]);
+    }
+
+    // Everything queued, oldest first, keeping the storage for reuse
+    pub fn drain(&mut self) -> Vec<f64> {
+        self.dropped = 0;
+        self.words.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len() / EVENT_WORDS
+    }
+
+    // Events lost to a full queue since the last drain
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
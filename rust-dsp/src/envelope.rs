@@ -1,3 +1,9 @@
+// What a note-on does to an envelope that is still sounding: restart from
+// silence, attack again from the current level, or carry on (legato)
+pub const RETRIGGER_FULL: u8 = 0;
+pub const RETRIGGER_SOFT: u8 = 1;
+pub const RETRIGGER_LEGATO: u8 = 2;
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum EnvelopeStage {
     Idle,
@@ -7,6 +13,7 @@ pub enum EnvelopeStage {
     Release,
 }
 
+#[derive(Clone)]
 pub struct Envelope {
     stage: EnvelopeStage,
     value: f32,
@@ -57,12 +64,22 @@ impl Envelope {
     }
 
     pub fn gate_off(&mut self) {
+        self.release_over(self.release_time);
+    }
+
+    // Note off with the set release time multiplied by `scale`
+    pub fn gate_off_scaled(&mut self, scale: f32) {
+        self.release_over(self.release_time * scale);
+    }
+
+    // Release over `seconds` instead of the set release time
+    pub fn release_over(&mut self, seconds: f32) {
         if self.stage != EnvelopeStage::Idle {
             // Store current value when starting release
             self.release_start_value = self.value;
             
             // Calculate release increment from current value to 0
-            let release_samples = (self.release_time * self.sample_rate).max(1.0);
+            let release_samples = (seconds * self.sample_rate).max(1.0);
             self.release_increment = self.release_start_value / release_samples;
             
             self.stage = EnvelopeStage::Release;
@@ -102,8 +119,40 @@ impl Envelope {
         self.value
     }
 
+    // Runs the envelope on by a block of samples for block-rate control use
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        for _ in 0..samples {
+            self.process();
+        }
+        self.value
+    }
+
+    // Straight to silence, for a fresh start
+    pub fn reset(&mut self) {
+        self.stage = EnvelopeStage::Idle;
+        self.value = 0.0;
+    }
+
     pub fn is_active(&self) -> bool {
         self.stage != EnvelopeStage::Idle
     }
+
+    // Note still down: attack, decay or sustain
+    pub fn is_held(&self) -> bool {
+        self.is_active() && !self.is_releasing()
+    }
+
+    pub fn is_releasing(&self) -> bool {
+        self.stage == EnvelopeStage::Release
+    }
+
+    pub fn stage(&self) -> EnvelopeStage {
+        self.stage
+    }
+
+    // Current output, 0-1
+    pub fn level(&self) -> f32 {
+        self.value
+    }
 }
 
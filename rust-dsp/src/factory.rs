@@ -0,0 +1,289 @@
+// Compiled-in factory bank so every engine sounds usable without the UI
+// supplying each parameter. Patches are built on top of `Patch::default()`
+// so parameters added later simply keep their defaults here.
+
+use crate::patch::Patch;
+
+pub const FACTORY_PATCH_COUNT: usize = 32;
+
+const NAMES: [&str; FACTORY_PATCH_COUNT] = [
+    // Electric pianos
+    "Tine EP", "Dyno EP", "Suitcase EP", "Wurly Drive",
+    // Organs
+    "Full Drawbar", "Gospel Organ", "Jazz Organ", "Cathedral",
+    // Strings
+    "String Section", "Slow Strings", "Chamber Strings", "Pizzicato",
+    // Brass
+    "Brass Stab", "Soft Brass", "Synth Horns", "Brass Swell",
+    // Plucks
+    "Basic Pluck", "Bell Pluck", "Crushed Pluck", "Harp Pluck",
+    // Basses
+    "Sub Bass", "Saw Bass", "Square Bass", "Acid Bass",
+    // Pads
+    "Warm Pad", "Glass Pad", "Dark Pad", "Choir Pad",
+    // Keys and leads
+    "Grand Piano", "FM Keys", "Mono Lead", "Chord Memory",
+];
+
+pub fn factory_patch_name(id: u8) -> Option<&'static str> {
+    NAMES.get(id as usize).copied()
+}
+
+pub fn factory_patch(id: u8) -> Option<Patch> {
+    let base = Patch::default();
+    let patch = match id {
+        // ---- Electric pianos ----
+        0 => Patch {
+            waveform: 7,
+            attack: 0.002, decay: 1.2, sustain: 0.35, release: 0.4,
+            tremolo_enabled: true, tremolo_rate: 4.5, tremolo_depth: 0.25, tremolo_mix: 0.6,
+            reverb_enabled: true, reverb_room_size: 0.4, reverb_mix: 0.15,
+            ..base
+        },
+        1 => Patch {
+            waveform: 7,
+            attack: 0.001, decay: 0.8, sustain: 0.3, release: 0.3,
+            eq_enabled: true, eq_mid_gain_db: 3.0, eq_mid_freq: 1500.0, eq_high_gain_db: 2.0,
+            flanger_enabled: true, flanger_rate: 0.3, flanger_depth: 2.0, flanger_feedback: 0.1, flanger_mix: 0.25,
+            ..base
+        },
+        2 => Patch {
+            waveform: 7,
+            attack: 0.003, decay: 1.5, sustain: 0.4, release: 0.5,
+            tremolo_enabled: true, tremolo_rate: 5.5, tremolo_depth: 0.5, tremolo_mix: 1.0,
+            filter_enabled: true, filter_cutoff: 5000.0,
+            ..base
+        },
+        3 => Patch {
+            waveform: 2,
+            attack: 0.002, decay: 0.9, sustain: 0.3, release: 0.25,
+            filter_enabled: true, filter_cutoff: 2500.0, filter_resonance: 0.2,
+            bitcrusher_enabled: true, bitcrusher_bits: 12.0, bitcrusher_downsample: 1.0, bitcrusher_mix: 0.3,
+            tremolo_enabled: true, tremolo_rate: 6.0, tremolo_depth: 0.3,
+            ..base
+        },
+        // ---- Organs ----
+        4 => Patch {
+            waveform: 6,
+            attack: 0.005, decay: 0.1, sustain: 1.0, release: 0.05,
+            organ_drawbars: [8.0, 8.0, 8.0, 8.0, 8.0, 8.0, 8.0, 8.0, 8.0],
+            rotary_enabled: true, rotary_fast: false,
+            ..base
+        },
+        5 => Patch {
+            waveform: 6,
+            attack: 0.005, decay: 0.1, sustain: 1.0, release: 0.08,
+            organ_drawbars: [8.0, 8.0, 8.0, 8.0, 0.0, 0.0, 0.0, 0.0, 8.0],
+            organ_percussion: true, organ_percussion_harmonic: 3, organ_key_click: 0.4,
+            rotary_enabled: true, rotary_fast: true, rotary_acceleration: 1.5,
+            reverb_enabled: true, reverb_room_size: 0.5, reverb_mix: 0.15,
+            ..base
+        },
+        6 => Patch {
+            waveform: 6,
+            attack: 0.004, decay: 0.1, sustain: 1.0, release: 0.05,
+            organ_drawbars: [8.0, 8.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            organ_percussion: true, organ_percussion_harmonic: 3, organ_percussion_fast: true,
+            rotary_enabled: true, rotary_balance: 0.4, rotary_spread: 0.6,
+            ..base
+        },
+        7 => Patch {
+            waveform: 6,
+            attack: 0.08, decay: 0.2, sustain: 1.0, release: 1.2,
+            organ_drawbars: [8.0, 6.0, 8.0, 6.0, 4.0, 5.0, 0.0, 3.0, 4.0],
+            organ_key_click: 0.0,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.9, reverb_mix: 0.4,
+            ..base
+        },
+        // ---- Strings ----
+        8 => Patch {
+            waveform: 1,
+            attack: 0.25, decay: 0.4, sustain: 0.85, release: 0.8,
+            filter_enabled: true, filter_cutoff: 4000.0,
+            unison_voices: 5, unison_detune: 12.0, unison_filter_spread: 0.2,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.6, reverb_mix: 0.25,
+            ..base
+        },
+        9 => Patch {
+            waveform: 1,
+            attack: 1.2, decay: 0.5, sustain: 0.9, release: 2.0,
+            filter_enabled: true, filter_cutoff: 2800.0,
+            unison_voices: 5, unison_detune: 15.0,
+            flanger_enabled: true, flanger_rate: 0.2, flanger_depth: 3.0, flanger_feedback: 0.2, flanger_mix: 0.3,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.8, reverb_mix: 0.3,
+            ..base
+        },
+        10 => Patch {
+            waveform: 1,
+            attack: 0.15, decay: 0.3, sustain: 0.8, release: 0.6,
+            filter_enabled: true, filter_cutoff: 5500.0,
+            unison_voices: 3, unison_detune: 8.0,
+            lfo_rate: 5.5, lfo_depth: 0.05,
+            reverb_enabled: true, reverb_room_size: 0.5, reverb_mix: 0.2,
+            ..base
+        },
+        11 => Patch {
+            waveform: 1,
+            attack: 0.002, decay: 0.25, sustain: 0.0, release: 0.2,
+            filter_enabled: true, filter_cutoff: 3000.0,
+            unison_voices: 3, unison_detune: 6.0,
+            reverb_enabled: true, reverb_room_size: 0.5, reverb_mix: 0.25,
+            ..base
+        },
+        // ---- Brass ----
+        12 => Patch {
+            waveform: 1,
+            attack: 0.01, decay: 0.15, sustain: 0.6, release: 0.15,
+            filter_enabled: true, filter_cutoff: 900.0, filter_resonance: 0.15,
+            filter_env_attack: 0.02, filter_env_decay: 0.3, filter_env_sustain: 0.2, filter_env_amount: 3.5,
+            drive: 0.35,
+            unison_voices: 3, unison_detune: 7.0,
+            ..base
+        },
+        13 => Patch {
+            waveform: 1,
+            attack: 0.12, decay: 0.3, sustain: 0.75, release: 0.35,
+            filter_enabled: true, filter_cutoff: 1800.0,
+            unison_voices: 2, unison_detune: 5.0,
+            reverb_enabled: true, reverb_room_size: 0.5, reverb_mix: 0.15,
+            ..base
+        },
+        14 => Patch {
+            waveform: 2,
+            attack: 0.03, decay: 0.2, sustain: 0.7, release: 0.2,
+            filter_enabled: true, filter_cutoff: 2800.0, filter_resonance: 0.25,
+            unison_voices: 3, unison_detune: 10.0,
+            ..base
+        },
+        15 => Patch {
+            waveform: 1,
+            attack: 0.6, decay: 0.3, sustain: 0.9, release: 0.5,
+            filter_enabled: true, filter_cutoff: 2200.0,
+            unison_voices: 4, unison_detune: 9.0,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.6, reverb_mix: 0.2,
+            ..base
+        },
+        // ---- Plucks ----
+        16 => Patch {
+            waveform: 1,
+            attack: 0.001, decay: 0.3, sustain: 0.0, release: 0.2,
+            filter_enabled: true, filter_cutoff: 2500.0, filter_resonance: 0.3,
+            delay_enabled: true, delay_time_ms: 375.0, delay_feedback: 0.3, delay_mix: 0.2,
+            ..base
+        },
+        17 => Patch {
+            waveform: 4,
+            attack: 0.001, decay: 0.8, sustain: 0.0, release: 0.6,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.7, reverb_mix: 0.3,
+            ..base
+        },
+        18 => Patch {
+            waveform: 2,
+            attack: 0.001, decay: 0.2, sustain: 0.0, release: 0.15,
+            bitcrusher_enabled: true, bitcrusher_bits: 6.0, bitcrusher_downsample: 3.0, bitcrusher_mix: 0.7,
+            delay_enabled: true, delay_mode: 2, delay_time_ms: 250.0, delay_feedback: 0.35, delay_mix: 0.25,
+            ..base
+        },
+        19 => Patch {
+            waveform: 3,
+            attack: 0.001, decay: 1.0, sustain: 0.0, release: 0.8,
+            filter_enabled: true, filter_cutoff: 6000.0,
+            reverb_enabled: true, reverb_room_size: 0.6, reverb_mix: 0.25,
+            ..base
+        },
+        // ---- Basses ----
+        20 => Patch {
+            waveform: 0,
+            attack: 0.005, decay: 0.2, sustain: 0.9, release: 0.1,
+            ..base
+        },
+        21 => Patch {
+            waveform: 1,
+            attack: 0.002, decay: 0.25, sustain: 0.6, release: 0.1,
+            filter_enabled: true, filter_cutoff: 900.0, filter_resonance: 0.2,
+            unison_voices: 2, unison_detune: 5.0,
+            ..base
+        },
+        22 => Patch {
+            waveform: 2,
+            attack: 0.002, decay: 0.3, sustain: 0.5, release: 0.08,
+            filter_enabled: true, filter_cutoff: 700.0, filter_resonance: 0.3,
+            ..base
+        },
+        23 => Patch {
+            waveform: 1,
+            attack: 0.001, decay: 0.2, sustain: 0.3, release: 0.05,
+            glide_time_ms: 60.0,
+            filter_enabled: true, filter_cutoff: 600.0, filter_resonance: 0.7,
+            auto_wah_enabled: true, auto_wah_sensitivity: 0.7, auto_wah_range: 0.6, auto_wah_q: 5.0, auto_wah_mix: 0.6,
+            ..base
+        },
+        // ---- Pads ----
+        24 => Patch {
+            waveform: 1,
+            attack: 0.8, decay: 1.0, sustain: 0.8, release: 2.0,
+            filter_enabled: true, filter_cutoff: 1800.0,
+            unison_voices: 5, unison_detune: 14.0, unison_filter_spread: 0.3,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.8, reverb_mix: 0.35,
+            ..base
+        },
+        25 => Patch {
+            waveform: 3,
+            attack: 0.5, decay: 1.5, sustain: 0.7, release: 2.5,
+            unison_voices: 3, unison_detune: 8.0,
+            flanger_enabled: true, flanger_rate: 0.15, flanger_depth: 4.0, flanger_feedback: 0.4, flanger_mix: 0.35,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.9, reverb_mix: 0.4,
+            ..base
+        },
+        26 => Patch {
+            waveform: 1,
+            attack: 1.5, decay: 1.0, sustain: 0.9, release: 3.0,
+            filter_enabled: true, filter_cutoff: 700.0, filter_resonance: 0.2,
+            unison_voices: 5, unison_detune: 18.0,
+            lfo_rate: 0.2, lfo_depth: 0.1, lfo_waveform: 1,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.9, reverb_mix: 0.4,
+            ..base
+        },
+        27 => Patch {
+            waveform: 3,
+            attack: 0.6, decay: 0.8, sustain: 0.85, release: 1.8,
+            filter_enabled: true, filter_cutoff: 1500.0, filter_mode: 2, filter_resonance: 0.3,
+            unison_voices: 5, unison_detune: 10.0, unison_filter_spread: 0.4,
+            reverb_enabled: true, reverb_type: 2, reverb_room_size: 0.85, reverb_mix: 0.4,
+            ..base
+        },
+        // ---- Keys and leads ----
+        28 => Patch {
+            waveform: 5,
+            attack: 0.001, decay: 1.8, sustain: 0.2, release: 0.6,
+            reverb_enabled: true, reverb_room_size: 0.5, reverb_mix: 0.15,
+            ..base
+        },
+        29 => Patch {
+            waveform: 4,
+            attack: 0.001, decay: 0.5, sustain: 0.5, release: 0.4,
+            delay_enabled: true, delay_mode: 1, delay_time_ms: 300.0, delay_feedback: 0.25, delay_mix: 0.15,
+            ..base
+        },
+        30 => Patch {
+            waveform: 1,
+            attack: 0.005, decay: 0.3, sustain: 0.8, release: 0.2,
+            glide_time_ms: 80.0,
+            filter_enabled: true, filter_cutoff: 3500.0, filter_resonance: 0.3,
+            unison_voices: 2, unison_detune: 6.0,
+            lfo_rate: 5.0, lfo_depth: 0.05,
+            delay_enabled: true, delay_mode: 2, delay_time_ms: 375.0, delay_feedback: 0.3, delay_mix: 0.2,
+            ..base
+        },
+        31 => Patch {
+            waveform: 2,
+            attack: 0.002, decay: 0.4, sustain: 0.4, release: 0.3,
+            filter_enabled: true, filter_cutoff: 2000.0, filter_resonance: 0.4,
+            unison_voices: 3, unison_detune: 12.0,
+            delay_enabled: true, delay_time_ms: 500.0, delay_feedback: 0.3, delay_mix: 0.2,
+            ..base
+        },
+        _ => return None,
+    };
+    Some(patch)
+}
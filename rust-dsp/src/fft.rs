@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Default)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn new(re: f32, im: f32) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    pub fn conj(self) -> Complex {
+        Complex { re: self.re, im: -self.im }
+    }
+}
+
+// In-place iterative radix-2 FFT with precomputed twiddles and bit-reversal
+#[derive(Clone)]
+pub struct Fft {
+    size: usize,
+    twiddles: Vec<Complex>,
+    bit_reverse: Vec<usize>,
+}
+
+impl Fft {
+    // `size` is rounded up to the next power of two
+    pub fn new(size: usize) -> Self {
+        let size = size.max(2).next_power_of_two();
+        let bits = size.trailing_zeros();
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f32 / size as f32;
+                Complex::new(angle.cos(), angle.sin())
+            })
+            .collect();
+
+        let bit_reverse = (0..size)
+            .map(|i| i.reverse_bits() >> (usize::BITS - bits))
+            .collect();
+
+        Fft { size, twiddles, bit_reverse }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn forward(&self, buffer: &mut [Complex]) {
+        for i in 0..self.size {
+            let j = self.bit_reverse[i];
+            if i < j {
+                buffer.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= self.size {
+            let half = len / 2;
+            let stride = self.size / len;
+            for start in (0..self.size).step_by(len) {
+                for k in 0..half {
+                    let t = buffer[start + k + half].mul(self.twiddles[k * stride]);
+                    let u = buffer[start + k];
+                    buffer[start + k] = Complex::new(u.re + t.re, u.im + t.im);
+                    buffer[start + k + half] = Complex::new(u.re - t.re, u.im - t.im);
+                }
+            }
+            len *= 2;
+        }
+    }
+
+    // Inverse transform via the conjugation trick, scaled by 1/N
+    pub fn inverse(&self, buffer: &mut [Complex]) {
+        for value in buffer.iter_mut() {
+            *value = value.conj();
+        }
+        self.forward(buffer);
+        let scale = 1.0 / self.size as f32;
+        for value in buffer.iter_mut() {
+            *value = Complex::new(value.re * scale, -value.im * scale);
+        }
+    }
+}
@@ -1,10 +1,50 @@
+use crate::denormal::flush;
+#[cfg(feature = "simd")]
+use crate::denormal::THRESHOLD;
+#[cfg(feature = "simd")]
+use crate::simd::F32x4;
+
+pub const BANK_LANES: usize = 8;
+
+// The 24 dB/octave slope runs two stages. The first has this fixed damping
+// and the second the resonance, so at no resonance the pair comes out close
+// to a Butterworth response (stage Qs 0.54 and 1.31).
+const CASCADE_DAMPING: f32 = 1.848;
+
+#[derive(Clone, Copy)]
+pub enum FilterMode {
+    Lowpass = 0,
+    Highpass = 1,
+    Bandpass = 2,
+    Notch = 3,
+}
+
+impl FilterMode {
+    fn from_u8(mode: u8) -> Self {
+        match mode {
+            0 => FilterMode::Lowpass,
+            1 => FilterMode::Highpass,
+            2 => FilterMode::Bandpass,
+            3 => FilterMode::Notch,
+            _ => FilterMode::Lowpass,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct StateVariableFilter {
     sample_rate: f32,
     cutoff: f32,
+    damping: f32,
+    mode: FilterMode,
     low: f32,
     band: f32,
     high: f32,
     notch: f32,
+    // First stage of the 24 dB slope, when on
+    cascade: bool,
+    cascade_low: f32,
+    cascade_band: f32,
 }
 
 impl StateVariableFilter {
@@ -12,10 +52,15 @@ impl StateVariableFilter {
         StateVariableFilter {
             sample_rate,
             cutoff: 20000.0,
+            damping: 0.707,
+            mode: FilterMode::Lowpass,
             low: 0.0,
             band: 0.0,
             high: 0.0,
             notch: 0.0,
+            cascade: false,
+            cascade_low: 0.0,
+            cascade_band: 0.0,
         }
     }
 
@@ -23,43 +68,194 @@ impl StateVariableFilter {
         self.cutoff = cutoff.clamp(20.0, 20000.0);
     }
 
+    // 2 poles (12 dB/octave) or 4 (24 dB/octave)
+    pub fn set_poles(&mut self, poles: u8) {
+        let cascade = poles >= 4;
+        if cascade && !self.cascade {
+            self.cascade_low = 0.0;
+            self.cascade_band = 0.0;
+        }
+        self.cascade = cascade;
+    }
+
+    // 0 keeps the stable, musical 0.707 response; 1 is close to self-oscillation
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.damping = 0.707 * (1.0 - 0.9 * resonance.clamp(0.0, 1.0));
+    }
+
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = FilterMode::from_u8(mode);
+    }
+
+    pub fn reset(&mut self) {
+        self.low = 0.0;
+        self.band = 0.0;
+        self.high = 0.0;
+        self.notch = 0.0;
+        self.cascade_low = 0.0;
+        self.cascade_band = 0.0;
+    }
+
     pub fn process(&mut self, input: f32) -> f32 {
         let f = 2.0 * (self.cutoff / self.sample_rate);
         let f = f.clamp(0.0, 0.5);
-        let q = 0.707; // Fixed Q for stable, musical filter response
+
+        let input = if self.cascade {
+            let low = flush(self.cascade_low + f * self.cascade_band);
+            let high = input - low - CASCADE_DAMPING * self.cascade_band;
+            let band = flush(self.cascade_band + f * high);
+            self.cascade_low = low;
+            self.cascade_band = band;
+            match self.mode {
+                FilterMode::Lowpass => low,
+                FilterMode::Highpass => high,
+                FilterMode::Bandpass => band,
+                FilterMode::Notch => high + low,
+            }
+        } else {
+            input
+        };
 
         // State variable filter algorithm
-        self.low += f * self.band;
-        self.high = input - self.low - q * self.band;
-        self.band += f * self.high;
+        self.low = flush(self.low + f * self.band);
+        self.high = input - self.low - self.damping * self.band;
+        self.band = flush(self.band + f * self.high);
         self.notch = self.high + self.low;
 
-        // Return lowpass output
-        self.low
+        match self.mode {
+            FilterMode::Lowpass => self.low,
+            FilterMode::Highpass => self.high,
+            FilterMode::Bandpass => self.band,
+            FilterMode::Notch => self.notch,
+        }
     }
+}
 
-    pub fn process_highpass(&mut self, input: f32) -> f32 {
-        let f = 2.0 * (self.cutoff / self.sample_rate);
-        let f = f.clamp(0.0, 0.5);
-        let q = 0.707; // Fixed Q for stable, musical filter response
+// A voice's unison stack: up to BANK_LANES state variable filters sharing
+// mode and resonance, each with its own cutoff. State is kept per lane so
+// the `simd` feature can run four filters at once; either way each lane
+// computes exactly what a StateVariableFilter would.
+#[derive(Clone)]
+pub struct FilterBank {
+    sample_rate: f32,
+    damping: f32,
+    mode: FilterMode,
+    // Per-lane integrator coefficient, 2 * cutoff / sample_rate
+    f: [f32; BANK_LANES],
+    low: [f32; BANK_LANES],
+    band: [f32; BANK_LANES],
+    // First stage of the 24 dB slope, when on
+    cascade: bool,
+    cascade_low: [f32; BANK_LANES],
+    cascade_band: [f32; BANK_LANES],
+}
 
-        self.low += f * self.band;
-        self.high = input - self.low - q * self.band;
-        self.band += f * self.high;
+impl FilterBank {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut bank = FilterBank {
+            sample_rate,
+            damping: 0.707,
+            mode: FilterMode::Lowpass,
+            f: [0.0; BANK_LANES],
+            low: [0.0; BANK_LANES],
+            band: [0.0; BANK_LANES],
+            cascade: false,
+            cascade_low: [0.0; BANK_LANES],
+            cascade_band: [0.0; BANK_LANES],
+        };
+        for lane in 0..BANK_LANES {
+            bank.set_cutoff(lane, 20000.0);
+        }
+        bank
+    }
 
-        self.high
+    pub fn set_cutoff(&mut self, lane: usize, cutoff: f32) {
+        let cutoff = cutoff.clamp(20.0, 20000.0);
+        self.f[lane] = (2.0 * (cutoff / self.sample_rate)).clamp(0.0, 0.5);
     }
 
-    pub fn process_bandpass(&mut self, input: f32) -> f32 {
-        let f = 2.0 * (self.cutoff / self.sample_rate);
-        let f = f.clamp(0.0, 0.5);
-        let q = 0.707; // Fixed Q for stable, musical filter response
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.damping = 0.707 * (1.0 - 0.9 * resonance.clamp(0.0, 1.0));
+    }
 
-        self.low += f * self.band;
-        self.high = input - self.low - q * self.band;
-        self.band += f * self.high;
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = FilterMode::from_u8(mode);
+    }
 
-        self.band
+    // 2 poles (12 dB/octave) or 4 (24 dB/octave)
+    pub fn set_poles(&mut self, poles: u8) {
+        let cascade = poles >= 4;
+        if cascade && !self.cascade {
+            self.cascade_low = [0.0; BANK_LANES];
+            self.cascade_band = [0.0; BANK_LANES];
+        }
+        self.cascade = cascade;
+    }
+
+    // Filters the first `count` lanes of `samples` in place
+    pub fn process(&mut self, samples: &mut [f32; BANK_LANES], count: usize) {
+        if self.cascade {
+            let state = (&mut self.cascade_low, &mut self.cascade_band);
+            run_stage(&self.f, CASCADE_DAMPING, self.mode, state, samples, count);
+        }
+        run_stage(&self.f, self.damping, self.mode, (&mut self.low, &mut self.band), samples, count);
+    }
+}
+
+type StageState<'a> = (&'a mut [f32; BANK_LANES], &'a mut [f32; BANK_LANES]);
+
+// One state variable stage over the lanes, with `state` its low and band
+// integrators
+#[cfg(not(feature = "simd"))]
+fn run_stage(
+    f: &[f32; BANK_LANES],
+    damping: f32,
+    mode: FilterMode,
+    (lows, bands): StageState,
+    samples: &mut [f32; BANK_LANES],
+    count: usize,
+) {
+    for (lane, sample) in samples.iter_mut().enumerate().take(count) {
+        let low = flush(lows[lane] + f[lane] * bands[lane]);
+        let high = *sample - low - damping * bands[lane];
+        let band = flush(bands[lane] + f[lane] * high);
+        lows[lane] = low;
+        bands[lane] = band;
+        *sample = match mode {
+            FilterMode::Lowpass => low,
+            FilterMode::Highpass => high,
+            FilterMode::Bandpass => band,
+            FilterMode::Notch => high + low,
+        };
     }
 }
 
+// Four lanes at a time; lanes past `count` in the last group run on
+// whatever input they hold, which the caller ignores
+#[cfg(feature = "simd")]
+fn run_stage(
+    f: &[f32; BANK_LANES],
+    damping: f32,
+    mode: FilterMode,
+    (lows, bands): StageState,
+    samples: &mut [f32; BANK_LANES],
+    count: usize,
+) {
+    let damping = F32x4::splat(damping);
+    for i in (0..count.min(BANK_LANES)).step_by(4) {
+        let f = F32x4::load(&f[i..]);
+        let band = F32x4::load(&bands[i..]);
+        let low = (F32x4::load(&lows[i..]) + f * band).flush_below(THRESHOLD);
+        let high = F32x4::load(&samples[i..]) - low - damping * band;
+        let band = (band + f * high).flush_below(THRESHOLD);
+        low.store(&mut lows[i..]);
+        band.store(&mut bands[i..]);
+        let output = match mode {
+            FilterMode::Lowpass => low,
+            FilterMode::Highpass => high,
+            FilterMode::Bandpass => band,
+            FilterMode::Notch => high + low,
+        };
+        output.store(&mut samples[i..]);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::block;
+use crate::patch::Patch;
+
+pub const FX_SLOTS: usize = 2;
+
+// How long the loudness measurement averages over, in seconds
+const MEASURE_SECONDS: f32 = 1.5;
+// Mean-square input level below which a block doesn't count, so silence
+// and decaying tails don't skew the ratio
+const MEASURE_FLOOR: f32 = 1e-7;
+// Compensation is held within this many dB either way, and glides to a
+// new level over about this long so switching sides doesn't click
+const MAX_COMPENSATION_DB: f32 = 12.0;
+const GAIN_SMOOTHING_MS: f32 = 30.0;
+
+// A/B comparison of two effect chain settings. Each slot keeps a stored
+// patch (only its effect settings are recalled) and its own running
+// measure of how much louder or quieter the chain makes the signal; while
+// both slots are filled the chain's output is scaled back to the level
+// going in, so neither side wins just by being louder.
+#[derive(Clone)]
+pub struct FxAb {
+    slots: [Option<Patch>; FX_SLOTS],
+    active: usize,
+    // Smoothed mean squares going into and out of the chain per slot
+    input_energy: [f32; FX_SLOTS],
+    output_energy: [f32; FX_SLOTS],
+    // Gain applied at the end of the last block
+    gain: f32,
+    measure_coefficient: f32,
+    gain_coefficient: f32,
+}
+
+impl FxAb {
+    pub fn new(sample_rate: f32) -> Self {
+        FxAb {
+            slots: [None; FX_SLOTS],
+            active: 0,
+            input_energy: [0.0; FX_SLOTS],
+            output_energy: [0.0; FX_SLOTS],
+            gain: 1.0,
+            measure_coefficient: 1.0 / (MEASURE_SECONDS * sample_rate),
+            gain_coefficient: 1000.0 / (GAIN_SMOOTHING_MS * sample_rate),
+        }
+    }
+
+    // Stores `patch` in `slot`, which becomes the one playing, and starts
+    // its measurement afresh. False for a slot out of range.
+    pub fn store(&mut self, slot: usize, patch: &Patch) -> bool {
+        if slot >= FX_SLOTS {
+            return false;
+        }
+        self.slots[slot] = Some(*patch);
+        self.active = slot;
+        self.input_energy[slot] = 0.0;
+        self.output_energy[slot] = 0.0;
+        true
+    }
+
+    // Switches to the other slot, returning its patch, or None while it's
+    // empty
+    pub fn toggle(&mut self) -> Option<Patch> {
+        let other = (self.active + 1) % FX_SLOTS;
+        let patch = self.slots[other]?;
+        self.active = other;
+        Some(patch)
+    }
+
+    // Forgets both slots; the compensation eases back to unity
+    pub fn clear(&mut self) {
+        self.slots = [None; FX_SLOTS];
+        self.active = 0;
+        self.input_energy = [0.0; FX_SLOTS];
+        self.output_energy = [0.0; FX_SLOTS];
+    }
+
+    // The slot playing, or None before anything is stored
+    pub fn active_slot(&self) -> Option<usize> {
+        self.slots[self.active].map(|_| self.active)
+    }
+
+    // Compensation runs while both sides are stored, and until its gain
+    // has eased back to unity after they're cleared
+    pub fn is_active(&self) -> bool {
+        self.slots.iter().all(Option::is_some) || self.gain != 1.0
+    }
+
+    // Measures a block of the chain (input is mono, output stereo) and
+    // scales the output, ramping from the last block's gain
+    pub fn process_block(&mut self, input: &[f32], left: &mut [f32], right: &mut [f32]) {
+        let len = left.len().max(1) as f32;
+        let engaged = self.slots.iter().all(Option::is_some);
+        let target = if engaged {
+            let input_energy = input.iter().map(|x| x * x).sum::<f32>() / len;
+            if input_energy > MEASURE_FLOOR {
+                let output_energy =
+                    left.iter().zip(right.iter()).map(|(l, r)| (l * l + r * r) * 0.5).sum::<f32>() / len;
+                let coefficient = (self.measure_coefficient * len).min(1.0);
+                let slot = self.active;
+                self.input_energy[slot] += (input_energy - self.input_energy[slot]) * coefficient;
+                self.output_energy[slot] += (output_energy - self.output_energy[slot]) * coefficient;
+            }
+            self.compensation()
+        } else {
+            1.0
+        };
+
+        let start = self.gain;
+        if start == 1.0 && target == 1.0 {
+            return;
+        }
+        let mut end = start + (target - start) * (self.gain_coefficient * len).min(1.0);
+        if (end - target).abs() < 1e-4 {
+            end = target;
+        }
+        let step = (end - start) / len;
+        block::apply_gain_ramp_stereo(left, right, start, step);
+        self.gain = end;
+    }
+
+    fn compensation(&self) -> f32 {
+        let slot = self.active;
+        if self.input_energy[slot] <= MEASURE_FLOOR || self.output_energy[slot] <= MEASURE_FLOOR {
+            return self.gain;
+        }
+        let max = 10.0_f32.powf(MAX_COMPENSATION_DB / 20.0);
+        (self.input_energy[slot] / self.output_energy[slot]).sqrt().clamp(1.0 / max, max)
+    }
+}
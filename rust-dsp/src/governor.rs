@@ -0,0 +1,80 @@
+// CPU governor: watches the render load reported by the host and steps
+// through progressively cheaper voice settings before the audio thread
+// overruns. Each level first thins unison stacks, then caps polyphony;
+// the allocator drops inner chord voices first so the outline survives.
+
+// Action codes reported to the host, each followed by its value
+pub const ACTION_UNISON_LIMIT: u32 = 0;
+pub const ACTION_POLYPHONY_LIMIT: u32 = 1;
+pub const ACTION_VOICES_RELEASED: u32 = 2;
+
+// (max unison oscillators per voice, max voices per engine) for each level
+const LEVELS: [(u8, u8); 5] = [(7, 16), (3, 16), (1, 16), (1, 12), (1, 8)];
+
+const OVERLOAD: f32 = 0.85;
+const RECOVERED: f32 = 0.5;
+// Reports to wait after a level change before judging the load again
+const SETTLE_REPORTS: u32 = 32;
+
+pub struct Governor {
+    enabled: bool,
+    load: f32,
+    level: usize,
+    settle: u32,
+}
+
+impl Governor {
+    pub fn new() -> Self {
+        Governor {
+            enabled: true,
+            load: 0.0,
+            level: 0,
+            settle: 0,
+        }
+    }
+
+    // Disabling returns straight to full quality
+    pub fn set_enabled(&mut self, enabled: bool) -> Option<(u8, u8)> {
+        self.enabled = enabled;
+        if !enabled && self.level != 0 {
+            self.level = 0;
+            self.settle = 0;
+            return Some(self.limits());
+        }
+        None
+    }
+
+    // `load` is render time divided by the time budget of the rendered block.
+    // Returns the new (unison, polyphony) limits when the level changes.
+    pub fn report_load(&mut self, load: f32) -> Option<(u8, u8)> {
+        if !self.enabled {
+            return None;
+        }
+
+        self.load += (load.max(0.0) - self.load) * 0.1;
+        if self.settle > 0 {
+            self.settle -= 1;
+            return None;
+        }
+
+        let level = if self.load > OVERLOAD && self.level + 1 < LEVELS.len() {
+            self.level + 1
+        } else if self.load < RECOVERED && self.level > 0 {
+            self.level - 1
+        } else {
+            return None;
+        };
+
+        self.level = level;
+        self.settle = SETTLE_REPORTS;
+        Some(self.limits())
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level as u8
+    }
+
+    pub fn limits(&self) -> (u8, u8) {
+        LEVELS[self.level]
+    }
+}
@@ -0,0 +1,68 @@
+use std::f32::consts::PI;
+
+// Delay-line pitch shifter: two read heads sweep through a short window at
+// a speed set by the pitch ratio, each faded in and out with a raised-cosine
+// so the jump back to the start of the window is inaudible. Cheap and
+// latency-free apart from the window, at the cost of some grain texture.
+#[derive(Clone)]
+pub struct GrainShifter {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    window: f32,
+    phase: f32,
+    ratio: f32,
+}
+
+impl GrainShifter {
+    // `window_ms` trades smearing (long) against roughness (short)
+    pub fn new(sample_rate: f32, window_ms: f32) -> Self {
+        let window = (window_ms * sample_rate / 1000.0).max(16.0);
+        GrainShifter {
+            buffer: vec![0.0; window as usize + 4],
+            write_pos: 0,
+            window,
+            phase: 0.0,
+            ratio: 1.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.clamp(0.25, 4.0);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.buffer[self.write_pos] = input;
+
+        // The delay shrinks (pitch up) or grows (pitch down) by `ratio - 1`
+        // samples per sample; `phase` is the position within the window
+        self.phase -= (self.ratio - 1.0) / self.window;
+        self.phase -= self.phase.floor();
+
+        let first = self.read(self.phase * self.window);
+        let second_phase = (self.phase + 0.5).fract();
+        let second = self.read(second_phase * self.window);
+        let output = first * fade(self.phase) + second * fade(second_phase);
+
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+        output
+    }
+
+    fn read(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = delay.clamp(1.0, (len - 2) as f32);
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = self.buffer[(self.write_pos + len - whole) % len];
+        let b = self.buffer[(self.write_pos + len - whole - 1) % len];
+        a + (b - a) * frac
+    }
+}
+
+// Raised-cosine window over one sweep; two heads half a sweep apart sum to one
+fn fade(phase: f32) -> f32 {
+    0.5 - 0.5 * (phase * 2.0 * PI).cos()
+}
@@ -0,0 +1,50 @@
+// Groove templates: a timing offset (in steps) and a velocity scale for each
+// step of a short cycle, laid over a pattern's steps by
+// Sequencer::apply_groove. The swing feels follow drum machine swing
+// percentages, where 50% is straight and 66% a triplet shuffle.
+
+pub const GROOVE_STRAIGHT: u8 = 0;
+// The groove last taken from a pattern with Sequencer::extract_groove
+pub const GROOVE_USER: u8 = 7;
+// Longest cycle, a bar of sixteenths
+pub const MAX_GROOVE_STEPS: usize = 16;
+
+#[derive(Clone, Copy)]
+pub struct Groove {
+    pub offsets: [f32; MAX_GROOVE_STEPS],
+    pub velocities: [f32; MAX_GROOVE_STEPS],
+    // Steps in the cycle
+    pub length: usize,
+}
+
+impl Groove {
+    pub fn straight() -> Self {
+        Groove { offsets: [0.0; MAX_GROOVE_STEPS], velocities: [1.0; MAX_GROOVE_STEPS], length: 1 }
+    }
+
+    fn from_cycle(offsets: &[f32], velocities: &[f32]) -> Self {
+        let mut groove = Groove { length: offsets.len(), ..Groove::straight() };
+        groove.offsets[..offsets.len()].copy_from_slice(offsets);
+        groove.velocities[..velocities.len()].copy_from_slice(velocities);
+        groove
+    }
+}
+
+// Built-in template `id`: 0 straight, 1-4 swing at 54, 58, 62 and 66%,
+// 5 laid back (late backbeats, soft sixteenths), 6 pushed (off-beats early)
+pub fn template(id: u8) -> Option<Groove> {
+    let swing = |percent: f32| Groove::from_cycle(&[0.0, percent / 50.0 - 1.0], &[1.0, 0.9]);
+    Some(match id {
+        GROOVE_STRAIGHT => Groove::straight(),
+        1 => swing(54.0),
+        2 => swing(58.0),
+        3 => swing(62.0),
+        4 => swing(66.0),
+        5 => Groove::from_cycle(
+            &[0.0, 0.04, 0.0, 0.04, 0.1, 0.04, 0.0, 0.04, 0.0, 0.04, 0.0, 0.04, 0.1, 0.04, 0.0, 0.04],
+            &[1.0, 0.75, 0.9, 0.75, 1.0, 0.75, 0.9, 0.75, 1.0, 0.75, 0.9, 0.75, 1.0, 0.75, 0.9, 0.75],
+        ),
+        6 => Groove::from_cycle(&[0.0, -0.05, -0.08, -0.05], &[1.0, 0.8, 0.9, 0.8]),
+        _ => return None,
+    })
+}
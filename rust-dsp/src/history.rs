@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+
+// Undo/redo history of whole-engine snapshots (save_state JSON). The UI
+// pushes a snapshot before each edit; undo swaps the current state for the
+// newest snapshot and keeps the current one for redo. Only the last
+// MAX_HISTORY snapshots are kept.
+
+pub const MAX_HISTORY: usize = 64;
+
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<String>,
+    redo: Vec<String>,
+}
+
+impl History {
+    // A new edit branch: anything that could be redone is dropped. Pushing
+    // the same state twice in a row keeps a single entry.
+    pub fn push(&mut self, state: String) {
+        self.redo.clear();
+        if self.undo.back() == Some(&state) {
+            return;
+        }
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(state);
+    }
+
+    // The state to restore, given the one it replaces
+    pub fn undo(&mut self, current: String) -> Option<String> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    pub fn redo(&mut self, current: String) -> Option<String> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
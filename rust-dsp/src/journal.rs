@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+// Session journal: the performance inputs (notes, chords, transport and
+// tempo) and every patch parameter change on both tracks, stamped with the
+// engine sample clock and transport beat. Replaying it from the recorded
+// starting state reproduces the session sample for sample. Patch changes
+// are found by diffing each track's patch, so every setter is covered
+// without journaling them one by one.
+
+pub const JOURNAL_IDLE: u8 = 0;
+pub const JOURNAL_RECORDING: u8 = 1;
+pub const JOURNAL_REPLAYING: u8 = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEvent {
+    NoteOn { note: u8, velocity: f32 },
+    NoteOff {
+        note: u8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        release_velocity: Option<f32>,
+    },
+    TimelineNoteOn {
+        note: u8,
+        velocity: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cents: Option<f32>,
+    },
+    TimelineNoteOff { note: u8 },
+    StopAllTimelineNotes,
+    Panic,
+    ChannelPressure { value: f32 },
+    NotePressure { note: u8, value: f32 },
+    PlayChord {
+        root: u8,
+        chord_type: u8,
+        inversion: u8,
+        velocity: f32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        cents: Vec<f32>,
+    },
+    PlayVoicing { notes: Vec<u8>, velocities: Vec<f32> },
+    ReleaseChord,
+    GlideToChord { notes: Vec<u8>, time_ms: f32 },
+    TransportPlay,
+    TransportStop,
+    SetBpm { bpm: f32 },
+    // Changed entries of an engine's Patch::values as (index, value)
+    Patch { engine: u32, changes: Vec<(usize, f32)> },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    // Samples since recording started
+    pub sample: u64,
+    // Transport position when the event happened, for display
+    pub beat: f64,
+    pub event: JournalEvent,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    // State when recording started
+    pub bpm: f32,
+    pub playing: bool,
+    // Patch::values of each engine, by engine index
+    pub patches: Vec<Vec<f32>>,
+    pub entries: Vec<JournalEntry>,
+}
+
+// (index, value) for every entry of `new` that differs from `old`
+pub fn value_changes(old: &[f32], new: &[f32]) -> Vec<(usize, f32)> {
+    new.iter()
+        .enumerate()
+        .filter(|&(i, value)| old.get(i) != Some(value))
+        .map(|(i, &value)| (i, value))
+        .collect()
+}
@@ -6,6 +6,17 @@ pub enum LfoWaveform {
     SampleHold = 3,
 }
 
+// Free runs forever; one-shot restarts on each note and stops at zero after
+// a single cycle, like an extra envelope; transport restarts with playback
+// so the modulation lines up with the song
+#[derive(Clone, Copy, PartialEq)]
+pub enum LfoMode {
+    Free = 0,
+    OneShot = 1,
+    Transport = 2,
+}
+
+#[derive(Clone)]
 pub struct Lfo {
     phase: f32,
     phase_increment: f32,
@@ -15,6 +26,9 @@ pub struct Lfo {
     waveform: LfoWaveform,
     sample_hold_value: f32,
     sample_hold_counter: f32,
+    mode: LfoMode,
+    // One-shot cycle has run out
+    finished: bool,
 }
 
 impl Lfo {
@@ -28,6 +42,8 @@ impl Lfo {
             waveform: LfoWaveform::Sine,
             sample_hold_value: 0.0,
             sample_hold_counter: 0.0,
+            mode: LfoMode::Free,
+            finished: false,
         }
     }
 
@@ -50,6 +66,55 @@ impl Lfo {
         };
     }
 
+    pub fn set_mode(&mut self, mode: u8) {
+        self.mode = match mode {
+            1 => LfoMode::OneShot,
+            2 => LfoMode::Transport,
+            _ => LfoMode::Free,
+        };
+        // A one-shot waits for the next note
+        self.finished = self.mode == LfoMode::OneShot;
+    }
+
+    pub fn mode(&self) -> LfoMode {
+        self.mode
+    }
+
+    // Back to the start of the cycle with a fresh sample-and-hold value
+    pub fn retrigger(&mut self) {
+        self.phase = 0.0;
+        self.sample_hold_value = (rand::random() * 2.0) - 1.0;
+        self.finished = false;
+    }
+
+    // Output at the current phase without moving it, for block-rate
+    // modulation; sample-and-hold steps once per cycle
+    pub fn value(&self) -> f32 {
+        self.shape() * self.depth
+    }
+
+    // `value` before the depth, -1 to 1, for destinations with their own
+    // amounts
+    pub fn shape(&self) -> f32 {
+        if self.finished {
+            return 0.0;
+        }
+        match self.waveform {
+            LfoWaveform::Sine => (self.phase * 2.0 * std::f32::consts::PI).sin(),
+            LfoWaveform::Triangle => {
+                if self.phase < 0.5 {
+                    4.0 * self.phase - 1.0
+                } else {
+                    3.0 - 4.0 * self.phase
+                }
+            }
+            LfoWaveform::Square => {
+                if self.phase < 0.5 { 1.0 } else { -1.0 }
+            }
+            LfoWaveform::SampleHold => self.sample_hold_value,
+        }
+    }
+
     pub fn process(&mut self) -> f32 {
         let output = match self.waveform {
             LfoWaveform::Sine => {
@@ -82,6 +147,31 @@ impl Lfo {
 
         output * self.depth
     }
+
+    // Move the phase forward without computing output samples
+    pub fn advance(&mut self, samples: usize) {
+        if self.finished {
+            return;
+        }
+        let phase = self.phase + self.phase_increment * samples as f32;
+        if phase >= 1.0 {
+            if self.mode == LfoMode::OneShot {
+                self.phase = 0.0;
+                self.finished = true;
+                return;
+            }
+            self.sample_hold_value = (rand::random() * 2.0) - 1.0;
+        }
+        self.phase = phase.fract();
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.finished && self.depth > 0.0
+    }
+
+    pub fn get_phase(&self) -> f32 {
+        self.phase
+    }
 }
 
 // Simple PRNG for sample-and-hold
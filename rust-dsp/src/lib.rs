@@ -1,416 +1,6222 @@
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 mod oscillator;
 mod envelope;
 mod voice;
+mod filter;
 mod lfo;
 mod effects;
+mod patch;
+mod midi;
+mod transport;
+mod metadata;
+mod biquad;
+mod fft;
+mod pan;
+mod governor;
+mod articulation;
+mod factory;
+mod grain_shifter;
+mod string_machine;
+mod looper;
+mod motion;
+mod denormal;
+mod sequencer;
+mod arpeggiator;
+mod chords;
+mod note_queue;
+mod debug;
+mod scales;
+mod test_vector;
+mod journal;
+mod smf;
+mod midi_clock;
+mod wav;
+mod state;
+mod history;
+mod params;
+mod message_ring;
+mod meter;
+mod spectrum;
+mod clock;
+mod tuning;
+mod pressure;
+mod random;
+mod progression;
+mod cc_map;
+mod resampler;
+mod oversampler;
+mod smooth_random;
+mod macros;
+mod state_binary;
+mod metronome;
+mod automation;
+mod groove;
+mod fx_ab;
+mod engine_events;
+mod deprecated;
+mod block;
+#[cfg(feature = "simd")]
+mod simd;
 
-use voice::Voice;
-use lfo::Lfo;
+use voice::{Voice, CONTROL_BLOCK, MAX_UNISON, PHASE_FREE, PHASE_RANDOM, PHASE_RESET};
+use envelope::{EnvelopeStage, RETRIGGER_FULL, RETRIGGER_LEGATO, RETRIGGER_SOFT};
+use oscillator::Waveform;
+use lfo::{Lfo, LfoMode};
+use filter::StateVariableFilter;
 use effects::delay::Delay;
 use effects::reverb::Reverb;
 use effects::tremolo::Tremolo;
 use effects::flanger::Flanger;
+use effects::bitcrusher::Bitcrusher;
+use effects::limiter::Limiter;
+use effects::eq::Eq;
+use effects::convolution::ConvolutionReverb;
+use effects::fdn_reverb::{FdnReverb, DENSITY_DENSE, DENSITY_NORMAL, DENSITY_SPARSE};
+use effects::rumble_filter::RumbleFilter;
+use effects::bass_mono::BassMono;
+use effects::multiband_compressor::MultibandCompressor;
+use effects::auto_wah::AutoWah;
+use effects::vintage::Vintage;
+use effects::rotary::Rotary;
+use effects::pitch_shifter::PitchShifter;
+use effects::frequency_shifter::FrequencyShifter;
+use effects::tape::Tape;
+use effects::glide::Glide;
+use effects::soft_clipper::SoftClipper;
+use effects::dc_blocker::DcBlocker;
+use effects::vocoder::Vocoder;
+use effects::{effect_name, Effect, EFFECT_BITCRUSHER, EFFECT_FLANGER, EFFECT_TREMOLO, EFFECT_EQ, EFFECT_DELAY, EFFECT_REVERB, EFFECT_AUTO_WAH, EFFECT_ROTARY, EFFECT_PITCH_SHIFTER, EFFECT_FREQUENCY_SHIFTER};
+use patch::Patch;
+use midi::MidiMessage;
+use transport::Transport;
+use metadata::Metadata;
+use pan::{Downmix, PanLaw};
+use governor::Governor;
+use articulation::{Articulation, ChordGesture};
+use string_machine::StringMachine;
+use looper::Looper;
+use motion::Motion;
+use sequencer::{FollowAction, Merged, NoteSource, Sequencer, SequencerEvent};
+use arpeggiator::Arpeggiator;
+use note_queue::NoteQueue;
+use debug::EventLog;
+use test_vector::{NoteScript, ScriptCommand};
+use midi_clock::MidiClock;
+use history::History;
+use meter::Meter;
+use tuning::Tuning;
+use pressure::Pressure;
+use smooth_random::SmoothRandom;
+use cc_map::{CcMap, CcMapping};
+use macros::{MacroTarget, Macros};
+use metronome::Metronome;
+use automation::{Automation, AutomationPoint};
+use fx_ab::FxAb;
+use engine_events::{EngineEvent, EngineEvents, EventQueue, EVENT_CLIP, EVENT_NOTE_ENDED, EVENT_NOTE_STARTED, EVENT_PATTERN_LOOPED, EVENT_VOICE_STOLEN};
+use resampler::Resampler;
+use random::Random;
+use spectrum::{SpectrumAnalyzer, SPECTRUM_BINS};
+use params::ParamId;
+use message_ring::{MessageRing, MESSAGE_RING_CAPACITY, MSG_NOTE_OFF, MSG_NOTE_ON, MSG_SET_PARAM, MSG_TIMELINE_NOTE_OFF, MSG_TIMELINE_NOTE_ON};
+use state::{AutomationLaneState, EngineState, PatternState, StepState, TrackState};
+use journal::{Journal, JournalEntry, JournalEvent, JOURNAL_IDLE, JOURNAL_RECORDING, JOURNAL_REPLAYING};
 
 const SAMPLE_RATE: f32 = 48000.0;
 const MAX_VOICES_PER_ENGINE: usize = 16; // Each engine gets 16 voices
+// Voices pick up block-rate modulation (motion macro, filter keyboard) at
+// this rate
+const MOTION_BLOCK: usize = 32;
+// Filter sweep of the LFO at full depth, up and down
+const LFO_CUTOFF_OCTAVES: f32 = 2.0;
+// Fade applied to choked voices: quick, but long enough not to click
+const CHOKE_FADE: f32 = 0.005;
+// Bend time of a sequencer slide when the engine's glide time is 0
+const SLIDE_TIME_MS: f32 = 60.0;
+// Micro-fade on the previous note when a mono melody moves on
+const MONO_TRIM_FADE: f32 = 0.004;
+// Strum humanization at full amount: timing scatter and velocity spread
+const STRUM_JITTER_MS: f32 = 12.0;
+// Largest timing offset the sequencer humanize can apply
+const MAX_HUMANIZE_MS: f32 = 50.0;
+const STRUM_VELOCITY_SPREAD: f32 = 0.25;
+// Offline renders run in Web Audio sized quanta so a bounce matches what
+// plays back live, and are capped in length to bound memory
+const OFFLINE_BLOCK: usize = 128;
+const MAX_OFFLINE_SECONDS: f32 = 600.0;
+// Longest stretch rendered in one go; matches the largest ScriptProcessor
+// buffer the app asks for. Longer requests are rendered in pieces.
+const MAX_QUANTUM: usize = 2048;
+// Output rates set_output_sample_rate accepts
+const MIN_OUTPUT_RATE: f32 = 8000.0;
+const MAX_OUTPUT_RATE: f32 = 192000.0;
+// Humanize seed for golden renders
+const TEST_VECTOR_SEED: u32 = 0x2545_F491;
+// Velocity randomization seed of a new engine
+const VELOCITY_RANDOM_SEED: u32 = 0x6C07_8965;
+// Marks an unused slot in a per-note table
+const NO_NOTE: u8 = 255;
+// Time constant of the glide toward a new preset morph position
+const MORPH_SMOOTHING_MS: f32 = 50.0;
+
+// Time constant of the crossfader glide, so slider moves don't zipper
+const CROSSFADE_SMOOTHING_MS: f32 = 20.0;
+// Per-call weight of the newest measurement in the smoothed DSP load
+const DSP_LOAD_SMOOTHING: f32 = 0.1;
+// Send returns keep running after the last send closes until their output
+// has stayed under RETURN_SILENCE for longer than the longest delay time
+const RETURN_SILENCE: f32 = 1.0e-5;
+const RETURN_TAIL_SAMPLES: usize = (SAMPLE_RATE * 2.5) as usize;
+
+// Engine indices used by the engine-addressed API
+const ENGINE_TIMELINE: u32 = 0;
+const ENGINE_LIVE: u32 = 1;
+// Built-in engines; create_engine hands out ids from here up
+const ENGINE_COUNT: usize = 2;
+// Built-in and created engines together
+const MAX_ENGINES: usize = 16;
+// Rows of pattern assignments launch_scene can switch between
+const MAX_SCENES: usize = 16;
+
+// Reverb algorithms selectable per engine
+const REVERB_ALGORITHMIC: u8 = 0;
+const REVERB_CONVOLUTION: u8 = 1;
+const REVERB_FDN: u8 = 2;
+const REVERB_SHIMMER: u8 = 3;
+
+// Quality modes for set_quality_mode
+const QUALITY_ECO: u8 = 0;
+const QUALITY_NORMAL: u8 = 1;
+const QUALITY_HIGH: u8 = 2;
+
+// Tremolo switched on together with the electric piano waveform
+const EP_TREMOLO_RATE: f32 = 4.5;
+const EP_TREMOLO_DEPTH: f32 = 0.3;
+const EP_TREMOLO_MIX: f32 = 0.6;
 
 // Dual engine system: separate timeline and live performance engines
+#[derive(Clone)]
 struct Engine {
     voices: Vec<Voice>,
+    // One voice's render, summed into the engine output
+    #[cfg(feature = "simd")]
+    voice_buffer: Vec<f32>,
     lfo: Lfo,
+    // Cutoff offset the LFO last gave the voices, so they get it back when
+    // it stops
+    lfo_cutoff_octaves: f32,
+    // LFO shape the effects were last modulated with
+    lfo_fx: f32,
+    // Stored effect chain settings to A/B, with loudness compensation
+    fx_ab: FxAb,
+    // Output gain and voice normalization as last applied, ramped toward
+    // their target each block
+    output_gain: f32,
     delay: Delay,
     reverb: Reverb,
+    convolution: ConvolutionReverb,
+    fdn_reverb: FdnReverb,
+    reverb_type: u8,
     tremolo: Tremolo,
     flanger: Flanger,
+    bitcrusher: Bitcrusher,
+    eq: Eq,
+    rumble_filter: RumbleFilter,
+    auto_wah: AutoWah,
+    rotary: Rotary,
+    pitch_shifter: PitchShifter,
+    frequency_shifter: FrequencyShifter,
+    dc_blocker: DcBlocker,
+    // External audio from process_input, filtered like the voices
+    input_filter: StateVariableFilter,
+    // Replaces mixing the input in: the input modulates the voices instead
+    vocoder: Vocoder,
+    vocoder_enabled: bool,
+    string_machine: StringMachine,
+    motion: Motion,
+    // The audio engine's pan law, for panning done inside the engine
+    pan_law: PanLaw,
+    pressure: Pressure,
+    smooth_random: SmoothRandom,
+    // Per-note velocity variation
+    velocity_random: Random,
+    // Vector LFO position around its circle, 0-1
+    vector_lfo_phase: f32,
+    auto_sustain: bool,
+    auto_sustain_crossfade: f32,
+    sustained_notes: Vec<u8>,
+    // Choke group per MIDI note (0 = none)
+    choke_groups: [u8; 128],
+    // Note-ons so far, for each voice's note id
+    note_count: u64,
+    // Frequency of every MIDI note
+    tuning: Tuning,
+    // Microtuning offset in cents per MIDI note on top of the tuning, for
+    // just-intonation chord voicings
+    note_cents: [f32; 128],
+    // Activity for the event queue, collected after every render segment
+    events: EngineEvents,
+    // Voices sounding as of the last note-end check, one bit each
+    sounding: u32,
+    filter_keyboard: bool,
+    filter_key_glide: Glide,
+    filter_keys: Vec<u8>,
     delay_enabled: bool,
     reverb_enabled: bool,
     tremolo_enabled: bool,
     flanger_enabled: bool,
-    detune_cents: f32,
+    bitcrusher_enabled: bool,
+    eq_enabled: bool,
+    rumble_filter_enabled: bool,
+    auto_wah_enabled: bool,
+    rotary_enabled: bool,
+    pitch_shifter_enabled: bool,
+    frequency_shifter_enabled: bool,
+    patch: Patch,
+    // Patch to switch to when each sequencer pattern starts playing
+    pattern_presets: Vec<Option<Patch>>,
+    metadata: Metadata,
+    pan: f32,
+    delay_send: f32,
+    reverb_send: f32,
+    unison_limit: u8,
+    polyphony_limit: usize,
+    gesture: Option<ChordGesture>,
 }
 
 impl Engine {
-    fn new(sample_rate: f32) -> Self {
+    fn new(sample_rate: f32, name: &str) -> Self {
         let mut voices = Vec::with_capacity(MAX_VOICES_PER_ENGINE);
         for _ in 0..MAX_VOICES_PER_ENGINE {
             voices.push(Voice::new(sample_rate));
         }
 
-        Engine {
+        let mut engine = Engine {
             voices,
+            #[cfg(feature = "simd")]
+            voice_buffer: vec![0.0; MAX_QUANTUM],
             lfo: Lfo::new(sample_rate),
+            lfo_cutoff_octaves: 0.0,
+            lfo_fx: 0.0,
+            fx_ab: FxAb::new(sample_rate),
+            output_gain: 1.0,
             delay: Delay::new(sample_rate, 2000.0),
             reverb: Reverb::new(sample_rate),
+            convolution: ConvolutionReverb::new(sample_rate),
+            fdn_reverb: FdnReverb::new(sample_rate),
+            reverb_type: REVERB_ALGORITHMIC,
             tremolo: Tremolo::new(sample_rate),
             flanger: Flanger::new(sample_rate),
+            bitcrusher: Bitcrusher::new(),
+            eq: Eq::new(sample_rate),
+            rumble_filter: RumbleFilter::new(sample_rate),
+            auto_wah: AutoWah::new(sample_rate),
+            rotary: Rotary::new(sample_rate),
+            pitch_shifter: PitchShifter::new(sample_rate),
+            frequency_shifter: FrequencyShifter::new(sample_rate),
+            dc_blocker: DcBlocker::new(sample_rate),
+            input_filter: StateVariableFilter::new(sample_rate),
+            vocoder: Vocoder::new(sample_rate),
+            vocoder_enabled: false,
+            string_machine: StringMachine::new(sample_rate),
+            motion: Motion::new(sample_rate),
+            pan_law: PanLaw::EqualPower,
+            pressure: Pressure::new(sample_rate),
+            smooth_random: SmoothRandom::new(sample_rate),
+            velocity_random: Random::new(VELOCITY_RANDOM_SEED),
+            vector_lfo_phase: 0.0,
+            auto_sustain: false,
+            auto_sustain_crossfade: 0.15,
+            sustained_notes: Vec::new(),
+            choke_groups: [0; 128],
+            note_count: 0,
+            tuning: Tuning::equal(),
+            note_cents: [0.0; 128],
+            events: EngineEvents::new(),
+            sounding: 0,
+            filter_keyboard: false,
+            filter_key_glide: Glide::new(sample_rate),
+            filter_keys: Vec::new(),
             delay_enabled: false,
             reverb_enabled: false,
             tremolo_enabled: false,
             flanger_enabled: false,
-            detune_cents: 0.0,
+            bitcrusher_enabled: false,
+            eq_enabled: false,
+            rumble_filter_enabled: false,
+            auto_wah_enabled: false,
+            rotary_enabled: false,
+            pitch_shifter_enabled: false,
+            frequency_shifter_enabled: false,
+            patch: Patch::default(),
+            pattern_presets: vec![None; sequencer::MAX_PATTERNS],
+            metadata: Metadata::named(name),
+            pan: 0.0,
+            delay_send: 0.0,
+            reverb_send: 0.0,
+            unison_limit: MAX_UNISON as u8,
+            polyphony_limit: MAX_VOICES_PER_ENGINE,
+            gesture: None,
+        };
+        engine.apply_patch(&Patch::default());
+        engine
+    }
+
+    // Push every parameter of a patch into the DSP modules
+    fn apply_patch(&mut self, patch: &Patch) {
+        // Lets go of the old LFO destinations; the effects are set afresh
+        // below
+        self.apply_lfo_fx(0.0);
+        self.patch = *patch;
+
+        let unison_voices = self.unison_voices();
+        for voice in &mut self.voices {
+            voice.set_waveform(patch.waveform);
+            voice.set_drawbars(&patch.organ_drawbars);
+            voice.set_vector_sources(&patch.vector_sources);
+            voice.set_vector_envelope(patch.vector_env_x, patch.vector_env_y);
+            voice.set_velocity_curve(patch.velocity_curve);
+            voice.set_percussion(
+                patch.organ_percussion,
+                patch.organ_percussion_harmonic,
+                patch.organ_percussion_fast,
+                patch.organ_percussion_level,
+            );
+            voice.set_key_click(patch.organ_key_click);
+            voice.set_adsr(patch.attack, patch.decay, patch.sustain, patch.release);
+            voice.set_detune(patch.detune_cents);
+            voice.set_glide_time(patch.glide_time_ms);
+            voice.set_glide_mode(patch.glide_mode);
+            voice.set_retrigger_mode(patch.env_retrigger_mode);
+            voice.set_phase_mode(patch.phase_mode);
+            voice.set_filter_enabled(patch.filter_enabled);
+            voice.set_filter_resonance(patch.filter_resonance);
+            voice.set_filter_mode(patch.filter_mode);
+            voice.set_filter_poles(patch.filter_poles);
+            voice.set_filter_cutoff(patch.filter_cutoff);
+            voice.set_filter_envelope(
+                patch.filter_env_attack,
+                patch.filter_env_decay,
+                patch.filter_env_sustain,
+                patch.filter_env_release,
+                patch.filter_env_amount,
+            );
+            voice.set_pitch_envelope(patch.pitch_env_attack, patch.pitch_env_decay, patch.pitch_env_amount);
+            voice.set_drive(patch.drive);
+            voice.set_ghost_layer(patch.ghost_threshold, patch.ghost_tone, patch.ghost_length);
+            voice.set_unison(unison_voices, patch.unison_detune, patch.unison_filter_spread);
+        }
+
+        self.input_filter.set_cutoff(patch.filter_cutoff);
+        self.input_filter.set_resonance(patch.filter_resonance);
+        self.input_filter.set_mode(patch.filter_mode);
+        self.input_filter.set_poles(patch.filter_poles);
+
+        self.lfo.set_rate(patch.lfo_rate);
+        self.lfo.set_depth(patch.lfo_depth);
+        self.lfo.set_waveform(patch.lfo_waveform);
+        self.lfo.set_mode(patch.lfo_mode);
+        self.update_vector_position();
+        self.motion.set_amount(patch.motion_amount);
+        self.smooth_random.set_rate(patch.random_rate);
+        self.smooth_random.set_amounts(patch.random_cutoff_octaves, patch.random_pitch_cents, patch.random_fx_mix);
+        if !self.motion.is_active() && !self.smooth_random.is_active() {
+            self.reset_motion();
+        }
+        self.pressure.set_amounts(patch.pressure_vibrato_cents, patch.pressure_cutoff_octaves, patch.pressure_volume);
+        for voice in &mut self.voices {
+            voice.set_pressure_amounts(patch.pressure_vibrato_cents, patch.pressure_cutoff_octaves, patch.pressure_volume);
+        }
+
+        self.delay_enabled = patch.delay_enabled;
+        self.delay.set_delay_time(patch.delay_time_ms);
+        self.delay.set_feedback(patch.delay_feedback);
+        self.delay.set_mix(patch.delay_mix);
+        self.delay.set_mode(patch.delay_mode);
+        self.delay.set_tone(patch.delay_low_cut, patch.delay_high_cut);
+        self.delay.set_right_delay_time(patch.delay_right_time_ms);
+        self.delay.set_cross_feedback(patch.delay_cross_feedback);
+        self.delay.set_modulation(patch.delay_mod_depth_ms, patch.delay_mod_rate);
+
+        self.reverb_enabled = patch.reverb_enabled;
+        self.reverb.set_room_size(patch.reverb_room_size);
+        self.reverb.set_damping(patch.reverb_damping);
+        self.reverb_type = patch.reverb_type;
+        self.fdn_reverb.set_size(patch.reverb_room_size);
+        self.fdn_reverb.set_damping(patch.reverb_damping);
+        self.fdn_reverb.set_pre_delay(patch.reverb_pre_delay_ms);
+        self.fdn_reverb.set_diffusion(patch.reverb_diffusion);
+        self.fdn_reverb.set_modulation(patch.reverb_modulation);
+        self.update_shimmer();
+        self.set_reverb_mix(patch.reverb_mix);
+        self.set_reverb_low_cut(patch.reverb_low_cut);
+
+        self.tremolo_enabled = patch.tremolo_enabled;
+        self.tremolo.set_rate(patch.tremolo_rate);
+        self.tremolo.set_depth(patch.tremolo_depth);
+        self.tremolo.set_mix(patch.tremolo_mix);
+        self.tremolo.set_waveform(patch.tremolo_waveform);
+        self.tremolo.set_auto_pan(patch.tremolo_auto_pan);
+
+        self.flanger_enabled = patch.flanger_enabled;
+        self.flanger.set_lfo_rate(patch.flanger_rate);
+        self.flanger.set_delay_range(patch.flanger_depth);
+        self.flanger.set_feedback(patch.flanger_feedback);
+        self.flanger.set_mix(patch.flanger_mix);
+
+        self.bitcrusher_enabled = patch.bitcrusher_enabled;
+        self.bitcrusher.set_bits(patch.bitcrusher_bits);
+        self.bitcrusher.set_downsample(patch.bitcrusher_downsample);
+        self.bitcrusher.set_mix(patch.bitcrusher_mix);
+
+        self.eq_enabled = patch.eq_enabled;
+        self.eq.set_low(patch.eq_low_gain_db, patch.eq_low_freq);
+        self.eq.set_mid(patch.eq_mid_gain_db, patch.eq_mid_freq);
+        self.eq.set_high(patch.eq_high_gain_db, patch.eq_high_freq);
+
+        self.rumble_filter_enabled = patch.rumble_filter_enabled;
+        self.rumble_filter.set_cutoff(patch.rumble_filter_freq);
+
+        self.auto_wah_enabled = patch.auto_wah_enabled;
+        self.auto_wah.set_sensitivity(patch.auto_wah_sensitivity);
+        self.auto_wah.set_range(patch.auto_wah_range);
+        self.auto_wah.set_q(patch.auto_wah_q);
+        self.auto_wah.set_mix(patch.auto_wah_mix);
+
+        if !patch.string_machine_enabled {
+            self.string_machine.release_all();
         }
+        self.string_machine.set_envelope(patch.string_attack, patch.string_release);
+        self.string_machine.set_brightness(patch.string_brightness);
+        self.string_machine.set_ensemble(patch.string_ensemble);
+
+        self.rotary_enabled = patch.rotary_enabled;
+        self.rotary.set_fast(patch.rotary_fast);
+        self.rotary.set_acceleration(patch.rotary_acceleration);
+        self.rotary.set_balance(patch.rotary_balance);
+        self.rotary.set_spread(patch.rotary_spread);
+        self.rotary.set_mix(patch.rotary_mix);
+        self.pitch_shifter_enabled = patch.pitch_shifter_enabled;
+        self.pitch_shifter.set_semitones(patch.pitch_shifter_semitones);
+        self.pitch_shifter.set_mix(patch.pitch_shifter_mix);
+        self.frequency_shifter_enabled = patch.frequency_shifter_enabled;
+        self.frequency_shifter.set_shift(patch.frequency_shifter_hz);
+        self.frequency_shifter.set_mix(patch.frequency_shifter_mix);
     }
 
-    fn process_voices(&mut self, output: &mut [f32]) {
+    // Switching into the electric piano also switches on its customary
+    // tremolo unless one is already running
+    fn set_waveform(&mut self, waveform: u8) {
+        let ep = Waveform::ElectricPiano as u8;
+        if waveform == ep && self.patch.waveform != ep && !self.tremolo_enabled {
+            self.set_tremolo(true, EP_TREMOLO_RATE, EP_TREMOLO_DEPTH, EP_TREMOLO_MIX);
+        }
+        self.patch.waveform = waveform;
         for voice in &mut self.voices {
-            if voice.is_active() {
-                voice.process(output);
-            }
+            voice.set_waveform(waveform);
         }
     }
 
-    fn process_effects(&mut self, buffer: &mut [f32]) {
-        for i in 0..buffer.len() {
-            let mut sample = buffer[i];
+    // Vector waveform XY position, 0-1 each
+    fn set_vector_position(&mut self, x: f32, y: f32) {
+        self.patch.vector_x = x.clamp(0.0, 1.0);
+        self.patch.vector_y = y.clamp(0.0, 1.0);
+        self.update_vector_position();
+    }
 
-            // Apply effects chain
-            if self.flanger_enabled {
-                sample = self.flanger.process(sample);
-            }
-            if self.tremolo_enabled {
-                sample = self.tremolo.process(sample);
-            }
-            if self.delay_enabled {
-                sample = self.delay.process(sample);
-            }
-            if self.reverb_enabled {
-                sample = self.reverb.process(sample);
-            }
+    // Waveform ids (0-7) for corners A-D; missing entries keep their setting
+    fn set_vector_sources(&mut self, sources: &[u8]) {
+        for (source, &id) in self.patch.vector_sources.iter_mut().zip(sources) {
+            *source = id;
+        }
+        for voice in &mut self.voices {
+            voice.set_vector_sources(&self.patch.vector_sources);
+        }
+    }
 
-            buffer[i] = sample;
+    // LFO circling the position (rate in Hz, radius 0-1) and the offsets the
+    // filter envelope pushes it by at its peak (-1 to 1)
+    fn set_vector_modulation(&mut self, lfo_rate: f32, lfo_depth: f32, env_x: f32, env_y: f32) {
+        self.patch.vector_lfo_rate = lfo_rate.clamp(0.01, 20.0);
+        self.patch.vector_lfo_depth = lfo_depth.clamp(0.0, 1.0);
+        self.patch.vector_env_x = env_x.clamp(-1.0, 1.0);
+        self.patch.vector_env_y = env_y.clamp(-1.0, 1.0);
+        for voice in &mut self.voices {
+            voice.set_vector_envelope(env_x, env_y);
         }
+        self.update_vector_position();
     }
-}
 
-#[wasm_bindgen]
-pub struct AudioEngine {
-    timeline_engine: Engine,
-    live_engine: Engine,
-    timeline_volume: f32,
-    live_volume: f32,
-    master_volume: f32,
-}
+    fn vector_lfo_active(&self) -> bool {
+        self.patch.waveform == Waveform::Vector as u8 && self.patch.vector_lfo_depth > 0.0
+    }
 
-#[wasm_bindgen]
-impl AudioEngine {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> AudioEngine {
-        AudioEngine {
-            timeline_engine: Engine::new(SAMPLE_RATE),
-            live_engine: Engine::new(SAMPLE_RATE),
-            timeline_volume: 0.21, // 70% of 0.3 max
-            live_volume: 0.21,     // 70% of 0.3 max
-            master_volume: 1.0,    // Master is now just a final gain stage
+    // Hands the voices the patch position plus where the LFO has got to
+    fn update_vector_position(&mut self) {
+        let (mut x, mut y) = (self.patch.vector_x, self.patch.vector_y);
+        if self.vector_lfo_active() {
+            let angle = self.vector_lfo_phase * 2.0 * std::f32::consts::PI;
+            x += angle.cos() * self.patch.vector_lfo_depth;
+            y += angle.sin() * self.patch.vector_lfo_depth;
+        }
+        for voice in &mut self.voices {
+            voice.set_vector_position(x, y);
         }
     }
 
-    pub fn process(&mut self, output: &mut [f32]) {
-        let len = output.len();
-        
-        // Process timeline engine
-        let mut timeline_buffer = vec![0.0; len];
-        self.timeline_engine.process_voices(&mut timeline_buffer);
-        self.timeline_engine.process_effects(&mut timeline_buffer);
-        
-        // Process live engine  
-        let mut live_buffer = vec![0.0; len];
-        self.live_engine.process_voices(&mut live_buffer);
-        self.live_engine.process_effects(&mut live_buffer);
-        
-        // Mix both engines with independent volumes
-        for i in 0..len {
-            output[i] = (timeline_buffer[i] * self.timeline_volume + 
-                        live_buffer[i] * self.live_volume) * self.master_volume;
+    // Levels 0-8 for the nine drawbars; missing entries keep their setting
+    fn set_organ_drawbars(&mut self, levels: &[f32]) {
+        for (drawbar, &level) in self.patch.organ_drawbars.iter_mut().zip(levels) {
+            *drawbar = level.clamp(0.0, 8.0);
+        }
+        for voice in &mut self.voices {
+            voice.set_drawbars(&self.patch.organ_drawbars);
         }
     }
 
-    // Live performance note methods (use live_engine)
-    pub fn note_on(&mut self, midi_note: u8, velocity: f32) {
-        let mut voice_idx = None;
-        
-        for (i, voice) in self.live_engine.voices.iter().enumerate() {
-            if !voice.is_active() {
-                voice_idx = Some(i);
-                break;
-            }
+    fn set_organ_percussion(&mut self, enabled: bool, harmonic: u8, fast: bool, level: f32) {
+        self.patch.organ_percussion = enabled;
+        self.patch.organ_percussion_harmonic = harmonic;
+        self.patch.organ_percussion_fast = fast;
+        self.patch.organ_percussion_level = level;
+        for voice in &mut self.voices {
+            voice.set_percussion(enabled, harmonic, fast, level);
         }
+    }
 
-        if voice_idx.is_none() {
-            let mut oldest_releasing = None;
-            let mut oldest_releasing_age = 0.0;
-            
-            for (i, voice) in self.live_engine.voices.iter().enumerate() {
-                if voice.is_releasing() && voice.get_age() > oldest_releasing_age {
-                    oldest_releasing = Some(i);
-                    oldest_releasing_age = voice.get_age();
-                }
-            }
-            
-            if oldest_releasing.is_some() {
-                voice_idx = oldest_releasing;
-            } else {
-                let mut oldest_age = 0.0;
-                for (i, voice) in self.live_engine.voices.iter().enumerate() {
-                    if voice.get_age() > oldest_age {
-                        oldest_age = voice.get_age();
-                        voice_idx = Some(i);
-                    }
-                }
-            }
+    fn set_organ_key_click(&mut self, level: f32) {
+        self.patch.organ_key_click = level;
+        for voice in &mut self.voices {
+            voice.set_key_click(level);
         }
+    }
 
-        if let Some(idx) = voice_idx {
-            let freq = midi_to_freq(midi_note);
-            self.live_engine.voices[idx].note_on(freq, velocity);
+    // One-call string ensemble: while enabled, notes play on the shared
+    // divide-down saw bank instead of the voices
+    fn set_string_machine(&mut self, enabled: bool, attack: f32, release: f32, brightness: f32, ensemble: f32) {
+        self.patch.string_machine_enabled = enabled;
+        self.patch.string_attack = attack;
+        self.patch.string_release = release;
+        self.patch.string_brightness = brightness;
+        self.patch.string_ensemble = ensemble;
+        if !enabled {
+            self.string_machine.release_all();
         }
+        self.string_machine.set_envelope(attack, release);
+        self.string_machine.set_brightness(brightness);
+        self.string_machine.set_ensemble(ensemble);
     }
 
-    pub fn note_off(&mut self, midi_note: u8) {
-        let freq = midi_to_freq(midi_note);
-        for voice in &mut self.live_engine.voices {
-            if (voice.get_frequency() - freq).abs() < 0.1 {
-                voice.note_off();
-            }
+    fn set_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        self.patch.attack = attack;
+        self.patch.decay = decay;
+        self.patch.sustain = sustain;
+        self.patch.release = release;
+        for voice in &mut self.voices {
+            voice.set_adsr(attack, decay, sustain, release);
         }
     }
 
-    // Timeline note methods (use timeline_engine)
-    pub fn timeline_note_on(&mut self, midi_note: u8, velocity: f32) {
-        let mut voice_idx = None;
-        
-        for (i, voice) in self.timeline_engine.voices.iter().enumerate() {
-            if !voice.is_active() {
-                voice_idx = Some(i);
-                break;
-            }
+    fn set_lfo_rate(&mut self, rate: f32) {
+        self.patch.lfo_rate = rate;
+        self.lfo.set_rate(rate);
+    }
+
+    fn set_lfo_depth(&mut self, depth: f32) {
+        self.patch.lfo_depth = depth;
+        self.lfo.set_depth(depth);
+    }
+
+    fn set_lfo_waveform(&mut self, waveform: u8) {
+        self.patch.lfo_waveform = waveform;
+        self.lfo.set_waveform(waveform);
+    }
+
+    fn set_lfo_mode(&mut self, mode: u8) {
+        self.patch.lfo_mode = mode.min(LfoMode::Transport as u8);
+        self.lfo.set_mode(self.patch.lfo_mode);
+    }
+
+    fn set_lfo_destinations(&mut self, delay_time: f32, reverb_size: f32, flanger_rate: f32, tremolo_depth: f32) {
+        // Hands the effects back their own settings before the new amounts
+        // take over
+        self.apply_lfo_fx(0.0);
+        self.patch.lfo_delay_time = delay_time.clamp(0.0, 1.0);
+        self.patch.lfo_reverb_size = reverb_size.clamp(0.0, 1.0);
+        self.patch.lfo_flanger_rate = flanger_rate.clamp(0.0, 1.0);
+        self.patch.lfo_tremolo_depth = tremolo_depth.clamp(0.0, 1.0);
+    }
+
+    fn lfo_fx_active(&self) -> bool {
+        let p = &self.patch;
+        p.lfo_delay_time > 0.0 || p.lfo_reverb_size > 0.0 || p.lfo_flanger_rate > 0.0 || p.lfo_tremolo_depth > 0.0
+    }
+
+    // Effect settings moved by the LFO at `shape` (-1 to 1); destinations
+    // without an amount are left alone
+    fn apply_lfo_fx(&mut self, shape: f32) {
+        let p = &self.patch;
+        if p.lfo_delay_time > 0.0 {
+            self.delay.set_time_offset(p.delay_time_ms * 0.5 * p.lfo_delay_time * shape);
         }
+        if p.lfo_reverb_size > 0.0 {
+            let size = (p.reverb_room_size + 0.5 * p.lfo_reverb_size * shape).clamp(0.0, 1.0);
+            self.reverb.set_room_size(size);
+            self.fdn_reverb.set_size(size);
+        }
+        if p.lfo_flanger_rate > 0.0 {
+            self.flanger.set_lfo_rate(p.flanger_rate * 2.0_f32.powf(2.0 * p.lfo_flanger_rate * shape));
+        }
+        if p.lfo_tremolo_depth > 0.0 {
+            self.tremolo.set_depth((p.tremolo_depth + 0.5 * p.lfo_tremolo_depth * shape).clamp(0.0, 1.0));
+        }
+    }
 
-        if voice_idx.is_none() {
-            let mut oldest_releasing = None;
-            let mut oldest_releasing_age = 0.0;
-            
-            for (i, voice) in self.timeline_engine.voices.iter().enumerate() {
-                if voice.is_releasing() && voice.get_age() > oldest_releasing_age {
-                    oldest_releasing = Some(i);
-                    oldest_releasing_age = voice.get_age();
-                }
-            }
-            
-            if oldest_releasing.is_some() {
-                voice_idx = oldest_releasing;
-            } else {
-                let mut oldest_age = 0.0;
-                for (i, voice) in self.timeline_engine.voices.iter().enumerate() {
-                    if voice.get_age() > oldest_age {
-                        oldest_age = voice.get_age();
-                        voice_idx = Some(i);
-                    }
-                }
-            }
+    fn set_detune(&mut self, cents: f32) {
+        self.patch.detune_cents = cents;
+        for voice in &mut self.voices {
+            voice.set_detune(cents);
         }
+    }
 
-        if let Some(idx) = voice_idx {
-            let freq = midi_to_freq(midi_note);
-            self.timeline_engine.voices[idx].note_on(freq, velocity);
+    fn set_glide_time(&mut self, time_ms: f32) {
+        self.patch.glide_time_ms = time_ms;
+        for voice in &mut self.voices {
+            voice.set_glide_time(time_ms);
         }
     }
 
-    pub fn timeline_note_off(&mut self, midi_note: u8) {
-        let freq = midi_to_freq(midi_note);
-        for voice in &mut self.timeline_engine.voices {
-            if (voice.get_frequency() - freq).abs() < 0.1 {
-                voice.note_off();
-            }
+    fn set_release_velocity_amount(&mut self, amount: f32) {
+        self.patch.release_velocity_amount = amount.clamp(0.0, 1.0);
+    }
+
+    fn set_velocity_random(&mut self, amount: f32) {
+        self.patch.velocity_random = amount.clamp(0.0, 1.0);
+    }
+
+    fn set_glide_mode(&mut self, mode: u8) {
+        self.patch.glide_mode = mode;
+        for voice in &mut self.voices {
+            voice.set_glide_mode(mode);
         }
     }
 
-    // Stop all timeline notes (for loop restart)
-    pub fn stop_all_timeline_notes(&mut self) {
-        for voice in &mut self.timeline_engine.voices {
-            if voice.is_active() {
-                voice.note_off();
-            }
+    fn set_env_retrigger_mode(&mut self, mode: u8) {
+        let mode = match mode {
+            RETRIGGER_SOFT | RETRIGGER_LEGATO => mode,
+            _ => RETRIGGER_FULL,
+        };
+        self.patch.env_retrigger_mode = mode;
+        for voice in &mut self.voices {
+            voice.set_retrigger_mode(mode);
         }
     }
 
-    pub fn set_master_volume(&mut self, volume: f32) {
-        self.master_volume = volume.clamp(0.0, 1.0);
+    fn set_phase_mode(&mut self, mode: u8) {
+        let mode = match mode {
+            PHASE_FREE | PHASE_RANDOM => mode,
+            _ => PHASE_RESET,
+        };
+        self.patch.phase_mode = mode;
+        for voice in &mut self.voices {
+            voice.set_phase_mode(mode);
+        }
     }
 
-    pub fn set_timeline_volume(&mut self, volume: f32) {
-        // Scale input 0-1 to output 0-0.3 (30% max to prevent clipping/distortion)
-        self.timeline_volume = (volume * 0.3).clamp(0.0, 0.3);
+    fn set_filter_enabled(&mut self, enabled: bool) {
+        self.patch.filter_enabled = enabled;
+        for voice in &mut self.voices {
+            voice.set_filter_enabled(enabled);
+        }
     }
 
-    pub fn set_live_volume(&mut self, volume: f32) {
-        // Scale input 0-1 to output 0-0.3 (30% max to prevent clipping/distortion)
-        self.live_volume = (volume * 0.3).clamp(0.0, 0.3);
+    fn set_filter_cutoff(&mut self, cutoff: f32) {
+        self.patch.filter_cutoff = cutoff;
+        self.input_filter.set_cutoff(cutoff);
+        for voice in &mut self.voices {
+            voice.set_filter_cutoff(cutoff);
+        }
     }
 
-    pub fn set_waveform(&mut self, waveform: u8) {
-        for voice in &mut self.live_engine.voices {
-            voice.set_waveform(waveform);
+    fn set_filter_resonance(&mut self, resonance: f32) {
+        self.patch.filter_resonance = resonance;
+        self.input_filter.set_resonance(resonance);
+        for voice in &mut self.voices {
+            voice.set_filter_resonance(resonance);
         }
-        // Timeline engine gets updated when pattern parameters are applied
     }
 
-    pub fn set_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
-        for voice in &mut self.live_engine.voices {
-            voice.set_adsr(attack, decay, sustain, release);
+    fn set_filter_mode(&mut self, mode: u8) {
+        self.patch.filter_mode = mode;
+        self.input_filter.set_mode(mode);
+        for voice in &mut self.voices {
+            voice.set_filter_mode(mode);
         }
     }
-    
-    // Apply synthesis settings to timeline engine (used when pattern parameters are applied)
-    pub fn set_timeline_waveform(&mut self, waveform: u8) {
-        for voice in &mut self.timeline_engine.voices {
-            voice.set_waveform(waveform);
+
+    fn set_filter_slope(&mut self, poles: u8) {
+        self.patch.filter_poles = if poles >= 4 { 4 } else { 2 };
+        self.input_filter.set_poles(poles);
+        for voice in &mut self.voices {
+            voice.set_filter_poles(poles);
         }
     }
 
-    pub fn set_timeline_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
-        for voice in &mut self.timeline_engine.voices {
-            voice.set_adsr(attack, decay, sustain, release);
+    fn set_filter_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32, amount_octaves: f32) {
+        self.patch.filter_env_attack = attack;
+        self.patch.filter_env_decay = decay;
+        self.patch.filter_env_sustain = sustain;
+        self.patch.filter_env_release = release;
+        self.patch.filter_env_amount = amount_octaves;
+        for voice in &mut self.voices {
+            voice.set_filter_envelope(attack, decay, sustain, release, amount_octaves);
+        }
+    }
+
+    fn set_pitch_envelope(&mut self, attack: f32, decay: f32, amount_semitones: f32) {
+        self.patch.pitch_env_attack = attack;
+        self.patch.pitch_env_decay = decay;
+        self.patch.pitch_env_amount = amount_semitones;
+        for voice in &mut self.voices {
+            voice.set_pitch_envelope(attack, decay, amount_semitones);
+        }
+    }
+
+    fn set_drive(&mut self, drive: f32) {
+        self.patch.drive = drive;
+        for voice in &mut self.voices {
+            voice.set_drive(drive);
         }
     }
 
+    // Brass stab built on the filter envelope: detuned saw stack, snappy
+    // cutoff sweep and a little drive. Brightness (0-1) opens the filter
+    // and deepens the sweep; tightness (0-1) shortens everything.
+    fn set_brass_stab(&mut self, brightness: f32, tightness: f32) {
+        let brightness = brightness.clamp(0.0, 1.0);
+        let tightness = tightness.clamp(0.0, 1.0);
+        let decay = 0.6 - tightness * 0.5;
 
-    // Live engine LFO controls
-    pub fn set_lfo_rate(&mut self, rate: f32) {
-        self.live_engine.lfo.set_rate(rate);
+        self.set_waveform(Waveform::Sawtooth as u8);
+        self.set_unison(3, 8.0, 0.1);
+        self.set_adsr(0.005, decay, 0.6 - tightness * 0.35, 0.25 - tightness * 0.15);
+        self.set_filter_enabled(true);
+        self.set_filter_mode(0);
+        self.set_filter_resonance(0.15);
+        self.set_filter_cutoff(250.0 + brightness * 1250.0);
+        self.set_filter_envelope(0.01 + (1.0 - tightness) * 0.04, decay, 0.2, 0.2, 2.0 + brightness * 3.0);
+        self.set_drive(0.35);
     }
 
-    pub fn set_lfo_depth(&mut self, depth: f32) {
-        self.live_engine.lfo.set_depth(depth);
+    fn set_ghost_layer(&mut self, threshold: f32, tone_hz: f32, length: f32) {
+        self.patch.ghost_threshold = threshold;
+        self.patch.ghost_tone = tone_hz;
+        self.patch.ghost_length = length;
+        for voice in &mut self.voices {
+            voice.set_ghost_layer(threshold, tone_hz, length);
+        }
     }
 
-    pub fn set_lfo_waveform(&mut self, waveform: u8) {
-        self.live_engine.lfo.set_waveform(waveform);
+    // Motion macro, 0-1: slow pan sway, cutoff breathing and per-voice pitch
+    // drift, with reverb and delay mixes raised to match. Turning it back to
+    // zero stops the movement but leaves the effects as they are.
+    fn set_motion(&mut self, amount: f32) {
+        let amount = amount.clamp(0.0, 1.0);
+        self.patch.motion_amount = amount;
+        self.motion.set_amount(amount);
+        if amount > 0.0 {
+            let patch = self.patch;
+            self.set_reverb(true, patch.reverb_room_size, patch.reverb_damping, 0.2 + amount * 0.3);
+            self.set_delay(true, patch.delay_time_ms, patch.delay_feedback, amount * 0.25);
+        } else if !self.smooth_random.is_active() {
+            self.reset_motion();
+        }
     }
 
-    // Timeline engine LFO controls
-    pub fn set_timeline_lfo_rate(&mut self, rate: f32) {
-        self.timeline_engine.lfo.set_rate(rate);
+    // Smooth random drift of cutoff, per-voice pitch and the delay and
+    // reverb mixes. All depths at zero turns it off.
+    fn set_random_modulation(&mut self, rate: f32, cutoff_octaves: f32, pitch_cents: f32, fx_mix: f32) {
+        self.patch.random_rate = rate;
+        self.patch.random_cutoff_octaves = cutoff_octaves;
+        self.patch.random_pitch_cents = pitch_cents;
+        self.patch.random_fx_mix = fx_mix;
+        self.smooth_random.set_rate(rate);
+        self.smooth_random.set_amounts(cutoff_octaves, pitch_cents, fx_mix);
+        if !self.smooth_random.is_active() && !self.motion.is_active() {
+            self.reset_motion();
+        }
+        if fx_mix == 0.0 {
+            self.apply_fx_mix_offset(0.0);
+        }
     }
 
-    pub fn set_timeline_lfo_depth(&mut self, depth: f32) {
-        self.timeline_engine.lfo.set_depth(depth);
+    // Delay and reverb mixes moved by `offset` from the patch's
+    fn apply_fx_mix_offset(&mut self, offset: f32) {
+        self.delay.set_mix((self.patch.delay_mix + offset).clamp(0.0, 1.0));
+        self.set_reverb_mix((self.patch.reverb_mix + offset).clamp(0.0, 1.0));
     }
 
-    pub fn set_timeline_lfo_waveform(&mut self, waveform: u8) {
-        self.timeline_engine.lfo.set_waveform(waveform);
+    fn set_pressure_modulation(&mut self, vibrato_cents: f32, cutoff_octaves: f32, volume: f32) {
+        self.patch.pressure_vibrato_cents = vibrato_cents;
+        self.patch.pressure_cutoff_octaves = cutoff_octaves;
+        self.patch.pressure_volume = volume;
+        self.pressure.set_amounts(vibrato_cents, cutoff_octaves, volume);
+        for voice in &mut self.voices {
+            voice.set_pressure_amounts(vibrato_cents, cutoff_octaves, volume);
+        }
     }
 
+    fn reset_motion(&mut self) {
+        for voice in &mut self.voices {
+            voice.set_motion(0.0, 0.0);
+        }
+    }
 
-    // Live engine detune
-    pub fn set_detune(&mut self, cents: f32) {
-        self.live_engine.detune_cents = cents;
-        for voice in &mut self.live_engine.voices {
-            voice.set_detune(cents);
+    fn set_unison(&mut self, voices: u8, detune_cents: f32, filter_spread: f32) {
+        self.patch.unison_voices = voices;
+        self.patch.unison_detune = detune_cents;
+        self.patch.unison_filter_spread = filter_spread;
+        let unison_voices = self.unison_voices();
+        for voice in &mut self.voices {
+            voice.set_unison(unison_voices, detune_cents, filter_spread);
         }
     }
 
-    pub fn set_glide_time(&mut self, time_ms: f32) {
-        for voice in &mut self.live_engine.voices {
-            voice.set_glide_time(time_ms);
+    // Patch unison count capped by the governor
+    fn unison_voices(&self) -> usize {
+        self.patch.unison_voices.min(self.unison_limit) as usize
+    }
+
+    // Applies governor limits. Held voices beyond the new polyphony are
+    // released, inner chord voices first; returns how many were released.
+    fn set_oversampling(&mut self, factor: u32) {
+        for voice in &mut self.voices {
+            voice.set_oversampling(factor);
         }
     }
 
-    // Timeline engine detune
-    pub fn set_timeline_detune(&mut self, cents: f32) {
-        self.timeline_engine.detune_cents = cents;
-        for voice in &mut self.timeline_engine.voices {
-            voice.set_detune(cents);
+    fn set_reverb_density(&mut self, density: u8) {
+        self.fdn_reverb.set_density(density);
+    }
+
+    fn set_pan_law(&mut self, law: PanLaw) {
+        self.pan_law = law;
+        self.tremolo.set_pan_law(law);
+    }
+
+    // Delay the voices add before the mix, in samples
+    fn latency(&self) -> f32 {
+        self.voices.iter().map(Voice::latency).fold(0.0, f32::max)
+    }
+
+    fn set_voice_limits(&mut self, unison: u8, polyphony: u8) -> u32 {
+        self.unison_limit = unison;
+        self.polyphony_limit = polyphony as usize;
+        let patch = self.patch;
+        self.set_unison(patch.unison_voices, patch.unison_detune, patch.unison_filter_spread);
+
+        let mut released = 0;
+        while self.held_voices() > self.polyphony_limit {
+            let Some(idx) = self.oldest_inner_voice() else {
+                break;
+            };
+            self.voices[idx].note_off();
+            released += 1;
         }
+        released
     }
 
-    pub fn set_timeline_glide_time(&mut self, time_ms: f32) {
-        for voice in &mut self.timeline_engine.voices {
-            voice.set_glide_time(time_ms);
+    fn is_governed(&self) -> bool {
+        self.unison_limit < MAX_UNISON as u8 || self.polyphony_limit < MAX_VOICES_PER_ENGINE
+    }
+
+    fn set_delay(&mut self, enabled: bool, time_ms: f32, feedback: f32, mix: f32) {
+        self.delay_enabled = enabled;
+        self.patch.delay_enabled = enabled;
+        if enabled {
+            self.patch.delay_time_ms = time_ms;
+            self.patch.delay_feedback = feedback;
+            self.patch.delay_mix = mix;
+            self.delay.set_delay_time(time_ms);
+            self.delay.set_feedback(feedback);
+            self.delay.set_mix(mix);
+        }
+    }
+
+    fn set_delay_mode(&mut self, mode: u8) {
+        self.patch.delay_mode = mode;
+        self.delay.set_mode(mode);
+    }
+
+    fn set_delay_tone(&mut self, low_cut: f32, high_cut: f32) {
+        self.patch.delay_low_cut = low_cut;
+        self.patch.delay_high_cut = high_cut;
+        self.delay.set_tone(low_cut, high_cut);
+    }
+
+    fn set_delay_stereo(&mut self, right_time_ms: f32, cross_feedback: f32) {
+        self.patch.delay_right_time_ms = right_time_ms;
+        self.patch.delay_cross_feedback = cross_feedback;
+        self.delay.set_right_delay_time(right_time_ms);
+        self.delay.set_cross_feedback(cross_feedback);
+    }
+
+    fn set_delay_modulation(&mut self, depth_ms: f32, rate: f32) {
+        self.patch.delay_mod_depth_ms = depth_ms;
+        self.patch.delay_mod_rate = rate;
+        self.delay.set_modulation(depth_ms, rate);
+    }
+
+    fn set_velocity_curve(&mut self, curve: f32) {
+        self.patch.velocity_curve = curve.clamp(-1.0, 1.0);
+        for voice in &mut self.voices {
+            voice.set_velocity_curve(curve);
         }
     }
 
-    // ==== LIVE ENGINE EFFECTS CONTROL ====
+    fn set_gain_staging(&mut self, gain_db: f32, normalize: bool) {
+        self.patch.output_gain_db = gain_db.clamp(-24.0, 12.0);
+        self.patch.voice_normalize = normalize;
+    }
+
+    fn set_reverb_low_cut(&mut self, freq: f32) {
+        self.patch.reverb_low_cut = freq;
+        self.reverb.set_low_cut(freq);
+        self.convolution.set_low_cut(freq);
+        self.fdn_reverb.set_low_cut(freq);
+    }
 
-    pub fn set_delay(&mut self, enabled: bool, time_ms: f32, feedback: f32, mix: f32) {
-        self.live_engine.delay_enabled = enabled;
+    fn set_reverb(&mut self, enabled: bool, room_size: f32, damping: f32, mix: f32) {
+        self.reverb_enabled = enabled;
+        self.patch.reverb_enabled = enabled;
         if enabled {
-            self.live_engine.delay.set_delay_time(time_ms);
-            self.live_engine.delay.set_feedback(feedback);
-            self.live_engine.delay.set_mix(mix);
+            self.patch.reverb_room_size = room_size;
+            self.patch.reverb_damping = damping;
+            self.patch.reverb_mix = mix;
+            self.reverb.set_room_size(room_size);
+            self.reverb.set_damping(damping);
+            self.fdn_reverb.set_size(room_size);
+            self.fdn_reverb.set_damping(damping);
+            self.set_reverb_mix(mix);
         }
     }
 
-    pub fn set_reverb(&mut self, enabled: bool, room_size: f32, damping: f32) {
-        self.live_engine.reverb_enabled = enabled;
+    // Every reverb algorithm shares the patch's mix
+    fn set_reverb_mix(&mut self, mix: f32) {
+        self.reverb.set_mix(mix);
+        self.convolution.set_mix(mix);
+        self.fdn_reverb.set_mix(mix);
+    }
+
+    fn set_reverb_freeze(&mut self, frozen: bool) {
+        self.reverb.set_freeze(frozen);
+        self.convolution.set_freeze(frozen);
+        self.fdn_reverb.set_freeze(frozen);
+    }
+
+    fn set_reverb_character(&mut self, pre_delay_ms: f32, diffusion: f32, modulation: f32) {
+        self.patch.reverb_pre_delay_ms = pre_delay_ms;
+        self.patch.reverb_diffusion = diffusion;
+        self.patch.reverb_modulation = modulation;
+        self.fdn_reverb.set_pre_delay(pre_delay_ms);
+        self.fdn_reverb.set_diffusion(diffusion);
+        self.fdn_reverb.set_modulation(modulation);
+    }
+
+    fn set_reverb_type(&mut self, reverb_type: u8) {
+        self.reverb_type = reverb_type;
+        self.patch.reverb_type = reverb_type;
+        self.update_shimmer();
+    }
+
+    fn set_reverb_shimmer(&mut self, amount: f32) {
+        self.patch.reverb_shimmer = amount;
+        self.update_shimmer();
+    }
+
+    // The shimmer type is the FDN with its pitch-shifted feedback switched on
+    fn update_shimmer(&mut self) {
+        let amount = if self.reverb_type == REVERB_SHIMMER { self.patch.reverb_shimmer } else { 0.0 };
+        self.fdn_reverb.set_shimmer(amount);
+    }
+
+    fn set_tremolo(&mut self, enabled: bool, rate: f32, depth: f32, mix: f32) {
+        self.tremolo_enabled = enabled;
+        self.patch.tremolo_enabled = enabled;
         if enabled {
-            self.live_engine.reverb.set_room_size(room_size);
-            self.live_engine.reverb.set_damping(damping);
+            self.patch.tremolo_rate = rate;
+            self.patch.tremolo_depth = depth;
+            self.patch.tremolo_mix = mix;
+            self.tremolo.set_rate(rate);
+            self.tremolo.set_depth(depth);
+            self.tremolo.set_mix(mix);
         }
     }
 
-    pub fn set_tremolo(&mut self, enabled: bool, rate: f32, depth: f32) {
-        self.live_engine.tremolo_enabled = enabled;
+    fn set_tremolo_shape(&mut self, waveform: u8, auto_pan: bool) {
+        self.patch.tremolo_waveform = waveform.min(2);
+        self.patch.tremolo_auto_pan = auto_pan;
+        self.tremolo.set_waveform(waveform);
+        self.tremolo.set_auto_pan(auto_pan);
+    }
+
+    fn set_flanger(&mut self, enabled: bool, rate: f32, depth: f32, feedback: f32, mix: f32) {
+        self.flanger_enabled = enabled;
+        self.patch.flanger_enabled = enabled;
         if enabled {
-            self.live_engine.tremolo.set_rate(rate);
-            self.live_engine.tremolo.set_depth(depth);
+            self.patch.flanger_rate = rate;
+            self.patch.flanger_depth = depth;
+            self.patch.flanger_feedback = feedback;
+            self.patch.flanger_mix = mix;
+            self.flanger.set_lfo_rate(rate);
+            self.flanger.set_delay_range(depth);
+            self.flanger.set_feedback(feedback);
+            self.flanger.set_mix(mix);
         }
     }
 
-    pub fn set_flanger(&mut self, enabled: bool, rate: f32, depth: f32, feedback: f32, mix: f32) {
-        self.live_engine.flanger_enabled = enabled;
+    fn set_bitcrusher(&mut self, enabled: bool, bits: f32, downsample: f32, mix: f32) {
+        self.bitcrusher_enabled = enabled;
+        self.patch.bitcrusher_enabled = enabled;
         if enabled {
-            self.live_engine.flanger.set_lfo_rate(rate);
-            self.live_engine.flanger.set_delay_range(depth);
-            self.live_engine.flanger.set_feedback(feedback);
-            self.live_engine.flanger.set_mix(mix);
+            self.patch.bitcrusher_bits = bits;
+            self.patch.bitcrusher_downsample = downsample;
+            self.patch.bitcrusher_mix = mix;
+            self.bitcrusher.set_bits(bits);
+            self.bitcrusher.set_downsample(downsample);
+            self.bitcrusher.set_mix(mix);
         }
     }
 
-    // ==== TIMELINE ENGINE EFFECTS CONTROL ====
+    #[allow(clippy::too_many_arguments)]
+    fn set_eq(&mut self, enabled: bool, low_gain_db: f32, low_freq: f32, mid_gain_db: f32, mid_freq: f32, high_gain_db: f32, high_freq: f32) {
+        self.eq_enabled = enabled;
+        self.patch.eq_enabled = enabled;
+        if enabled {
+            self.patch.eq_low_gain_db = low_gain_db;
+            self.patch.eq_low_freq = low_freq;
+            self.patch.eq_mid_gain_db = mid_gain_db;
+            self.patch.eq_mid_freq = mid_freq;
+            self.patch.eq_high_gain_db = high_gain_db;
+            self.patch.eq_high_freq = high_freq;
+            self.eq.set_low(low_gain_db, low_freq);
+            self.eq.set_mid(mid_gain_db, mid_freq);
+            self.eq.set_high(high_gain_db, high_freq);
+        }
+    }
 
-    pub fn set_timeline_delay(&mut self, enabled: bool, time_ms: f32, feedback: f32, mix: f32) {
-        self.timeline_engine.delay_enabled = enabled;
+    fn set_rumble_filter(&mut self, enabled: bool, freq: f32) {
+        self.rumble_filter_enabled = enabled;
+        self.patch.rumble_filter_enabled = enabled;
         if enabled {
-            self.timeline_engine.delay.set_delay_time(time_ms);
-            self.timeline_engine.delay.set_feedback(feedback);
-            self.timeline_engine.delay.set_mix(mix);
+            self.patch.rumble_filter_freq = freq;
+            self.rumble_filter.set_cutoff(freq);
         }
     }
 
-    pub fn set_timeline_reverb(&mut self, enabled: bool, room_size: f32, damping: f32) {
-        self.timeline_engine.reverb_enabled = enabled;
+    fn set_auto_wah(&mut self, enabled: bool, sensitivity: f32, range: f32, q: f32, mix: f32) {
+        self.auto_wah_enabled = enabled;
+        self.patch.auto_wah_enabled = enabled;
         if enabled {
-            self.timeline_engine.reverb.set_room_size(room_size);
-            self.timeline_engine.reverb.set_damping(damping);
+            self.patch.auto_wah_sensitivity = sensitivity;
+            self.patch.auto_wah_range = range;
+            self.patch.auto_wah_q = q;
+            self.patch.auto_wah_mix = mix;
+            self.auto_wah.set_sensitivity(sensitivity);
+            self.auto_wah.set_range(range);
+            self.auto_wah.set_q(q);
+            self.auto_wah.set_mix(mix);
         }
     }
 
-    pub fn set_timeline_tremolo(&mut self, enabled: bool, rate: f32, depth: f32) {
-        self.timeline_engine.tremolo_enabled = enabled;
+    fn set_rotary(&mut self, enabled: bool, fast: bool, acceleration: f32, balance: f32, spread: f32, mix: f32) {
+        self.rotary_enabled = enabled;
+        self.patch.rotary_enabled = enabled;
         if enabled {
-            self.timeline_engine.tremolo.set_rate(rate);
-            self.timeline_engine.tremolo.set_depth(depth);
+            self.patch.rotary_fast = fast;
+            self.patch.rotary_acceleration = acceleration;
+            self.patch.rotary_balance = balance;
+            self.patch.rotary_spread = spread;
+            self.patch.rotary_mix = mix;
+            self.rotary.set_fast(fast);
+            self.rotary.set_acceleration(acceleration);
+            self.rotary.set_balance(balance);
+            self.rotary.set_spread(spread);
+            self.rotary.set_mix(mix);
         }
     }
 
-    pub fn set_timeline_flanger(&mut self, enabled: bool, rate: f32, depth: f32, feedback: f32, mix: f32) {
-        self.timeline_engine.flanger_enabled = enabled;
+    fn set_pitch_shifter(&mut self, enabled: bool, semitones: f32, mix: f32) {
+        self.pitch_shifter_enabled = enabled;
+        self.patch.pitch_shifter_enabled = enabled;
         if enabled {
-            self.timeline_engine.flanger.set_lfo_rate(rate);
-            self.timeline_engine.flanger.set_delay_range(depth);
-            self.timeline_engine.flanger.set_feedback(feedback);
-            self.timeline_engine.flanger.set_mix(mix);
+            self.patch.pitch_shifter_semitones = semitones;
+            self.patch.pitch_shifter_mix = mix;
+            self.pitch_shifter.set_semitones(semitones);
+            self.pitch_shifter.set_mix(mix);
         }
     }
 
-    pub fn get_sample_rate(&self) -> f32 {
-        SAMPLE_RATE
+    fn set_frequency_shifter(&mut self, enabled: bool, shift_hz: f32, mix: f32) {
+        self.frequency_shifter_enabled = enabled;
+        self.patch.frequency_shifter_enabled = enabled;
+        if enabled {
+            self.patch.frequency_shifter_hz = shift_hz;
+            self.patch.frequency_shifter_mix = mix;
+            self.frequency_shifter.set_shift(shift_hz);
+            self.frequency_shifter.set_mix(mix);
+        }
+    }
+
+    // Any permutation of the EFFECT_* ids; see effects::effect_order
+    fn set_effect_order(&mut self, order: &[u8]) {
+        self.patch.effect_order = effects::effect_order(order);
+    }
+
+    fn store_fx_snapshot(&mut self, slot: usize) -> bool {
+        self.fx_ab.store(slot, &self.patch)
+    }
+
+    // Recalls the other slot's effect settings, keeping the voice as it is
+    fn toggle_fx_ab(&mut self) -> bool {
+        let Some(snapshot) = self.fx_ab.toggle() else {
+            return false;
+        };
+        self.apply_patch(&self.patch.with_effects_of(&snapshot));
+        true
+    }
+
+    // One parameter by id, through the same setter its dedicated method
+    // uses so only the affected DSP is touched. The value goes onto the
+    // patch first, so a bypassed effect, whose setter ignores its settings,
+    // still picks it up when switched on.
+    fn set_param(&mut self, param: ParamId, value: f32) {
+        use ParamId::*;
+        let previous_waveform = self.patch.waveform;
+        self.patch.set_param(param, value);
+        let p = self.patch;
+        match param {
+            // The electric piano's automatic tremolo looks at the waveform
+            // being switched from
+            Waveform => {
+                self.patch.waveform = previous_waveform;
+                self.set_waveform(p.waveform);
+            }
+            Attack | Decay | Sustain | Release => self.set_adsr(p.attack, p.decay, p.sustain, p.release),
+            LfoRate => self.set_lfo_rate(p.lfo_rate),
+            LfoDepth => self.set_lfo_depth(p.lfo_depth),
+            LfoWaveform => self.set_lfo_waveform(p.lfo_waveform),
+            LfoMode => self.set_lfo_mode(p.lfo_mode),
+            LfoDelayTime | LfoReverbSize | LfoFlangerRate | LfoTremoloDepth => self.set_lfo_destinations(
+                p.lfo_delay_time,
+                p.lfo_reverb_size,
+                p.lfo_flanger_rate,
+                p.lfo_tremolo_depth,
+            ),
+            DetuneCents => self.set_detune(p.detune_cents),
+            GlideTimeMs => self.set_glide_time(p.glide_time_ms),
+            DelayEnabled | DelayTimeMs | DelayFeedback | DelayMix => {
+                self.set_delay(p.delay_enabled, p.delay_time_ms, p.delay_feedback, p.delay_mix)
+            }
+            DelayMode => self.set_delay_mode(p.delay_mode),
+            DelayLowCut | DelayHighCut => self.set_delay_tone(p.delay_low_cut, p.delay_high_cut),
+            ReverbLowCut => self.set_reverb_low_cut(p.reverb_low_cut),
+            DelayRightTimeMs | DelayCrossFeedback => self.set_delay_stereo(p.delay_right_time_ms, p.delay_cross_feedback),
+            DelayModDepthMs | DelayModRate => self.set_delay_modulation(p.delay_mod_depth_ms, p.delay_mod_rate),
+            VelocityCurve => self.set_velocity_curve(p.velocity_curve),
+            OutputGainDb | VoiceNormalize => self.set_gain_staging(p.output_gain_db, p.voice_normalize),
+            ReverbEnabled | ReverbRoomSize | ReverbDamping | ReverbMix => {
+                self.set_reverb(p.reverb_enabled, p.reverb_room_size, p.reverb_damping, p.reverb_mix)
+            }
+            ReverbType => self.set_reverb_type(p.reverb_type),
+            ReverbPreDelayMs | ReverbDiffusion | ReverbModulation => {
+                self.set_reverb_character(p.reverb_pre_delay_ms, p.reverb_diffusion, p.reverb_modulation)
+            }
+            ReverbShimmer => self.set_reverb_shimmer(p.reverb_shimmer),
+            TremoloEnabled | TremoloRate | TremoloDepth | TremoloMix => {
+                self.set_tremolo(p.tremolo_enabled, p.tremolo_rate, p.tremolo_depth, p.tremolo_mix)
+            }
+            TremoloWaveform | TremoloAutoPan => self.set_tremolo_shape(p.tremolo_waveform, p.tremolo_auto_pan),
+            FlangerEnabled | FlangerRate | FlangerDepth | FlangerFeedback | FlangerMix => {
+                self.set_flanger(p.flanger_enabled, p.flanger_rate, p.flanger_depth, p.flanger_feedback, p.flanger_mix)
+            }
+            BitcrusherEnabled | BitcrusherBits | BitcrusherDownsample | BitcrusherMix => {
+                self.set_bitcrusher(p.bitcrusher_enabled, p.bitcrusher_bits, p.bitcrusher_downsample, p.bitcrusher_mix)
+            }
+            EqEnabled | EqLowGainDb | EqLowFreq | EqMidGainDb | EqMidFreq | EqHighGainDb | EqHighFreq => self.set_eq(
+                p.eq_enabled,
+                p.eq_low_gain_db,
+                p.eq_low_freq,
+                p.eq_mid_gain_db,
+                p.eq_mid_freq,
+                p.eq_high_gain_db,
+                p.eq_high_freq,
+            ),
+            FilterEnabled => self.set_filter_enabled(p.filter_enabled),
+            FilterCutoff => self.set_filter_cutoff(p.filter_cutoff),
+            FilterResonance => self.set_filter_resonance(p.filter_resonance),
+            FilterMode => self.set_filter_mode(p.filter_mode),
+            FilterPoles => self.set_filter_slope(p.filter_poles),
+            PhaseMode => self.set_phase_mode(p.phase_mode),
+            VelocityRandom => self.set_velocity_random(p.velocity_random),
+            UnisonVoices | UnisonDetune | UnisonFilterSpread => {
+                self.set_unison(p.unison_voices, p.unison_detune, p.unison_filter_spread)
+            }
+            RumbleFilterEnabled | RumbleFilterFreq => self.set_rumble_filter(p.rumble_filter_enabled, p.rumble_filter_freq),
+            ProtectLowestNote => self.set_protect_lowest_note(p.protect_lowest_note),
+            AutoWahEnabled | AutoWahSensitivity | AutoWahRange | AutoWahQ | AutoWahMix => self.set_auto_wah(
+                p.auto_wah_enabled,
+                p.auto_wah_sensitivity,
+                p.auto_wah_range,
+                p.auto_wah_q,
+                p.auto_wah_mix,
+            ),
+            RotaryEnabled | RotaryFast | RotaryAcceleration | RotaryBalance | RotarySpread | RotaryMix => self.set_rotary(
+                p.rotary_enabled,
+                p.rotary_fast,
+                p.rotary_acceleration,
+                p.rotary_balance,
+                p.rotary_spread,
+                p.rotary_mix,
+            ),
+            OrganDrawbar1 | OrganDrawbar2 | OrganDrawbar3 | OrganDrawbar4 | OrganDrawbar5 | OrganDrawbar6
+            | OrganDrawbar7 | OrganDrawbar8 | OrganDrawbar9 => self.set_organ_drawbars(&p.organ_drawbars),
+            VectorX | VectorY => self.set_vector_position(p.vector_x, p.vector_y),
+            VectorSourceA | VectorSourceB | VectorSourceC | VectorSourceD => self.set_vector_sources(&p.vector_sources),
+            VectorLfoRate | VectorLfoDepth | VectorEnvX | VectorEnvY => {
+                self.set_vector_modulation(p.vector_lfo_rate, p.vector_lfo_depth, p.vector_env_x, p.vector_env_y)
+            }
+            OrganPercussion | OrganPercussionHarmonic | OrganPercussionFast | OrganPercussionLevel => self
+                .set_organ_percussion(
+                    p.organ_percussion,
+                    p.organ_percussion_harmonic,
+                    p.organ_percussion_fast,
+                    p.organ_percussion_level,
+                ),
+            OrganKeyClick => self.set_organ_key_click(p.organ_key_click),
+            StringMachineEnabled | StringAttack | StringRelease | StringBrightness | StringEnsemble => self
+                .set_string_machine(
+                    p.string_machine_enabled,
+                    p.string_attack,
+                    p.string_release,
+                    p.string_brightness,
+                    p.string_ensemble,
+                ),
+            FilterEnvAttack | FilterEnvDecay | FilterEnvSustain | FilterEnvRelease | FilterEnvAmount => self
+                .set_filter_envelope(
+                    p.filter_env_attack,
+                    p.filter_env_decay,
+                    p.filter_env_sustain,
+                    p.filter_env_release,
+                    p.filter_env_amount,
+                ),
+            PitchEnvAttack | PitchEnvDecay | PitchEnvAmount => {
+                self.set_pitch_envelope(p.pitch_env_attack, p.pitch_env_decay, p.pitch_env_amount)
+            }
+            Drive => self.set_drive(p.drive),
+            PitchShifterEnabled | PitchShifterSemitones | PitchShifterMix => {
+                self.set_pitch_shifter(p.pitch_shifter_enabled, p.pitch_shifter_semitones, p.pitch_shifter_mix)
+            }
+            FrequencyShifterEnabled | FrequencyShifterHz | FrequencyShifterMix => self.set_frequency_shifter(
+                p.frequency_shifter_enabled,
+                p.frequency_shifter_hz,
+                p.frequency_shifter_mix,
+            ),
+            MotionAmount => self.set_motion(p.motion_amount),
+            MonoMelody => {}
+            GhostThreshold | GhostTone | GhostLength => self.set_ghost_layer(p.ghost_threshold, p.ghost_tone, p.ghost_length),
+            GlideMode => self.set_glide_mode(p.glide_mode),
+            EnvRetriggerMode => self.set_env_retrigger_mode(p.env_retrigger_mode),
+            ReleaseVelocityAmount => self.set_release_velocity_amount(p.release_velocity_amount),
+            PressureVibratoCents | PressureCutoffOctaves | PressureVolume => {
+                self.set_pressure_modulation(p.pressure_vibrato_cents, p.pressure_cutoff_octaves, p.pressure_volume)
+            }
+            RandomRate | RandomCutoffOctaves | RandomPitchCents | RandomFxMix => self.set_random_modulation(
+                p.random_rate,
+                p.random_cutoff_octaves,
+                p.random_pitch_cents,
+                p.random_fx_mix,
+            ),
+            Pan => self.pan = value.clamp(-1.0, 1.0),
+            DelaySend => self.delay_send = value.clamp(0.0, 1.0),
+            ReverbSend => self.reverb_send = value.clamp(0.0, 1.0),
+            Volume => {}
+        }
     }
-}
 
-fn midi_to_freq(midi: u8) -> f32 {
-    440.0 * 2.0_f32.powf((midi as f32 - 69.0) / 12.0)
+    fn param(&self, param: ParamId) -> Option<f32> {
+        match param {
+            ParamId::Pan => Some(self.pan),
+            ParamId::DelaySend => Some(self.delay_send),
+            ParamId::ReverbSend => Some(self.reverb_send),
+            _ => self.patch.param(param),
+        }
+    }
+
+    // The chain slot for an effect id, or None while that effect is bypassed
+    fn effect_mut(&mut self, id: u8) -> Option<&mut dyn Effect> {
+        match id {
+            EFFECT_BITCRUSHER if self.bitcrusher_enabled => Some(&mut self.bitcrusher),
+            EFFECT_FLANGER if self.flanger_enabled => Some(&mut self.flanger),
+            EFFECT_TREMOLO if self.tremolo_enabled => Some(&mut self.tremolo),
+            EFFECT_EQ if self.eq_enabled => Some(&mut self.eq),
+            EFFECT_DELAY if self.delay_enabled => Some(&mut self.delay),
+            EFFECT_AUTO_WAH if self.auto_wah_enabled => Some(&mut self.auto_wah),
+            EFFECT_ROTARY if self.rotary_enabled => Some(&mut self.rotary),
+            EFFECT_PITCH_SHIFTER if self.pitch_shifter_enabled => Some(&mut self.pitch_shifter),
+            EFFECT_FREQUENCY_SHIFTER if self.frequency_shifter_enabled => Some(&mut self.frequency_shifter),
+            EFFECT_REVERB if self.reverb_enabled => Some(match self.reverb_type {
+                REVERB_CONVOLUTION => &mut self.convolution,
+                REVERB_FDN | REVERB_SHIMMER => &mut self.fdn_reverb,
+                _ => &mut self.reverb,
+            }),
+            _ => None,
+        }
+    }
+
+    fn note_on(&mut self, midi_note: u8, velocity: f32) {
+        // A NaN velocity would reach every voice parameter it scales
+        if !velocity.is_finite() {
+            return;
+        }
+        if self.filter_keyboard {
+            self.filter_keys.retain(|&n| n != midi_note);
+            self.filter_keys.push(midi_note);
+            self.filter_key_glide.set_target(self.note_frequency(midi_note));
+            return;
+        }
+        let velocity = if self.patch.velocity_random > 0.0 {
+            (velocity * (1.0 + self.patch.velocity_random * self.velocity_random.bipolar())).clamp(0.0, 1.0)
+        } else {
+            velocity
+        };
+        // Taken before any trimming below, for legato-only glide
+        let legato = self.held_voices() > 0;
+        // A new chord ends the one being held over
+        if !self.sustained_notes.is_empty() {
+            self.release_sustained(Some(self.auto_sustain_crossfade));
+        }
+        let group = self.choke_groups[midi_note as usize & 127];
+        if group != 0 {
+            self.choke(group);
+        }
+        // Last-note priority: whatever is still sounding gets trimmed
+        if self.patch.mono_melody {
+            for voice in self.voices.iter_mut().filter(|v| v.is_active()) {
+                voice.fade_out(MONO_TRIM_FADE);
+            }
+        }
+        if self.patch.string_machine_enabled {
+            self.string_machine.note_on(midi_note);
+            return;
+        }
+        if self.lfo.mode() == LfoMode::OneShot {
+            self.lfo.retrigger();
+        }
+        let retriggered = self.retrigger_voice(midi_note);
+        if let Some(idx) = retriggered.or_else(|| self.allocate_voice()) {
+            let voice = &self.voices[idx];
+            if retriggered.is_none() && voice.is_active() {
+                self.events.push(EVENT_VOICE_STOLEN, voice.note() as f32, midi_note as f32);
+            }
+            let freq = self.note_frequency(midi_note);
+            self.note_count += 1;
+            self.voices[idx].note_on(midi_note, self.note_count, freq, velocity, legato);
+            self.events.push(EVENT_NOTE_STARTED, midi_note as f32, velocity);
+            self.sounding |= 1 << idx;
+        }
+    }
+
+    fn note_off(&mut self, midi_note: u8) {
+        self.note_off_with_velocity(midi_note, None);
+    }
+
+    // Moves the held voices onto a new chord: pairs of (held voice, new
+    // note) are matched closest first and the voice glides to its note over
+    // `time_ms`. Held voices left over are released; notes left over start
+    // fresh at the average velocity of the chord they replace.
+    fn glide_to_chord(&mut self, notes: &[u8], time_ms: f32) {
+        let held: Vec<usize> = (0..self.voices.len())
+            .filter(|&i| self.voices[i].is_active() && !self.voices[i].is_releasing())
+            .collect();
+        let mut pairs: Vec<(u8, usize, usize)> = Vec::with_capacity(held.len() * notes.len());
+        for &voice in &held {
+            for (index, &note) in notes.iter().enumerate() {
+                pairs.push((self.voices[voice].note().abs_diff(note), voice, index));
+            }
+        }
+        pairs.sort_unstable();
+
+        let mut voice_moved = [false; MAX_VOICES_PER_ENGINE];
+        let mut note_placed = vec![false; notes.len()];
+        for (_, voice, index) in pairs {
+            if voice_moved[voice] || note_placed[index] {
+                continue;
+            }
+            voice_moved[voice] = true;
+            note_placed[index] = true;
+            self.glide_voice(voice, notes[index], time_ms);
+        }
+
+        let velocity = match held.len() {
+            0 => 0.8,
+            n => held.iter().map(|&i| self.voices[i].get_velocity()).sum::<f32>() / n as f32,
+        };
+        for &voice in held.iter().filter(|&&i| !voice_moved[i]) {
+            self.voices[voice].note_off();
+        }
+        for (index, &note) in notes.iter().enumerate() {
+            if !note_placed[index] {
+                self.note_on(note, velocity);
+            }
+        }
+    }
+
+    // Bends a sounding voice onto `note`; to the event queue its old note
+    // ends and the new one starts
+    fn glide_voice(&mut self, voice: usize, note: u8, time_ms: f32) {
+        let from = self.voices[voice].note();
+        let freq = self.note_frequency(note);
+        self.voices[voice].glide_to(note, freq, time_ms);
+        if from != note {
+            self.events.push(EVENT_NOTE_ENDED, from as f32, 0.0);
+            self.events.push(EVENT_NOTE_STARTED, note as f32, self.voices[voice].get_velocity());
+        }
+    }
+
+    // Bends the voice holding `from` to `to` over the glide time, for a
+    // sequencer slide. With no such voice (it was stolen, or the notes go
+    // elsewhere) `to` is simply played.
+    fn slide(&mut self, from: u8, to: u8, velocity: f32) {
+        let time_ms = if self.patch.glide_time_ms > 0.0 { self.patch.glide_time_ms } else { SLIDE_TIME_MS };
+        let voice = self.voices.iter().position(|v| v.is_active() && !v.is_releasing() && v.note() == from);
+        match voice {
+            Some(i) if !self.filter_keyboard && !self.patch.string_machine_enabled => {
+                self.glide_voice(i, to, time_ms);
+            }
+            _ => {
+                self.note_off(from);
+                self.note_on(to, velocity);
+            }
+        }
+    }
+
+    // `release_velocity` 0-1 scales the release time by up to 4x either way
+    // (fast releases shorter) as far as release_velocity_amount allows; 0.5
+    // or None leaves it as set
+    fn note_off_with_velocity(&mut self, midi_note: u8, release_velocity: Option<f32>) {
+        // Keys only steer the filter; the latched drone keeps sounding
+        if self.filter_keyboard {
+            self.filter_keys.retain(|&n| n != midi_note);
+            if let Some(&previous) = self.filter_keys.last() {
+                self.filter_key_glide.set_target(self.note_frequency(previous));
+            }
+            return;
+        }
+        if self.auto_sustain {
+            if !self.sustained_notes.contains(&midi_note) {
+                self.sustained_notes.push(midi_note);
+            }
+            return;
+        }
+        self.string_machine.note_off(midi_note);
+        let scale = match release_velocity {
+            Some(velocity) if velocity.is_finite() => 4.0_f32.powf(self.patch.release_velocity_amount * (1.0 - 2.0 * velocity.clamp(0.0, 1.0))),
+            _ => 1.0,
+        };
+        for voice in self.voices_playing(midi_note) {
+            voice.note_off_scaled(scale);
+        }
+    }
+
+    // Per-note pressure (polyphonic aftertouch) 0-1 on the voices playing
+    // `midi_note`, by the patch's pressure depths
+    fn set_note_pressure(&mut self, midi_note: u8, pressure: f32) {
+        for voice in self.voices_playing(midi_note) {
+            voice.set_note_pressure(pressure);
+        }
+    }
+
+    // Sounding voices started for `note`. Voices are matched by the note
+    // they were allocated for, not their pitch, which glide, tuning and
+    // modulation all move.
+    fn voices_playing(&mut self, note: u8) -> impl Iterator<Item = &mut Voice> + '_ {
+        self.voices.iter_mut().filter(move |voice| voice.note() == note && voice.is_active())
+    }
+
+    // Filter keyboard: while on, notes no longer trigger voices but move the
+    // filter cutoff to their pitch (last held key wins), gliding over
+    // `glide_ms`. Whatever was sounding when it was switched on keeps
+    // droning underneath; switching it off releases that drone.
+    fn set_filter_keyboard(&mut self, enabled: bool, glide_ms: f32) {
+        if enabled && !self.filter_keyboard {
+            self.filter_key_glide.set_glide_time(0.0);
+            self.filter_key_glide.set_target(self.patch.filter_cutoff);
+            self.set_filter_enabled(true);
+        }
+        self.filter_key_glide.set_glide_time(glide_ms.clamp(0.0, 2000.0));
+        if !enabled && self.filter_keyboard {
+            self.filter_keys.clear();
+            let cutoff = self.patch.filter_cutoff;
+            for voice in &mut self.voices {
+                voice.set_filter_cutoff(cutoff);
+                voice.note_off();
+            }
+        }
+        self.filter_keyboard = enabled;
+    }
+
+    // Sounding notes keep their pitch; the tuning applies from the next note
+    fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+    }
+
+    // Detunes later notes on `note` by `cents` (-100 to 100; 0 clears it)
+    fn set_note_cents(&mut self, note: u8, cents: f32) {
+        self.note_cents[note as usize & 127] = cents.clamp(-100.0, 100.0);
+    }
+
+    // `note` under the tuning, moved by its microtuning offset
+    fn note_frequency(&self, note: u8) -> f32 {
+        let cents = self.note_cents[note as usize & 127];
+        let freq = self.tuning.frequency(note);
+        if cents == 0.0 { freq } else { freq * 2.0_f32.powf(cents / 1200.0) }
+    }
+
+    // Applies the patch bound to `pattern`, if any, as it starts
+    fn start_pattern(&mut self, pattern: usize) {
+        if let Some(Some(patch)) = self.pattern_presets.get(pattern) {
+            let patch = *patch;
+            self.apply_patch(&patch);
+        }
+    }
+
+    // Puts notes `low`..=`high` in choke group 1-255, or takes them out of
+    // any group with 0
+    fn set_choke_group(&mut self, low: u8, high: u8, group: u8) {
+        let high = high.min(127);
+        if low <= high {
+            self.choke_groups[low as usize..=high as usize].fill(group);
+        }
+    }
+
+    // Fades out every sounding voice in `group`, release tails included
+    fn choke(&mut self, group: u8) {
+        for voice in &mut self.voices {
+            if voice.is_active() && self.choke_groups[voice.note() as usize] == group {
+                voice.fade_out(CHOKE_FADE);
+            }
+        }
+    }
+
+    // Silences everything at once: voices without release, the string
+    // machine, held-over notes, pressure and every effect's delay lines and
+    // tails
+    fn panic(&mut self) {
+        for voice in &mut self.voices {
+            voice.silence();
+        }
+        self.string_machine.silence();
+        self.sustained_notes.clear();
+        self.filter_keys.clear();
+        self.gesture = None;
+        self.pressure.reset();
+        let effects: [&mut dyn Effect; 14] = [
+            &mut self.delay,
+            &mut self.reverb,
+            &mut self.convolution,
+            &mut self.fdn_reverb,
+            &mut self.tremolo,
+            &mut self.flanger,
+            &mut self.bitcrusher,
+            &mut self.eq,
+            &mut self.rumble_filter,
+            &mut self.auto_wah,
+            &mut self.rotary,
+            &mut self.pitch_shifter,
+            &mut self.frequency_shifter,
+            &mut self.dc_blocker,
+        ];
+        for effect in effects {
+            effect.reset();
+        }
+        self.input_filter.reset();
+        self.vocoder.reset();
+    }
+
+    // External audio into the voice bus, through the patch's filter when
+    // it's on
+    fn mix_input(&mut self, input: &[f32], output: &mut [f32]) {
+        if self.vocoder_enabled {
+            for (out, &x) in output.iter_mut().zip(input) {
+                *out = self.vocoder.process(*out, x);
+            }
+            return;
+        }
+        for (out, &x) in output.iter_mut().zip(input) {
+            *out += if self.patch.filter_enabled { self.input_filter.process(x) } else { x };
+        }
+    }
+
+    fn set_vocoder(&mut self, enabled: bool, bands: u32, release_ms: f32, sibilance: f32) {
+        if enabled && !self.vocoder_enabled {
+            self.vocoder.reset();
+        }
+        self.vocoder_enabled = enabled;
+        self.vocoder.set_bands(bands as usize);
+        self.vocoder.set_release(release_ms);
+        self.vocoder.set_sibilance(sibilance);
+    }
+
+    // Auto-sustain holds every note past its note-off until the next note-on,
+    // then crossfades into it over `crossfade_ms`. Turning it off releases
+    // anything still held.
+    fn set_auto_sustain(&mut self, enabled: bool, crossfade_ms: f32) {
+        self.auto_sustain = enabled;
+        self.auto_sustain_crossfade = crossfade_ms.clamp(5.0, 2000.0) / 1000.0;
+        if !enabled {
+            self.release_sustained(None);
+        }
+    }
+
+    // Releases the held-over notes, over `fade` seconds or the patch release
+    fn release_sustained(&mut self, fade: Option<f32>) {
+        for note in std::mem::take(&mut self.sustained_notes) {
+            self.string_machine.note_off(note);
+            for voice in self.voices_playing(note).filter(|voice| !voice.is_releasing()) {
+                match fade {
+                    Some(seconds) => voice.fade_out(seconds),
+                    None => voice.note_off(),
+                }
+            }
+        }
+    }
+
+    // Free voice first, then the oldest releasing voice, then the oldest
+    // held voice. With `protect_lowest_note` the lowest held voice (usually
+    // the chord's root) is never stolen; while the governor is reducing load,
+    // inner chord voices go before the outer ones.
+    fn allocate_voice(&self) -> Option<usize> {
+        if self.active_voices() < self.polyphony_limit {
+            if let Some(idx) = self.voices.iter().position(|voice| !voice.is_active()) {
+                return Some(idx);
+            }
+        }
+
+        let oldest_releasing = self
+            .voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| voice.is_active() && voice.is_releasing())
+            .min_by_key(|(_, voice)| voice.note_id())
+            .map(|(i, _)| i);
+        if oldest_releasing.is_some() {
+            return oldest_releasing;
+        }
+
+        if self.is_governed() {
+            if let Some(idx) = self.oldest_inner_voice() {
+                return Some(idx);
+            }
+        }
+
+        let protected = if self.patch.protect_lowest_note {
+            self.lowest_held_voice()
+        } else {
+            None
+        };
+        self.oldest_voice(protected)
+    }
+
+    // Soft and legato retrigger play a repeated note on the voice already
+    // sounding it rather than stacking a second one
+    fn retrigger_voice(&self, note: u8) -> Option<usize> {
+        if self.patch.env_retrigger_mode == RETRIGGER_FULL {
+            return None;
+        }
+        self.voices.iter().position(|voice| voice.note() == note && voice.is_active())
+    }
+
+    // Oldest by note id, which unlike age orders notes started in the same
+    // block
+    fn oldest_voice(&self, protected: Option<usize>) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|&(i, voice)| Some(i) != protected && voice.is_active())
+            .min_by_key(|(_, voice)| voice.note_id())
+            .map(|(i, _)| i)
+    }
+
+    fn write_debug(&self, out: &mut String) -> std::fmt::Result {
+        let held = self.held_voices();
+        let active = self.active_voices();
+        writeln!(out, "[{}]", self.metadata.name)?;
+        writeln!(out, "pan {:.2}, delay send {:.2}, reverb send {:.2}", self.pan, self.delay_send, self.reverb_send)?;
+        writeln!(
+            out,
+            "voices {} active / {} held / {} max, unison limit {}, polyphony limit {}",
+            active,
+            held,
+            MAX_VOICES_PER_ENGINE,
+            self.unison_limit,
+            self.polyphony_limit
+        )?;
+        writeln!(
+            out,
+            "auto-sustain {} ({} sustained), filter keyboard {}, mono melody {}",
+            self.auto_sustain,
+            self.sustained_notes.len(),
+            self.filter_keyboard,
+            self.patch.mono_melody
+        )?;
+        let order: Vec<&str> = self.patch.effect_order.iter().map(|&id| effect_name(id)).collect();
+        writeln!(out, "effect order: {}", order.join(" > "))?;
+        writeln!(out, "patch values: {:?}", self.patch.values())?;
+        for (i, voice) in self.voices.iter().enumerate().filter(|(_, voice)| voice.is_active()) {
+            writeln!(
+                out,
+                "  voice {:2}: note {:3} {:8.2} Hz, velocity {:.2}, age {:.0}{}",
+                i,
+                voice.note(),
+                voice.get_frequency(),
+                voice.get_velocity(),
+                voice.get_age(),
+                if voice.is_releasing() { ", releasing" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+
+    // Sounding voices, releasing ones included
+    fn active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.is_active()).count()
+    }
+
+    // Notes still down on the voices, lowest first and each once
+    fn active_notes(&self) -> Vec<u8> {
+        let mut notes: Vec<u8> = self.voices.iter().filter(|v| v.is_active() && !v.is_releasing()).map(Voice::note).collect();
+        notes.sort_unstable();
+        notes.dedup();
+        notes
+    }
+
+    fn held_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.is_active() && !voice.is_releasing()).count()
+    }
+
+    fn lowest_held_voice(&self) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| voice.is_active() && !voice.is_releasing())
+            .min_by_key(|(_, voice)| voice.note())
+            .map(|(i, _)| i)
+    }
+
+    fn highest_held_voice(&self) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| voice.is_active() && !voice.is_releasing())
+            .max_by_key(|(_, voice)| voice.note())
+            .map(|(i, _)| i)
+    }
+
+    // Oldest held voice that is neither the lowest nor the highest note
+    fn oldest_inner_voice(&self) -> Option<usize> {
+        let lowest = self.lowest_held_voice();
+        let highest = self.highest_held_voice();
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != lowest && Some(i) != highest)
+            .filter(|(_, voice)| voice.is_active() && !voice.is_releasing())
+            .min_by_key(|(_, voice)| voice.note_id())
+            .map(|(i, _)| i)
+    }
+
+    fn set_protect_lowest_note(&mut self, enabled: bool) {
+        self.patch.protect_lowest_note = enabled;
+    }
+
+    // Any gesture still playing is released before the new one starts
+    fn play_gesture(&mut self, notes: &[u8], velocity: f32, articulation: Articulation) {
+        self.release_gesture();
+        self.gesture = Some(ChordGesture::new(notes, velocity, articulation));
+    }
+
+    fn release_gesture(&mut self) {
+        if let Some(mut gesture) = self.gesture.take() {
+            for event in gesture.release() {
+                self.note_off(event.note);
+            }
+        }
+    }
+
+    fn fire_gesture(&mut self, samples_per_beat: f64) {
+        let Some(gesture) = &mut self.gesture else {
+            return;
+        };
+        for event in gesture.fire(samples_per_beat) {
+            if event.velocity > 0.0 {
+                self.note_on(event.note, event.velocity);
+            } else {
+                self.note_off(event.note);
+            }
+        }
+        if self.gesture.as_ref().is_some_and(|g| g.is_finished()) {
+            self.gesture = None;
+        }
+    }
+
+    // Renders the voices, splitting the block wherever a chord gesture has an
+    // event due so gestures stay sample-accurate
+    fn render_voices(&mut self, output: &mut [f32], samples_per_beat: f64) {
+        let mut start = 0;
+        while start < output.len() {
+            let due = self.gesture.as_ref().and_then(|g| g.samples_until_next());
+            if due == Some(0) {
+                self.fire_gesture(samples_per_beat);
+                continue;
+            }
+
+            let end = due.map_or(output.len(), |d| (start + d as usize).min(output.len()));
+            self.process_voices(&mut output[start..end]);
+            self.apply_output_gain(&mut output[start..end]);
+            if let Some(gesture) = &mut self.gesture {
+                gesture.advance(end - start);
+            }
+            start = end;
+        }
+        self.check_note_ends();
+    }
+
+    // Raises a note end for each voice that has fallen silent since the
+    // last check
+    fn check_note_ends(&mut self) {
+        for (i, voice) in self.voices.iter().enumerate() {
+            if self.sounding & (1 << i) != 0 && !voice.is_active() {
+                self.sounding &= !(1 << i);
+                self.events.push(EVENT_NOTE_ENDED, voice.note() as f32, 0.0);
+            }
+        }
+    }
+
+    // Engine gain, divided by the square root of the sounding voices when
+    // normalizing so a chord's overall level matches a single note's.
+    // Ramped across the block so voices coming and going don't step it.
+    fn apply_output_gain(&mut self, output: &mut [f32]) {
+        let mut target = 10.0_f32.powf(self.patch.output_gain_db / 20.0);
+        if self.patch.voice_normalize {
+            let sounding = self.voices.iter().filter(|v| v.is_active()).count().max(1);
+            target /= (sounding as f32).sqrt();
+        }
+        let start = self.output_gain;
+        if start == 1.0 && target == 1.0 {
+            return;
+        }
+        let step = (target - start) / output.len().max(1) as f32;
+        block::apply_gain_ramp(output, start, step);
+        self.output_gain = target;
+    }
+
+    fn process_voices(&mut self, output: &mut [f32]) {
+        let random = self.smooth_random.is_active();
+        let vector_lfo = self.vector_lfo_active();
+        // Runs one more pass after stopping to hand back the cutoff
+        let lfo = self.lfo.is_running() || self.lfo_cutoff_octaves != 0.0;
+        if !self.motion.is_active()
+            && !self.filter_keyboard
+            && !self.pressure.is_active()
+            && !random
+            && !vector_lfo
+            && !lfo
+        {
+            self.render_block(output);
+            return;
+        }
+        for block in output.chunks_mut(MOTION_BLOCK) {
+            if vector_lfo {
+                self.update_vector_position();
+                let seconds = block.len() as f32 / SAMPLE_RATE;
+                self.vector_lfo_phase = (self.vector_lfo_phase + self.patch.vector_lfo_rate * seconds).fract();
+            }
+            if self.filter_keyboard {
+                let cutoff = self.filter_key_glide.advance(block.len());
+                for voice in &mut self.voices {
+                    voice.set_filter_cutoff(cutoff);
+                }
+            }
+            // Advanced first so the block that lets go of the keys already
+            // hands the voices back their unmodulated settings
+            let pressure = self.pressure.is_active();
+            let start_gain = self.pressure.gain();
+            if pressure {
+                self.pressure.advance(block.len());
+            }
+            if lfo {
+                self.lfo_cutoff_octaves = self.lfo.value() * LFO_CUTOFF_OCTAVES;
+            }
+            if self.motion.is_active() || pressure || random || lfo {
+                let cutoff = self.motion.cutoff_octaves()
+                    + self.pressure.cutoff_octaves()
+                    + self.smooth_random.cutoff_octaves()
+                    + self.lfo_cutoff_octaves;
+                let vibrato = self.pressure.vibrato_cents();
+                for (i, voice) in self.voices.iter_mut().enumerate() {
+                    let drift = self.motion.drift_cents(i) + self.smooth_random.pitch_cents(i);
+                    voice.set_motion(cutoff, drift + vibrato);
+                }
+            }
+            self.render_block(block);
+            if pressure {
+                // Ramped across the block so volume swells don't step
+                let end_gain = self.pressure.gain();
+                let step = (end_gain - start_gain) / block.len() as f32;
+                block::apply_gain_ramp(block, start_gain, step);
+            }
+            self.motion.advance(block.len());
+            if random {
+                self.smooth_random.advance(block.len());
+            }
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    fn render_block(&mut self, output: &mut [f32]) {
+        self.lfo.advance(output.len());
+        for voice in &mut self.voices {
+            if voice.is_active() {
+                voice.process(output);
+            }
+        }
+        if self.string_machine.is_active() {
+            self.string_machine.process(output);
+        }
+    }
+
+    // Each voice renders on its own and the sum runs four samples at a time
+    #[cfg(feature = "simd")]
+    fn render_block(&mut self, output: &mut [f32]) {
+        self.lfo.advance(output.len());
+        let voice_buffer = &mut self.voice_buffer[..output.len()];
+        for voice in &mut self.voices {
+            if voice.is_active() {
+                voice_buffer.fill(0.0);
+                voice.process(voice_buffer);
+                block::add_into(output, voice_buffer);
+            }
+        }
+        if self.string_machine.is_active() {
+            self.string_machine.process(output);
+        }
+    }
+
+    // Voices render mono into `left`. The rumble filter always runs first,
+    // the rest of the chain follows the patch's effect order and a DC
+    // blocker always closes it.
+    fn process_effects(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let order = self.patch.effect_order;
+        let len = left.len();
+        let lfo_fx = self.lfo_fx_active().then(|| self.lfo.shape());
+        // Stage by stage over short blocks: each effect runs a tight loop
+        // over the block while its state stays in cache
+        for (i, (block_left, block_right)) in left.chunks_mut(CONTROL_BLOCK).zip(right.chunks_mut(CONTROL_BLOCK)).enumerate() {
+            block_right.copy_from_slice(block_left);
+            let mut dry = [0.0; CONTROL_BLOCK];
+            let compensate = self.fx_ab.is_active();
+            if compensate {
+                dry[..block_left.len()].copy_from_slice(block_left);
+            }
+            if let Some(target) = lfo_fx {
+                // Eased from where the last call left it, block by block
+                let t = ((i + 1) * CONTROL_BLOCK).min(len) as f32 / len as f32;
+                self.apply_lfo_fx(self.lfo_fx + (target - self.lfo_fx) * t);
+            }
+            if self.patch.random_fx_mix > 0.0 {
+                self.apply_fx_mix_offset(self.smooth_random.fx_mix());
+            }
+
+            if self.rumble_filter_enabled {
+                self.rumble_filter.process_block(block_left, block_right);
+            }
+            for &id in &order {
+                if let Some(effect) = self.effect_mut(id) {
+                    effect.process_block(block_left, block_right);
+                }
+            }
+            if compensate {
+                self.fx_ab.process_block(&dry[..block_left.len()], block_left, block_right);
+            }
+            if self.motion.is_active() {
+                for (l, r) in block_left.iter_mut().zip(block_right.iter_mut()) {
+                    let (pan_left, pan_right) = self.pan_law.gains(self.motion.next_pan());
+                    *l *= pan_left;
+                    *r *= pan_right;
+                }
+            }
+            self.dc_blocker.process_block(block_left, block_right);
+        }
+        if let Some(target) = lfo_fx {
+            self.lfo_fx = target;
+        }
+    }
+}
+
+// Working buffers for one render segment, allocated up front so the audio
+// callback never allocates
+#[derive(Default)]
+struct ScratchBuffers {
+    timeline: [Vec<f32>; 2],
+    live: [Vec<f32>; 2],
+    // Render target for each created engine in turn
+    extra: [Vec<f32>; 2],
+    hot_swap: [Vec<f32>; 2],
+    // Stereo render behind the mono `process`
+    output: [Vec<f32>; 2],
+    // External audio for process_input
+    input: Vec<f32>,
+    // Per-engine stems for process_multi_out, left/right per engine id
+    stems: Vec<Vec<f32>>,
+    buses: MixBuses,
+}
+
+impl ScratchBuffers {
+    fn new() -> Self {
+        let stereo = || [vec![0.0; MAX_QUANTUM], vec![0.0; MAX_QUANTUM]];
+        ScratchBuffers {
+            timeline: stereo(),
+            live: stereo(),
+            extra: stereo(),
+            hot_swap: stereo(),
+            output: stereo(),
+            input: vec![0.0; MAX_QUANTUM],
+            stems: vec![vec![0.0; MAX_QUANTUM]; ENGINE_COUNT * 2],
+            buses: MixBuses {
+                dry: stereo(),
+                delay: stereo(),
+                reverb: stereo(),
+            },
+        }
+    }
+}
+
+// Every engine's post-fader output summed for one segment, and what it
+// sends to the shared delay and reverb returns
+#[derive(Default)]
+struct MixBuses {
+    dry: [Vec<f32>; 2],
+    delay: [Vec<f32>; 2],
+    reverb: [Vec<f32>; 2],
+}
+
+// An engine added with create_engine, alongside the built-in timeline and
+// live pair, and its fader
+struct ExtraEngine {
+    engine: Engine,
+    volume: f32,
+    // The track's own pattern bank, running with the transport
+    sequencer: Sequencer,
+}
+
+// The engine a hot swap replaced, still sounding while it fades out under
+// its replacement
+struct HotSwap {
+    engine: Engine,
+    gain: f32,
+    step: f32,
+}
+
+// Two parsed state snapshots being blended, and how far along the blend
+// is. `current` glides toward `target` so sweeps from a slider stay smooth.
+struct Morph {
+    a_json: String,
+    b_json: String,
+    a: EngineState,
+    b: EngineState,
+    target: f32,
+    current: f32,
+}
+
+// Phases of every clocked component, captured together at the start of a
+// render quantum so visualizers can lock to one consistent instant
+#[derive(Clone, Copy, Default)]
+struct PhaseInfo {
+    sample_time: u64,
+    beat_position: f64,
+    timeline_lfo_phase: f32,
+    live_lfo_phase: f32,
+    sequencer_step: Option<usize>,
+}
+
+// How live keys spread over the engines: all to the live engine, those
+// below a split note to the zone engine instead, or every note on both
+#[derive(Clone, Copy, PartialEq)]
+enum KeyboardMode {
+    Single,
+    Split(u8),
+    Layer,
+}
+
+// Where a live key's note went
+const ROUTE_LIVE: u8 = 1;
+const ROUTE_ZONE: u8 = 2;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AudioEngine {
+    timeline_engine: Engine,
+    live_engine: Engine,
+    timeline_volume: f32,
+    live_volume: f32,
+    master_volume: f32,
+    pan_law: PanLaw,
+    // Stereo to mono fold for the single-channel outputs
+    downmix: Downmix,
+    // Timeline/live crossfader position 0-1 and where it has glided to
+    crossfade: f32,
+    crossfade_current: f32,
+    return_delay: Delay,
+    return_reverb: FdnReverb,
+    // Consecutive samples the returns have been silent with no send open
+    returns_silent: usize,
+    tape: Tape,
+    tape_enabled: bool,
+    vintage: Vintage,
+    vintage_enabled: bool,
+    vintage_in_export: bool,
+    export_mode: bool,
+    bass_mono: BassMono,
+    bass_mono_enabled: bool,
+    multiband: MultibandCompressor,
+    multiband_enabled: bool,
+    limiter: Limiter,
+    limiter_enabled: bool,
+    soft_clipper: SoftClipper,
+    soft_clipper_enabled: bool,
+    oversampling: u32,
+    quality_mode: u8,
+    governor: Governor,
+    governor_actions: Vec<u32>,
+    events: EventQueue,
+    transport: Transport,
+    sequencer: Sequencer,
+    sequencer_events: Vec<SequencerEvent>,
+    // Track whose patterns the step and song calls edit
+    edit_track: u32,
+    // Pattern per engine id for each scene, negative to stop the track
+    scenes: Vec<Vec<i8>>,
+    arpeggiator: Arpeggiator,
+    arpeggiator_enabled: bool,
+    held_chord: Vec<u8>,
+    strum_queue: NoteQueue,
+    strum_time_ms: f32,
+    strum_direction: u8,
+    strum_humanize: f32,
+    strum_upward: bool,
+    random: Random,
+    looper: Looper,
+    // Recording: bars of count-in before capture starts from stop, beats
+    // of it left while counting, the punch range in beats and the click
+    metronome: Metronome,
+    metronome_enabled: bool,
+    count_in_bars: u32,
+    count_in_beats: Option<f64>,
+    punch: Option<(f64, f64)>,
+    scale_root: u8,
+    scale_type: u8,
+    scale_mask: u16,
+    scale_quantize: bool,
+    // Note each held live key actually triggered (NO_NOTE when not held), so
+    // releases still match after the scale changes
+    quantized_notes: [u8; 128],
+    keyboard_mode: KeyboardMode,
+    zone_engine: u32,
+    // ROUTE_ bits of each held live key, so its release follows the note
+    // even if the zones change in between
+    key_routes: [u8; 128],
+    event_log: EventLog,
+    journal: Journal,
+    journal_state: u8,
+    // sample_time when recording or replay started
+    journal_origin: u64,
+    // Next entry to replay
+    journal_cursor: usize,
+    // Each engine's patch as last journaled, to diff against
+    journal_patches: [Patch; 2],
+    // Engines from create_engine, id ENGINE_COUNT + index; None once
+    // destroyed until create_engine reuses the id
+    extra_engines: Vec<Option<ExtraEngine>>,
+    // Outgoing engine per engine id while a hot swap crossfades
+    hot_swaps: Vec<Option<HotSwap>>,
+    morph: Option<Morph>,
+    history: History,
+    message_ring: MessageRing,
+    scratch: ScratchBuffers,
+    // Write position into scratch.stems while process_multi_out runs
+    stem_cursor: Option<usize>,
+    // Read position into scratch.input while process_input runs, and the
+    // engine that hears it
+    input_cursor: Option<usize>,
+    input_engine: u32,
+    // Post-fader level per engine, and the final output after the master chain
+    meters: Vec<Meter>,
+    master_meter: Meter,
+    // Smoothed render time over the real-time budget, see get_dsp_load
+    dsp_load: f32,
+    // Recent post-effects signal per engine for get_spectrum and the scope
+    spectra: Vec<SpectrumAnalyzer>,
+    midi_clock: MidiClock,
+    midi_clock_sync: bool,
+    cc_map: CcMap,
+    macros: Macros,
+    // Parameter lanes per sequencer pattern, played on the timeline engine
+    automation: Automation,
+    resampler: Option<Resampler>,
+    sample_time: u64,
+    phase_info: PhaseInfo,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AudioEngine {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> AudioEngine {
+        // Returns carry only the effect, the dry signal stays on the engines
+        let mut return_delay = Delay::new(SAMPLE_RATE, 2000.0);
+        return_delay.set_delay_time(375.0);
+        return_delay.set_feedback(0.35);
+        let mut return_reverb = FdnReverb::new(SAMPLE_RATE);
+        return_reverb.set_mix(1.0);
+
+        let transport = Transport::new(SAMPLE_RATE);
+        let sequencer = Sequencer::new(transport.samples_per_beat());
+        let arpeggiator = Arpeggiator::new(transport.samples_per_beat());
+
+        AudioEngine {
+            timeline_engine: Engine::new(SAMPLE_RATE, "Timeline"),
+            live_engine: Engine::new(SAMPLE_RATE, "Live"),
+            timeline_volume: 0.7,
+            live_volume: 0.7,
+            master_volume: 1.0,    // Master is now just a final gain stage
+            pan_law: PanLaw::EqualPower,
+            downmix: Downmix::new(SAMPLE_RATE, PanLaw::EqualPower),
+            crossfade: 0.5,
+            crossfade_current: 0.5,
+            return_delay,
+            return_reverb,
+            returns_silent: RETURN_TAIL_SAMPLES,
+            tape: Tape::new(SAMPLE_RATE),
+            tape_enabled: false,
+            vintage: Vintage::new(SAMPLE_RATE),
+            vintage_enabled: false,
+            vintage_in_export: true,
+            export_mode: false,
+            bass_mono: BassMono::new(SAMPLE_RATE),
+            bass_mono_enabled: false,
+            multiband: MultibandCompressor::new(SAMPLE_RATE),
+            multiband_enabled: false,
+            limiter: Limiter::new(SAMPLE_RATE, 5.0),
+            limiter_enabled: true,
+            soft_clipper: SoftClipper::new(),
+            soft_clipper_enabled: false,
+            oversampling: 1,
+            quality_mode: QUALITY_NORMAL,
+            governor: Governor::new(),
+            governor_actions: Vec::new(),
+            events: EventQueue::new(),
+            transport,
+            sequencer,
+            sequencer_events: Vec::new(),
+            edit_track: ENGINE_TIMELINE,
+            scenes: vec![Vec::new(); MAX_SCENES],
+            arpeggiator,
+            arpeggiator_enabled: false,
+            held_chord: Vec::new(),
+            strum_queue: NoteQueue::new(),
+            strum_time_ms: 0.0,
+            strum_direction: 0,
+            strum_humanize: 0.0,
+            strum_upward: true,
+            random: Random::new(0x1234_5678),
+            looper: Looper::new(SAMPLE_RATE),
+            metronome: Metronome::new(SAMPLE_RATE),
+            metronome_enabled: false,
+            count_in_bars: 0,
+            count_in_beats: None,
+            punch: None,
+            scale_root: 0,
+            scale_type: 0,
+            scale_mask: scales::scale_mask(0, 0).unwrap_or(0),
+            scale_quantize: false,
+            quantized_notes: [NO_NOTE; 128],
+            keyboard_mode: KeyboardMode::Single,
+            zone_engine: ENGINE_TIMELINE,
+            key_routes: [0; 128],
+            event_log: EventLog::new(),
+            journal: Journal::default(),
+            journal_state: JOURNAL_IDLE,
+            journal_origin: 0,
+            journal_cursor: 0,
+            journal_patches: [Patch::default(); 2],
+            extra_engines: Vec::new(),
+            hot_swaps: vec![None, None],
+            morph: None,
+            history: History::default(),
+            message_ring: MessageRing::new(),
+            scratch: ScratchBuffers::new(),
+            stem_cursor: None,
+            input_cursor: None,
+            input_engine: ENGINE_LIVE,
+            meters: vec![Meter::new(SAMPLE_RATE), Meter::new(SAMPLE_RATE)],
+            master_meter: Meter::new(SAMPLE_RATE),
+            dsp_load: 0.0,
+            spectra: vec![SpectrumAnalyzer::new(), SpectrumAnalyzer::new()],
+            midi_clock: MidiClock::new(),
+            midi_clock_sync: false,
+            cc_map: CcMap::new(),
+            macros: Macros::new(),
+            automation: Automation::new(sequencer::MAX_PATTERNS),
+            resampler: None,
+            sample_time: 0,
+            phase_info: PhaseInfo::default(),
+        }
+    }
+
+    // Mono output for single-channel integrations: renders the full stereo
+    // graph (ping-pong, stereo reverb...) and folds it down so nothing is
+    // dropped and material keeps its level wherever it is panned
+    pub fn process(&mut self, output: &mut [f32]) {
+        let [mut left, mut right] = std::mem::take(&mut self.scratch.output);
+        for chunk in output.chunks_mut(MAX_QUANTUM) {
+            let len = chunk.len();
+            self.process_stereo(&mut left[..len], &mut right[..len]);
+            for i in 0..len {
+                chunk[i] = self.downmix.process(left[i], right[i]);
+            }
+        }
+        self.scratch.output = [left, right];
+    }
+
+    // Renders at the output rate: straight through at 48 kHz, otherwise via
+    // the resampler set up by set_output_sample_rate
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let len = left.len().min(right.len());
+        let Some(mut resampler) = self.resampler.take() else {
+            self.render_stereo(&mut left[..len], &mut right[..len]);
+            return;
+        };
+        let needed = resampler.input_needed(len);
+        let (input_left, input_right) = resampler.input_mut(needed);
+        self.render_stereo(input_left, input_right);
+        resampler.process(&mut left[..len], &mut right[..len]);
+        self.resampler = Some(resampler);
+    }
+
+    // Host sample rate the output is resampled to (8-192 kHz). The engine
+    // always runs at 48 kHz internally, so sound and timing are the same at
+    // every rate; other rates go through a 32-tap polyphase sinc filter and
+    // add about 0.3 ms of latency. process_multi_out, offline renders and
+    // exports stay at 48 kHz.
+    pub fn set_output_sample_rate(&mut self, rate: f32) {
+        let rate = rate.clamp(MIN_OUTPUT_RATE, MAX_OUTPUT_RATE);
+        if rate == SAMPLE_RATE {
+            self.resampler = None;
+        } else if self.resampler.as_ref().is_none_or(|r| r.output_rate() != rate) {
+            self.resampler = Some(Resampler::new(SAMPLE_RATE, rate));
+        }
+    }
+
+    pub fn get_output_sample_rate(&self) -> f32 {
+        self.resampler.as_ref().map_or(SAMPLE_RATE, |r| r.output_rate())
+    }
+
+    // Renders the main mix into `left`/`right` like process_stereo and each
+    // engine's own post-fader output into `stems`, so hosts can route tracks
+    // to separate channels for external processing. `stems` is planar:
+    // timeline left, timeline right, live left, live right, then left and
+    // right for each created engine id in turn (silent once destroyed), each
+    // left.len() samples; get_stem_count() gives the number of channels.
+    // Stems carry master volume but not the shared returns, looper or master
+    // inserts.
+    pub fn process_multi_out(&mut self, left: &mut [f32], right: &mut [f32], stems: &mut [f32]) {
+        let len = left.len().min(right.len()).min(stems.len() / self.scratch.stems.len());
+        let mut start = 0;
+        while start < len {
+            let end = len.min(start + MAX_QUANTUM);
+            self.stem_cursor = Some(0);
+            self.render_stereo(&mut left[start..end], &mut right[start..end]);
+            self.stem_cursor = None;
+            for (channel, stem) in self.scratch.stems.iter().enumerate() {
+                let offset = channel * len;
+                stems[offset + start..offset + end].copy_from_slice(&stem[..end - start]);
+            }
+            start = end;
+        }
+    }
+
+    // ==== AUDIO INPUT ====
+
+    // Engine whose filter and effect chain process_input runs external
+    // audio through (default live). Pick an engine that isn't playing notes
+    // to use it purely as an effects processor.
+    pub fn set_input_engine(&mut self, engine: u32) {
+        if self.engine(engine).is_some() {
+            self.input_engine = engine;
+        }
+    }
+
+    // Renders like process with `input` (mono, e.g. a microphone or line
+    // input) fed into the input engine before its filter and effects, so it
+    // goes through that engine's chain, fader, sends and the master with
+    // everything else. Runs at 48 kHz, ignoring set_output_sample_rate.
+    pub fn process_input(&mut self, input: &[f32], output: &mut [f32]) {
+        let [mut left, mut right] = std::mem::take(&mut self.scratch.output);
+        let len = input.len().min(output.len());
+        for (chunk, input) in output[..len].chunks_mut(MAX_QUANTUM).zip(input.chunks(MAX_QUANTUM)) {
+            let len = chunk.len();
+            self.process_input_stereo(input, &mut left[..len], &mut right[..len]);
+            for i in 0..len {
+                chunk[i] = self.downmix.process(left[i], right[i]);
+            }
+        }
+        self.scratch.output = [left, right];
+    }
+
+    // process_input with stereo output
+    pub fn process_input_stereo(&mut self, input: &[f32], left: &mut [f32], right: &mut [f32]) {
+        let len = input.len().min(left.len()).min(right.len());
+        let mut start = 0;
+        while start < len {
+            let end = len.min(start + MAX_QUANTUM);
+            self.scratch.input[..end - start].copy_from_slice(&input[start..end]);
+            self.input_cursor = Some(0);
+            self.render_stereo(&mut left[start..end], &mut right[start..end]);
+            self.input_cursor = None;
+            start = end;
+        }
+    }
+
+    // Vocodes the input engine's voices with the external input instead of
+    // mixing the input in: hold a chord and speak to make it sing. Bands
+    // 4-32, release 5-500 ms, sibilance 0-1 mixes high-passed input back in
+    // so consonants stay clear.
+    pub fn set_vocoder(&mut self, enabled: bool, bands: u32, release_ms: f32, sibilance: f32) {
+        let engine = self.input_engine;
+        if let Some(engine) = self.engine_mut(engine) {
+            engine.set_vocoder(enabled, bands, release_ms, sibilance);
+        }
+    }
+
+    // Stem channels process_multi_out writes: two per engine id
+    pub fn get_stem_count(&self) -> u32 {
+        self.scratch.stems.len() as u32
+    }
+
+    // Live performance note methods (use live_engine)
+    pub fn note_on(&mut self, midi_note: u8, velocity: f32) {
+        if !velocity.is_finite() {
+            return;
+        }
+        self.journal_event(JournalEvent::NoteOn { note: midi_note, velocity });
+        if self.in_punch() {
+            self.sequencer.record_note_on(midi_note, velocity);
+        }
+        self.live_note_on(midi_note, velocity);
+    }
+
+    // `release_velocity` (0-1, optional) shortens or stretches the release
+    // as far as the patch's release velocity amount allows
+    pub fn note_off(&mut self, midi_note: u8, release_velocity: Option<f32>) {
+        self.journal_event(JournalEvent::NoteOff { note: midi_note, release_velocity });
+        self.sequencer.record_note_off(midi_note);
+        self.live_note_off(midi_note, release_velocity);
+    }
+
+    fn live_note_on(&mut self, midi_note: u8, velocity: f32) {
+        self.event_log.record(self.sample_time, "live note on", [midi_note as f32, velocity]);
+        let route = match self.keyboard_mode {
+            KeyboardMode::Single => ROUTE_LIVE,
+            KeyboardMode::Split(split) if midi_note < split => ROUTE_ZONE,
+            KeyboardMode::Split(_) => ROUTE_LIVE,
+            KeyboardMode::Layer => ROUTE_LIVE | ROUTE_ZONE,
+        };
+        self.key_routes[midi_note.min(127) as usize] = route;
+        let Some(midi_note) = self.quantize_note_on(midi_note) else {
+            return;
+        };
+        if route & ROUTE_ZONE != 0 {
+            if let Some(e) = self.engine_mut(self.zone_engine) {
+                e.note_on(midi_note, velocity);
+            }
+        }
+        if route & ROUTE_LIVE == 0 {
+            return;
+        }
+        if self.arpeggiator_enabled {
+            self.arpeggiator.note_on(midi_note, velocity);
+        } else {
+            self.live_engine.note_on(midi_note, velocity);
+        }
+    }
+
+    fn live_note_off(&mut self, midi_note: u8, release_velocity: Option<f32>) {
+        self.event_log.record(self.sample_time, "live note off", [midi_note as f32, 0.0]);
+        let route = match std::mem::take(&mut self.key_routes[midi_note.min(127) as usize]) {
+            0 => ROUTE_LIVE,
+            route => route,
+        };
+        let Some(midi_note) = self.quantize_note_off(midi_note) else {
+            return;
+        };
+        if route & ROUTE_ZONE != 0 {
+            if let Some(e) = self.engine_mut(self.zone_engine) {
+                e.note_off_with_velocity(midi_note, release_velocity);
+            }
+        }
+        if route & ROUTE_LIVE == 0 {
+            return;
+        }
+        if self.arpeggiator_enabled {
+            self.arpeggiator.note_off(midi_note);
+        } else {
+            self.live_engine.note_off_with_velocity(midi_note, release_velocity);
+        }
+    }
+
+    // Channel pressure (aftertouch) 0-1 on the live engine, modulating held
+    // notes by the amounts from set_pressure_modulation
+    pub fn set_channel_pressure(&mut self, value: f32) {
+        self.journal_event(JournalEvent::ChannelPressure { value });
+        self.live_engine.pressure.set_pressure(value);
+    }
+
+    // Polyphonic aftertouch 0-1 for one held live note: only the voice
+    // playing it picks up the pressure depths, so single notes of a chord
+    // can swell or bend on their own
+    pub fn set_note_pressure(&mut self, midi_note: u8, value: f32) {
+        self.journal_event(JournalEvent::NotePressure { note: midi_note, value });
+        // Follow the key to the note scale quantize moved it to
+        let note = match self.quantized_notes[midi_note.min(127) as usize] {
+            NO_NOTE => midi_note,
+            target => target,
+        };
+        self.live_engine.set_note_pressure(note, value);
+    }
+
+    // Depths at full channel or per-note pressure: vibrato up to 100 cents,
+    // cutoff offset -4 to 4 octaves, volume 0-1 (up to +6 dB)
+    pub fn set_pressure_modulation(&mut self, engine: u32, vibrato_cents: f32, cutoff_octaves: f32, volume: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_pressure_modulation(vibrato_cents, cutoff_octaves, volume);
+        }
+    }
+
+    // Smooth random drift, a slower and more organic alternative to the LFO:
+    // rate 0.01-20 Hz, cutoff depth 0-4 octaves, pitch depth 0-100 cents
+    // (each voice drifts on its own) and fx mix 0-1 added to the delay and
+    // reverb mixes. All depths at zero turns it off.
+    pub fn set_random_modulation(&mut self, engine: u32, rate: f32, cutoff_octaves: f32, pitch_cents: f32, fx_mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_random_modulation(rate, cutoff_octaves, pitch_cents, fx_mix);
+        }
+    }
+
+    // Timeline note methods (use timeline_engine)
+    pub fn timeline_note_on(&mut self, midi_note: u8, velocity: f32) {
+        self.timeline_note_on_detuned(midi_note, velocity, None);
+    }
+
+    // A timeline note moved by `cents` from the tuning, which its voice
+    // keeps for as long as it sounds
+    fn timeline_note_on_detuned(&mut self, midi_note: u8, velocity: f32, cents: Option<f32>) {
+        self.journal_event(JournalEvent::TimelineNoteOn { note: midi_note, velocity, cents });
+        self.event_log.record(self.sample_time, "timeline note on", [midi_note as f32, velocity]);
+        match cents {
+            Some(cents) => {
+                self.timeline_engine.set_note_cents(midi_note, cents);
+                self.timeline_engine.note_on(midi_note, velocity);
+                self.timeline_engine.set_note_cents(midi_note, 0.0);
+            }
+            None => self.timeline_engine.note_on(midi_note, velocity),
+        }
+    }
+
+    pub fn timeline_note_off(&mut self, midi_note: u8) {
+        self.journal_event(JournalEvent::TimelineNoteOff { note: midi_note });
+        self.event_log.record(self.sample_time, "timeline note off", [midi_note as f32, 0.0]);
+        self.timeline_engine.note_off(midi_note);
+    }
+
+    // Starts a whole timeline chord in one call, `velocities` per note as
+    // for play_voicing and optional `cents` (-100 to 100) per note for
+    // just-intonation voicings, as for play_chord
+    pub fn timeline_chord_on(&mut self, notes: &[u8], velocities: &[f32], cents: Option<Vec<f32>>) {
+        let cents = cents.unwrap_or_default();
+        for (i, &note) in notes.iter().enumerate() {
+            self.timeline_note_on_detuned(note, chord_velocity(velocities, i), cents.get(i).copied());
+        }
+    }
+
+    pub fn timeline_chord_off(&mut self, notes: &[u8]) {
+        for &note in notes {
+            self.timeline_note_off(note);
+        }
+    }
+
+    // Auto-sustain for sketching progressions: each chord rings until the
+    // next one starts, whatever its stored length, crossfading over
+    // `crossfade_ms` (5-2000)
+    pub fn set_auto_sustain(&mut self, engine: u32, enabled: bool, crossfade_ms: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_auto_sustain(enabled, crossfade_ms);
+        }
+    }
+
+    // Stop all timeline notes (for loop restart)
+    pub fn stop_all_timeline_notes(&mut self) {
+        self.journal_event(JournalEvent::StopAllTimelineNotes);
+        self.timeline_engine.sustained_notes.clear();
+        for voice in &mut self.timeline_engine.voices {
+            if voice.is_active() {
+                voice.note_off();
+            }
+        }
+    }
+
+    // All notes off, hard: every engine stops dead (no release tails) and
+    // every delay line, reverb and return is emptied, along with held
+    // chords, strums waiting to fire, the arpeggiator's keys and any hot
+    // swap. For recovering from stuck notes, e.g. after a MIDI device drops
+    // out. The transport and looper keep running.
+    pub fn panic(&mut self) {
+        self.journal_event(JournalEvent::Panic);
+        self.event_log.record(self.sample_time, "panic", [0.0; 2]);
+        for e in self.engines_mut() {
+            e.panic();
+        }
+        for swap in &mut self.hot_swaps {
+            *swap = None;
+        }
+        self.return_delay.reset();
+        self.return_reverb.reset();
+        self.returns_silent = RETURN_TAIL_SAMPLES;
+        self.held_chord.clear();
+        self.strum_queue.clear();
+        self.arpeggiator.reset(&mut self.sequencer_events);
+        self.sequencer_events.clear();
+        self.quantized_notes = [NO_NOTE; 128];
+        self.key_routes = [0; 128];
+    }
+
+    // ==== KEYBOARD ZONES ====
+
+    // Live keys below `split_note` play `lower_engine` (e.g. the timeline
+    // engine with a bass patch) instead of the live engine; the split note
+    // and up stay on the live engine, arpeggiator included. Held keys finish
+    // where they started.
+    pub fn set_keyboard_split(&mut self, split_note: u8, lower_engine: u32) {
+        self.keyboard_mode = KeyboardMode::Split(split_note.min(128));
+        self.zone_engine = lower_engine;
+    }
+
+    // Every live key plays `engine` as well as the live engine, for stacked
+    // sounds
+    pub fn set_keyboard_layer(&mut self, engine: u32) {
+        self.keyboard_mode = KeyboardMode::Layer;
+        self.zone_engine = engine;
+    }
+
+    // Back to every live key playing only the live engine
+    pub fn clear_keyboard_zones(&mut self) {
+        self.keyboard_mode = KeyboardMode::Single;
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+    }
+
+    // DJ-style crossfader between the engines, 0 = timeline only, 1 = live
+    // only. Equal power, so the overall level holds through a transition;
+    // the centre (the default) leaves both at their own volume, the ends
+    // bring the remaining engine up by 3 dB.
+    pub fn set_crossfade(&mut self, t: f32) {
+        self.crossfade = t.clamp(0.0, 1.0);
+    }
+
+    // Pan law used by every panning stage: 0 = -3 dB (equal power),
+    // 1 = -4.5 dB, 2 = -6 dB (linear, mono-compatible)
+    pub fn set_pan_law(&mut self, law: u8) {
+        self.pan_law = PanLaw::from_u8(law);
+        let law = self.pan_law;
+        self.downmix.set_pan_law(law);
+        for engine in self.engines_mut() {
+            engine.set_pan_law(law);
+        }
+    }
+
+    // Track fader 0-1 for any engine id
+    pub fn set_engine_volume(&mut self, engine: u32, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        match engine {
+            ENGINE_TIMELINE => self.timeline_volume = volume,
+            ENGINE_LIVE => self.live_volume = volume,
+            _ => {
+                if let Some(Some(extra)) = self.extra_engines.get_mut(engine as usize - ENGINE_COUNT) {
+                    extra.volume = volume;
+                }
+            }
+        }
+    }
+
+    // Track pan, -1 (left) to 1 (right)
+    pub fn set_engine_pan(&mut self, engine: u32, pan: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.pan = pan.clamp(-1.0, 1.0);
+        }
+    }
+
+    // Gain staging ahead of the engine's effects: a trim from -24 to +12 dB
+    // and, optionally, turning the voices down by the square root of how
+    // many are sounding so a full chord lands near a single note's level
+    // instead of summing past full scale. Both are part of the patch.
+    pub fn set_engine_gain(&mut self, engine: u32, gain_db: f32, normalize_voices: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_gain_staging(gain_db, normalize_voices);
+        }
+    }
+
+    // How an engine's note velocity maps to level, -1 to 1: 0 linear,
+    // positive makes soft notes quieter, negative louder
+    pub fn set_velocity_curve(&mut self, engine: u32, curve: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_velocity_curve(curve);
+        }
+    }
+
+    // Post-fader send levels (0-1) into the shared master delay and reverb
+    pub fn set_delay_send(&mut self, engine: u32, level: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.delay_send = level.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn set_reverb_send(&mut self, engine: u32, level: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.reverb_send = level.clamp(0.0, 1.0);
+        }
+    }
+
+    // Shared delay return; mode 0 = mono, 1 = stereo, 2 = ping-pong
+    pub fn set_return_delay(&mut self, time_ms: f32, feedback: f32, mode: u8) {
+        self.return_delay.set_delay_time(time_ms);
+        self.return_delay.set_feedback(feedback);
+        self.return_delay.set_mode(mode);
+    }
+
+    // Shared FDN reverb return
+    pub fn set_return_reverb(&mut self, room_size: f32, damping: f32, pre_delay_ms: f32) {
+        self.return_reverb.set_size(room_size);
+        self.return_reverb.set_damping(damping);
+        self.return_reverb.set_pre_delay(pre_delay_ms);
+    }
+
+    // Master tape insert: saturation drive 0-1, wow/flutter amount 0-1 and
+    // high roll-off corner 2-20 kHz
+    pub fn set_tape(&mut self, enabled: bool, drive: f32, wow_flutter: f32, rolloff_freq: f32) {
+        self.tape_enabled = enabled;
+        self.tape.set_drive(drive);
+        self.tape.set_wow_flutter(wow_flutter);
+        self.tape.set_rolloff(rolloff_freq);
+    }
+
+    // Master "vintage" colour: hiss in dBFS (-96 to -48) and saturation drive
+    // 0-1. With `include_in_export` off it is skipped while export mode is on.
+    pub fn set_vintage(&mut self, enabled: bool, noise_level_db: f32, drive: f32, include_in_export: bool) {
+        self.vintage_enabled = enabled;
+        self.vintage_in_export = include_in_export;
+        self.vintage.set_noise_level_db(noise_level_db);
+        self.vintage.set_drive(drive);
+    }
+
+    // Hosts switch this on while capturing output for export
+    pub fn set_export_mode(&mut self, enabled: bool) {
+        self.export_mode = enabled;
+    }
+
+    // Master bass mono: everything below `freq` (20-500 Hz) is summed to mono
+    pub fn set_bass_mono(&mut self, enabled: bool, freq: f32) {
+        self.bass_mono_enabled = enabled;
+        self.bass_mono.set_frequency(freq);
+    }
+
+    // Master OTT-style multiband compressor: `amount` 0-1 sets how hard
+    // every band is pushed down above its upper threshold and lifted below
+    // its lower one
+    pub fn set_multiband_compressor(&mut self, enabled: bool, amount: f32) {
+        self.multiband_enabled = enabled;
+        self.multiband.set_amount(amount);
+    }
+
+    // Band 0 low, 1 mid, 2 high: thresholds in dBFS (-60 to 0, upward at or
+    // below downward) and makeup gain in dB (-24 to 24)
+    pub fn set_multiband_band(&mut self, band: u32, downward_threshold_db: f32, upward_threshold_db: f32, gain_db: f32) {
+        self.multiband.set_band(band as usize, downward_threshold_db, upward_threshold_db, gain_db);
+    }
+
+    // Band split points in Hz: low 40-1000, high 500-12000
+    pub fn set_multiband_crossovers(&mut self, low_freq: f32, high_freq: f32) {
+        self.multiband.set_crossovers(low_freq, high_freq);
+    }
+
+    // ==== ENGINES ====
+    // Beyond the built-in timeline (0) and live (1) engines, a project can
+    // add one engine per track (drums, bass, chords, lead...). Created
+    // engines have their own patch, effects, fader, pan, sends, meter and
+    // stem, and take every engine-indexed method: set_param and
+    // apply_param_block for the patch, set_engine_volume, set_engine_pan,
+    // the sends, metering, hot swaps. They play only what engine_note_on
+    // sends them; the sequencer, arpeggiator and journal stay on the
+    // built-in pair.
+
+    // Adds an engine with the default patch and returns its id, or
+    // undefined once MAX_ENGINES (16, built-ins included) are in use. Ids of
+    // destroyed engines are handed out again.
+    pub fn create_engine(&mut self) -> Option<u32> {
+        let slot = match self.extra_engines.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None if ENGINE_COUNT + self.extra_engines.len() < MAX_ENGINES => self.extra_engines.len(),
+            None => return None,
+        };
+        self.install_engine(slot);
+        Some((ENGINE_COUNT + slot) as u32)
+    }
+
+    // Removes a created engine, cutting off anything still sounding on it.
+    // Returns false for the built-in engines and unknown ids.
+    pub fn destroy_engine(&mut self, engine: u32) -> bool {
+        let Some(slot) = (engine as usize).checked_sub(ENGINE_COUNT) else {
+            return false;
+        };
+        match self.extra_engines.get_mut(slot) {
+            Some(extra @ Some(_)) => *extra = None,
+            _ => return false,
+        }
+        let id = engine as usize;
+        self.hot_swaps[id] = None;
+        if self.input_engine == engine {
+            self.input_engine = ENGINE_LIVE;
+        }
+        if self.edit_track == engine {
+            self.edit_track = ENGINE_TIMELINE;
+        }
+        for stem in &mut self.scratch.stems[id * 2..id * 2 + 2] {
+            stem.fill(0.0);
+        }
+        true
+    }
+
+    pub fn get_engine_count(&self) -> u32 {
+        (ENGINE_COUNT + self.extra_engines.iter().flatten().count()) as u32
+    }
+
+    // Note on for any engine id: the built-in pair go through note_on and
+    // timeline_note_on (scale quantize, arpeggiator, journal included)
+    pub fn engine_note_on(&mut self, engine: u32, midi_note: u8, velocity: f32) {
+        match engine {
+            ENGINE_TIMELINE => self.timeline_note_on(midi_note, velocity),
+            ENGINE_LIVE => self.note_on(midi_note, velocity),
+            _ => {
+                self.event_log.record(self.sample_time, "engine note on", [midi_note as f32, engine as f32]);
+                if let Some(e) = self.engine_mut(engine) {
+                    e.note_on(midi_note, velocity);
+                }
+            }
+        }
+    }
+
+    pub fn engine_note_off(&mut self, engine: u32, midi_note: u8, release_velocity: Option<f32>) {
+        match engine {
+            ENGINE_TIMELINE => self.timeline_note_off(midi_note),
+            ENGINE_LIVE => self.note_off(midi_note, release_velocity),
+            _ => {
+                self.event_log.record(self.sample_time, "engine note off", [midi_note as f32, engine as f32]);
+                if let Some(e) = self.engine_mut(engine) {
+                    e.note_off_with_velocity(midi_note, release_velocity);
+                }
+            }
+        }
+    }
+
+    // ==== METERING ====
+
+    // [peak, rms] of an engine's post-fader output, linear 0-1+. Peak falls
+    // 20 dB over 1.5 s, RMS averages over 300 ms. Empty for unknown engines.
+    pub fn get_meter(&self, engine: u32) -> Vec<f32> {
+        match self.meters.get(engine as usize) {
+            Some(meter) => vec![meter.peak(), meter.rms()],
+            None => Vec::new(),
+        }
+    }
+
+    // [peak, rms] of the final output, after the master chain
+    pub fn get_master_meter(&self) -> Vec<f32> {
+        vec![self.master_meter.peak(), self.master_meter.rms()]
+    }
+
+    // dB between the loudest post-fader peak of an engine since the last
+    // reset_headroom and full scale; negative once it has clipped
+    pub fn get_headroom(&self, engine: u32) -> Option<f32> {
+        self.meters.get(engine as usize).map(Meter::headroom_db)
+    }
+
+    pub fn get_master_headroom(&self) -> f32 {
+        self.master_meter.headroom_db()
+    }
+
+    // Starts every headroom reading over, engines and master
+    pub fn reset_headroom(&mut self) {
+        for meter in &mut self.meters {
+            meter.reset_headroom();
+        }
+        self.master_meter.reset_headroom();
+    }
+
+    // Linear magnitudes of a 2048-point Hann-windowed FFT over an engine's
+    // latest post-effects output (pre-fader), bin k at k * 48000 / 2048 Hz.
+    // Fills up to get_spectrum_bins() values of `out`; a full-scale sine
+    // reads about 1.0 in its bin.
+    pub fn get_spectrum(&mut self, engine: u32, out: &mut [f32]) {
+        if let Some(analyzer) = self.spectra.get_mut(engine as usize) {
+            analyzer.magnitudes(out);
+        }
+    }
+
+    pub fn get_spectrum_bins(&self) -> u32 {
+        SPECTRUM_BINS as u32
+    }
+
+    // The last out.len() samples (up to 2048) of an engine's post-effects
+    // output, mono. `trigger` aligns the start to a rising zero crossing for
+    // a stable oscilloscope display.
+    pub fn get_scope_buffer(&self, engine: u32, out: &mut [f32], trigger: bool) {
+        if let Some(analyzer) = self.spectra.get(engine as usize) {
+            analyzer.waveform(out, trigger);
+        }
+    }
+
+    // ==== TELEMETRY ====
+
+    // Sounding voices on an engine, releasing tails included (0 for unknown
+    // engines)
+    pub fn get_active_voice_count(&self, engine: u32) -> u32 {
+        self.engine(engine).map_or(0, |e| e.active_voices() as u32)
+    }
+
+    // MIDI notes an engine is holding (key or pedal down, not released),
+    // lowest first, for an on-screen keyboard. Empty for unknown engines.
+    pub fn get_active_notes(&self, engine: u32) -> Vec<u8> {
+        self.engine(engine).map(Engine::active_notes).unwrap_or_default()
+    }
+
+    // Every sounding voice of an engine as flat [note, stage, level]
+    // triples: stage 0 attack, 1 decay, 2 sustain, 3 release; level is the
+    // envelope times velocity, 0-1
+    pub fn get_voice_states(&self, engine: u32) -> Vec<f32> {
+        let Some(e) = self.engine(engine) else {
+            return Vec::new();
+        };
+        e.voices
+            .iter()
+            .filter(|voice| voice.is_active())
+            .flat_map(|voice| {
+                let stage = match voice.stage() {
+                    EnvelopeStage::Attack => 0.0,
+                    EnvelopeStage::Decay => 1.0,
+                    EnvelopeStage::Sustain => 2.0,
+                    EnvelopeStage::Release | EnvelopeStage::Idle => 3.0,
+                };
+                [voice.note() as f32, stage, voice.level()]
+            })
+            .collect()
+    }
+
+    // Smoothed time spent in process calls divided by the duration of the
+    // audio they produced, measured by the engine itself: 1.0 means no
+    // headroom left. Hosts without their own timing can pass it on to
+    // report_cpu_load.
+    pub fn get_dsp_load(&self) -> f32 {
+        self.dsp_load
+    }
+
+    // ==== ENGINE EVENTS ====
+
+    // Drains the activity since the last call as flat [kind, engine, a, b,
+    // sample_time] records, oldest first:
+    //   0 note started   a = note, b = velocity
+    //   1 note ended     a = note (its voice has fallen silent)
+    //   2 voice stolen   a = note cut off, b = note taking its voice
+    //   3 pattern looped a = pattern
+    //   4 clip           a = peak; engine -1 is the master output
+    // sample_time is the frame of the render segment the event fell in. Up
+    // to 1024 events are held between drains.
+    pub fn drain_events(&mut self) -> Vec<f64> {
+        self.events.drain()
+    }
+
+    // Events lost to a full queue since the last drain_events
+    pub fn get_dropped_events(&self) -> u32 {
+        self.events.dropped()
+    }
+
+    // On by default; disabling clears the queue and stops collecting
+    pub fn set_events_enabled(&mut self, enabled: bool) {
+        self.events.set_enabled(enabled);
+    }
+
+    pub fn get_events_enabled(&self) -> bool {
+        self.events.is_enabled()
+    }
+
+    // ==== CPU GOVERNOR ====
+
+    // Host-measured render load: time spent in the last process call divided
+    // by the duration of the audio it produced (1.0 = no headroom left)
+    pub fn report_cpu_load(&mut self, load: f32) {
+        if let Some(limits) = self.governor.report_load(load) {
+            self.apply_voice_limits(limits);
+        }
+    }
+
+    pub fn set_governor_enabled(&mut self, enabled: bool) {
+        if let Some(limits) = self.governor.set_enabled(enabled) {
+            self.apply_voice_limits(limits);
+        }
+    }
+
+    // 0 = full quality, each step up is cheaper
+    pub fn get_governor_level(&self) -> u8 {
+        self.governor.level()
+    }
+
+    // Drains the actions taken since the last call as (code, value) pairs:
+    // 0 = unison limit, 1 = polyphony limit, 2 = voices released
+    pub fn take_governor_actions(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.governor_actions)
+    }
+
+    // Master lookahead limiter; ceiling in dBFS (-24 to 0), release in ms
+    pub fn set_limiter(&mut self, enabled: bool, ceiling_db: f32, release_ms: f32) {
+        self.limiter_enabled = enabled;
+        if enabled {
+            self.limiter.set_ceiling_db(ceiling_db);
+            self.limiter.set_release(release_ms);
+        }
+    }
+
+    // Last stage of the master: tanh soft-clip with 0-24 dB input gain and
+    // shape 0 (gentle) to 1 (hard knee)
+    pub fn set_soft_clipper(&mut self, enabled: bool, input_gain_db: f32, shape: f32) {
+        self.soft_clipper_enabled = enabled;
+        self.soft_clipper.set_input_gain_db(input_gain_db);
+        self.soft_clipper.set_shape(shape);
+    }
+
+    // Runs the nonlinear stages (per-voice drive after the filter, tape and
+    // vintage saturation, soft clipper) at 1x, 2x or 4x the sample rate
+    // through half-band filters, so hard drive doesn't alias; 4x costs the
+    // most CPU. A change crossfades over a few ms, so it's safe mid-note.
+    pub fn set_oversampling(&mut self, factor: u32) {
+        let factor = oversampler::supported_factor(factor);
+        self.oversampling = factor;
+        for engine in self.engines_mut() {
+            engine.set_oversampling(factor);
+        }
+        self.tape.set_oversampling(factor);
+        self.vintage.set_oversampling(factor);
+        self.soft_clipper.set_oversampling(factor);
+    }
+
+    pub fn get_oversampling(&self) -> u32 {
+        self.oversampling
+    }
+
+    // One switch for CPU against quality, on every engine and the master:
+    // 0 = eco (no oversampling, sparser FDN reverb diffusion with its line
+    // modulation worked out at block rate) for phones on battery, 1 =
+    // normal (the defaults), 2 = high (4x oversampling of the drive,
+    // saturation and clipper so they don't alias, denser reverb
+    // diffusion). Oscillators keep their PolyBLEP anti-aliasing in every
+    // mode since it costs next to nothing. Switches without a gap; the
+    // oversampling crossfades and get_latency_samples follows it. Sets the
+    // oversampling factor, so call set_oversampling afterwards to override.
+    pub fn set_quality_mode(&mut self, mode: u8) {
+        self.quality_mode = mode.min(QUALITY_HIGH);
+        let (oversampling, density) = quality_settings(self.quality_mode);
+        self.set_oversampling(oversampling);
+        for engine in self.engines_mut() {
+            engine.set_reverb_density(density);
+        }
+        self.return_reverb.set_density(density);
+    }
+
+    pub fn get_quality_mode(&self) -> u8 {
+        self.quality_mode
+    }
+
+    // Processing latency in output samples (at the output sample rate): the
+    // master chain's delay (limiter lookahead, tape delay, oversampling)
+    // plus the voice drive's oversampling on the worst engine. Schedule
+    // visuals this much later than notes, and shift recorded audio this
+    // much earlier, to line them up. Changes with those settings.
+    pub fn get_latency_samples(&self) -> u32 {
+        let mut latency = 0.0;
+        if self.tape_enabled {
+            latency += self.tape.latency();
+        }
+        if self.vintage_enabled && (self.vintage_in_export || !self.export_mode) {
+            latency += self.vintage.latency();
+        }
+        if self.limiter_enabled {
+            latency += self.limiter.latency() as f32;
+        }
+        if self.soft_clipper_enabled {
+            latency += self.soft_clipper.latency();
+        }
+        latency += self.engines().map(Engine::latency).fold(0.0, f32::max);
+        (latency * self.get_output_sample_rate() / SAMPLE_RATE).round() as u32
+    }
+
+    pub fn set_waveform(&mut self, engine: u32, waveform: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_waveform(waveform);
+        }
+    }
+
+    pub fn set_adsr(&mut self, engine: u32, attack: f32, decay: f32, sustain: f32, release: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_adsr(attack, decay, sustain, release);
+        }
+    }
+
+    // Cutoff envelope: ADSR in seconds (sustain 0-1) and the sweep depth in
+    // octaves above (positive) or below (negative) the filter cutoff
+    pub fn set_filter_envelope(&mut self, engine: u32, attack: f32, decay: f32, sustain: f32, release: f32, amount_octaves: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_envelope(attack, decay, sustain, release, amount_octaves);
+        }
+    }
+
+    // Pitch envelope: a quick attack-decay sweep (seconds) of `amount`
+    // semitones at its peak, up or down, on each new note; fast decays give
+    // blips and kick-drum drops. 0 semitones (the default) turns it off.
+    pub fn set_pitch_env(&mut self, engine: u32, attack: f32, decay: f32, amount: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_pitch_envelope(attack, decay, amount);
+        }
+    }
+
+    // Per-voice saturation after the filter, 0-1
+    pub fn set_drive(&mut self, engine: u32, drive: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_drive(drive);
+        }
+    }
+
+    // One-call brass stab patch with two macros, brightness and tightness (0-1)
+    pub fn set_brass_stab(&mut self, engine: u32, brightness: f32, tightness: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_brass_stab(brightness, tightness);
+        }
+    }
+
+    // String machine mode: attack/release swell in seconds, brightness and
+    // ensemble depth 0-1. Chords share one divide-down oscillator bank.
+    // Velocity-switched ghost layer: notes softer than `threshold` (0-1, 0
+    // turns it off) are darkened by a low-pass at `tone_hz` and have their
+    // decay and release scaled by `length` (0.1-1)
+    pub fn set_ghost_layer(&mut self, engine: u32, threshold: f32, tone_hz: f32, length: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_ghost_layer(threshold, tone_hz, length);
+        }
+    }
+
+    // Motion macro for pads, 0-1: slow pan, cutoff and pitch-drift LFOs plus
+    // reverb and delay mix, all from one control
+    pub fn set_motion(&mut self, engine: u32, amount: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_motion(amount);
+        }
+    }
+
+    pub fn set_string_machine(&mut self, engine: u32, enabled: bool, attack: f32, release: f32, brightness: f32, ensemble: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_string_machine(enabled, attack, release, brightness, ensemble);
+        }
+    }
+
+    // Organ voice mode (waveform 6): nine drawbar levels 0-8, from 16' to 1'
+    pub fn set_organ_drawbars(&mut self, engine: u32, levels: &[f32]) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_organ_drawbars(levels);
+        }
+    }
+
+    // Percussion on the 2nd or 3rd harmonic, fast (0.2s) or slow (1s) decay
+    pub fn set_organ_percussion(&mut self, engine: u32, enabled: bool, harmonic: u8, fast: bool, level: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_organ_percussion(enabled, harmonic, fast, level);
+        }
+    }
+
+    pub fn set_organ_key_click(&mut self, engine: u32, level: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_organ_key_click(level);
+        }
+    }
+
+    // Vector mode (waveform 8): four waveforms at the corners of an XY pad,
+    // A (0, 0), B (1, 0), C (0, 1) and D (1, 1), crossfaded by the position
+    // 0-1 on each axis
+    pub fn set_vector_position(&mut self, engine: u32, x: f32, y: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_vector_position(x, y);
+        }
+    }
+
+    // Waveform ids 0-7 for corners A-D
+    pub fn set_vector_sources(&mut self, engine: u32, sources: &[u8]) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_vector_sources(sources);
+        }
+    }
+
+    // Movement for evolving pads: an LFO circling the position (rate
+    // 0.01-20 Hz, radius 0-1) and how far the filter envelope pushes it
+    // along X and Y at its peak (-1 to 1), so each chord sweeps as it sounds
+    pub fn set_vector_modulation(&mut self, engine: u32, lfo_rate: f32, lfo_depth: f32, env_x: f32, env_y: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_vector_modulation(lfo_rate, lfo_depth, env_x, env_y);
+        }
+    }
+    
+    // LFO controls
+    pub fn set_lfo_rate(&mut self, engine: u32, rate: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_lfo_rate(rate);
+        }
+    }
+
+    pub fn set_lfo_depth(&mut self, engine: u32, depth: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_lfo_depth(depth);
+        }
+    }
+
+    pub fn set_lfo_waveform(&mut self, engine: u32, waveform: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_lfo_waveform(waveform);
+        }
+    }
+
+    // 0 free running, 1 one-shot: a single cycle from each note, then
+    // still; 2 restarted whenever the transport starts
+    pub fn set_lfo_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_lfo_mode(mode);
+        }
+    }
+
+    // LFO amounts (0-1) on the effects, besides the filter its depth sets:
+    // delay time (up to half either way), reverb size and tremolo depth (up
+    // to 0.5 either way) and flanger rate (up to two octaves either way).
+    // The effects follow smoothly, without zipper noise or clicks.
+    pub fn set_lfo_destinations(&mut self, engine: u32, delay_time: f32, reverb_size: f32, flanger_rate: f32, tremolo_depth: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_lfo_destinations(delay_time, reverb_size, flanger_rate, tremolo_depth);
+        }
+    }
+
+    pub fn set_detune(&mut self, engine: u32, cents: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_detune(cents);
+        }
+    }
+
+    pub fn set_glide_time(&mut self, engine: u32, time_ms: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_glide_time(time_ms);
+        }
+    }
+
+    // Glide mode flags: 1 = legato only (fresh notes start at pitch, only
+    // notes played over a held one glide), 2 = constant rate (the glide time
+    // covers one octave, so wider leaps glide longer). 0 glides every note
+    // over the same time.
+    pub fn set_glide_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_glide_mode(mode);
+        }
+    }
+
+    // What a note-on does to a voice that is still sounding, whether stolen
+    // or playing the same note again: 0 = full retrigger (the old note
+    // fades out and the new one attacks from silence, the default), 1 = soft
+    // (attack again from the current level), 2 = legato (a held envelope
+    // carries on without a new attack). In soft and legato mode a repeated
+    // note reuses its voice.
+    pub fn set_env_retrigger_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_env_retrigger_mode(mode);
+        }
+    }
+
+    // Where the oscillators start on a fresh note: 0 = reset (a fixed phase,
+    // unison staggered evenly; the default), 1 = free-running (on from
+    // wherever they stopped), 2 = random. Free and random keep repeated
+    // chords from sounding identical.
+    pub fn set_phase_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_phase_mode(mode);
+        }
+    }
+
+    // Varies each note's velocity at random by up to `amount` (0-1) of its
+    // value either way, so repeated notes and chords don't strike exactly
+    // alike. 0 (the default) plays velocities as they come.
+    pub fn set_velocity_random(&mut self, engine: u32, amount: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_velocity_random(amount);
+        }
+    }
+
+    // How much note-off velocity shapes the release, 0-1: at 1 the fastest
+    // release plays a quarter of the release time and the slowest four times
+    // it. 0 (the default) ignores release velocity.
+    pub fn set_release_velocity(&mut self, engine: u32, amount: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_release_velocity_amount(amount);
+        }
+    }
+
+    // Filter (per voice, per unison oscillator)
+    pub fn set_filter_enabled(&mut self, engine: u32, enabled: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_enabled(enabled);
+        }
+    }
+
+    pub fn set_filter_cutoff(&mut self, engine: u32, cutoff: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_cutoff(cutoff);
+        }
+    }
+
+    pub fn set_filter_resonance(&mut self, engine: u32, resonance: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_resonance(resonance);
+        }
+    }
+
+    // 0 = lowpass, 1 = highpass, 2 = bandpass, 3 = notch
+    pub fn set_filter_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_mode(mode);
+        }
+    }
+
+    // 2 = 12 dB/octave, 4 = 24 dB/octave for steeper bass patches
+    pub fn set_filter_slope(&mut self, engine: u32, poles: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_slope(poles);
+        }
+    }
+
+    // Up to 7 stacked oscillators per note; filter_spread (0-1) gives each
+    // a slightly different cutoff, up to ±1 octave
+    pub fn set_unison(&mut self, engine: u32, voices: u8, detune_cents: f32, filter_spread: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_unison(voices, detune_cents, filter_spread);
+        }
+    }
+
+    // Keep the lowest held note when all voices are busy and one must be stolen
+    pub fn set_protect_lowest_note(&mut self, engine: u32, enabled: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_protect_lowest_note(enabled);
+        }
+    }
+
+    // ==== ENGINE EFFECTS CONTROL ====
+
+    pub fn set_delay(&mut self, engine: u32, enabled: bool, time_ms: f32, feedback: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_delay(enabled, time_ms, feedback, mix);
+        }
+    }
+
+    // 0 = mono, 1 = stereo, 2 = ping-pong
+    pub fn set_delay_mode(&mut self, engine: u32, mode: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_delay_mode(mode);
+        }
+    }
+
+    // Low cut and high cut in Hz inside the delay's feedback loop, so each
+    // repeat loses more lows and highs; 20 and 20000 leave it open
+    pub fn set_delay_tone(&mut self, engine: u32, low_cut: f32, high_cut: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_delay_tone(low_cut, high_cut);
+        }
+    }
+
+    // Right channel delay time in ms for stereo and ping-pong modes (0
+    // follows the main time) and, in stereo mode, the share of each side's
+    // feedback taken from the other (0-1)
+    pub fn set_delay_stereo(&mut self, engine: u32, right_time_ms: f32, cross_feedback: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_delay_stereo(right_time_ms, cross_feedback);
+        }
+    }
+
+    // Analog-style wobble of the delay time: depth 0-10 ms, rate in Hz
+    pub fn set_delay_modulation(&mut self, engine: u32, depth_ms: f32, rate: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_delay_modulation(depth_ms, rate);
+        }
+    }
+
+    // High-pass in Hz on the reverb input, 20 for none
+    pub fn set_reverb_low_cut(&mut self, engine: u32, freq: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb_low_cut(freq);
+        }
+    }
+
+    pub fn set_reverb(&mut self, engine: u32, enabled: bool, room_size: f32, damping: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb(enabled, room_size, damping, mix);
+        }
+    }
+
+    // Holds the current reverb tail indefinitely and keeps new input out of
+    // it, for drones built from a single chord. The convolution reverb can
+    // only stop taking input; its tail still ends with the impulse response.
+    pub fn set_reverb_freeze(&mut self, engine: u32, frozen: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb_freeze(frozen);
+        }
+    }
+
+    // 0 = algorithmic (Freeverb), 1 = convolution with the loaded impulse response,
+    // 2 = feedback delay network (room size sets the decay time),
+    // 3 = shimmer (the FDN with octave-up feedback)
+    pub fn set_reverb_type(&mut self, engine: u32, reverb_type: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb_type(reverb_type);
+        }
+    }
+
+    // Shimmer amount 0-1, used by reverb type 3
+    pub fn set_reverb_shimmer(&mut self, engine: u32, amount: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb_shimmer(amount);
+        }
+    }
+
+    // FDN and shimmer reverbs only: pre-delay up to 200ms, diffusion and modulation 0-1
+    pub fn set_reverb_character(&mut self, engine: u32, pre_delay_ms: f32, diffusion: f32, modulation: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_reverb_character(pre_delay_ms, diffusion, modulation);
+        }
+    }
+
+    // Mono impulse response at the engine sample rate, up to 3 seconds
+    pub fn load_impulse_response(&mut self, engine: u32, ir: &[f32]) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.convolution.load_impulse_response(ir);
+        }
+    }
+
+    pub fn set_tremolo(&mut self, engine: u32, enabled: bool, rate: f32, depth: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_tremolo(enabled, rate, depth, mix);
+        }
+    }
+
+    // Tremolo waveform, 0 = sine (default), 1 = triangle, 2 = square; with
+    // `auto_pan` the left and right channels swing half a cycle apart so the
+    // sound moves across the stereo field instead of pulsing in place
+    pub fn set_tremolo_shape(&mut self, engine: u32, waveform: u8, auto_pan: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_tremolo_shape(waveform, auto_pan);
+        }
+    }
+
+    pub fn set_flanger(&mut self, engine: u32, enabled: bool, rate: f32, depth: f32, feedback: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_flanger(enabled, rate, depth, feedback, mix);
+        }
+    }
+
+    pub fn set_bitcrusher(&mut self, engine: u32, enabled: bool, bits: f32, downsample: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_bitcrusher(enabled, bits, downsample, mix);
+        }
+    }
+
+    // Gains in dB (±24), frequencies in Hz for the low shelf, mid peak and high shelf
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_eq(&mut self, engine: u32, enabled: bool, low_gain_db: f32, low_freq: f32, mid_gain_db: f32, mid_freq: f32, high_gain_db: f32, high_freq: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_eq(enabled, low_gain_db, low_freq, mid_gain_db, mid_freq, high_gain_db, high_freq);
+        }
+    }
+
+    // 12 dB/oct high-pass at the head of the chain, 20-300 Hz
+    pub fn set_rumble_filter(&mut self, engine: u32, enabled: bool, freq: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_rumble_filter(enabled, freq);
+        }
+    }
+
+    // Envelope-following band-pass: sensitivity and range 0-1, Q 0.7-10
+    pub fn set_auto_wah(&mut self, engine: u32, enabled: bool, sensitivity: f32, range: f32, q: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_auto_wah(enabled, sensitivity, range, q, mix);
+        }
+    }
+
+    // Rotary speaker: `fast` switches chorale/tremolo, acceleration is the
+    // spin-up time in seconds (0.1-10), balance 0 drum to 1 horn, spread 0-1
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_rotary(&mut self, engine: u32, enabled: bool, fast: bool, acceleration: f32, balance: f32, spread: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_rotary(enabled, fast, acceleration, balance, spread, mix);
+        }
+    }
+
+    // Blends in a copy shifted by -12 to +12 semitones, e.g. 12 with mix 0.5
+    // for octave-doubled chords
+    pub fn set_pitch_shifter(&mut self, engine: u32, enabled: bool, semitones: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_pitch_shifter(enabled, semitones, mix);
+        }
+    }
+
+    // Bode-style shifter: moves every partial by `shift_hz` (-2000 to 2000,
+    // negative down) for metallic, inharmonic tones; a few Hz gives a slow
+    // phasing swirl
+    pub fn set_frequency_shifter(&mut self, engine: u32, enabled: bool, shift_hz: f32, mix: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_frequency_shifter(enabled, shift_hz, mix);
+        }
+    }
+
+    // ==== EFFECT CHAIN ====
+
+    // Effect ids: 0 bitcrusher, 1 flanger, 2 tremolo, 3 eq, 4 delay, 5 reverb,
+    // 6 auto-wah, 7 rotary, 8 pitch shifter, 9 frequency shifter.
+    // Effects left out of `order` keep their default relative position after
+    // the listed ones; the rumble filter always stays at the head.
+    pub fn set_effect_order(&mut self, engine: u32, order: &[u8]) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_effect_order(order);
+        }
+    }
+
+    pub fn get_effect_order(&self, engine: u32) -> Vec<u8> {
+        self.engine(engine)
+            .map(|e| e.patch.effect_order.to_vec())
+            .unwrap_or_default()
+    }
+
+    // A/B comparison of effect chains: store the current effect settings
+    // in slot 0 (A) or 1 (B), change them and store the other slot, then
+    // toggle_fx_ab swaps the whole chain between the two, leaving the
+    // voice alone. While both slots are stored, each side's output is
+    // matched to the level going into the chain (within 12 dB), so a
+    // louder setting doesn't win on loudness. False for a bad slot.
+    pub fn store_fx_snapshot(&mut self, engine: u32, slot: u32) -> bool {
+        self.engine_mut(engine).is_some_and(|e| e.store_fx_snapshot(slot as usize))
+    }
+
+    // Switches to the other stored slot; false if it's empty
+    pub fn toggle_fx_ab(&mut self, engine: u32) -> bool {
+        self.engine_mut(engine).is_some_and(|e| e.toggle_fx_ab())
+    }
+
+    // The slot playing, or -1 before anything is stored
+    pub fn get_fx_ab_slot(&self, engine: u32) -> i32 {
+        self.engine(engine)
+            .and_then(|e| e.fx_ab.active_slot())
+            .map_or(-1, |slot| slot as i32)
+    }
+
+    // Forgets both slots; the effects stay as they are and the level
+    // compensation eases off
+    pub fn clear_fx_ab(&mut self, engine: u32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.fx_ab.clear();
+        }
+    }
+
+    // ==== PARAMETERS BY ID ====
+
+    // Sets any parameter by its params::ParamId number, so new parameters
+    // and generic automation need no dedicated method. Switches take 0/1.
+    // Unknown ids are ignored.
+    pub fn set_param(&mut self, engine: u32, param: u32, value: f32) {
+        let Some(param) = ParamId::from_u32(param) else {
+            return;
+        };
+        match (param, engine) {
+            (ParamId::Volume, _) => self.set_engine_volume(engine, value),
+            _ => {
+                if let Some(e) = self.engine_mut(engine) {
+                    e.set_param(param, value);
+                }
+            }
+        }
+    }
+
+    // Current value of a parameter, or undefined for an unknown id or engine
+    pub fn get_param(&self, engine: u32, param: u32) -> Option<f32> {
+        let param = ParamId::from_u32(param)?;
+        if param == ParamId::Volume {
+            return self.engine(engine).map(|_| self.track_volume(engine));
+        }
+        self.engine(engine)?.param(param)
+    }
+
+    // Many set_param calls in one crossing of the JS/WASM boundary: `block`
+    // is packed (param id, value) pairs, e.g. a Float32Array filled once per
+    // render quantum while knobs are automated. Applied in order; unknown
+    // ids and a trailing unpaired value are skipped.
+    pub fn apply_param_block(&mut self, engine: u32, block: &[f32]) {
+        for pair in block.chunks_exact(2) {
+            self.set_param(engine, pair[0] as u32, pair[1]);
+        }
+    }
+
+    // ==== AUTOMATION ====
+
+    // Adds a breakpoint to a pattern's lane for a params::ParamId on the
+    // timeline engine: `value` at `beat` from the pattern start. While the
+    // pattern plays the parameter follows the lane, moving from each point
+    // to the next along its curve (0 linear, 1 exponential, 2 hold) and
+    // updated every 32 samples. A point at the same beat is replaced.
+    // Returns false for a bad pattern or a full lane (256 points) or
+    // pattern (32 lanes).
+    pub fn add_automation_point(&mut self, pattern: u32, param_id: u32, beat: f64, value: f32, curve: u8) -> bool {
+        let point = AutomationPoint { beat, value, curve };
+        self.automation.add_point(pattern as usize, param_id, point)
+    }
+
+    pub fn remove_automation_point(&mut self, pattern: u32, param_id: u32, beat: f64) {
+        self.automation.remove_point(pattern as usize, param_id, beat);
+    }
+
+    pub fn clear_automation(&mut self, pattern: u32, param_id: u32) {
+        self.automation.clear_lane(pattern as usize, param_id);
+    }
+
+    // A lane's points as flat [beat, value, curve, ...]
+    pub fn get_automation_points(&self, pattern: u32, param_id: u32) -> Vec<f64> {
+        self.automation
+            .points(pattern as usize, param_id)
+            .iter()
+            .flat_map(|p| [p.beat, p.value as f64, p.curve as f64])
+            .collect()
+    }
+
+    // Parameter ids with a lane in `pattern`
+    pub fn get_automated_params(&self, pattern: u32) -> Vec<u32> {
+        self.automation.params(pattern as usize).collect()
+    }
+
+    // ==== MACROS ====
+    // Four macro knobs, each sweeping any number of parameters at once with
+    // the scaling done here, so the UI only sends one 0-1 value per knob.
+
+    // Adds a live engine parameter (id as set_param) to macro `macro_idx`
+    // (0-3), scaled from `min` at 0 to `max` at 1. curve as map_cc: 0
+    // linear, 1 exponential, 2 toggle. Assigning the same parameter again
+    // replaces it. Returns false for an unknown slot or a full one.
+    pub fn assign_macro(&mut self, macro_idx: u32, param_id: u32, min: f32, max: f32, curve: u8) -> bool {
+        self.assign_engine_macro(macro_idx, ENGINE_LIVE, param_id, min, max, curve)
+    }
+
+    // assign_macro for a parameter on any engine
+    pub fn assign_engine_macro(&mut self, macro_idx: u32, engine: u32, param_id: u32, min: f32, max: f32, curve: u8) -> bool {
+        self.macros.assign(macro_idx as usize, MacroTarget::new(engine, param_id, min, max, curve))
+    }
+
+    pub fn unassign_macro(&mut self, macro_idx: u32, engine: u32, param_id: u32) {
+        self.macros.unassign(macro_idx as usize, engine, param_id);
+    }
+
+    pub fn clear_macro(&mut self, macro_idx: u32) {
+        self.macros.clear(macro_idx as usize);
+    }
+
+    // Turns macro `macro_idx` to `value` (0-1), setting every parameter
+    // assigned to it
+    pub fn set_macro(&mut self, macro_idx: u32, value: f32) {
+        let count = self.macros.set(macro_idx as usize, value).len();
+        let value = value.clamp(0.0, 1.0);
+        for index in 0..count {
+            let target = self.macros.targets(macro_idx as usize)[index];
+            self.set_param(target.engine, target.param, target.scale(value));
+        }
+    }
+
+    pub fn get_macro(&self, macro_idx: u32) -> Option<f32> {
+        self.macros.value(macro_idx as usize)
+    }
+
+    // The macro's assignments as (engine, param id) pairs
+    pub fn get_macro_targets(&self, macro_idx: u32) -> Vec<u32> {
+        self.macros.targets(macro_idx as usize).iter().flat_map(|t| [t.engine, t.param]).collect()
+    }
+
+    // ==== MESSAGE RING ====
+
+    // Where the shared message ring lives in WASM memory, for a main thread
+    // writing to it through a SharedArrayBuffer view; see message_ring.rs
+    // for the layout. Messages are applied at the start of the next
+    // process_stereo. Only wasm32 has addresses that fit the u32 JS sees.
+    #[cfg(target_arch = "wasm32")]
+    pub fn get_message_ring_ptr(&self) -> u32 {
+        self.message_ring.slots_ptr() as u32
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn get_message_ring_indices_ptr(&self) -> u32 {
+        self.message_ring.indices_ptr() as u32
+    }
+
+    pub fn get_message_ring_capacity(&self) -> u32 {
+        MESSAGE_RING_CAPACITY as u32
+    }
+
+    // Queues a message from Rust or the audio thread's own JS, e.g. a
+    // native host sharing the engine between threads. Returns false when
+    // the ring is full.
+    pub fn push_message(&self, kind: u32, a: f32, b: f32, c: f32) -> bool {
+        self.message_ring.push([kind as f32, a, b, c])
+    }
+
+    // ==== CHORD SYMBOLS ====
+
+    // Plays a chord on the live engine by symbol. chord_type: 0 major,
+    // 1 minor, 2 dom7, 3 maj7, 4 min7, 5 sus2, 6 sus4, 7 aug, 8 dim, 9 maj9,
+    // 10 min9, 11 maj6, 12 dom9, 13 dom13, 14 maj13, 15 min13. The chord
+    // replaces any chord still held from an earlier play_chord. `cents`
+    // optionally detunes each chord tone, lowest first, by -100 to 100
+    // cents for just-intonation voicings (e.g. [0, -13.7, 2] for a pure
+    // major triad); the offsets last while the chord is held and every
+    // other note stays on the tuning.
+    pub fn play_chord(&mut self, root_midi: u8, chord_type: u8, inversion: u8, velocity: f32, cents: Option<Vec<f32>>) {
+        let cents = cents.unwrap_or_default();
+        self.journal_event(JournalEvent::PlayChord { root: root_midi, chord_type, inversion, velocity, cents: cents.clone() });
+        let notes = chords::chord_notes(root_midi, chord_type, inversion);
+        self.play_held_chord(&notes, &[velocity], &cents);
+    }
+
+    // play_chord for an explicit voicing with a velocity per note, so a
+    // melody note can ride on top of its chord. Notes without a velocity
+    // take the last one given (0.8 if there are none). Strums, the
+    // arpeggiator and release_chord treat it like any play_chord chord.
+    pub fn play_voicing(&mut self, notes: &[u8], velocities: &[f32]) {
+        self.journal_event(JournalEvent::PlayVoicing { notes: notes.to_vec(), velocities: velocities.to_vec() });
+        self.play_held_chord(notes, velocities, &[]);
+    }
+
+    pub fn release_chord(&mut self) {
+        self.journal_event(JournalEvent::ReleaseChord);
+        self.release_held_chord();
+    }
+
+    // Staggers play_chord notes over `time_ms` (0-500) from first to last.
+    // direction: 0 upward (lowest note first), 1 downward, 2 alternating
+    // like strumming a guitar up and down. humanize 0-1 scatters each
+    // note's timing and velocity a little.
+    pub fn set_strum(&mut self, time_ms: f32, direction: u8, humanize_amount: f32) {
+        self.strum_time_ms = time_ms.clamp(0.0, 500.0);
+        self.strum_direction = direction.min(2);
+        self.strum_humanize = humanize_amount.clamp(0.0, 1.0);
+        self.strum_upward = self.strum_direction != 1;
+    }
+
+    // Glides the live engine's held notes onto a new chord over `time_ms`
+    // instead of releasing and retriggering: each new note takes over the
+    // nearest sounding voice, extra voices are released and extra notes
+    // start fresh. The notes then count as the held play_chord chord. The
+    // arpeggiator, string machine and filter keyboard can't glide whole
+    // chords, so there the old chord is released and the new one played.
+    pub fn glide_to_chord(&mut self, notes: &[u8], time_ms: f32) {
+        self.journal_event(JournalEvent::GlideToChord { notes: notes.to_vec(), time_ms });
+        let mut notes = notes.to_vec();
+        if self.scale_quantize {
+            for note in &mut notes {
+                *note = scales::quantize(*note, self.scale_mask);
+            }
+            notes.dedup();
+        }
+        let live = &self.live_engine;
+        if self.arpeggiator_enabled || live.patch.string_machine_enabled || live.filter_keyboard {
+            self.release_held_chord();
+            for &note in &notes {
+                self.live_note_on(note, 0.8);
+            }
+        } else {
+            for note in std::mem::take(&mut self.held_chord) {
+                self.strum_queue.cancel_note_on(note);
+            }
+            self.live_engine.glide_to_chord(&notes, time_ms.max(0.0));
+        }
+        self.held_chord = notes;
+    }
+
+    // Notes play_chord would play, for display
+    pub fn get_chord_notes(&self, root_midi: u8, chord_type: u8, inversion: u8) -> Vec<u8> {
+        chords::chord_notes(root_midi, chord_type, inversion)
+    }
+
+    pub fn get_chord_type_name(&self, chord_type: u8) -> String {
+        chords::chord_type_name(chord_type).unwrap_or_default().to_string()
+    }
+
+    // ==== SCALE QUANTIZE ====
+
+    // Key for quantizing live notes: root pitch class 0-11 (0 = C) and
+    // scale 0 major, 1 natural minor, 2 dorian, 3 phrygian, 4 lydian,
+    // 5 mixolydian, 6 locrian, 7 harmonic minor, 8 melodic minor,
+    // 9 major pentatonic, 10 minor pentatonic, 11 blues
+    pub fn set_scale(&mut self, root: u8, scale_type: u8) {
+        if let Some(mask) = scales::scale_mask(root % 12, scale_type) {
+            self.scale_root = root % 12;
+            self.scale_type = scale_type;
+            self.scale_mask = mask;
+        }
+    }
+
+    // Snaps out-of-scale live notes (keys, MIDI and play_chord) to the
+    // nearest note of the scale before they trigger
+    pub fn set_scale_quantize(&mut self, enabled: bool) {
+        self.scale_quantize = enabled;
+    }
+
+    pub fn get_scale_quantize(&self) -> bool {
+        self.scale_quantize
+    }
+
+    // Where a live note would land with quantizing on, for highlighting keys
+    pub fn get_quantized_note(&self, midi_note: u8) -> u8 {
+        scales::quantize(midi_note, self.scale_mask)
+    }
+
+    // ==== PROGRESSIONS ====
+
+    // A chord progression in the key set with set_scale, one chord per bar,
+    // as (root MIDI note, chord_type) pairs for play_chord or
+    // set_pattern_progression. style: 0 pop, 1 jazz (seventh chords),
+    // 2 twelve-bar blues, 3 classical cadences. Templates are picked at
+    // random, so each call can give a different progression; with
+    // `secondary_dominants` some chords become the V7 of the next one.
+    pub fn generate_progression(&mut self, style: u8, bars: u32, secondary_dominants: bool) -> Vec<u8> {
+        progression::generate(
+            self.scale_root,
+            self.scale_type,
+            style,
+            bars as usize,
+            secondary_dominants,
+            &mut self.random,
+        )
+        .iter()
+        .flat_map(|chord| [chord.root, chord.chord_type])
+        .collect()
+    }
+
+    // Writes (root, chord_type) pairs into the step pattern, voice-led as
+    // voice_lead, one chord per 4/4 bar held for the whole bar, and sets the
+    // pattern length to match. Bars that don't fit in 64 steps are left out.
+    pub fn set_pattern_progression(&mut self, chords: &[u8]) {
+        let bar_steps = 4 * self.edit_sequencer().steps_per_beat() as usize;
+        let symbols: Vec<(u8, u8)> = chords.chunks_exact(2).map(|chord| (chord[0], chord[1])).collect();
+        let bars = symbols.len().min(sequencer::MAX_STEPS / bar_steps).max(1);
+        self.edit_sequencer_mut().clear();
+        for (bar, voicing) in chords::voice_lead(&symbols).iter().take(bars).enumerate() {
+            self.edit_sequencer_mut().set_step(bar * bar_steps, &voicing.notes(), 0.8, bar_steps as f32);
+        }
+        self.edit_sequencer_mut().set_length(bars * bar_steps);
+    }
+
+    // Chooses inversions and octaves for a run of (root MIDI note,
+    // chord_type) pairs so each chord moves as little as possible from the
+    // one before, without drifting far from where the first chord sits.
+    // Returns (root, chord_type, inversion) triples to pass to play_chord
+    // in turn.
+    pub fn voice_lead(&self, chords: &[u8]) -> Vec<u8> {
+        let symbols: Vec<(u8, u8)> = chords.chunks_exact(2).map(|chord| (chord[0], chord[1])).collect();
+        chords::voice_lead(&symbols)
+            .iter()
+            .flat_map(|voicing| [voicing.root, voicing.chord_type, voicing.inversion])
+            .collect()
+    }
+
+    // ==== CHORD TRANSFORMS ====
+
+    // Moves every note of the step pattern by `semitones`; notes pushed
+    // outside the MIDI range are dropped
+    pub fn transpose_pattern(&mut self, semitones: i32) {
+        for step in 0..sequencer::MAX_STEPS {
+            self.edit_sequencer_mut().transform_step(step, |notes| {
+                notes.iter().map(|&n| n as i32 + semitones).filter(|n| (0..=127).contains(n)).map(|n| n as u8).collect()
+            });
+        }
+    }
+
+    // Each positive inversion moves the lowest note up an octave, each
+    // negative one the highest note down
+    pub fn invert_chord(&self, notes: &[u8], inversions: i32) -> Vec<u8> {
+        chords::invert(notes, inversions)
+    }
+
+    // Inverts the chord on one step of the pattern, as invert_chord
+    pub fn invert_pattern_step(&mut self, step: u32, inversions: i32) {
+        self.edit_sequencer_mut().transform_step(step as usize, |notes| chords::invert(notes, inversions));
+    }
+
+    // Negative harmony: mirrors notes around the axis between the root and
+    // fifth of the key set with set_scale (in C, G <-> C and E <-> Eb),
+    // keeping the chord in about the same register
+    pub fn negative_harmony_chord(&self, notes: &[u8]) -> Vec<u8> {
+        chords::negative_harmony(notes, self.scale_root)
+    }
+
+    // Applies negative_harmony_chord to every step of the pattern
+    pub fn negative_harmony_pattern(&mut self) {
+        let key_root = self.scale_root;
+        for step in 0..sequencer::MAX_STEPS {
+            self.edit_sequencer_mut().transform_step(step, |notes| chords::negative_harmony(notes, key_root));
+        }
+    }
+
+    // ==== CHORD ARTICULATION ====
+
+    // Plays a chord as a tempo-synced gesture: 0 = staccato, 1 = legato pad,
+    // 2 = pulsing 8ths, 3 = arpeggiated 16ths. Repeating gestures run until
+    // released.
+    pub fn play_articulated_chord(&mut self, engine: u32, notes: &[u8], velocity: f32, articulation: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.play_gesture(notes, velocity, Articulation::from_u8(articulation));
+        }
+    }
+
+    pub fn release_articulated_chord(&mut self, engine: u32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.release_gesture();
+        }
+    }
+
+    // ==== TRANSPORT ====
+
+    // Also starts the step sequencer from its first step
+    pub fn transport_play(&mut self) {
+        self.journal_event(JournalEvent::TransportPlay);
+        self.event_log.record(self.sample_time, "transport play", [0.0; 2]);
+        self.transport.play();
+        self.start_sequencers();
+        self.automation.resync();
+        for engine in self.engines_mut() {
+            if engine.lfo.mode() == LfoMode::Transport {
+                engine.lfo.retrigger();
+            }
+        }
+    }
+
+    pub fn transport_stop(&mut self) {
+        self.journal_event(JournalEvent::TransportStop);
+        self.event_log.record(self.sample_time, "transport stop", [0.0; 2]);
+        self.transport.stop();
+        self.count_in_beats = None;
+        self.stop_sequencers();
+    }
+
+    pub fn set_bpm(&mut self, bpm: f32) {
+        self.journal_event(JournalEvent::SetBpm { bpm });
+        self.event_log.record(self.sample_time, "set bpm", [bpm, 0.0]);
+        self.transport.set_bpm(bpm);
+    }
+
+    // Loops playback between two beat positions, wrapping on the exact
+    // sample; the step sequencer jumps back with it. An end less than a
+    // quarter beat after the start turns looping off.
+    pub fn set_loop(&mut self, start_beat: f64, end_beat: f64) {
+        self.transport.set_loop(start_beat, end_beat);
+    }
+
+    pub fn clear_loop(&mut self) {
+        self.transport.set_loop(0.0, 0.0);
+    }
+
+    // [start_beat, end_beat], or empty when not looping
+    pub fn get_loop(&self) -> Vec<f64> {
+        self.transport.loop_range().map_or(Vec::new(), |(start, end)| vec![start, end])
+    }
+
+    // Playhead in beats as of the end of the last render quantum, for
+    // drawing; stays at 0 while stopped
+    pub fn get_position_beats(&self) -> f64 {
+        self.transport.position_beats()
+    }
+
+    pub fn is_transport_playing(&self) -> bool {
+        self.transport.is_playing()
+    }
+
+    // ==== STEP SEQUENCER ====
+
+    // Sets one step of the timeline pattern: the notes of a chord (empty for
+    // a rest), velocity 0-1 and gate length in steps (above 1 ties into the
+    // following steps). Played sample-accurately while the transport runs.
+    pub fn set_pattern_step(&mut self, step: u32, notes: &[u8], velocity: f32, gate: f32) {
+        self.edit_sequencer_mut().set_step(step as usize, notes, velocity, gate);
+    }
+
+    // Chance (0-1, default 1) that a pattern step plays on each pass of the
+    // loop; a step that misses is a rest for that pass
+    pub fn set_step_probability(&mut self, step: u32, probability: f32) {
+        self.edit_sequencer_mut().set_step_probability(step as usize, probability);
+    }
+
+    // Ratchet: the step retriggers as 1-4 evenly spaced hits within its
+    // length, each gated for its share of the step's gate, for rolls and
+    // builds
+    pub fn set_step_ratchet(&mut self, step: u32, count: u32) {
+        self.edit_sequencer_mut().set_step_ratchet(step as usize, count);
+    }
+
+    // Tie: chord tones the step shares with the step before keep sounding
+    // across the boundary instead of being released and struck again, so a
+    // chord can be sustained over several steps with different gates or
+    // changing upper notes
+    pub fn set_step_tie(&mut self, step: u32, tie: bool) {
+        self.edit_sequencer_mut().set_step_tie(step as usize, tie);
+    }
+
+    // Slide (303 style): the notes of the step before are held into this
+    // one and bend to its notes over the glide time (60ms if glide is off)
+    // without retriggering the envelope
+    pub fn set_step_slide(&mut self, step: u32, slide: bool) {
+        self.edit_sequencer_mut().set_step_slide(step as usize, slide);
+    }
+
+    // Microtiming: starts the step `nudge` steps (-0.5 to 0.5) off the grid,
+    // on top of swing and humanize
+    pub fn set_step_nudge(&mut self, step: u32, nudge: f32) {
+        self.edit_sequencer_mut().set_step_nudge(step as usize, nudge);
+    }
+
+    // Groove templates move step timing and velocity in a repeating feel:
+    // 0 straight (clears nudges), 1-4 swing at 54, 58, 62 and 66% (a
+    // triplet shuffle), 5 laid back, 6 pushed, 7 the groove extracted last.
+    // `strength` 0-1 blends from the pattern as it is to the full groove.
+    // Works on any of the 16 patterns, recorded or programmed; false if the
+    // pattern or template doesn't exist.
+    pub fn apply_groove(&mut self, pattern: u32, template_id: u8, strength: f32) -> bool {
+        self.edit_sequencer_mut().apply_groove(pattern as usize, template_id, strength)
+    }
+
+    // Takes the timing nudges and relative velocities of a pattern's first
+    // bar as groove 7, to lay its feel over other patterns
+    pub fn extract_groove(&mut self, pattern: u32) {
+        self.edit_sequencer_mut().extract_groove(pattern as usize);
+    }
+
+    pub fn clear_pattern(&mut self) {
+        self.edit_sequencer_mut().clear();
+    }
+
+    // Loop length, 1-64 steps
+    pub fn set_pattern_length(&mut self, steps: u32) {
+        self.edit_sequencer_mut().set_length(steps as usize);
+    }
+
+    // Steps per beat, 4 for sixteenths (default)
+    pub fn set_pattern_resolution(&mut self, steps_per_beat: u32) {
+        let samples_per_beat = self.transport.samples_per_beat();
+        self.edit_sequencer_mut().set_steps_per_beat(steps_per_beat, samples_per_beat);
+    }
+
+    // Shuffle: every second step is delayed by `amount` of a step, 0-0.75
+    // (0 straight, 0.33 triplet swing)
+    pub fn set_swing(&mut self, amount: f32) {
+        self.edit_sequencer_mut().set_swing(amount);
+    }
+
+    // Random offsets applied as the sequencer fires steps: each step starts
+    // up to `timing_ms` early or late (0-50 ms, never more than an eighth of
+    // a step) and each note's velocity varies by up to `velocity_amount`
+    // (0-1) of its value. 0 and 0 play the pattern exactly on the grid.
+    pub fn set_humanize(&mut self, timing_ms: f32, velocity_amount: f32) {
+        let timing = timing_ms.clamp(0.0, MAX_HUMANIZE_MS) * SAMPLE_RATE / 1000.0;
+        self.edit_sequencer_mut().set_humanize(timing as f64, velocity_amount);
+    }
+
+    // One pass of the step pattern as a Standard MIDI File (format 0, 480
+    // ticks per beat) at the current tempo, named after the timeline track
+    pub fn export_midi_file(&self) -> Vec<u8> {
+        let ticks_per_step = smf::TICKS_PER_BEAT as f64 / self.sequencer.steps_per_beat() as f64;
+        let to_ticks = |steps: f64| (steps * ticks_per_step).round() as u32;
+        let notes: Vec<smf::SmfNote> = self
+            .sequencer
+            .pattern_notes()
+            .iter()
+            .map(|n| smf::SmfNote {
+                start: to_ticks(n.start),
+                length: to_ticks(n.length),
+                note: n.note,
+                velocity: (n.velocity * 127.0).round() as u8,
+            })
+            .collect();
+        let end = to_ticks(self.sequencer.length() as f64);
+        smf::write(&self.timeline_engine.metadata.name, self.transport.bpm(), &notes, end)
+    }
+
+    // ==== SONG MODE ====
+
+    // The timeline and each created engine are tracks with a sequencer of
+    // their own, all running with the transport. The step, length,
+    // progression and transform calls above and the pattern and song calls
+    // below act on the edit track, the timeline by default. Returns false
+    // for the live engine and unknown ids.
+    pub fn set_edit_track(&mut self, engine: u32) -> bool {
+        if self.track_sequencer(engine).is_none() {
+            return false;
+        }
+        self.edit_track = engine;
+        true
+    }
+
+    pub fn get_edit_track(&self) -> u32 {
+        if self.track_sequencer(self.edit_track).is_some() { self.edit_track } else { ENGINE_TIMELINE }
+    }
+
+    // Each sequencer holds 16 patterns. The pattern calls above (steps,
+    // length, progressions, transforms) act on the edit pattern, 0-15,
+    // which needn't be the one playing; MIDI export always takes the
+    // timeline's.
+    pub fn set_edit_pattern(&mut self, pattern: u32) {
+        self.edit_sequencer_mut().set_edit_pattern(pattern as usize);
+    }
+
+    pub fn get_edit_pattern(&self) -> u32 {
+        self.edit_sequencer().edit_pattern() as u32
+    }
+
+    // Length of the edit pattern in 4/4 bars at the current resolution,
+    // up to the 64 step limit
+    pub fn set_pattern_bars(&mut self, bars: u32) {
+        let steps = bars.max(1) as usize * self.edit_sequencer_mut().bar_steps();
+        self.edit_sequencer_mut().set_length(steps);
+    }
+
+    // Switches playback to `pattern` at the next bar line, starting from
+    // its first step; straight away while stopped. Leaves song mode.
+    pub fn queue_pattern(&mut self, pattern: u32) {
+        self.edit_sequencer_mut().queue_pattern(pattern as usize);
+    }
+
+    // Song mode: the patterns play in this order, each once through, then
+    // the song loops. While playing, the current pattern finishes first.
+    // Transport positions count through the whole song. Empty turns song
+    // mode off and the current pattern loops.
+    pub fn set_song_sequence(&mut self, pattern_ids: &[u8]) {
+        let song: Vec<usize> = pattern_ids.iter().map(|&p| p as usize).collect();
+        self.edit_sequencer_mut().set_song(&song);
+    }
+
+    pub fn get_song_sequence(&self) -> Vec<u8> {
+        self.edit_sequencer().song().iter().map(|&p| p as u8).collect()
+    }
+
+    pub fn get_playing_pattern(&self) -> u32 {
+        self.edit_sequencer().playing_pattern() as u32
+    }
+
+    // Outside song mode, what happens when `pattern` of the edit track has
+    // played through `repeats` times in a row: 0 loops on (default), 1
+    // plays the next pattern with notes, 2 a random other pattern with
+    // notes from the same `group`, 3 stops the track until a pattern is
+    // queued. The move happens at the pattern's end; a queued pattern or
+    // scene still wins at the bar line. Patterns with no next pattern to
+    // go to loop.
+    pub fn set_follow_action(&mut self, pattern: u32, action: u8, repeats: u32, group: u8) {
+        self.edit_sequencer_mut().set_follow_action(pattern as usize, FollowAction::from_u8(action), repeats, group);
+    }
+
+    // [action, repeats, group], empty for an unknown pattern
+    pub fn get_follow_action(&self, pattern: u32) -> Vec<u32> {
+        self.edit_sequencer()
+            .follow_action(pattern as usize)
+            .map_or(Vec::new(), |(action, repeats, group)| vec![action as u32, repeats, group as u32])
+    }
+
+    // Binds a timeline patch, in get_patch_json's form, to a pattern: the
+    // timeline engine switches to it on the exact sample the pattern starts
+    // playing, so each section of a song can have its own sound. An empty
+    // string unbinds. Returns false if the pattern or patch isn't valid.
+    pub fn bind_pattern_preset(&mut self, pattern: u32, preset_json: &str) -> bool {
+        let preset = match preset_json {
+            "" => None,
+            json => match Patch::from_json(json) {
+                Some(patch) => Some(patch),
+                None => return false,
+            },
+        };
+        match self.timeline_engine.pattern_presets.get_mut(pattern as usize) {
+            Some(slot) => {
+                *slot = preset;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Patch bound to a pattern as JSON, empty if none
+    pub fn get_pattern_preset(&self, pattern: u32) -> String {
+        match self.timeline_engine.pattern_presets.get(pattern as usize) {
+            Some(Some(patch)) => patch.to_json(),
+            _ => String::new(),
+        }
+    }
+
+    // Entry of the song sequence playing, or -1 outside song mode
+    pub fn get_song_position(&self) -> i32 {
+        self.edit_sequencer().song_position().map_or(-1, |p| p as i32)
+    }
+
+    // ==== SCENES ====
+
+    // A scene is a row of patterns across the tracks, by engine id: entry 0
+    // for the timeline, 2 onwards for created engines (the live engine's
+    // entry is ignored). A negative entry stops that track; tracks past the
+    // end of the row carry on as they are. Up to 16 scenes; false for an
+    // id past that.
+    pub fn set_scene(&mut self, scene: u32, patterns: &[i8]) -> bool {
+        match self.scenes.get_mut(scene as usize) {
+            Some(row) => {
+                row.clear();
+                row.extend(patterns.iter().take(MAX_ENGINES).map(|&p| p.min(sequencer::MAX_PATTERNS as i8 - 1)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_scene(&self, scene: u32) -> Vec<i8> {
+        self.scenes.get(scene as usize).cloned().unwrap_or_default()
+    }
+
+    // Switches every track in the scene to its pattern together at the next
+    // bar line, or straight away while stopped, leaving song mode on each.
+    // False for an unknown or empty scene.
+    pub fn launch_scene(&mut self, scene: u32) -> bool {
+        if self.scenes.get(scene as usize).is_none_or(Vec::is_empty) {
+            return false;
+        }
+        // Taken out for the loop and put back, so nothing is allocated
+        let row = std::mem::take(&mut self.scenes[scene as usize]);
+        for (engine, &pattern) in row.iter().enumerate() {
+            if let Some(sequencer) = self.track_sequencer_mut(engine as u32) {
+                match usize::try_from(pattern) {
+                    Ok(pattern) => sequencer.queue_pattern(pattern),
+                    Err(_) => sequencer.queue_silence(),
+                }
+            }
+        }
+        self.scenes[scene as usize] = row;
+        true
+    }
+
+    // Whether a track's sequencer has been stopped by a scene
+    pub fn is_track_stopped(&self, engine: u32) -> bool {
+        self.track_sequencer(engine).is_some_and(Sequencer::is_silent)
+    }
+
+    // ==== RECORDING ====
+
+    // Records live note_on/note_off into the playing pattern while the
+    // transport runs, timed by the sequencer's own clock. Notes are added to
+    // the steps each pass, like overdubbing on a loop pedal.
+    // quantize_division snaps starts and lengths to a grid in notes per
+    // whole note (16 = sixteenths, 8 = eighths, 12 and 24 = eighth and
+    // sixteenth triplets), never finer than a step; 0 only rounds starts to
+    // the nearest step. Grid lines between steps, like triplets over
+    // sixteenth steps, are kept by nudging the step. Started from stop with a
+    // count-in set, the metronome counts in first and then the transport
+    // starts.
+    pub fn start_recording(&mut self, quantize_division: u32) {
+        let grid = match quantize_division {
+            0 => 0.0,
+            division => {
+                let whole_steps = self.sequencer.steps_per_beat() as f64 * 4.0;
+                (whole_steps / division as f64).max(1.0)
+            }
+        };
+        self.sequencer.start_recording(grid);
+        if self.count_in_bars > 0 && !self.transport.is_playing() {
+            self.count_in_beats = Some((self.count_in_bars * 4) as f64);
+            self.metronome.reset();
+        }
+    }
+
+    // Notes still held are written with their length so far. Cancels a
+    // count-in still running.
+    pub fn stop_recording(&mut self) {
+        self.sequencer.stop_recording();
+        self.count_in_beats = None;
+    }
+
+    // Bars (0-4) of metronome before recording started from stop
+    pub fn set_count_in(&mut self, bars: u32) {
+        self.count_in_bars = bars.min(4);
+    }
+
+    // Beats of count-in left, 0 when not counting in
+    pub fn get_count_in_beats(&self) -> f64 {
+        self.count_in_beats.unwrap_or(0.0)
+    }
+
+    // Only notes started between `in_beat` and `out_beat` of the transport
+    // are recorded, and notes still held at the punch-out end there. With
+    // the transport looping over the range each pass adds a take on top of
+    // the last. An out at or before the in clears the punch.
+    pub fn set_punch(&mut self, in_beat: f64, out_beat: f64) {
+        let in_beat = in_beat.max(0.0);
+        self.punch = (out_beat > in_beat).then_some((in_beat, out_beat));
+    }
+
+    pub fn clear_punch(&mut self) {
+        self.punch = None;
+    }
+
+    // Click on every beat while the transport plays (count-ins always
+    // click), level 0-1. Left out of exports.
+    pub fn set_metronome(&mut self, enabled: bool, level: f32) {
+        self.metronome_enabled = enabled;
+        self.metronome.set_level(level);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.sequencer.is_recording()
+    }
+
+    // ==== ARPEGGIATOR ====
+
+    // While enabled, live notes are arpeggiated instead of played directly.
+    // Switching it off cuts the arpeggio and forgets the held keys.
+    pub fn set_arpeggiator_enabled(&mut self, enabled: bool) {
+        if !enabled && self.arpeggiator_enabled {
+            self.arpeggiator.reset(&mut self.sequencer_events);
+            apply_events(&mut self.live_engine, &mut self.sequencer_events);
+        }
+        self.arpeggiator_enabled = enabled;
+    }
+
+    // mode: 0 up, 1 down, 2 up-down, 3 random, 4 as played. octaves 1-4,
+    // division in notes per whole note (16 = sixteenths, 12 = eighth
+    // triplets), gate as a fraction of each step.
+    pub fn set_arpeggiator(&mut self, mode: u8, octaves: u32, division: u32, gate: f32) {
+        self.arpeggiator.set_mode(mode);
+        self.arpeggiator.set_octaves(octaves);
+        self.arpeggiator.set_division(division, self.transport.samples_per_beat());
+        self.arpeggiator.set_gate(gate);
+    }
+
+    // Snapshot taken at the start of the last render quantum:
+    // [sample_time, beat_position, beat_phase, timeline_lfo_phase, live_lfo_phase, sequencer_step]
+    // sample_time counts frames since the engine was created; sequencer_step is -1
+    // while the step sequencer is stopped
+    pub fn get_phase_info(&self) -> Vec<f64> {
+        let info = &self.phase_info;
+        vec![
+            info.sample_time as f64,
+            info.beat_position,
+            info.beat_position.fract(),
+            info.timeline_lfo_phase as f64,
+            info.live_lfo_phase as f64,
+            info.sequencer_step.map_or(-1.0, |step| step as f64),
+        ]
+    }
+
+    // ==== LOOPER ====
+
+    // Records the engine mix for `beats` beats at the current tempo, then
+    // loops it. Recording again replaces the loop.
+    pub fn looper_record(&mut self, beats: f32) {
+        self.looper.record(beats as f64, self.transport.samples_per_beat());
+    }
+
+    pub fn looper_play(&mut self) {
+        self.looper.play();
+    }
+
+    // Toggles overdub: new playing is summed onto the loop as it repeats
+    pub fn looper_overdub(&mut self) {
+        self.looper.overdub();
+    }
+
+    pub fn looper_stop(&mut self) {
+        self.looper.stop();
+    }
+
+    pub fn looper_clear(&mut self) {
+        self.looper.clear();
+    }
+
+    // 0 empty, 1 recording, 2 playing, 3 overdubbing, 4 stopped
+    pub fn get_looper_state(&self) -> u8 {
+        self.looper.state()
+    }
+
+    pub fn get_looper_length(&self) -> u32 {
+        self.looper.length() as u32
+    }
+
+    // ==== MIDI ====
+
+    // Raw MIDI bytes from Web MIDI: notes play the live engine, mapped CCs
+    // set their parameters, SysEx patch dumps are loaded into the engine
+    // they address
+    pub fn handle_midi_message(&mut self, data: &[u8]) {
+        match midi::parse(data) {
+            MidiMessage::NoteOn { note, velocity } => {
+                self.note_on(note, velocity as f32 / 127.0);
+            }
+            MidiMessage::NoteOff { note, velocity } => {
+                self.note_off(note, velocity.map(|v| v as f32 / 127.0));
+            }
+            MidiMessage::ChannelPressure { value } => {
+                self.set_channel_pressure(value as f32 / 127.0);
+            }
+            MidiMessage::PolyPressure { note, value } => {
+                self.set_note_pressure(note, value as f32 / 127.0);
+            }
+            MidiMessage::ControlChange { controller, value } => {
+                self.cc_map.learn(controller);
+                for index in 0..self.cc_map.mappings().len() {
+                    let mapping = self.cc_map.mappings()[index];
+                    if mapping.controller == controller {
+                        self.set_param(mapping.engine, mapping.param, mapping.scale(value));
+                    }
+                }
+            }
+            MidiMessage::SysEx(bytes) => {
+                if let Some((engine, values)) = midi::decode_patch_dump(bytes) {
+                    if let Some(engine) = self.engine_mut(engine as u32) {
+                        let mut patch = engine.patch;
+                        patch.set_values(&values);
+                        engine.apply_patch(&patch);
+                    }
+                } else if let Some((engine, metadata)) = midi::decode_metadata_dump(bytes) {
+                    if let Some(engine) = self.engine_mut(engine as u32) {
+                        engine.metadata = metadata;
+                    }
+                }
+            }
+            // Real-time bytes carry no timestamp here, so ticks are timed by
+            // the render clock; handle_midi_clock_tick is more precise
+            MidiMessage::Clock => {
+                self.handle_midi_clock_tick(self.sample_time as f64 * 1000.0 / SAMPLE_RATE as f64);
+            }
+            MidiMessage::Start => self.handle_midi_start(),
+            MidiMessage::Continue => self.handle_midi_continue(),
+            MidiMessage::Stop => self.handle_midi_stop(),
+            MidiMessage::Unsupported => {}
+        }
+    }
+
+    // Routes controller `cc_number` (0-127) to a parameter id (as set_param)
+    // on `engine`, scaled from `min` at CC 0 to `max` at CC 127. curve:
+    // 0 linear, 1 exponential (for frequencies and times; needs a positive
+    // range), 2 toggle (min below 64, max from 64). A controller can drive
+    // several parameters; mapping the same pair again replaces it.
+    pub fn map_cc(&mut self, cc_number: u8, engine: u32, param_id: u32, min: f32, max: f32, curve: u8) {
+        self.cc_map.map(CcMapping::new(cc_number, engine, param_id, min, max, curve));
+    }
+
+    // Removes every mapping of a controller
+    pub fn unmap_cc(&mut self, cc_number: u8) {
+        self.cc_map.unmap(cc_number);
+    }
+
+    pub fn clear_cc_mappings(&mut self) {
+        self.cc_map.clear();
+    }
+
+    // MIDI learn: the next controller to arrive through handle_midi_message
+    // is mapped to the parameter, as map_cc
+    pub fn learn_cc(&mut self, engine: u32, param_id: u32, min: f32, max: f32, curve: u8) {
+        self.cc_map.start_learn(CcMapping::new(0, engine, param_id, min, max, curve));
+    }
+
+    pub fn cancel_cc_learn(&mut self) {
+        self.cc_map.cancel_learn();
+    }
+
+    pub fn is_cc_learning(&self) -> bool {
+        self.cc_map.is_learning()
+    }
+
+    // Slaves the transport to an external MIDI clock: Start, Stop and
+    // Continue drive it and the tick rate sets the tempo
+    pub fn set_midi_clock_sync(&mut self, enabled: bool) {
+        self.midi_clock_sync = enabled;
+        if !enabled {
+            self.midi_clock.stop();
+        }
+    }
+
+    // One 0xF8 clock tick, with the Web MIDI event timestamp in ms
+    pub fn handle_midi_clock_tick(&mut self, timestamp_ms: f64) {
+        if !self.midi_clock_sync {
+            return;
+        }
+        if self.midi_clock.tick(timestamp_ms) {
+            self.transport_play();
+        }
+        // Set directly, a tempo update per tick would flood the event log
+        if let Some(bpm) = self.midi_clock.corrected_bpm(self.transport.position_beats()) {
+            self.transport.set_bpm(bpm as f32);
+        }
+    }
+
+    // Rewinds; playback starts on the next tick
+    pub fn handle_midi_start(&mut self) {
+        if self.midi_clock_sync {
+            self.transport_stop();
+            self.midi_clock.arm(0.0);
+        }
+    }
+
+    // Pauses in place, releasing the sequencer's notes
+    pub fn handle_midi_stop(&mut self) {
+        if self.midi_clock_sync {
+            self.midi_clock.stop();
+            self.transport.pause();
+            self.stop_sequencers();
+        }
+    }
+
+    // Resumes from where Stop left off on the next tick
+    pub fn handle_midi_continue(&mut self) {
+        if self.midi_clock_sync {
+            self.midi_clock.arm(self.transport.position_beats());
+        }
+    }
+
+    // Tempo measured from the external clock, 0 until it has settled
+    pub fn get_midi_clock_bpm(&self) -> f32 {
+        self.midi_clock.bpm().map_or(0.0, |bpm| bpm as f32)
+    }
+
+    // Bulk dump of an engine's full patch as a SysEx message
+    pub fn dump_patch_sysex(&self, engine: u32) -> Vec<u8> {
+        match self.engine(engine) {
+            Some(e) => midi::encode_patch_dump(engine as u8, &e.patch.values()),
+            None => Vec::new(),
+        }
+    }
+
+    // Track name, color and tags as a SysEx message, loadable through
+    // handle_midi_message alongside the patch dump
+    pub fn dump_track_metadata_sysex(&self, engine: u32) -> Vec<u8> {
+        match self.engine(engine) {
+            Some(e) => midi::encode_metadata_dump(engine as u8, &e.metadata),
+            None => Vec::new(),
+        }
+    }
+
+    // ==== MELODY ====
+
+    // Solo-note mode for melody tracks: each note trims the previous one
+    // (tail included) with a few-millisecond fade, so overlapping notes
+    // never smear into chords
+    pub fn set_mono_melody(&mut self, engine: u32, enabled: bool) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.patch.mono_melody = enabled;
+        }
+    }
+
+    pub fn get_mono_melody(&self, engine: u32) -> bool {
+        self.engine(engine).is_some_and(|e| e.patch.mono_melody)
+    }
+
+    // ==== FILTER KEYBOARD ====
+
+    // Turns the filter into the instrument: play a chord, switch this on,
+    // and from then on notes set the cutoff (gliding over `glide_ms`) over
+    // the latched chord instead of triggering voices
+    pub fn set_filter_keyboard(&mut self, engine: u32, enabled: bool, glide_ms: f32) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_filter_keyboard(enabled, glide_ms);
+        }
+    }
+
+    // ==== CHOKE GROUPS ====
+
+    // `note` joins choke group 1-255 (0 removes it). Any note in a group
+    // cuts every other sounding member with a short fade, e.g. a closed
+    // hi-hat choking the open one.
+    pub fn set_choke_group(&mut self, engine: u32, note: u8, group: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_choke_group(note, note, group);
+        }
+    }
+
+    // The same for notes `low_note`..=`high_note` at once, e.g. a bass range
+    // played monophonically
+    pub fn set_choke_group_range(&mut self, engine: u32, low_note: u8, high_note: u8, group: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.set_choke_group(low_note, high_note, group);
+        }
+    }
+
+    pub fn get_choke_group(&self, engine: u32, note: u8) -> u8 {
+        self.engine(engine)
+            .map(|e| e.choke_groups[note as usize & 127])
+            .unwrap_or(0)
+    }
+
+    // ==== TUNING ====
+
+    // Retunes every engine from a Scala .scl file, its 1/1 on middle C
+    // (MIDI 60, 261.63 Hz) and repeating at its last degree, for just
+    // intonation, 19-EDO and other microtonal scales. The string machine
+    // stays in equal temperament. Returns false, leaving the tuning as it
+    // was, when the file doesn't parse.
+    pub fn load_scala_tuning(&mut self, text: &str) -> bool {
+        let Some(tuning) = Tuning::from_scala(text) else {
+            return false;
+        };
+        for e in self.engines_mut() {
+            e.set_tuning(tuning.clone());
+        }
+        true
+    }
+
+    // Remaps every MIDI note to its own frequency: exactly 128 values in Hz,
+    // index = note number, e.g. from MIDI Tuning Standard messages. Returns
+    // false, leaving the tuning as it was, for any other length or
+    // non-positive values.
+    pub fn set_tuning_table(&mut self, freqs: &[f32]) -> bool {
+        let Some(tuning) = Tuning::from_table(freqs) else {
+            return false;
+        };
+        for e in self.engines_mut() {
+            e.set_tuning(tuning.clone());
+        }
+        true
+    }
+
+    // Back to 12-tone equal temperament at A4 = 440 Hz
+    pub fn reset_tuning(&mut self) {
+        for e in self.engines_mut() {
+            e.set_tuning(Tuning::equal());
+        }
+    }
+
+    // ==== FACTORY BANK ====
+
+    // Replaces the engine's whole patch with factory patch `id`
+    // (0..FACTORY_PATCH_COUNT). Returns false for an unknown id or engine.
+    pub fn load_factory_patch(&mut self, engine: u32, id: u8) -> bool {
+        match (self.engine_mut(engine), factory::factory_patch(id)) {
+            (Some(e), Some(patch)) => {
+                e.apply_patch(&patch);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Glitch-free preset change while playing: the new patch goes onto a
+    // copy of the engine (same held notes, effect tails and track settings)
+    // that takes over at once while the old one crossfades out over
+    // `fade_ms` (1-2000). A swap during a running fade cuts the older
+    // outgoing sound. Returns false for an unknown id or engine.
+    pub fn hot_swap_factory_patch(&mut self, engine: u32, id: u8, fade_ms: f32) -> bool {
+        match factory::factory_patch(id) {
+            Some(patch) => self.hot_swap(engine, &patch, fade_ms),
+            None => false,
+        }
+    }
+
+    // As hot_swap_factory_patch, with a patch in get_patch_json's form
+    pub fn hot_swap_patch(&mut self, engine: u32, patch_json: &str, fade_ms: f32) -> bool {
+        match Patch::from_json(patch_json) {
+            Some(patch) => self.hot_swap(engine, &patch, fade_ms),
+            None => false,
+        }
+    }
+
+    pub fn is_hot_swapping(&self, engine: u32) -> bool {
+        self.hot_swaps.get(engine as usize).is_some_and(Option::is_some)
+    }
+
+    pub fn get_factory_patch_count(&self) -> u32 {
+        factory::FACTORY_PATCH_COUNT as u32
+    }
+
+    pub fn get_factory_patch_name(&self, id: u8) -> String {
+        factory::factory_patch_name(id).unwrap_or_default().to_string()
+    }
+
+    // ==== TRACK METADATA ====
+
+    pub fn set_track_name(&mut self, engine: u32, name: &str) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.metadata.name = name.to_string();
+        }
+    }
+
+    pub fn set_track_color(&mut self, engine: u32, color: u8) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.metadata.color = color;
+        }
+    }
+
+    pub fn set_track_tags(&mut self, engine: u32, tags: Vec<String>) {
+        if let Some(e) = self.engine_mut(engine) {
+            e.metadata.set_tags(tags);
+        }
+    }
+
+    pub fn get_track_name(&self, engine: u32) -> String {
+        self.engine(engine).map(|e| e.metadata.name.clone()).unwrap_or_default()
+    }
+
+    pub fn get_track_color(&self, engine: u32) -> u8 {
+        self.engine(engine).map(|e| e.metadata.color).unwrap_or(0)
+    }
+
+    pub fn get_track_tags(&self, engine: u32) -> Vec<String> {
+        self.engine(engine).map(|e| e.metadata.tags.clone()).unwrap_or_default()
+    }
+
+    pub fn get_sample_rate(&self) -> f32 {
+        SAMPLE_RATE
+    }
+
+    // ==== PROJECT STATE ====
+
+    // Everything needed to restore the sound and arrangement as JSON: every
+    // track's full patch, name, volume, pan and sends, the tempo, master
+    // volume and the step pattern. Created engines follow the built-in pair
+    // in id order; ids freed by destroy_engine close up on load.
+    pub fn save_state(&self) -> String {
+        serde_json::to_string(&self.capture_state()).unwrap_or_default()
+    }
+
+    // Restores a save_state snapshot; fields missing from older files keep
+    // their defaults, and created engines are added or removed to match its
+    // tracks. Returns false, changing nothing, if the JSON doesn't parse.
+    pub fn load_state(&mut self, json: &str) -> bool {
+        let Ok(state) = serde_json::from_str::<EngineState>(json) else {
+            return false;
+        };
+        self.restore_state(state);
+        true
+    }
+
+    // save_state in a compact versioned binary form, for autosaving to
+    // IndexedDB or packing into a share URL (base64 it first)
+    pub fn save_state_binary(&self) -> Vec<u8> {
+        state_binary::encode(&self.capture_state())
+    }
+
+    // Restores a save_state_binary snapshot from this or any other version.
+    // Returns false, changing nothing, if the data is damaged.
+    pub fn load_state_binary(&mut self, data: &[u8]) -> bool {
+        let Some(state) = state_binary::decode(data) else {
+            return false;
+        };
+        self.restore_state(state);
+        true
+    }
+
+    // ==== UNDO ====
+
+    // Records the current state (as save_state) as an undo point; call it
+    // before each edit. Starts a new branch, so redo is no longer possible.
+    pub fn push_snapshot(&mut self) {
+        let state = self.save_state();
+        self.history.push(state);
+    }
+
+    // Restores the last snapshot. Returns false when there is nothing to
+    // undo.
+    pub fn undo(&mut self) -> bool {
+        let current = self.save_state();
+        match self.history.undo(current) {
+            Some(state) => self.load_state(&state),
+            None => false,
+        }
+    }
+
+    // Reapplies the state the last undo replaced. Returns false when there
+    // is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let current = self.save_state();
+        match self.history.redo(current) {
+            Some(state) => self.load_state(&state),
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    // ==== PRESET MORPH ====
+
+    // Blends between two save_state snapshots: t = 0 sounds like A, 1 like
+    // B. Continuous patch parameters, track volumes, pans, sends and the
+    // master volume interpolate; switches and modes flip at 0.5. Tempo and
+    // the pattern are left alone. Call again with a new t to sweep; the
+    // engine glides there over ~50ms. Returns false if either snapshot
+    // doesn't parse or t isn't a number.
+    pub fn morph_presets(&mut self, preset_a_json: &str, preset_b_json: &str, t: f32) -> bool {
+        if !t.is_finite() {
+            return false;
+        }
+        let t = t.clamp(0.0, 1.0);
+        if let Some(morph) = &mut self.morph {
+            if morph.a_json == preset_a_json && morph.b_json == preset_b_json {
+                morph.target = t;
+                return true;
+            }
+        }
+        let (Ok(a), Ok(b)) = (
+            serde_json::from_str::<EngineState>(preset_a_json),
+            serde_json::from_str::<EngineState>(preset_b_json),
+        ) else {
+            return false;
+        };
+        // A new pair jumps straight to t rather than gliding from wherever
+        // the last pair was
+        self.morph = Some(Morph {
+            a_json: preset_a_json.to_string(),
+            b_json: preset_b_json.to_string(),
+            a,
+            b,
+            target: t,
+            current: t,
+        });
+        self.apply_morph();
+        true
+    }
+
+    // ==== OFFLINE RENDER ====
+
+    // Bounces the engine-driven arrangement (step sequencer over both
+    // engines and the master chain) faster than realtime: rewinds and
+    // starts the transport, renders `duration_seconds` (up to 600) in export
+    // mode and stops again. Returns interleaved stereo frames. Notes sent
+    // from JS while this runs are not part of it.
+    pub fn render_offline(&mut self, duration_seconds: f32) -> Vec<f32> {
+        let frames = (duration_seconds.clamp(0.0, MAX_OFFLINE_SECONDS) * SAMPLE_RATE) as usize;
+        let export_mode = self.export_mode;
+        self.export_mode = true;
+        self.transport_stop();
+        self.transport_play();
+
+        let mut output = vec![0.0; frames * 2];
+        let mut left = [0.0; OFFLINE_BLOCK];
+        let mut right = [0.0; OFFLINE_BLOCK];
+        for chunk in output.chunks_mut(OFFLINE_BLOCK * 2) {
+            let len = chunk.len() / 2;
+            self.render_stereo(&mut left[..len], &mut right[..len]);
+            for (frame, (&l, &r)) in chunk.chunks_exact_mut(2).zip(left.iter().zip(&right)) {
+                frame[0] = l;
+                frame[1] = r;
+            }
+        }
+
+        self.transport_stop();
+        self.export_mode = export_mode;
+        output
+    }
+
+    // render_offline as a complete stereo WAV file ready to download:
+    // bit_depth 16 (PCM, dithered) or 32 (float). Empty for other depths.
+    pub fn render_to_wav(&mut self, duration_seconds: f32, bit_depth: u16) -> Vec<u8> {
+        if bit_depth != 16 && bit_depth != 32 {
+            return Vec::new();
+        }
+        let samples = self.render_offline(duration_seconds);
+        wav::encode(&samples, 2, SAMPLE_RATE as u32, bit_depth).unwrap_or_default()
+    }
+
+    // ==== SESSION JOURNAL ====
+
+    // Starts a new journal of notes, chords, transport, tempo and every
+    // patch parameter change, timestamped from now. Replaces the old one.
+    pub fn journal_record(&mut self) {
+        self.journal = Journal {
+            bpm: self.transport.bpm(),
+            playing: self.transport.is_playing(),
+            patches: vec![self.timeline_engine.patch.values(), self.live_engine.patch.values()],
+            entries: Vec::new(),
+        };
+        self.journal_patches = [self.timeline_engine.patch, self.live_engine.patch];
+        self.journal_origin = self.sample_time;
+        self.journal_state = JOURNAL_RECORDING;
+    }
+
+    // Ends recording or replay
+    pub fn journal_stop(&mut self) {
+        if self.journal_state == JOURNAL_RECORDING {
+            self.journal_patch_changes();
+        }
+        self.journal_state = JOURNAL_IDLE;
+    }
+
+    // Restores the journal's starting tempo, transport and patches, releases
+    // every note and plays the journal back on the same sample offsets.
+    // Returns false when there is nothing to replay.
+    pub fn journal_replay(&mut self) -> bool {
+        if self.journal.entries.is_empty() {
+            return false;
+        }
+        self.journal_state = JOURNAL_IDLE;
+        self.transport_stop();
+        self.release_held_chord();
+        for engine in [&mut self.timeline_engine, &mut self.live_engine] {
+            engine.sustained_notes.clear();
+            for voice in &mut engine.voices {
+                if voice.is_active() {
+                    voice.note_off();
+                }
+            }
+        }
+        self.set_bpm(self.journal.bpm);
+        for (index, values) in self.journal.patches.clone().iter().enumerate() {
+            if let Some(engine) = self.engine_mut(index as u32) {
+                let mut patch = engine.patch;
+                patch.set_values(values);
+                engine.apply_patch(&patch);
+            }
+        }
+        if self.journal.playing {
+            self.transport_play();
+        }
+        self.journal_origin = self.sample_time;
+        self.journal_cursor = 0;
+        self.journal_state = JOURNAL_REPLAYING;
+        true
+    }
+
+    // 0 idle, 1 recording, 2 replaying
+    pub fn get_journal_state(&self) -> u8 {
+        self.journal_state
+    }
+
+    pub fn get_journal_length(&self) -> u32 {
+        self.journal.entries.len() as u32
+    }
+
+    pub fn export_journal(&self) -> String {
+        serde_json::to_string(&self.journal).unwrap_or_default()
+    }
+
+    // Replaces the journal with an exported one, ready to replay. Returns
+    // false (keeping the current journal) if the JSON doesn't parse.
+    pub fn import_journal(&mut self, json: &str) -> bool {
+        let Ok(journal) = serde_json::from_str::<Journal>(json) else {
+            return false;
+        };
+        self.journal = journal;
+        self.journal_state = JOURNAL_IDLE;
+        true
+    }
+
+    // ==== DIAGNOSTICS ====
+
+    // Patch of an engine as JSON, in the form render_test_vector takes
+    pub fn get_patch_json(&self, engine: u32) -> String {
+        self.engine(engine).map(|e| e.patch.to_json()).unwrap_or_default()
+    }
+
+    // Golden render for regression checks: a fresh engine with `patch_json`
+    // on the live track plays `note_script` (see test_vector.rs for the
+    // format) and returns the mono output. Nothing depends on the calling
+    // instance or the clock, so the same inputs give bit-identical samples
+    // on a given build. Empty if the patch or script doesn't parse.
+    pub fn render_test_vector(patch_json: &str, note_script: &str) -> Vec<f32> {
+        let (Some(patch), Some(script)) = (Patch::from_json(patch_json), test_vector::parse_script(note_script)) else {
+            return Vec::new();
+        };
+
+        let mut engine = AudioEngine::new();
+        engine.random = Random::new(TEST_VECTOR_SEED);
+        engine.live_engine.apply_patch(&patch);
+        engine.render_script(&script)
+    }
+
+    // Golden render from the default patch, with every source of random
+    // variation (humanize, velocity randomization) started from `seed`, so
+    // one script can drive any module into a known state through its param
+    // lines, e.g. "0.0 param 20 1" for the flanger. Same guarantees as
+    // render_test_vector; empty if the script doesn't parse.
+    pub fn render_deterministic(seed: u32, script: &str) -> Vec<f32> {
+        let Some(script) = test_vector::parse_script(script) else {
+            return Vec::new();
+        };
+
+        let mut engine = AudioEngine::new();
+        engine.random = Random::new(seed);
+        for e in engine.engines_mut() {
+            e.velocity_random = Random::new(seed ^ VELOCITY_RANDOM_SEED);
+        }
+        engine.render_script(&script)
+    }
+
+    // Fingerprint of a golden render as 16 hex digits, for storing next to
+    // the script that made it instead of the samples
+    pub fn hash_render(samples: &[f32]) -> String {
+        format!("{:016x}", test_vector::hash_samples(samples))
+    }
+
+    // Plain-text report of the whole engine state (transport, master chain,
+    // both engines with their patch and sounding voices, pattern and looper
+    // state, buffer sizes and the last control events) for bug reports
+    pub fn dump_state_debug(&self) -> String {
+        let mut out = String::new();
+        // Writing into a String can't fail
+        let _ = self.write_debug(&mut out);
+        out
+    }
+}
+
+impl Default for AudioEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioEngine {
+    // The project state save_state and save_state_binary serialize
+    fn capture_state(&self) -> EngineState {
+        let tracks = (0..(ENGINE_COUNT + self.extra_engines.len()) as u32)
+            .filter_map(|index| {
+                let volume = self.track_volume(index);
+                self.engine(index).map(|e| TrackState {
+                    patch: e.patch,
+                    metadata: e.metadata.clone(),
+                    volume,
+                    pan: e.pan,
+                    delay_send: e.delay_send,
+                    reverb_send: e.reverb_send,
+                })
+            })
+            .collect();
+        let (humanize_timing, humanize_velocity) = self.sequencer.humanize();
+        let used = (1..sequencer::MAX_PATTERNS)
+            .rev()
+            .find(|&p| {
+                self.sequencer.pattern_has_notes(p)
+                    || self.timeline_engine.pattern_presets[p].is_some()
+                    || self.automation.lane_count(p) > 0
+            })
+            .unwrap_or(0);
+        EngineState {
+            bpm: self.transport.bpm(),
+            master_volume: self.master_volume,
+            tracks,
+            pattern: PatternState {
+                steps_per_beat: self.sequencer.steps_per_beat(),
+                swing: self.sequencer.swing(),
+                humanize_timing_ms: humanize_timing as f32 * 1000.0 / SAMPLE_RATE,
+                humanize_velocity,
+                ..self.capture_pattern(0)
+            },
+            patterns: (1..=used).map(|p| self.capture_pattern(p)).collect(),
+            song: self.sequencer.song().iter().map(|&p| p as u8).collect(),
+            ..EngineState::default()
+        }
+    }
+
+    // Length and steps of one sequencer pattern
+    fn capture_pattern(&self, pattern: usize) -> PatternState {
+        let steps = (0..self.sequencer.pattern_length(pattern))
+            .filter_map(|index| {
+                let (notes, velocity, gate) = self.sequencer.step(pattern, index)?;
+                let probability = self.sequencer.step_probability(pattern, index);
+                let ratchet = self.sequencer.step_ratchet(pattern, index);
+                let tie = self.sequencer.step_tie(pattern, index);
+                let slide = self.sequencer.step_slide(pattern, index);
+                let nudge = self.sequencer.step_nudge(pattern, index);
+                Some(StepState { notes: notes.to_vec(), velocity, gate, probability, ratchet, tie, slide, nudge })
+            })
+            .collect();
+        PatternState {
+            length: self.sequencer.pattern_length(pattern),
+            steps,
+            preset: self.timeline_engine.pattern_presets[pattern],
+            automation: self
+                .automation
+                .params(pattern)
+                .map(|param| AutomationLaneState { param, points: self.automation.points(pattern, param).to_vec() })
+                .collect(),
+            ..PatternState::default()
+        }
+    }
+
+    fn restore_state(&mut self, state: EngineState) {
+        self.set_bpm(state.bpm);
+        self.set_master_volume(state.master_volume);
+        self.set_extra_engine_count(state.tracks.len().saturating_sub(ENGINE_COUNT));
+        for (index, track) in state.tracks.into_iter().enumerate() {
+            let index = index as u32;
+            self.set_engine_volume(index, track.volume);
+            self.set_engine_pan(index, track.pan);
+            self.set_delay_send(index, track.delay_send);
+            self.set_reverb_send(index, track.reverb_send);
+            if let Some(e) = self.engine_mut(index) {
+                e.apply_patch(&track.patch);
+                e.metadata.name = track.metadata.name;
+                e.metadata.color = track.metadata.color;
+                e.metadata.set_tags(track.metadata.tags);
+            }
+        }
+
+        let pattern = state.pattern;
+        let edit = self.sequencer.edit_pattern();
+        for index in 0..sequencer::MAX_PATTERNS {
+            self.sequencer.set_edit_pattern(index);
+            self.sequencer.clear();
+            let restored = if index == 0 { Some(&pattern) } else { state.patterns.get(index - 1) };
+            self.timeline_engine.pattern_presets[index] = restored.and_then(|p| p.preset);
+            self.automation.clear_pattern(index);
+            for lane in restored.iter().flat_map(|p| &p.automation) {
+                for &point in &lane.points {
+                    self.automation.add_point(index, lane.param, point);
+                }
+            }
+            let Some(restored) = restored else {
+                self.sequencer.set_length(PatternState::default().length);
+                continue;
+            };
+            for (index, step) in restored.steps.iter().enumerate() {
+                self.sequencer.set_step(index, &step.notes, step.velocity, step.gate);
+                self.sequencer.set_step_probability(index, step.probability);
+                self.sequencer.set_step_ratchet(index, step.ratchet);
+                self.sequencer.set_step_tie(index, step.tie);
+                self.sequencer.set_step_slide(index, step.slide);
+                self.sequencer.set_step_nudge(index, step.nudge);
+            }
+            self.sequencer.set_length(restored.length);
+        }
+        self.sequencer.set_edit_pattern(edit);
+        let song: Vec<usize> = state.song.iter().map(|&p| p as usize).collect();
+        self.sequencer.set_song(&song);
+        self.sequencer.set_steps_per_beat(pattern.steps_per_beat, self.transport.samples_per_beat());
+        self.sequencer.set_swing(pattern.swing);
+        self.set_humanize(pattern.humanize_timing_ms, pattern.humanize_velocity);
+    }
+
+    // One block at the internal rate
+    fn render_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let len = left.len().min(right.len());
+        let started = clock::now_ms();
+        self.drain_message_ring();
+        self.capture_phase_info();
+        self.advance_morph(len);
+        if self.journal_state == JOURNAL_RECORDING {
+            self.journal_patch_changes();
+        }
+        // Split at the loop end so the jump back lands on its exact sample,
+        // at replayed journal events, and to fit the scratch buffers
+        let mut start = 0;
+        while start < len {
+            self.replay_due_journal_events();
+            let mut end = len.min(start + MAX_QUANTUM);
+            if let Some(samples) = self.transport.samples_until_loop_end() {
+                end = end.min(start + samples.max(1));
+            }
+            if let Some(samples) = self.samples_until_journal_event() {
+                end = end.min(start + samples.max(1));
+            }
+            if let Some(samples) = self.samples_until_recording_event() {
+                end = end.min(start + samples.max(1));
+            }
+            if self.apply_automation() {
+                end = end.min(start + MOTION_BLOCK);
+            }
+            self.process_segment(&mut left[start..end], &mut right[start..end]);
+            start = end;
+        }
+        if len > 0 {
+            let budget_ms = len as f64 * 1000.0 / SAMPLE_RATE as f64;
+            let load = ((clock::now_ms() - started) / budget_ms) as f32;
+            self.dsp_load += (load - self.dsp_load) * DSP_LOAD_SMOOTHING;
+        }
+    }
+
+    fn journal_event(&mut self, event: JournalEvent) {
+        if self.journal_state != JOURNAL_RECORDING {
+            return;
+        }
+        // Parameter changes made before this event go in first
+        self.journal_patch_changes();
+        self.push_journal_entry(event);
+    }
+
+    fn push_journal_entry(&mut self, event: JournalEvent) {
+        self.journal.entries.push(JournalEntry {
+            sample: self.sample_time - self.journal_origin,
+            beat: self.transport.position_beats(),
+            event,
+        });
+    }
+
+    fn journal_patch_changes(&mut self) {
+        for engine in [ENGINE_TIMELINE, ENGINE_LIVE] {
+            let Some(patch) = self.engine(engine).map(|e| e.patch) else {
+                continue;
+            };
+            let last = self.journal_patches[engine as usize];
+            if patch != last {
+                let changes = journal::value_changes(&last.values(), &patch.values());
+                self.journal_patches[engine as usize] = patch;
+                self.push_journal_entry(JournalEvent::Patch { engine, changes });
+            }
+        }
+    }
+
+    fn samples_until_journal_event(&self) -> Option<usize> {
+        if self.journal_state != JOURNAL_REPLAYING {
+            return None;
+        }
+        let entry = self.journal.entries.get(self.journal_cursor)?;
+        Some((self.journal_origin + entry.sample).saturating_sub(self.sample_time) as usize)
+    }
+
+    fn replay_due_journal_events(&mut self) {
+        while self.samples_until_journal_event() == Some(0) {
+            let event = self.journal.entries[self.journal_cursor].event.clone();
+            self.journal_cursor += 1;
+            self.apply_journal_event(event);
+        }
+        if self.journal_state == JOURNAL_REPLAYING && self.journal_cursor == self.journal.entries.len() {
+            self.journal_state = JOURNAL_IDLE;
+        }
+    }
+
+    fn apply_journal_event(&mut self, event: JournalEvent) {
+        match event {
+            JournalEvent::NoteOn { note, velocity } => self.note_on(note, velocity),
+            JournalEvent::NoteOff { note, release_velocity } => self.note_off(note, release_velocity),
+            JournalEvent::ChannelPressure { value } => self.set_channel_pressure(value),
+            JournalEvent::NotePressure { note, value } => self.set_note_pressure(note, value),
+            JournalEvent::TimelineNoteOn { note, velocity, cents } => {
+                self.timeline_note_on_detuned(note, velocity, cents)
+            }
+            JournalEvent::TimelineNoteOff { note } => self.timeline_note_off(note),
+            JournalEvent::StopAllTimelineNotes => self.stop_all_timeline_notes(),
+            JournalEvent::Panic => self.panic(),
+            JournalEvent::PlayChord { root, chord_type, inversion, velocity, cents } => {
+                self.play_chord(root, chord_type, inversion, velocity, Some(cents))
+            }
+            JournalEvent::PlayVoicing { notes, velocities } => self.play_voicing(&notes, &velocities),
+            JournalEvent::ReleaseChord => self.release_chord(),
+            JournalEvent::GlideToChord { notes, time_ms } => self.glide_to_chord(&notes, time_ms),
+            JournalEvent::TransportPlay => self.transport_play(),
+            JournalEvent::TransportStop => self.transport_stop(),
+            JournalEvent::SetBpm { bpm } => self.set_bpm(bpm),
+            JournalEvent::Patch { engine, changes } => {
+                if let Some(engine) = self.engine_mut(engine) {
+                    let mut values = engine.patch.values();
+                    for (index, value) in changes {
+                        if let Some(slot) = values.get_mut(index) {
+                            *slot = value;
+                        }
+                    }
+                    let mut patch = engine.patch;
+                    patch.set_values(&values);
+                    engine.apply_patch(&patch);
+                }
+            }
+        }
+    }
+
+    fn drain_message_ring(&mut self) {
+        while let Some([kind, a, b, c]) = self.message_ring.pop() {
+            match kind as u32 {
+                MSG_NOTE_ON => self.note_on(a as u8, b),
+                MSG_NOTE_OFF => self.note_off(a as u8, None),
+                MSG_TIMELINE_NOTE_ON => self.timeline_note_on(a as u8, b),
+                MSG_TIMELINE_NOTE_OFF => self.timeline_note_off(a as u8),
+                MSG_SET_PARAM => self.set_param(a as u32, b as u32, c),
+                _ => {}
+            }
+        }
+    }
+
+    // Moves the preset morph one block closer to its target
+    fn advance_morph(&mut self, len: usize) {
+        let Some(morph) = &mut self.morph else {
+            return;
+        };
+        if morph.current == morph.target {
+            return;
+        }
+        let coeff = 1.0 - (-(len as f32) / (MORPH_SMOOTHING_MS * 0.001 * SAMPLE_RATE)).exp();
+        morph.current += (morph.target - morph.current) * coeff;
+        if (morph.target - morph.current).abs() < 1e-4 {
+            morph.current = morph.target;
+        }
+        self.apply_morph();
+    }
+
+    fn apply_morph(&mut self) {
+        let Some(morph) = self.morph.take() else {
+            return;
+        };
+        let t = morph.current;
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        self.set_master_volume(lerp(morph.a.master_volume, morph.b.master_volume));
+        for (index, (a, b)) in morph.a.tracks.iter().zip(&morph.b.tracks).enumerate() {
+            let index = index as u32;
+            self.set_engine_volume(index, lerp(a.volume, b.volume));
+            self.set_engine_pan(index, lerp(a.pan, b.pan));
+            self.set_delay_send(index, lerp(a.delay_send, b.delay_send));
+            self.set_reverb_send(index, lerp(a.reverb_send, b.reverb_send));
+            if let Some(e) = self.engine_mut(index) {
+                e.apply_patch(&a.patch.morph(&b.patch, t));
+            }
+        }
+        self.morph = Some(morph);
+    }
+
+    fn track_volume(&self, engine: u32) -> f32 {
+        match engine {
+            ENGINE_TIMELINE => self.timeline_volume,
+            ENGINE_LIVE => self.live_volume,
+            _ => match self.extra_engines.get(engine as usize - ENGINE_COUNT) {
+                Some(Some(extra)) => extra.volume,
+                _ => 0.0,
+            },
+        }
+    }
+
+    fn hot_swap(&mut self, engine: u32, patch: &Patch, fade_ms: f32) -> bool {
+        let Some(e) = self.engine_mut(engine) else {
+            return false;
+        };
+        let mut incoming = e.clone();
+        incoming.apply_patch(patch);
+        let outgoing = std::mem::replace(e, incoming);
+        let fade_samples = fade_ms.clamp(1.0, 2000.0) * SAMPLE_RATE / 1000.0;
+        self.hot_swaps[engine as usize] = Some(HotSwap { engine: outgoing, gain: 1.0, step: 1.0 / fade_samples });
+        true
+    }
+
+    // Blends the outgoing engine of a running hot swap into an engine's
+    // processed output and drops it once silent
+    fn mix_hot_swap(&mut self, engine: u32, left: &mut [f32], right: &mut [f32], scratch: &mut [Vec<f32>; 2], samples_per_beat: f64) {
+        let slot = &mut self.hot_swaps[engine as usize];
+        let Some(swap) = slot else {
+            return;
+        };
+        let len = left.len();
+        let [old_left, old_right] = scratch;
+        let (old_left, old_right) = (&mut old_left[..len], &mut old_right[..len]);
+        old_left.fill(0.0);
+        old_right.fill(0.0);
+        swap.engine.render_voices(old_left, samples_per_beat);
+        swap.engine.process_effects(old_left, old_right);
+        // Linear, since both sides carry the same notes and tails
+        for i in 0..len {
+            swap.gain = (swap.gain - swap.step).max(0.0);
+            left[i] = left[i] * (1.0 - swap.gain) + old_left[i] * swap.gain;
+            right[i] = right[i] * (1.0 - swap.gain) + old_right[i] * swap.gain;
+        }
+        if swap.gain == 0.0 {
+            *slot = None;
+        }
+    }
+
+    // Adds an engine's processed output to the buses at `volume` and its
+    // pan, metering it and writing its stem while process_multi_out runs.
+    // Returns whether it sends anything to the returns.
+    fn mix_track(&mut self, engine: u32, left: &[f32], right: &[f32], volume: f32, buses: &mut MixBuses, stems: &mut [Vec<f32>]) -> bool {
+        let Some(e) = self.engine(engine) else {
+            return false;
+        };
+        let (pan_left, pan_right) = self.pan_law.gains(e.pan);
+        let (left_gain, right_gain) = (volume * pan_left, volume * pan_right);
+        let (delay_send, reverb_send) = (e.delay_send, e.reverb_send);
+        let meter = &mut self.meters[engine as usize];
+        let MixBuses { dry, delay, reverb } = buses;
+        for i in 0..left.len() {
+            let frame = (left[i] * left_gain, right[i] * right_gain);
+            meter.process(frame.0, frame.1);
+            dry[0][i] += frame.0;
+            dry[1][i] += frame.1;
+            delay[0][i] += frame.0 * delay_send;
+            delay[1][i] += frame.1 * delay_send;
+            reverb[0][i] += frame.0 * reverb_send;
+            reverb[1][i] += frame.1 * reverb_send;
+        }
+        if let Some(cursor) = self.stem_cursor {
+            let id = engine as usize;
+            for i in 0..left.len() {
+                stems[id * 2][cursor + i] = left[i] * left_gain * self.master_volume;
+                stems[id * 2 + 1][cursor + i] = right[i] * right_gain * self.master_volume;
+            }
+        }
+        delay_send > 0.0 || reverb_send > 0.0
+    }
+
+    // Glides the crossfader on by a segment and returns the (timeline, live)
+    // gains: the equal-power pan law across the two engines, unity for both
+    // at the centre
+    fn advance_crossfade(&mut self, samples: usize) -> (f32, f32) {
+        let coeff = 1.0 - (-(samples as f32) * 1000.0 / (CROSSFADE_SMOOTHING_MS * SAMPLE_RATE)).exp();
+        self.crossfade_current += (self.crossfade - self.crossfade_current) * coeff;
+        if (self.crossfade - self.crossfade_current).abs() < 1e-4 {
+            self.crossfade_current = self.crossfade;
+        }
+        PanLaw::EqualPower.gains(self.crossfade_current * 2.0 - 1.0)
+    }
+
+    // Renders a stretch of the quantum that doesn't cross the loop end
+    fn process_segment(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let len = left.len().min(right.len());
+        let samples_per_beat = self.transport.samples_per_beat();
+        
+        // Taken out for the duration so the engines can render into them
+        let mut scratch = std::mem::take(&mut self.scratch);
+
+        let MixBuses { dry, delay, reverb } = &mut scratch.buses;
+        for bus in [dry, delay, reverb].into_iter().flatten() {
+            bus[..len].fill(0.0);
+        }
+
+        // Process timeline engine
+        let [timeline_left, timeline_right] = &mut scratch.timeline;
+        let (timeline_left, timeline_right) = (&mut timeline_left[..len], &mut timeline_right[..len]);
+        timeline_left.fill(0.0);
+        timeline_right.fill(0.0);
+        self.render_timeline(timeline_left, samples_per_beat);
+        let input = self.input_cursor.map(|cursor| &scratch.input[cursor..cursor + len]);
+        let input_engine = self.input_engine;
+        let input_for = |engine: u32| input.filter(|_| engine == input_engine);
+        if let Some(input) = input_for(ENGINE_TIMELINE) {
+            self.timeline_engine.mix_input(input, timeline_left);
+        }
+        self.timeline_engine.process_effects(timeline_left, timeline_right);
+        self.mix_hot_swap(ENGINE_TIMELINE, timeline_left, timeline_right, &mut scratch.hot_swap, samples_per_beat);
+        self.spectra[ENGINE_TIMELINE as usize].push_block(timeline_left, timeline_right);
+
+        // Process live engine
+        let [live_left, live_right] = &mut scratch.live;
+        let (live_left, live_right) = (&mut live_left[..len], &mut live_right[..len]);
+        live_left.fill(0.0);
+        live_right.fill(0.0);
+        self.render_live(live_left, samples_per_beat);
+        if let Some(input) = input_for(ENGINE_LIVE) {
+            self.live_engine.mix_input(input, live_left);
+        }
+        self.live_engine.process_effects(live_left, live_right);
+        self.mix_hot_swap(ENGINE_LIVE, live_left, live_right, &mut scratch.hot_swap, samples_per_beat);
+        self.spectra[ENGINE_LIVE as usize].push_block(live_left, live_right);
+
+        // Mix both engines with independent volumes and pan
+        let (timeline_fade, live_fade) = self.advance_crossfade(len);
+        let mut returns_active = self.mix_track(
+            ENGINE_TIMELINE,
+            timeline_left,
+            timeline_right,
+            self.timeline_volume * timeline_fade,
+            &mut scratch.buses,
+            &mut scratch.stems,
+        );
+        returns_active |= self.mix_track(
+            ENGINE_LIVE,
+            live_left,
+            live_right,
+            self.live_volume * live_fade,
+            &mut scratch.buses,
+            &mut scratch.stems,
+        );
+
+        // Created engines play their own notes and patterns, one at a time
+        // through the same buffers
+        for slot in 0..self.extra_engines.len() {
+            let id = (ENGINE_COUNT + slot) as u32;
+            let [extra_left, extra_right] = &mut scratch.extra;
+            let (extra_left, extra_right) = (&mut extra_left[..len], &mut extra_right[..len]);
+            let Some(extra) = self.extra_engines[slot].as_mut() else {
+                continue;
+            };
+            extra_left.fill(0.0);
+            extra_right.fill(0.0);
+            extra.sequencer.set_samples_per_beat(samples_per_beat);
+            render_with_events(&mut extra.engine, &mut extra.sequencer, &mut self.sequencer_events, extra_left, samples_per_beat);
+            if let Some(input) = input_for(id) {
+                extra.engine.mix_input(input, extra_left);
+            }
+            extra.engine.process_effects(extra_left, extra_right);
+            let volume = extra.volume;
+            self.mix_hot_swap(id, extra_left, extra_right, &mut scratch.hot_swap, samples_per_beat);
+            self.spectra[id as usize].push_block(extra_left, extra_right);
+            returns_active |= self.mix_track(id, extra_left, extra_right, volume, &mut scratch.buses, &mut scratch.stems);
+        }
+        if let Some(cursor) = self.stem_cursor {
+            self.stem_cursor = Some(cursor + len);
+        }
+        if let Some(cursor) = self.input_cursor {
+            self.input_cursor = Some(cursor + len);
+        }
+
+        // Beat of the segment's first sample for the click, if it sounds
+        let click_beat = match self.count_in_beats {
+            _ if self.export_mode => None,
+            Some(beats) => Some(-beats),
+            None if self.metronome_enabled && self.transport.is_playing() => Some(self.transport.position_beats()),
+            None => None,
+        };
+        if click_beat.is_none() {
+            self.metronome.reset();
+        }
+
+        // The returns ring out after the sends close, and are only skipped
+        // once their tails have died away
+        if returns_active {
+            self.returns_silent = 0;
+        }
+        let run_returns = self.returns_silent < RETURN_TAIL_SAMPLES;
+
+        let MixBuses { dry, delay, reverb } = &scratch.buses;
+        for i in 0..len {
+            let mut mix_left = dry[0][i];
+            let mut mix_right = dry[1][i];
+
+            // Post-fader sends into the shared delay and reverb returns
+            if run_returns {
+                let (delay_left, delay_right) = self.return_delay.process_wet(delay[0][i], delay[1][i]);
+                let (reverb_left, reverb_right) = self.return_reverb.process(reverb[0][i], reverb[1][i]);
+                let wet_left = delay_left + reverb_left;
+                let wet_right = delay_right + reverb_right;
+                if !returns_active {
+                    if wet_left.abs().max(wet_right.abs()) < RETURN_SILENCE {
+                        self.returns_silent += 1;
+                    } else {
+                        self.returns_silent = 0;
+                    }
+                }
+                mix_left += wet_left;
+                mix_right += wet_right;
+            }
+
+            // The looper hears the mix without its own playback, so overdubs
+            // only add the new layer
+            let (loop_left, loop_right) = self.looper.process(mix_left, mix_right);
+            mix_left += loop_left;
+            mix_right += loop_right;
+
+            if let Some(beat) = click_beat {
+                let click = self.metronome.process(beat + i as f64 / samples_per_beat);
+                mix_left += click;
+                mix_right += click;
+            }
+
+            left[i] = mix_left * self.master_volume;
+            right[i] = mix_right * self.master_volume;
+        }
+        // Clear what is left under the threshold, so it can't come back
+        // when a send opens again
+        if run_returns && self.returns_silent >= RETURN_TAIL_SAMPLES {
+            self.return_delay.reset();
+            self.return_reverb.reset();
+        }
+        self.scratch = scratch;
+
+        if self.tape_enabled {
+            for i in 0..len {
+                (left[i], right[i]) = self.tape.process(left[i], right[i]);
+            }
+        }
+
+        if self.vintage_enabled && (self.vintage_in_export || !self.export_mode) {
+            for i in 0..len {
+                (left[i], right[i]) = self.vintage.process(left[i], right[i]);
+            }
+        }
+
+        if self.bass_mono_enabled {
+            for i in 0..len {
+                (left[i], right[i]) = self.bass_mono.process(left[i], right[i]);
+            }
+        }
+
+        if self.multiband_enabled {
+            for i in 0..len {
+                (left[i], right[i]) = self.multiband.process(left[i], right[i]);
+            }
+        }
+
+        // Brickwall limiter catches overs from the engine mix
+        if self.limiter_enabled {
+            for i in 0..len {
+                (left[i], right[i]) = self.limiter.process(left[i], right[i]);
+            }
+        }
+
+        if self.soft_clipper_enabled {
+            for i in 0..len {
+                (left[i], right[i]) = self.soft_clipper.process(left[i], right[i]);
+            }
+        }
+        self.master_meter.process_block(&left[..len], &right[..len]);
+        self.collect_events();
+
+        if self.transport.advance(len) {
+            self.locate_sequencers();
+        }
+        self.advance_recording(len as f64 / samples_per_beat);
+        self.sample_time += len as u64;
+    }
+
+    // Sets the timeline engine's automated parameters to their values at
+    // the playhead. True while automation runs, so segments stay short
+    // enough to follow it.
+    fn apply_automation(&mut self) -> bool {
+        let Some((pattern, beat)) = self.sequencer.position_beats() else {
+            return false;
+        };
+        let lanes = self.automation.lane_count(pattern);
+        for index in 0..lanes {
+            if let Some((param, value)) = self.automation.value(pattern, index, beat) {
+                self.set_param(ENGINE_TIMELINE, param, value);
+            }
+        }
+        lanes > 0
+    }
+
+    // Live notes are recorded outside any punch range only when none is set
+    fn in_punch(&self) -> bool {
+        match self.punch {
+            Some((punch_in, punch_out)) => {
+                let position = self.transport.position_beats();
+                position >= punch_in && position < punch_out
+            }
+            None => true,
+        }
+    }
+
+    // Whole samples until the count-in ends or the playhead reaches the
+    // punch-out, so both land on their exact sample
+    fn samples_until_recording_event(&self) -> Option<usize> {
+        let samples_per_beat = self.transport.samples_per_beat();
+        if let Some(beats) = self.count_in_beats {
+            return Some((beats * samples_per_beat).ceil() as usize);
+        }
+        let (_, punch_out) = self.punch?;
+        let position = self.transport.position_beats();
+        if !self.sequencer.is_recording() || !self.transport.is_playing() || position >= punch_out {
+            return None;
+        }
+        Some(((punch_out - position) * samples_per_beat).ceil() as usize)
+    }
+
+    // Counts the count-in down, starting the transport when it runs out, and
+    // ends held recorded notes once the playhead leaves the punch range
+    fn advance_recording(&mut self, beats: f64) {
+        if let Some(left) = self.count_in_beats {
+            // A fraction of a sample counts as done
+            if left - beats <= 1.0 / self.transport.samples_per_beat() {
+                self.count_in_beats = None;
+                self.transport_play();
+            } else {
+                self.count_in_beats = Some(left - beats);
+            }
+        }
+        if self.sequencer.is_recording() && !self.in_punch() {
+            self.sequencer.finish_recording_notes();
+        }
+    }
+
+    // Replaces the held play_chord chord with `notes`, each at its entry of
+    // `velocities` (see chord_velocity)
+    // `cents` are per-note microtuning offsets, in the order of `notes`
+    fn play_held_chord(&mut self, notes: &[u8], velocities: &[f32], cents: &[f32]) {
+        self.release_held_chord();
+        let mut voicing: Vec<(u8, f32)> =
+            notes.iter().enumerate().map(|(i, &note)| (note, chord_velocity(velocities, i))).collect();
+        // Quantized up front so strummed notes, which skip note_on, match
+        // too; chord tones that land on the same note sound once, at the
+        // louder velocity
+        if self.scale_quantize {
+            for (note, _) in &mut voicing {
+                *note = scales::quantize(*note, self.scale_mask);
+            }
+        }
+        // Set before anything plays, as strums and the arpeggiator play the
+        // notes later; the first of any repeated note wins
+        for (i, &(note, _)) in voicing.iter().enumerate().rev() {
+            self.set_chord_cents(note, cents.get(i).copied().unwrap_or(0.0));
+        }
+        if self.scale_quantize {
+            voicing.dedup_by(|(note, velocity), (kept_note, kept_velocity)| {
+                let duplicate = note == kept_note;
+                if duplicate {
+                    *kept_velocity = kept_velocity.max(*velocity);
+                }
+                duplicate
+            });
+        }
+        // The arpeggiator does its own ordering, so strums are skipped
+        if self.arpeggiator_enabled || (self.strum_time_ms == 0.0 && self.strum_humanize == 0.0) {
+            for &(note, velocity) in &voicing {
+                self.live_note_on(note, velocity);
+            }
+        } else {
+            self.strum(&voicing);
+        }
+        self.held_chord = voicing.into_iter().map(|(note, _)| note).collect();
+    }
+
+    fn release_held_chord(&mut self) {
+        for note in std::mem::take(&mut self.held_chord) {
+            self.strum_queue.cancel_note_on(note);
+            self.live_note_off(note, None);
+            self.set_chord_cents(note, 0.0);
+        }
+    }
+
+    // Microtuning offset for a live chord note, on the live engine and the
+    // split or layer zone it may be routed to
+    fn set_chord_cents(&mut self, note: u8, cents: f32) {
+        self.live_engine.set_note_cents(note, cents);
+        if let Some(e) = self.engine_mut(self.zone_engine) {
+            e.set_note_cents(note, cents);
+        }
+    }
+
+    fn write_debug(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "webchord dsp {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(out, "sample rate {} Hz, sample time {}", SAMPLE_RATE, self.sample_time)?;
+        writeln!(
+            out,
+            "transport {} at {:.2} bpm, beat {:.3}, loop {:?}",
+            if self.transport.is_playing() { "playing" } else { "stopped" },
+            self.transport.bpm(),
+            self.transport.position_beats(),
+            self.transport.loop_range()
+        )?;
+
+        writeln!(out, "\n[Master]")?;
+        writeln!(
+            out,
+            "volume {:.2}, timeline {:.2}, live {:.2}, export mode {}",
+            self.master_volume, self.timeline_volume, self.live_volume, self.export_mode
+        )?;
+        writeln!(
+            out,
+            "tape {}, vintage {} (in export {}), bass mono {}, multiband {}, limiter {}, soft clipper {}",
+            self.tape_enabled,
+            self.vintage_enabled,
+            self.vintage_in_export,
+            self.bass_mono_enabled,
+            self.multiband_enabled,
+            self.limiter_enabled,
+            self.soft_clipper_enabled
+        )?;
+        writeln!(
+            out,
+            "quality mode {}, oversampling {}x",
+            self.quality_mode, self.oversampling
+        )?;
+        writeln!(out, "governor level {}, limits {:?}", self.governor.level(), self.governor.limits())?;
+
+        let extras = self.extra_engines.iter().flatten().map(|extra| &extra.engine);
+        for engine in [&self.timeline_engine, &self.live_engine].into_iter().chain(extras) {
+            writeln!(out)?;
+            engine.write_debug(out)?;
+        }
+
+        writeln!(out, "\n[Performance]")?;
+        writeln!(
+            out,
+            "sequencer step {:?} of {} in pattern {}, song {:?}, arpeggiator {} ({} held), held chord {:?}",
+            self.sequencer.current_step(),
+            self.sequencer.pattern_length(self.sequencer.playing_pattern()),
+            self.sequencer.playing_pattern(),
+            self.sequencer.song(),
+            self.arpeggiator_enabled,
+            self.arpeggiator.held_count(),
+            self.held_chord
+        )?;
+        writeln!(
+            out,
+            "strum {:.0} ms, direction {}, humanize {:.2}",
+            self.strum_time_ms, self.strum_direction, self.strum_humanize
+        )?;
+        writeln!(out, "scale quantize {}, pitch classes {:012b}", self.scale_quantize, self.scale_mask)?;
+        writeln!(out, "journal state {}, {} entries", self.journal_state, self.journal.entries.len())?;
+        writeln!(
+            out,
+            "looper state {}, length {} samples",
+            self.looper.state(),
+            self.looper.length()
+        )?;
+        writeln!(
+            out,
+            "buffers: {} queued notes, {} pending sequencer events, {} governor actions, {} engine events ({} dropped)",
+            self.strum_queue.pending(),
+            self.sequencer_events.len(),
+            self.governor_actions.len(),
+            self.events.len(),
+            self.events.dropped()
+        )?;
+
+        writeln!(out, "\n[Recent events]")?;
+        for event in self.event_log.iter() {
+            writeln!(out, "{:>10} {} {} {}", event.sample_time, event.kind, event.args[0], event.args[1])?;
+        }
+        Ok(())
+    }
+
+    fn capture_phase_info(&mut self) {
+        self.phase_info = PhaseInfo {
+            sample_time: self.sample_time,
+            beat_position: self.transport.position_beats(),
+            timeline_lfo_phase: self.timeline_engine.lfo.get_phase(),
+            live_lfo_phase: self.live_engine.lfo.get_phase(),
+            sequencer_step: self.sequencer.current_step(),
+        };
+    }
+
+    fn render_timeline(&mut self, output: &mut [f32], samples_per_beat: f64) {
+        self.sequencer.set_samples_per_beat(samples_per_beat);
+        render_with_events(&mut self.timeline_engine, &mut self.sequencer, &mut self.sequencer_events, output, samples_per_beat);
+    }
+
+    fn render_live(&mut self, output: &mut [f32], samples_per_beat: f64) {
+        self.arpeggiator.set_samples_per_beat(samples_per_beat);
+        let mut sources = Merged(&mut self.arpeggiator, &mut self.strum_queue);
+        render_with_events(&mut self.live_engine, &mut sources, &mut self.sequencer_events, output, samples_per_beat);
+    }
+
+    // Note a live key should trigger, or None when another held key already
+    // sounds that note
+    fn quantize_note_on(&mut self, midi_note: u8) -> Option<u8> {
+        let key = midi_note.min(127) as usize;
+        let target = if self.scale_quantize { scales::quantize(midi_note, self.scale_mask) } else { midi_note };
+        // A repeated note-on for a key already down retriggers its own note
+        let shared = self.quantized_notes[key] != target && self.quantized_notes.contains(&target);
+        self.quantized_notes[key] = target;
+        if shared {
+            return None;
+        }
+        Some(target)
+    }
+
+    // Note a released key should stop, or None while another held key still
+    // shares it
+    fn quantize_note_off(&mut self, midi_note: u8) -> Option<u8> {
+        let key = midi_note.min(127) as usize;
+        let target = match std::mem::replace(&mut self.quantized_notes[key], NO_NOTE) {
+            NO_NOTE => midi_note,
+            target => target,
+        };
+        if self.quantized_notes.contains(&target) {
+            return None;
+        }
+        Some(target)
+    }
+
+    // Schedules a chord's note-ons across the strum time on the live engine
+    fn strum(&mut self, notes: &[(u8, f32)]) {
+        let upward = self.strum_upward;
+        if self.strum_direction == 2 {
+            self.strum_upward = !self.strum_upward;
+        }
+
+        let count = notes.len();
+        let spacing = if count > 1 { self.strum_time_ms / (count - 1) as f32 } else { 0.0 };
+        for (i, &(note, velocity)) in notes.iter().enumerate() {
+            let position = if upward { i } else { count - 1 - i };
+            let jitter = self.random.bipolar() * STRUM_JITTER_MS * self.strum_humanize;
+            let delay_ms = (position as f32 * spacing + jitter).max(0.0);
+            let spread = 1.0 + self.random.bipolar() * STRUM_VELOCITY_SPREAD * self.strum_humanize;
+            let event = SequencerEvent::NoteOn { note, velocity: (velocity * spread).clamp(0.0, 1.0) };
+            self.strum_queue.schedule(delay_ms as f64 * SAMPLE_RATE as f64 / 1000.0, event);
+        }
+    }
+
+    // Moves what the engines raised during the segment into the event
+    // queue, stamped with its first frame, along with any new clips on the
+    // engine and master meters
+    fn collect_events(&mut self) {
+        let time = self.sample_time;
+        let events = &mut self.events;
+        let extras = self
+            .extra_engines
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(slot, extra)| extra.as_mut().map(|extra| ((ENGINE_COUNT + slot) as u32, &mut extra.engine)));
+        let engines = [(ENGINE_TIMELINE, &mut self.timeline_engine), (ENGINE_LIVE, &mut self.live_engine)];
+        for (id, engine) in engines.into_iter().chain(extras) {
+            for event in engine.events.drain() {
+                events.push(Some(id), time, event);
+            }
+        }
+        for (id, meter) in self.meters.iter_mut().enumerate() {
+            if meter.take_clip() {
+                events.push(Some(id as u32), time, EngineEvent { kind: EVENT_CLIP, a: meter.peak(), b: 0.0 });
+            }
+        }
+        if self.master_meter.take_clip() {
+            let peak = self.master_meter.peak();
+            events.push(None, time, EngineEvent { kind: EVENT_CLIP, a: peak, b: 0.0 });
+        }
+    }
+
+    // Plays a parsed note script on the live track from the current state,
+    // returning the mono output
+    fn render_script(&mut self, script: &NoteScript) -> Vec<f32> {
+        let to_samples = |seconds: f64| (seconds * SAMPLE_RATE as f64).ceil() as usize;
+        let mut output = vec![0.0; to_samples(script.duration)];
+        let mut position = 0;
+        for &(time, command) in &script.events {
+            let at = to_samples(time).min(output.len());
+            self.process(&mut output[position..at]);
+            position = at;
+            match command {
+                ScriptCommand::NoteOn { note, velocity } => self.live_engine.note_on(note, velocity),
+                ScriptCommand::NoteOff { note } => self.live_engine.note_off(note),
+                ScriptCommand::Param { id, value } => self.set_param(ENGINE_LIVE, id, value),
+            }
+        }
+        self.process(&mut output[position..]);
+        output
+    }
+
+    fn apply_voice_limits(&mut self, (unison, polyphony): (u8, u8)) {
+        let released = self.engines_mut().map(|e| e.set_voice_limits(unison, polyphony)).sum();
+        self.governor_actions.extend([
+            governor::ACTION_UNISON_LIMIT,
+            unison as u32,
+            governor::ACTION_POLYPHONY_LIMIT,
+            polyphony as u32,
+        ]);
+        if released > 0 {
+            self.governor_actions.extend([governor::ACTION_VOICES_RELEASED, released]);
+        }
+    }
+
+    fn engine(&self, engine: u32) -> Option<&Engine> {
+        match engine {
+            ENGINE_TIMELINE => Some(&self.timeline_engine),
+            ENGINE_LIVE => Some(&self.live_engine),
+            _ => self.extra_engines.get(engine as usize - ENGINE_COUNT)?.as_ref().map(|extra| &extra.engine),
+        }
+    }
+
+    fn engine_mut(&mut self, engine: u32) -> Option<&mut Engine> {
+        match engine {
+            ENGINE_TIMELINE => Some(&mut self.timeline_engine),
+            ENGINE_LIVE => Some(&mut self.live_engine),
+            _ => self.extra_engines.get_mut(engine as usize - ENGINE_COUNT)?.as_mut().map(|extra| &mut extra.engine),
+        }
+    }
+
+    // Every engine, built-in and created
+    fn engines(&self) -> impl Iterator<Item = &Engine> {
+        let extras = self.extra_engines.iter().flatten().map(|extra| &extra.engine);
+        [&self.timeline_engine, &self.live_engine].into_iter().chain(extras)
+    }
+
+    fn engines_mut(&mut self) -> impl Iterator<Item = &mut Engine> {
+        let extras = self.extra_engines.iter_mut().flatten().map(|extra| &mut extra.engine);
+        [&mut self.timeline_engine, &mut self.live_engine].into_iter().chain(extras)
+    }
+
+    // Pattern sequencer of a track: the timeline's and each created
+    // engine's; the live engine has none
+    fn track_sequencer(&self, engine: u32) -> Option<&Sequencer> {
+        match engine {
+            ENGINE_TIMELINE => Some(&self.sequencer),
+            ENGINE_LIVE => None,
+            _ => self.extra_engines.get(engine as usize - ENGINE_COUNT)?.as_ref().map(|extra| &extra.sequencer),
+        }
+    }
+
+    fn track_sequencer_mut(&mut self, engine: u32) -> Option<&mut Sequencer> {
+        match engine {
+            ENGINE_TIMELINE => Some(&mut self.sequencer),
+            ENGINE_LIVE => None,
+            _ => self.extra_engines.get_mut(engine as usize - ENGINE_COUNT)?.as_mut().map(|extra| &mut extra.sequencer),
+        }
+    }
+
+    // The timeline's when the edit track has since been destroyed
+    fn edit_sequencer(&self) -> &Sequencer {
+        self.track_sequencer(self.edit_track).unwrap_or(&self.sequencer)
+    }
+
+    fn edit_sequencer_mut(&mut self) -> &mut Sequencer {
+        match self.edit_track {
+            ENGINE_TIMELINE => &mut self.sequencer,
+            engine => match self.extra_engines.get_mut(engine as usize - ENGINE_COUNT) {
+                Some(Some(extra)) => &mut extra.sequencer,
+                _ => &mut self.sequencer,
+            },
+        }
+    }
+
+    fn start_sequencers(&mut self) {
+        let beats = self.transport.position_beats();
+        let extras = self.extra_engines.iter_mut().flatten().map(|extra| &mut extra.sequencer);
+        for sequencer in std::iter::once(&mut self.sequencer).chain(extras) {
+            sequencer.start();
+            sequencer.locate(beats);
+        }
+    }
+
+    fn locate_sequencers(&mut self) {
+        let beats = self.transport.position_beats();
+        self.sequencer.locate(beats);
+        for extra in self.extra_engines.iter_mut().flatten() {
+            extra.sequencer.locate(beats);
+        }
+    }
+
+    // Releases every note the sequencers still hold
+    fn stop_sequencers(&mut self) {
+        self.sequencer.stop(&mut self.sequencer_events);
+        apply_events(&mut self.timeline_engine, &mut self.sequencer_events);
+        for extra in self.extra_engines.iter_mut().flatten() {
+            extra.sequencer.stop(&mut self.sequencer_events);
+            apply_events(&mut extra.engine, &mut self.sequencer_events);
+        }
+    }
+
+    // Puts a fresh engine in an extra slot, growing the per-engine state
+    // when the slot is new, with the tuning and voice limits the others run
+    // under
+    fn install_engine(&mut self, slot: usize) {
+        let id = ENGINE_COUNT + slot;
+        let mut engine = Engine::new(SAMPLE_RATE, &format!("Track {}", id + 1));
+        engine.set_tuning(self.live_engine.tuning.clone());
+        let (unison, polyphony) = self.governor.limits();
+        engine.set_voice_limits(unison, polyphony);
+        engine.set_oversampling(self.oversampling);
+        engine.set_reverb_density(quality_settings(self.quality_mode).1);
+        engine.set_pan_law(self.pan_law);
+        let mut sequencer = Sequencer::new(self.transport.samples_per_beat());
+        if self.transport.is_playing() {
+            sequencer.start();
+            sequencer.locate(self.transport.position_beats());
+        }
+        let extra = ExtraEngine { engine, volume: 0.7, sequencer };
+        if slot == self.extra_engines.len() {
+            self.extra_engines.push(Some(extra));
+            self.hot_swaps.push(None);
+            self.meters.push(Meter::new(SAMPLE_RATE));
+            self.spectra.push(SpectrumAnalyzer::new());
+            self.scratch.stems.extend([vec![0.0; MAX_QUANTUM], vec![0.0; MAX_QUANTUM]]);
+        } else {
+            self.extra_engines[slot] = Some(extra);
+            self.hot_swaps[id] = None;
+            self.meters[id] = Meter::new(SAMPLE_RATE);
+            self.spectra[id] = SpectrumAnalyzer::new();
+        }
+    }
+
+    // Keeps the first `count` created engines (recreating destroyed ones)
+    // and drops the rest
+    fn set_extra_engine_count(&mut self, count: usize) {
+        let count = count.min(MAX_ENGINES - ENGINE_COUNT);
+        let ids = ENGINE_COUNT + count;
+        self.extra_engines.truncate(count);
+        self.hot_swaps.truncate(ids);
+        self.meters.truncate(ids);
+        self.spectra.truncate(ids);
+        self.scratch.stems.truncate(ids * 2);
+        for slot in 0..count {
+            if self.extra_engines.get(slot).is_none_or(Option::is_none) {
+                self.install_engine(slot);
+            }
+        }
+    }
+}
+
+// Velocity for the `index`th note of a chord: its own entry, else the last
+// one given, else 0.8
+fn chord_velocity(velocities: &[f32], index: usize) -> f32 {
+    velocities.get(index).or(velocities.last()).copied().unwrap_or(0.8)
+}
+
+// Oversampling factor and FDN reverb density a quality mode runs
+fn quality_settings(mode: u8) -> (u32, u8) {
+    match mode {
+        QUALITY_ECO => (1, DENSITY_SPARSE),
+        QUALITY_HIGH => (4, DENSITY_DENSE),
+        _ => (1, DENSITY_NORMAL),
+    }
+}
+
+// Renders an engine's voices, splitting the block wherever `source` has an
+// event due so notes start and stop on their exact sample
+fn render_with_events(
+    engine: &mut Engine,
+    source: &mut impl NoteSource,
+    events: &mut Vec<SequencerEvent>,
+    output: &mut [f32],
+    samples_per_beat: f64,
+) {
+    let mut start = 0;
+    while start < output.len() {
+        if source.samples_until_next_event() == Some(0) {
+            source.take_due_events(events);
+            apply_events(engine, events);
+        }
+        let due = source.samples_until_next_event();
+        let end = due.map_or(output.len(), |d| (start + d.max(1)).min(output.len()));
+        engine.render_voices(&mut output[start..end], samples_per_beat);
+        source.advance(end - start);
+        start = end;
+    }
+}
+
+fn apply_events(engine: &mut Engine, events: &mut Vec<SequencerEvent>) {
+    for event in events.drain(..) {
+        match event {
+            SequencerEvent::NoteOn { note, velocity } => engine.note_on(note, velocity),
+            SequencerEvent::NoteOff { note } => engine.note_off(note),
+            SequencerEvent::Slide { from, to, velocity } => engine.slide(from, to, velocity),
+            SequencerEvent::PatternStart { pattern } => engine.start_pattern(pattern as usize),
+            SequencerEvent::PatternLoop { pattern } => engine.events.push(EVENT_PATTERN_LOOPED, pattern as f32, 0.0),
+        }
+    }
 }
 
@@ -0,0 +1,127 @@
+// Audio looper: records the engine mix into a stereo buffer whose length is
+// a whole number of samples fixed when recording starts, so a loop of N beats
+// stays locked to the tempo it was recorded at. Recording ends by itself at
+// the loop length and rolls straight into playback; overdubs are summed onto
+// the existing layers.
+
+// Longest loop, in seconds, that will be allocated
+const MAX_LOOP_SECONDS: f64 = 120.0;
+
+pub const LOOPER_EMPTY: u8 = 0;
+pub const LOOPER_RECORDING: u8 = 1;
+pub const LOOPER_PLAYING: u8 = 2;
+pub const LOOPER_OVERDUBBING: u8 = 3;
+pub const LOOPER_STOPPED: u8 = 4;
+
+pub struct Looper {
+    sample_rate: f32,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    length: usize,
+    position: usize,
+    state: u8,
+}
+
+impl Looper {
+    pub fn new(sample_rate: f32) -> Self {
+        Looper {
+            sample_rate,
+            left: Vec::new(),
+            right: Vec::new(),
+            length: 0,
+            position: 0,
+            state: LOOPER_EMPTY,
+        }
+    }
+
+    // Starts a fresh loop of `beats` beats, replacing any existing one. The
+    // length is rounded to the nearest sample once, here.
+    pub fn record(&mut self, beats: f64, samples_per_beat: f64) {
+        let max = MAX_LOOP_SECONDS * self.sample_rate as f64;
+        let length = (beats * samples_per_beat).min(max).round() as usize;
+        if length == 0 {
+            return;
+        }
+        self.left.clear();
+        self.left.resize(length, 0.0);
+        self.right.clear();
+        self.right.resize(length, 0.0);
+        self.length = length;
+        self.position = 0;
+        self.state = LOOPER_RECORDING;
+    }
+
+    // Plays from the top when stopped, or drops out of overdub when playing
+    pub fn play(&mut self) {
+        match self.state {
+            LOOPER_STOPPED => {
+                self.position = 0;
+                self.state = LOOPER_PLAYING;
+            }
+            LOOPER_OVERDUBBING => self.state = LOOPER_PLAYING,
+            _ => {}
+        }
+    }
+
+    // Toggles layering on top of the loop while it plays
+    pub fn overdub(&mut self) {
+        match self.state {
+            LOOPER_PLAYING | LOOPER_STOPPED => self.state = LOOPER_OVERDUBBING,
+            LOOPER_OVERDUBBING => self.state = LOOPER_PLAYING,
+            _ => {}
+        }
+    }
+
+    // Stopping during the first pass keeps what has been recorded so far
+    // but the loop length stays as requested
+    pub fn stop(&mut self) {
+        if self.state != LOOPER_EMPTY {
+            self.state = LOOPER_STOPPED;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.left = Vec::new();
+        self.right = Vec::new();
+        self.length = 0;
+        self.position = 0;
+        self.state = LOOPER_EMPTY;
+    }
+
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+
+    // Loop length in samples, 0 when empty
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    // Takes the incoming mix and returns the loop playback to add to it
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let output = match self.state {
+            LOOPER_RECORDING => {
+                self.left[self.position] = left;
+                self.right[self.position] = right;
+                (0.0, 0.0)
+            }
+            LOOPER_PLAYING => (self.left[self.position], self.right[self.position]),
+            LOOPER_OVERDUBBING => {
+                let layered = (self.left[self.position], self.right[self.position]);
+                self.left[self.position] += left;
+                self.right[self.position] += right;
+                layered
+            }
+            _ => return (0.0, 0.0),
+        };
+
+        self.position += 1;
+        if self.position == self.length {
+            self.position = 0;
+            if self.state == LOOPER_RECORDING {
+                self.state = LOOPER_PLAYING;
+            }
+        }
+        output
+    }
+}
@@ -0,0 +1,87 @@
+use crate::cc_map::{scale_to_range, CURVE_TOGGLE};
+
+// Macro knobs: each slot drives any number of parameters from one 0-1
+// value, every target scaled into its own range along its own curve (the
+// CC mapping curves), so a single UI knob can open the filter, raise the
+// reverb mix and lengthen the attack together.
+
+pub const MACRO_COUNT: usize = 4;
+// Targets per slot; more are ignored
+const MAX_TARGETS: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct MacroTarget {
+    pub engine: u32,
+    pub param: u32,
+    min: f32,
+    max: f32,
+    curve: u8,
+}
+
+impl MacroTarget {
+    pub fn new(engine: u32, param: u32, min: f32, max: f32, curve: u8) -> Self {
+        MacroTarget { engine, param, min, max, curve: curve.min(CURVE_TOGGLE) }
+    }
+
+    pub fn scale(&self, value: f32) -> f32 {
+        scale_to_range(value, self.min, self.max, self.curve)
+    }
+}
+
+#[derive(Default)]
+struct MacroSlot {
+    targets: Vec<MacroTarget>,
+    value: f32,
+}
+
+pub struct Macros {
+    slots: [MacroSlot; MACRO_COUNT],
+}
+
+impl Macros {
+    pub fn new() -> Self {
+        Macros { slots: Default::default() }
+    }
+
+    // Replaces any earlier assignment of the same parameter to the slot
+    pub fn assign(&mut self, index: usize, target: MacroTarget) -> bool {
+        let Some(slot) = self.slots.get_mut(index) else {
+            return false;
+        };
+        slot.targets.retain(|t| (t.engine, t.param) != (target.engine, target.param));
+        if slot.targets.len() >= MAX_TARGETS {
+            return false;
+        }
+        slot.targets.push(target);
+        true
+    }
+
+    pub fn unassign(&mut self, index: usize, engine: u32, param: u32) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.targets.retain(|t| (t.engine, t.param) != (engine, param));
+        }
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.targets.clear();
+        }
+    }
+
+    // Stores the slot's new 0-1 value and returns what it drives
+    pub fn set(&mut self, index: usize, value: f32) -> &[MacroTarget] {
+        let Some(slot) = self.slots.get_mut(index) else {
+            return &[];
+        };
+        slot.value = value.clamp(0.0, 1.0);
+        &slot.targets
+    }
+
+    pub fn value(&self, index: usize) -> Option<f32> {
+        self.slots.get(index).map(|slot| slot.value)
+    }
+
+    pub fn targets(&self, index: usize) -> &[MacroTarget] {
+        self.slots.get(index).map_or(&[], |slot| &slot.targets)
+    }
+}
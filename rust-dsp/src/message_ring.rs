@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Single-producer, single-consumer message queue for SharedArrayBuffer
+// deployments: the main thread writes notes and parameter changes straight
+// into WASM memory and the audio thread drains them at the top of each
+// render quantum, so nothing but process() is called on the audio thread.
+//
+// Layout, all little-endian 32-bit words:
+//   slots:   MESSAGE_RING_CAPACITY messages of MESSAGE_WORDS f32 each,
+//            [kind, a, b, c] with the MSG_* kinds below
+//   indices: [write, read] as u32 message counters that only ever count up
+//            (wrapping); a message lives in slot counter % capacity
+//
+// The producer fills slot `write % capacity` when write - read is below the
+// capacity, then publishes it with Atomics.store on the write index. The
+// consumer publishes the read index the same way once it is done.
+
+pub const MESSAGE_RING_CAPACITY: usize = 1024;
+pub const MESSAGE_WORDS: usize = 4;
+
+// [kind, note, velocity, -]
+pub const MSG_NOTE_ON: u32 = 0;
+// [kind, note, -, -]
+pub const MSG_NOTE_OFF: u32 = 1;
+pub const MSG_TIMELINE_NOTE_ON: u32 = 2;
+pub const MSG_TIMELINE_NOTE_OFF: u32 = 3;
+// [kind, engine, param id, value]
+pub const MSG_SET_PARAM: u32 = 4;
+
+const WRITE: usize = 0;
+const READ: usize = 1;
+
+pub struct MessageRing {
+    // f32 bit patterns, so JS can view them as a Float32Array
+    slots: Box<[AtomicU32]>,
+    indices: [AtomicU32; 2],
+}
+
+impl MessageRing {
+    pub fn new() -> Self {
+        MessageRing {
+            slots: (0..MESSAGE_RING_CAPACITY * MESSAGE_WORDS).map(|_| AtomicU32::new(0)).collect(),
+            indices: [AtomicU32::new(0), AtomicU32::new(0)],
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn slots_ptr(&self) -> *const AtomicU32 {
+        self.slots.as_ptr()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn indices_ptr(&self) -> *const AtomicU32 {
+        self.indices.as_ptr()
+    }
+
+    // Producer side, for hosts that share the engine's address space.
+    // Returns false, dropping the message, when the ring is full.
+    pub fn push(&self, message: [f32; MESSAGE_WORDS]) -> bool {
+        let write = self.indices[WRITE].load(Ordering::Relaxed);
+        let read = self.indices[READ].load(Ordering::Acquire);
+        if write.wrapping_sub(read) as usize >= MESSAGE_RING_CAPACITY {
+            return false;
+        }
+        let slot = (write as usize % MESSAGE_RING_CAPACITY) * MESSAGE_WORDS;
+        for (word, value) in self.slots[slot..slot + MESSAGE_WORDS].iter().zip(message) {
+            word.store(value.to_bits(), Ordering::Relaxed);
+        }
+        self.indices[WRITE].store(write.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    // Consumer side: the oldest unread message, if any
+    pub fn pop(&self) -> Option<[f32; MESSAGE_WORDS]> {
+        let read = self.indices[READ].load(Ordering::Relaxed);
+        let write = self.indices[WRITE].load(Ordering::Acquire);
+        if read == write {
+            return None;
+        }
+        let slot = (read as usize % MESSAGE_RING_CAPACITY) * MESSAGE_WORDS;
+        let mut message = [0.0; MESSAGE_WORDS];
+        for (value, word) in message.iter_mut().zip(&self.slots[slot..slot + MESSAGE_WORDS]) {
+            *value = f32::from_bits(word.load(Ordering::Relaxed));
+        }
+        self.indices[READ].store(read.wrapping_add(1), Ordering::Release);
+        Some(message)
+    }
+}
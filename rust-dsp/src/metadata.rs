@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+// User-assignable organization data for a track. The engine only stores and
+// serializes it so project files round-trip the UI layout.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub name: String,
+    pub color: u8,
+    pub tags: Vec<String>,
+}
+
+impl Metadata {
+    pub fn named(name: &str) -> Self {
+        Metadata {
+            name: name.to_string(),
+            ..Metadata::default()
+        }
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+    }
+}
@@ -0,0 +1,76 @@
+use crate::denormal::flush;
+
+// Peak falls back by 20 dB over this many seconds after a transient, like a
+// PPM; RMS integrates over a 300 ms window, like a VU
+const PEAK_FALL_SECONDS: f32 = 1.5;
+const RMS_WINDOW_SECONDS: f32 = 0.3;
+
+// Stereo level meter fed from inside the render loop, so the UI can poll
+// levels instead of running an AnalyserNode per bus. Peak is instant attack
+// with a slow exponential fall; RMS is a one-pole mean of the square of both
+// channels.
+#[derive(Clone)]
+pub struct Meter {
+    peak: f32,
+    mean_square: f32,
+    peak_fall: f32,
+    rms_coeff: f32,
+    // Highest peak since the last reset, for headroom
+    max_peak: f32,
+    // Set when a sample reaches full scale while the peak was below it
+    clip_started: bool,
+}
+
+impl Meter {
+    pub fn new(sample_rate: f32) -> Self {
+        Meter {
+            peak: 0.0,
+            mean_square: 0.0,
+            peak_fall: (0.1_f32.ln() / (PEAK_FALL_SECONDS * sample_rate)).exp(),
+            rms_coeff: 1.0 - (-1.0 / (RMS_WINDOW_SECONDS * sample_rate)).exp(),
+            max_peak: 0.0,
+            clip_started: false,
+        }
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) {
+        let level = left.abs().max(right.abs());
+        if level >= 1.0 && self.peak < 1.0 {
+            self.clip_started = true;
+        }
+        self.peak = flush(level.max(self.peak * self.peak_fall));
+        self.max_peak = self.max_peak.max(level);
+        let square = (left * left + right * right) * 0.5;
+        self.mean_square = flush(self.mean_square + (square - self.mean_square) * self.rms_coeff);
+    }
+
+    pub fn process_block(&mut self, left: &[f32], right: &[f32]) {
+        for (&l, &r) in left.iter().zip(right) {
+            self.process(l, r);
+        }
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    pub fn rms(&self) -> f32 {
+        self.mean_square.sqrt()
+    }
+
+    // dB from the highest peak to full scale: positive is room to spare,
+    // negative means it clipped. 120 before anything has played.
+    pub fn headroom_db(&self) -> f32 {
+        -20.0 * self.max_peak.max(1e-6).log10()
+    }
+
+    // Whether the output has started clipping since the last call; a
+    // clip counts once until the falling peak drops back under full scale
+    pub fn take_clip(&mut self) -> bool {
+        std::mem::take(&mut self.clip_started)
+    }
+
+    pub fn reset_headroom(&mut self) {
+        self.max_peak = 0.0;
+    }
+}
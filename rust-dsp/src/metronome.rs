@@ -0,0 +1,70 @@
+use std::f32::consts::TAU;
+
+// Click for count-ins and recording: a short decaying sine blip on every
+// beat, higher on the first beat of each 4/4 bar. The caller passes the beat
+// position of each sample and a click sounds whenever it crosses a beat.
+
+const ACCENT_FREQ: f32 = 1760.0;
+const BEAT_FREQ: f32 = 1320.0;
+const DECAY_SECONDS: f32 = 0.015;
+const BEATS_PER_BAR: i64 = 4;
+
+#[derive(Clone)]
+pub struct Metronome {
+    sample_rate: f32,
+    level: f32,
+    phase: f32,
+    increment: f32,
+    envelope: f32,
+    decay: f32,
+    // Beat the last click was for, None to click on the next sample
+    last_beat: Option<i64>,
+}
+
+impl Metronome {
+    pub fn new(sample_rate: f32) -> Self {
+        Metronome {
+            sample_rate,
+            level: 0.5,
+            phase: 0.0,
+            increment: 0.0,
+            envelope: 0.0,
+            decay: (-1.0 / (DECAY_SECONDS * sample_rate)).exp(),
+            last_beat: None,
+        }
+    }
+
+    // 0-1
+    pub fn set_level(&mut self, level: f32) {
+        self.level = level.clamp(0.0, 1.0);
+    }
+
+    // Forgets the last beat so the next sample clicks wherever it lands on
+    // the beat grid, e.g. when the transport starts or jumps
+    pub fn reset(&mut self) {
+        self.last_beat = None;
+    }
+
+    // `beat` is the musical position of this sample; negative during a
+    // count-in, so bars still line up
+    pub fn process(&mut self, beat: f64) -> f32 {
+        let index = beat.floor() as i64;
+        if self.last_beat != Some(index) {
+            // Only on the beat itself, not when joining mid-beat
+            if self.last_beat.is_some() || beat - (index as f64) < 1e-6 {
+                let freq = if index.rem_euclid(BEATS_PER_BAR) == 0 { ACCENT_FREQ } else { BEAT_FREQ };
+                self.increment = freq / self.sample_rate;
+                self.phase = 0.0;
+                self.envelope = 1.0;
+            }
+            self.last_beat = Some(index);
+        }
+        if self.envelope < 1e-4 {
+            return 0.0;
+        }
+        let out = (self.phase * TAU).sin() * self.envelope * self.level;
+        self.phase = (self.phase + self.increment).fract();
+        self.envelope *= self.decay;
+        out
+    }
+}
@@ -0,0 +1,193 @@
+// MIDI byte interface: channel message parsing and the SysEx bulk dump formats.
+
+use crate::metadata::Metadata;
+
+pub enum MidiMessage<'a> {
+    NoteOn { note: u8, velocity: u8 },
+    // Release velocity when the sender gave one (0x80), None for note on
+    // with zero velocity
+    NoteOff { note: u8, velocity: Option<u8> },
+    ChannelPressure { value: u8 },
+    PolyPressure { note: u8, value: u8 },
+    ControlChange { controller: u8, value: u8 },
+    SysEx(&'a [u8]),
+    Clock,
+    Start,
+    Continue,
+    Stop,
+    Unsupported,
+}
+
+pub fn parse(data: &[u8]) -> MidiMessage<'_> {
+    if data.is_empty() {
+        return MidiMessage::Unsupported;
+    }
+
+    match data[0] {
+        SYSEX_START => return MidiMessage::SysEx(data),
+        // System real-time, single byte
+        0xF8 => return MidiMessage::Clock,
+        0xFA => return MidiMessage::Start,
+        0xFB => return MidiMessage::Continue,
+        0xFC => return MidiMessage::Stop,
+        _ => {}
+    }
+
+    // Channel pressure is the one two-byte channel message handled here
+    if data[0] & 0xF0 == 0xD0 {
+        return match data.get(1) {
+            Some(&value) => MidiMessage::ChannelPressure { value: value & 0x7F },
+            None => MidiMessage::Unsupported,
+        };
+    }
+
+    if data.len() < 3 {
+        return MidiMessage::Unsupported;
+    }
+
+    let note = data[1] & 0x7F;
+    let velocity = data[2] & 0x7F;
+    match data[0] & 0xF0 {
+        // Note on with zero velocity is a note off by convention
+        0x90 if velocity > 0 => MidiMessage::NoteOn { note, velocity },
+        0x90 => MidiMessage::NoteOff { note, velocity: None },
+        0x80 => MidiMessage::NoteOff { note, velocity: Some(velocity) },
+        0xA0 => MidiMessage::PolyPressure { note, value: velocity },
+        0xB0 => MidiMessage::ControlChange { controller: note, value: velocity },
+        _ => MidiMessage::Unsupported,
+    }
+}
+
+// Dump layout:
+// F0 7D 57 <command> <engine> <version> <payload>... <checksum> F7
+//
+// 0x7D is the non-commercial manufacturer ID, so dumps are accepted by any
+// generic librarian. The Roland-style checksum covers everything from the
+// engine byte onwards.
+//
+// Patch payload: <count hi> <count lo> then each f32 split into five 7-bit
+// bytes (MSB first).
+// Metadata payload: <color> <name> 00 <tag> 00 <tag> 00 ... with text
+// restricted to 7-bit ASCII.
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+const MANUFACTURER_ID: u8 = 0x7D;
+const DEVICE_ID: u8 = 0x57;
+const CMD_PATCH_DUMP: u8 = 0x01;
+const CMD_METADATA_DUMP: u8 = 0x02;
+const FORMAT_VERSION: u8 = 1;
+
+pub fn encode_patch_dump(engine: u8, values: &[f32]) -> Vec<u8> {
+    let count = values.len().min(0x3FFF);
+    let mut payload = Vec::with_capacity(2 + count * 5);
+    payload.push(((count >> 7) & 0x7F) as u8);
+    payload.push((count & 0x7F) as u8);
+    for value in &values[..count] {
+        let bits = value.to_bits();
+        for shift in [28, 21, 14, 7, 0] {
+            payload.push(((bits >> shift) & 0x7F) as u8);
+        }
+    }
+
+    wrap_sysex(CMD_PATCH_DUMP, engine, &payload)
+}
+
+// Returns the target engine and the decoded value list, or None if the
+// message is not a valid patch dump for this device.
+pub fn decode_patch_dump(data: &[u8]) -> Option<(u8, Vec<f32>)> {
+    let (engine, payload) = unwrap_sysex(CMD_PATCH_DUMP, data)?;
+    if payload.len() < 2 {
+        return None;
+    }
+
+    let count = ((payload[0] as usize) << 7) | payload[1] as usize;
+    let payload = &payload[2..];
+    if payload.len() != count * 5 {
+        return None;
+    }
+
+    let values = payload
+        .chunks_exact(5)
+        .map(|chunk| {
+            let bits = chunk
+                .iter()
+                .fold(0u32, |bits, byte| (bits << 7) | (*byte & 0x7F) as u32);
+            f32::from_bits(bits)
+        })
+        .collect();
+
+    Some((engine, values))
+}
+
+pub fn encode_metadata_dump(engine: u8, metadata: &Metadata) -> Vec<u8> {
+    let mut payload = vec![metadata.color & 0x7F];
+    push_text(&mut payload, &metadata.name);
+    for tag in &metadata.tags {
+        push_text(&mut payload, tag);
+    }
+
+    wrap_sysex(CMD_METADATA_DUMP, engine, &payload)
+}
+
+pub fn decode_metadata_dump(data: &[u8]) -> Option<(u8, Metadata)> {
+    let (engine, payload) = unwrap_sysex(CMD_METADATA_DUMP, data)?;
+    let (color, text) = payload.split_first()?;
+
+    let mut fields = text
+        .split(|byte| *byte == 0)
+        .map(|field| field.iter().map(|byte| *byte as char).collect::<String>());
+    let name = fields.next().unwrap_or_default();
+    let tags = fields.filter(|tag| !tag.is_empty()).collect();
+
+    Some((engine, Metadata { name, color: *color, tags }))
+}
+
+fn push_text(payload: &mut Vec<u8>, text: &str) {
+    payload.extend(text.chars().map(|c| if c.is_ascii() && c != '\0' { c as u8 } else { b'?' }));
+    payload.push(0);
+}
+
+fn wrap_sysex(command: u8, engine: u8, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(2 + payload.len());
+    body.push(engine & 0x7F);
+    body.push(FORMAT_VERSION);
+    body.extend_from_slice(payload);
+
+    let mut data = Vec::with_capacity(body.len() + 6);
+    data.extend_from_slice(&[SYSEX_START, MANUFACTURER_ID, DEVICE_ID, command]);
+    data.extend_from_slice(&body);
+    data.push(checksum(&body));
+    data.push(SYSEX_END);
+    data
+}
+
+// Validates framing, device, command, checksum and version, returning the
+// addressed engine and the command payload
+fn unwrap_sysex(command: u8, data: &[u8]) -> Option<(u8, &[u8])> {
+    if data.len() < 8
+        || data[0] != SYSEX_START
+        || data[1] != MANUFACTURER_ID
+        || data[2] != DEVICE_ID
+        || data[3] != command
+        || data[data.len() - 1] != SYSEX_END
+    {
+        return None;
+    }
+
+    let body = &data[4..data.len() - 2];
+    if checksum(body) != data[data.len() - 2] {
+        return None;
+    }
+
+    let version = body[1];
+    if version == 0 || version > FORMAT_VERSION {
+        return None;
+    }
+
+    Some((body[0], &body[2..]))
+}
+
+fn checksum(body: &[u8]) -> u8 {
+    let sum = body.iter().fold(0u32, |sum, byte| sum + *byte as u32);
+    ((128 - (sum % 128)) % 128) as u8
+}
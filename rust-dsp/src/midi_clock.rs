@@ -0,0 +1,102 @@
+// Follows an external MIDI clock (24 ticks per quarter note). Tempo comes
+// from the average tick interval over the last beat, which irons out the
+// timestamp jitter of Web MIDI; a small tempo bias then pulls the internal
+// transport back into phase with the ticks counted since Start/Continue.
+
+pub const TICKS_PER_BEAT: u32 = 24;
+// Fraction of the phase error corrected per beat, and the most the bias may
+// bend the tempo
+const PHASE_CORRECTION: f64 = 0.25;
+const MAX_BIAS: f64 = 0.05;
+// A gap this many times the average interval means the clock dropped out
+const DROPOUT_RATIO: f64 = 4.0;
+
+pub struct MidiClock {
+    intervals: [f64; TICKS_PER_BEAT as usize],
+    count: usize,
+    next: usize,
+    last_tick_ms: Option<f64>,
+    // Ticks since Start/Continue, counted from the position sync began at
+    ticks: u64,
+    origin_beats: f64,
+    // Start or Continue received, waiting for the first tick to roll
+    armed: bool,
+}
+
+impl MidiClock {
+    pub fn new() -> Self {
+        MidiClock {
+            intervals: [0.0; TICKS_PER_BEAT as usize],
+            count: 0,
+            next: 0,
+            last_tick_ms: None,
+            ticks: 0,
+            origin_beats: 0.0,
+            armed: false,
+        }
+    }
+
+    // Start/Continue: the transport rolls from `position_beats` on the next tick
+    pub fn arm(&mut self, position_beats: f64) {
+        self.armed = true;
+        self.ticks = 0;
+        self.origin_beats = position_beats;
+    }
+
+    pub fn stop(&mut self) {
+        self.armed = false;
+        self.last_tick_ms = None;
+        self.count = 0;
+    }
+
+    // Returns true when this tick should start the transport
+    pub fn tick(&mut self, timestamp_ms: f64) -> bool {
+        if let Some(last) = self.last_tick_ms {
+            let interval = timestamp_ms - last;
+            let average = self.average_interval();
+            if interval <= 0.0 || average.is_some_and(|a| interval > a * DROPOUT_RATIO) {
+                self.count = 0;
+            } else {
+                self.intervals[self.next] = interval;
+                self.next = (self.next + 1) % self.intervals.len();
+                self.count = (self.count + 1).min(self.intervals.len());
+            }
+        }
+        self.last_tick_ms = Some(timestamp_ms);
+
+        let starts = self.armed;
+        if self.armed {
+            self.armed = false;
+        } else {
+            self.ticks += 1;
+        }
+        starts
+    }
+
+    fn average_interval(&self) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.intervals[..self.count].iter().sum::<f64>() / self.count as f64)
+    }
+
+    // Smoothed clock tempo, once a few ticks have arrived
+    pub fn bpm(&self) -> Option<f64> {
+        if self.count < 6 {
+            return None;
+        }
+        self.average_interval().map(|interval| 60_000.0 / (interval * TICKS_PER_BEAT as f64))
+    }
+
+    // Tempo for the transport: the clock tempo, bent slightly toward where
+    // the ticks say the playhead should be
+    pub fn corrected_bpm(&self, position_beats: f64) -> Option<f64> {
+        let bpm = self.bpm()?;
+        let expected = self.origin_beats + self.ticks as f64 / TICKS_PER_BEAT as f64;
+        // Only the phase within the beat counts, so transport loops don't
+        // read as a huge error
+        let error = expected - position_beats;
+        let bias = ((error - error.round()) * PHASE_CORRECTION).clamp(-MAX_BIAS, MAX_BIAS);
+        Some(bpm * (1.0 + bias))
+    }
+}
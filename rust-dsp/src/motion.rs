@@ -0,0 +1,73 @@
+use std::f32::consts::PI;
+
+// "Motion" macro for animated pads: one amount drives three slow,
+// deliberately unrelated LFOs so the movement never audibly repeats. Pan
+// sways the whole engine, cutoff breathes across all voices together and
+// pitch drifts per voice, each voice on its own phase like a detuned
+// analogue ensemble.
+
+// LFO rates in Hz, chosen to avoid simple ratios with each other
+const PAN_RATE: f32 = 0.071;
+const CUTOFF_RATE: f32 = 0.113;
+const DRIFT_RATE: f32 = 0.193;
+
+// Depths at full amount
+const PAN_DEPTH: f32 = 0.6;
+const CUTOFF_DEPTH_OCTAVES: f32 = 1.0;
+const DRIFT_DEPTH_CENTS: f32 = 9.0;
+
+// Phase offset between neighbouring voices' drift (golden ratio)
+const DRIFT_SPREAD: f32 = 0.618;
+
+#[derive(Clone)]
+pub struct Motion {
+    sample_rate: f32,
+    amount: f32,
+    pan_phase: f32,
+    cutoff_phase: f32,
+    drift_phase: f32,
+}
+
+impl Motion {
+    pub fn new(sample_rate: f32) -> Self {
+        Motion {
+            sample_rate,
+            amount: 0.0,
+            pan_phase: 0.0,
+            cutoff_phase: 0.25,
+            drift_phase: 0.5,
+        }
+    }
+
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount.clamp(0.0, 1.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.amount > 0.0
+    }
+
+    // Per-sample pan offset, -1..1
+    pub fn next_pan(&mut self) -> f32 {
+        self.pan_phase = (self.pan_phase + PAN_RATE / self.sample_rate).fract();
+        (self.pan_phase * 2.0 * PI).sin() * PAN_DEPTH * self.amount
+    }
+
+    // Moves the voice LFOs on by a block of samples
+    pub fn advance(&mut self, samples: usize) {
+        let seconds = samples as f32 / self.sample_rate;
+        self.cutoff_phase = (self.cutoff_phase + CUTOFF_RATE * seconds).fract();
+        self.drift_phase = (self.drift_phase + DRIFT_RATE * seconds).fract();
+    }
+
+    // Cutoff offset in octaves, shared by every voice
+    pub fn cutoff_octaves(&self) -> f32 {
+        (self.cutoff_phase * 2.0 * PI).sin() * CUTOFF_DEPTH_OCTAVES * self.amount
+    }
+
+    // Pitch drift in cents for the voice at `index`
+    pub fn drift_cents(&self, index: usize) -> f32 {
+        let phase = self.drift_phase + index as f32 * DRIFT_SPREAD;
+        (phase * 2.0 * PI).sin() * DRIFT_DEPTH_CENTS * self.amount
+    }
+}
@@ -0,0 +1,64 @@
+use crate::sequencer::{NoteSource, SequencerEvent};
+
+// Note events scheduled a number of samples ahead, for anything that needs
+// to place notes slightly in the future (strums, humanized timing). Delays
+// are fractional and each event lands on the first sample at or after its
+// exact time, like the sequencer's.
+pub struct NoteQueue {
+    pending: Vec<(f64, SequencerEvent)>,
+}
+
+impl NoteQueue {
+    pub fn new() -> Self {
+        NoteQueue {
+            pending: Vec::with_capacity(64),
+        }
+    }
+
+    pub fn schedule(&mut self, delay_samples: f64, event: SequencerEvent) {
+        self.pending.push((delay_samples.max(0.0), event));
+    }
+
+    // Events still waiting to fire
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    // Drops note-ons for `note` that haven't fired yet, so releasing a
+    // chord mid-strum can't leave late notes hanging
+    pub fn cancel_note_on(&mut self, note: u8) {
+        self.pending.retain(|(_, event)| !matches!(event, SequencerEvent::NoteOn { note: n, .. } if *n == note));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+impl NoteSource for NoteQueue {
+    fn samples_until_next_event(&self) -> Option<usize> {
+        self.pending
+            .iter()
+            .map(|&(samples, _)| samples)
+            .reduce(f64::min)
+            .map(|next| next.max(0.0).ceil() as usize)
+    }
+
+    fn advance(&mut self, samples: usize) {
+        for (remaining, _) in &mut self.pending {
+            *remaining -= samples as f64;
+        }
+    }
+
+    fn take_due_events(&mut self, events: &mut Vec<SequencerEvent>) {
+        // Keep scheduling order for events due on the same sample
+        let mut i = 0;
+        while i < self.pending.len() {
+            if self.pending[i].0 <= 0.0 {
+                events.push(self.pending.remove(i).1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
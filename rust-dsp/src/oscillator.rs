@@ -6,8 +6,38 @@ pub enum Waveform {
     Triangle = 3,
     FM = 4,
     Piano = 5,
+    Organ = 6,
+    ElectricPiano = 7,
+    // Crossfade of four other waveforms by an XY position
+    Vector = 8,
 }
 
+// Tonewheel drawbars: 16', 5 1/3', 8', 4', 2 2/3', 2', 1 3/5', 1 1/3', 1'
+// as multiples of the played pitch, each set to a level from 0 to 8
+pub const DRAWBAR_COUNT: usize = 9;
+const DRAWBAR_RATIOS: [f32; DRAWBAR_COUNT] = [0.5, 1.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0];
+pub const DEFAULT_DRAWBARS: [f32; DRAWBAR_COUNT] = [8.0, 8.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+
+// Vector corners: A at (0, 0), B at (1, 0), C at (0, 1), D at (1, 1)
+pub const VECTOR_SOURCES: usize = 4;
+pub const DEFAULT_VECTOR_SOURCES: [u8; VECTOR_SOURCES] = [0, 1, 2, 3];
+
+fn waveform_from_id(id: u8) -> Waveform {
+    match id {
+        0 => Waveform::Sine,
+        1 => Waveform::Sawtooth,
+        2 => Waveform::Square,
+        3 => Waveform::Triangle,
+        4 => Waveform::FM,
+        5 => Waveform::Piano,
+        6 => Waveform::Organ,
+        7 => Waveform::ElectricPiano,
+        8 => Waveform::Vector,
+        _ => Waveform::Sine,
+    }
+}
+
+#[derive(Clone)]
 pub struct Oscillator {
     phase: f32,
     phase_increment: f32,
@@ -15,18 +45,33 @@ pub struct Oscillator {
     sample_rate: f32,
     waveform: Waveform,
     detune: f32,
+    drawbars: [f32; DRAWBAR_COUNT],
+    organ_phase: f32,
+    bark: f32,
+    tine: f32,
+    vector_sources: [Waveform; VECTOR_SOURCES],
+    vector_weights: [f32; VECTOR_SOURCES],
 }
 
 impl Oscillator {
     pub fn new(sample_rate: f32) -> Self {
-        Oscillator {
+        let mut oscillator = Oscillator {
             phase: 0.0,
             phase_increment: 0.0,
             frequency: 440.0,
             sample_rate,
             waveform: Waveform::Sine,
             detune: 0.0,
-        }
+            drawbars: [0.0; DRAWBAR_COUNT],
+            organ_phase: 0.0,
+            bark: 0.0,
+            tine: 0.0,
+            vector_sources: [Waveform::Sine; VECTOR_SOURCES],
+            vector_weights: [1.0, 0.0, 0.0, 0.0],
+        };
+        oscillator.set_drawbars(&DEFAULT_DRAWBARS);
+        oscillator.set_vector_sources(&DEFAULT_VECTOR_SOURCES);
+        oscillator
     }
 
     pub fn set_frequency(&mut self, freq: f32) {
@@ -36,15 +81,34 @@ impl Oscillator {
     }
 
     pub fn set_waveform(&mut self, waveform: u8) {
-        self.waveform = match waveform {
-            0 => Waveform::Sine,
-            1 => Waveform::Sawtooth,
-            2 => Waveform::Square,
-            3 => Waveform::Triangle,
-            4 => Waveform::FM,
-            5 => Waveform::Piano,
-            _ => Waveform::Sine,
-        };
+        self.waveform = waveform_from_id(waveform);
+    }
+
+    // Waveform ids for the four vector corners; a corner can't itself be
+    // the vector waveform (it plays a sine instead)
+    pub fn set_vector_sources(&mut self, sources: &[u8; VECTOR_SOURCES]) {
+        for (source, &id) in self.vector_sources.iter_mut().zip(sources) {
+            *source = match waveform_from_id(id) {
+                Waveform::Vector => Waveform::Sine,
+                waveform => waveform,
+            };
+        }
+    }
+
+    // XY position 0-1, bilinear between the corners
+    pub fn set_vector_position(&mut self, x: f32, y: f32) {
+        let (x, y) = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+        self.vector_weights = [(1.0 - x) * (1.0 - y), x * (1.0 - y), (1.0 - x) * y, x * y];
+    }
+
+    // Levels 0-8 per drawbar. Stored pre-scaled so a full registration
+    // peaks around unity like the other waveforms.
+    pub fn set_drawbars(&mut self, levels: &[f32; DRAWBAR_COUNT]) {
+        let total: f32 = levels.iter().map(|level| level.clamp(0.0, 8.0)).sum();
+        let scale = 1.0 / total.max(8.0);
+        for (gain, level) in self.drawbars.iter_mut().zip(levels) {
+            *gain = level.clamp(0.0, 8.0) * scale;
+        }
     }
 
     pub fn set_detune(&mut self, cents: f32) {
@@ -54,20 +118,43 @@ impl Oscillator {
     }
 
     pub fn process(&mut self) -> f32 {
-        let output = match self.waveform {
+        let output = self.wave(self.waveform);
+
+        self.phase += self.phase_increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+        self.organ_phase += self.phase_increment;
+        if self.organ_phase >= 2.0 {
+            self.organ_phase -= 2.0;
+        }
+
+        output
+    }
+
+    fn wave(&self, waveform: Waveform) -> f32 {
+        match waveform {
             Waveform::Sine => self.sine(),
             Waveform::Sawtooth => self.sawtooth(),
             Waveform::Square => self.square(),
             Waveform::Triangle => self.triangle(),
             Waveform::FM => self.fm(),
             Waveform::Piano => self.piano(),
-        };
-
-        self.phase += self.phase_increment;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
+            Waveform::Organ => self.organ(),
+            Waveform::ElectricPiano => self.electric_piano(),
+            Waveform::Vector => self.vector(),
         }
+    }
 
+    // Every corner runs off the one phase, so they stay in tune and in
+    // phase however the mix moves
+    fn vector(&self) -> f32 {
+        let mut output = 0.0;
+        for (&source, &weight) in self.vector_sources.iter().zip(&self.vector_weights) {
+            if weight > 0.0 {
+                output += self.wave(source) * weight;
+            }
+        }
         output
     }
 
@@ -127,6 +214,42 @@ impl Oscillator {
         output / 1.875 // Normalize
     }
 
+    fn organ(&self) -> f32 {
+        // Sine tonewheels summed at the drawbar footages. The 16' and 5 1/3'
+        // wheels only complete their cycles over two periods of the played
+        // pitch, hence the separate two-period phase.
+        let angle = self.organ_phase * 2.0 * std::f32::consts::PI;
+        let mut output = 0.0;
+        for (gain, ratio) in self.drawbars.iter().zip(DRAWBAR_RATIOS) {
+            if *gain > 0.0 {
+                output += gain * (angle * ratio).sin();
+            }
+        }
+        output
+    }
+
+    // Per-sample modulation for the electric piano: `bark` is the FM index
+    // of the tine pair, `tine` the level of the metallic attack partial
+    pub fn set_ep_modulation(&mut self, bark: f32, tine: f32) {
+        self.bark = bark;
+        self.tine = tine;
+    }
+
+    fn electric_piano(&self) -> f32 {
+        // Tine/tone-bar FM pair at 1:1 - more index means more growl - plus
+        // a short high partial for the hammer strike
+        let carrier = self.phase * 2.0 * std::f32::consts::PI;
+        let body = (carrier + self.bark * carrier.sin()).sin();
+        let strike = (carrier * 14.0).sin() * self.tine;
+        (body + strike) / (1.0 + self.tine)
+    }
+
+    // Sine at a whole-number multiple of the current pitch, phase-locked to
+    // this oscillator (used for organ percussion)
+    pub fn harmonic(&self, multiple: f32) -> f32 {
+        (self.phase * multiple * 2.0 * std::f32::consts::PI).sin()
+    }
+
     fn poly_blep(&self, t: f32) -> f32 {
         let dt = self.phase_increment;
         if t < dt {
@@ -140,8 +263,9 @@ impl Oscillator {
         0.0
     }
 
-    pub fn reset_phase(&mut self) {
-        self.phase = 0.0;
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.fract();
+        self.organ_phase = self.phase;
     }
 }
 
@@ -0,0 +1,174 @@
+// 2x/4x oversampling around a memoryless nonlinearity (tanh drive,
+// saturation, clipping) so the harmonics it adds above Nyquist are filtered
+// out instead of folding back as aliasing. Each 2x stage is a linear-phase
+// half-band FIR, run polyphase so only its non-zero taps are computed; 4x
+// cascades two stages.
+//
+// For a half-band filter h of length 4K+3 every other tap away from the
+// centre is zero and the centre is 1/2, so with e = even taps:
+//   up:   y[2n] = 2 * sum_j e[j] x[n-j],      y[2n+1] = x[n-K]
+//   down: y[n]  = sum_j e[j] v[2n-2j] + v[2(n-K)-1] / 2
+
+use std::sync::OnceLock;
+
+const K: usize = 11;
+// Non-zero taps either side of the centre
+const EVEN_TAPS: usize = 2 * K + 2;
+const KAISER_BETA: f32 = 8.0;
+
+fn even_taps() -> &'static [f32; EVEN_TAPS] {
+    static TAPS: OnceLock<[f32; EVEN_TAPS]> = OnceLock::new();
+    TAPS.get_or_init(|| {
+        let centre = (2 * K + 1) as f32;
+        let half_width = centre + 1.0;
+        let mut taps = [0.0; EVEN_TAPS];
+        for (j, tap) in taps.iter_mut().enumerate() {
+            let t = 2.0 * j as f32 - centre;
+            let x = std::f32::consts::PI * t / 2.0;
+            let window = bessel_i0(KAISER_BETA * (1.0 - (t / half_width).powi(2)).sqrt()) / bessel_i0(KAISER_BETA);
+            *tap = 0.5 * x.sin() / x * window;
+        }
+        // The even taps of a half-band filter sum to 1/2 at DC
+        let sum: f32 = taps.iter().sum();
+        taps.map(|tap| tap * 0.5 / sum)
+    })
+}
+
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..20 {
+        term *= (x / (2.0 * k as f32)).powi(2);
+        sum += term;
+    }
+    sum
+}
+
+#[derive(Clone, Default)]
+struct HalfBand {
+    // Newest first
+    up: [f32; EVEN_TAPS],
+    down_even: [f32; EVEN_TAPS],
+    down_odd: [f32; K + 1],
+}
+
+impl HalfBand {
+    fn upsample(&mut self, x: f32) -> [f32; 2] {
+        self.up.copy_within(..EVEN_TAPS - 1, 1);
+        self.up[0] = x;
+        let taps = even_taps();
+        let even: f32 = self.up.iter().zip(taps).map(|(x, t)| x * t).sum();
+        [2.0 * even, self.up[K]]
+    }
+
+    fn downsample(&mut self, pair: [f32; 2]) -> f32 {
+        self.down_even.copy_within(..EVEN_TAPS - 1, 1);
+        self.down_even[0] = pair[0];
+        let taps = even_taps();
+        let even: f32 = self.down_even.iter().zip(taps).map(|(x, t)| x * t).sum();
+        // v[2(n-K)-1] is the odd sample of K+1 pairs ago
+        let odd = self.down_odd[K];
+        self.down_odd.copy_within(..K, 1);
+        self.down_odd[0] = pair[1];
+        even + 0.5 * odd
+    }
+}
+
+// 1, 2 or 4, rounding anything else down
+pub fn supported_factor(factor: u32) -> u32 {
+    match factor {
+        0 | 1 => 1,
+        2 | 3 => 2,
+        _ => 4,
+    }
+}
+
+// Length of the crossfade when the factor changes, in base-rate samples
+const FADE_SAMPLES: usize = 256;
+
+#[derive(Clone)]
+struct Stages {
+    factor: usize,
+    outer: HalfBand,
+    inner: [HalfBand; 2],
+}
+
+impl Stages {
+    fn with_factor(factor: usize) -> Self {
+        Stages { factor, outer: HalfBand::default(), inner: Default::default() }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32, shape: &impl Fn(f32) -> f32) -> f32 {
+        match self.factor {
+            1 => shape(x),
+            2 => {
+                let [a, b] = self.outer.upsample(x);
+                self.outer.downsample([shape(a), shape(b)])
+            }
+            _ => {
+                let [a, b] = self.outer.upsample(x);
+                let [a0, a1] = self.inner[0].upsample(a);
+                let [b0, b1] = self.inner[0].upsample(b);
+                let a = self.inner[1].downsample([shape(a0), shape(a1)]);
+                let b = self.inner[1].downsample([shape(b0), shape(b1)]);
+                self.outer.downsample([a, b])
+            }
+        }
+    }
+}
+
+// A change of factor crossfades from the old stages to the new over
+// FADE_SAMPLES, so it can happen while notes sound
+#[derive(Clone)]
+pub struct Oversampler {
+    current: Stages,
+    previous: Stages,
+    // Samples of crossfade still to run
+    fade: usize,
+}
+
+impl Oversampler {
+    pub fn new() -> Self {
+        Oversampler { current: Stages::with_factor(1), previous: Stages::with_factor(1), fade: 0 }
+    }
+
+    // 1 (off), 2 or 4, as supported_factor
+    pub fn set_factor(&mut self, factor: u32) {
+        let factor = supported_factor(factor) as usize;
+        if factor != self.current.factor {
+            self.previous = std::mem::replace(&mut self.current, Stages::with_factor(factor));
+            self.fade = FADE_SAMPLES;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.current = Stages::with_factor(self.current.factor);
+        self.fade = 0;
+    }
+
+    // Delay the filters add, in samples at the base rate: each 2x stage's
+    // up and down filters delay by 2K+1 samples of its own rate between them
+    pub fn latency(&self) -> f32 {
+        let stage = (2 * K + 1) as f32;
+        match self.current.factor {
+            1 => 0.0,
+            2 => stage,
+            _ => stage * 1.5,
+        }
+    }
+
+    // Runs `shape` at the oversampled rate. Adds a few samples of delay
+    // when on.
+    #[inline]
+    pub fn process(&mut self, x: f32, shape: impl Fn(f32) -> f32) -> f32 {
+        let y = self.current.process(x, &shape);
+        if self.fade == 0 {
+            return y;
+        }
+        let old = self.previous.process(x, &shape);
+        self.fade -= 1;
+        let t = self.fade as f32 / FADE_SAMPLES as f32;
+        y + (old - y) * t
+    }
+}
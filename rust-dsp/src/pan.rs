@@ -0,0 +1,114 @@
+use std::f32::consts::FRAC_PI_4;
+
+// Pan law shared by every panning stage. Gains are
+// normalized so a centered source passes at unity in both channels; the law
+// sets how much the near side rises as the source moves outward:
+//   -3 dB   equal power, constant loudness across the field in stereo
+//   -6 dB   linear, constant level when folded to mono
+//   -4.5 dB compromise between the two
+#[derive(Clone, Copy, PartialEq)]
+pub enum PanLaw {
+    EqualPower = 0,
+    Compromise = 1,
+    Linear = 2,
+}
+
+impl PanLaw {
+    pub fn from_u8(law: u8) -> Self {
+        match law {
+            0 => PanLaw::EqualPower,
+            1 => PanLaw::Compromise,
+            2 => PanLaw::Linear,
+            _ => PanLaw::EqualPower,
+        }
+    }
+
+    // (left, right) gains for pan in -1 (left) .. 1 (right)
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let pan = pan.clamp(-1.0, 1.0);
+        let left = self.curve(pan) / self.curve(0.0);
+        let right = self.curve(-pan) / self.curve(0.0);
+        (left, right)
+    }
+
+    // Gain of the left channel for a given pan; the right side mirrors it
+    fn curve(self, pan: f32) -> f32 {
+        let linear = (1.0 - pan) * 0.5;
+        // cos(pi / 2) rounds to just below zero, which the compromise
+        // law's square root would turn into NaN at the hard edge
+        let equal_power = ((pan + 1.0) * FRAC_PI_4).cos().max(0.0);
+        match self {
+            PanLaw::EqualPower => equal_power,
+            PanLaw::Compromise => (linear * equal_power).sqrt(),
+            PanLaw::Linear => linear,
+        }
+    }
+}
+
+// Entries in the fold-down gain table, spread evenly over the balance
+const FOLD_POINTS: usize = 33;
+// Time the channel level followers take to settle on a new balance
+const FOLD_FOLLOW_SECONDS: f32 = 0.01;
+
+// Folds stereo to mono at the level a source had before it was panned.
+// Averaging the two sides only does that for centered sources; with any law
+// but linear the near side rises as a source moves out, so a hard-panned
+// source would fold down 3 dB (equal power) or 1.5 dB (compromise) quiet.
+// Followers on the two channel levels estimate where the material sits and
+// the law gives the gain that restores it.
+#[derive(Clone)]
+pub struct Downmix {
+    // 1 / (left gain + right gain), indexed by balance -1..1
+    gains: [f32; FOLD_POINTS],
+    follow: f32,
+    left_level: f32,
+    right_level: f32,
+}
+
+impl Downmix {
+    pub fn new(sample_rate: f32, law: PanLaw) -> Self {
+        let mut downmix = Downmix {
+            gains: [0.5; FOLD_POINTS],
+            follow: 1.0 - (-1.0 / (FOLD_FOLLOW_SECONDS * sample_rate)).exp(),
+            left_level: 0.0,
+            right_level: 0.0,
+        };
+        downmix.set_pan_law(law);
+        downmix
+    }
+
+    pub fn set_pan_law(&mut self, law: PanLaw) {
+        let balance = |pan: f32| {
+            let (left, right) = law.gains(pan);
+            (right - left) / (right + left)
+        };
+        for (i, gain) in self.gains.iter_mut().enumerate() {
+            // Balance rises with pan under every law, so bisect for the pan
+            // that lands on this entry's balance
+            let target = i as f32 / (FOLD_POINTS - 1) as f32 * 2.0 - 1.0;
+            let (mut low, mut high) = (-1.0, 1.0);
+            for _ in 0..24 {
+                let mid = (low + high) * 0.5;
+                if balance(mid) < target {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            let (left, right) = law.gains((low + high) * 0.5);
+            *gain = 1.0 / (left + right);
+        }
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> f32 {
+        self.left_level += (left.abs() - self.left_level) * self.follow;
+        self.right_level += (right.abs() - self.right_level) * self.follow;
+        let total = self.left_level + self.right_level;
+        let balance = if total > 1e-9 { (self.right_level - self.left_level) / total } else { 0.0 };
+        let position = (balance + 1.0) * 0.5 * (FOLD_POINTS - 1) as f32;
+        let index = (position as usize).min(FOLD_POINTS - 2);
+        let frac = position - index as f32;
+        let gain = self.gains[index] + (self.gains[index + 1] - self.gains[index]) * frac;
+        (left + right) * gain
+    }
+}
@@ -0,0 +1,242 @@
+use crate::patch::Patch;
+
+// Numeric ids for every automatable parameter, so one set_param/get_param
+// pair covers them all. Patch parameters use their index in
+// `Patch::values`, which makes ids stable and shared with journal patch
+// changes and SysEx dumps; the gaps are effect order slots, which aren't
+// parameters. Track mixer parameters live above TRACK_PARAM_BASE.
+// Enabled flags and switches read as 0/1 and count as on from 0.5.
+
+pub const TRACK_PARAM_BASE: u32 = 1000;
+
+// Patch field conversions to and from a parameter value
+trait ParamValue {
+    fn to_param(self) -> f32;
+    fn from_param(value: f32) -> Self;
+}
+
+impl ParamValue for f32 {
+    fn to_param(self) -> f32 {
+        self
+    }
+
+    fn from_param(value: f32) -> Self {
+        value
+    }
+}
+
+impl ParamValue for u8 {
+    fn to_param(self) -> f32 {
+        self as f32
+    }
+
+    fn from_param(value: f32) -> Self {
+        value as u8
+    }
+}
+
+impl ParamValue for bool {
+    fn to_param(self) -> f32 {
+        if self { 1.0 } else { 0.0 }
+    }
+
+    fn from_param(value: f32) -> Self {
+        value >= 0.5
+    }
+}
+
+macro_rules! params {
+    (
+        patch { $($name:ident = $id:literal => ($($field:tt)+),)* }
+        track { $($track_name:ident = $track_id:expr,)* }
+    ) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #[repr(u32)]
+        pub enum ParamId {
+            $($name = $id,)*
+            $($track_name = $track_id,)*
+        }
+
+        impl ParamId {
+            pub fn from_u32(id: u32) -> Option<ParamId> {
+                match id {
+                    $($id => Some(ParamId::$name),)*
+                    $(id if id == $track_id => Some(ParamId::$track_name),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl Patch {
+            // None for track parameters, which aren't part of the patch
+            pub fn param(&self, param: ParamId) -> Option<f32> {
+                match param {
+                    $(ParamId::$name => Some(self.$($field)+.to_param()),)*
+                    _ => None,
+                }
+            }
+
+            pub fn set_param(&mut self, param: ParamId, value: f32) {
+                match param {
+                    $(ParamId::$name => self.$($field)+ = ParamValue::from_param(value),)*
+                    _ => {}
+                }
+            }
+        }
+    };
+}
+
+params! {
+    patch {
+        Waveform = 0 => (waveform),
+        Attack = 1 => (attack),
+        Decay = 2 => (decay),
+        Sustain = 3 => (sustain),
+        Release = 4 => (release),
+        LfoRate = 5 => (lfo_rate),
+        LfoDepth = 6 => (lfo_depth),
+        LfoWaveform = 7 => (lfo_waveform),
+        DetuneCents = 8 => (detune_cents),
+        GlideTimeMs = 9 => (glide_time_ms),
+        DelayEnabled = 10 => (delay_enabled),
+        DelayTimeMs = 11 => (delay_time_ms),
+        DelayFeedback = 12 => (delay_feedback),
+        DelayMix = 13 => (delay_mix),
+        ReverbEnabled = 14 => (reverb_enabled),
+        ReverbRoomSize = 15 => (reverb_room_size),
+        ReverbDamping = 16 => (reverb_damping),
+        TremoloEnabled = 17 => (tremolo_enabled),
+        TremoloRate = 18 => (tremolo_rate),
+        TremoloDepth = 19 => (tremolo_depth),
+        FlangerEnabled = 20 => (flanger_enabled),
+        FlangerRate = 21 => (flanger_rate),
+        FlangerDepth = 22 => (flanger_depth),
+        FlangerFeedback = 23 => (flanger_feedback),
+        FlangerMix = 24 => (flanger_mix),
+        BitcrusherEnabled = 25 => (bitcrusher_enabled),
+        BitcrusherBits = 26 => (bitcrusher_bits),
+        BitcrusherDownsample = 27 => (bitcrusher_downsample),
+        BitcrusherMix = 28 => (bitcrusher_mix),
+        EqEnabled = 29 => (eq_enabled),
+        EqLowGainDb = 30 => (eq_low_gain_db),
+        EqLowFreq = 31 => (eq_low_freq),
+        EqMidGainDb = 32 => (eq_mid_gain_db),
+        EqMidFreq = 33 => (eq_mid_freq),
+        EqHighGainDb = 34 => (eq_high_gain_db),
+        EqHighFreq = 35 => (eq_high_freq),
+        DelayMode = 36 => (delay_mode),
+        FilterEnabled = 37 => (filter_enabled),
+        FilterCutoff = 38 => (filter_cutoff),
+        FilterResonance = 39 => (filter_resonance),
+        FilterMode = 40 => (filter_mode),
+        UnisonVoices = 41 => (unison_voices),
+        UnisonDetune = 42 => (unison_detune),
+        UnisonFilterSpread = 43 => (unison_filter_spread),
+        ReverbType = 44 => (reverb_type),
+        ReverbPreDelayMs = 45 => (reverb_pre_delay_ms),
+        ReverbDiffusion = 46 => (reverb_diffusion),
+        ReverbModulation = 47 => (reverb_modulation),
+        RumbleFilterEnabled = 48 => (rumble_filter_enabled),
+        RumbleFilterFreq = 49 => (rumble_filter_freq),
+        ReverbMix = 56 => (reverb_mix),
+        TremoloMix = 57 => (tremolo_mix),
+        ProtectLowestNote = 58 => (protect_lowest_note),
+        AutoWahEnabled = 59 => (auto_wah_enabled),
+        AutoWahSensitivity = 60 => (auto_wah_sensitivity),
+        AutoWahRange = 61 => (auto_wah_range),
+        AutoWahQ = 62 => (auto_wah_q),
+        AutoWahMix = 63 => (auto_wah_mix),
+        RotaryEnabled = 65 => (rotary_enabled),
+        RotaryFast = 66 => (rotary_fast),
+        RotaryAcceleration = 67 => (rotary_acceleration),
+        RotaryBalance = 68 => (rotary_balance),
+        RotarySpread = 69 => (rotary_spread),
+        RotaryMix = 70 => (rotary_mix),
+        OrganDrawbar1 = 72 => (organ_drawbars[0]),
+        OrganDrawbar2 = 73 => (organ_drawbars[1]),
+        OrganDrawbar3 = 74 => (organ_drawbars[2]),
+        OrganDrawbar4 = 75 => (organ_drawbars[3]),
+        OrganDrawbar5 = 76 => (organ_drawbars[4]),
+        OrganDrawbar6 = 77 => (organ_drawbars[5]),
+        OrganDrawbar7 = 78 => (organ_drawbars[6]),
+        OrganDrawbar8 = 79 => (organ_drawbars[7]),
+        OrganDrawbar9 = 80 => (organ_drawbars[8]),
+        OrganPercussion = 81 => (organ_percussion),
+        OrganPercussionHarmonic = 82 => (organ_percussion_harmonic),
+        OrganPercussionFast = 83 => (organ_percussion_fast),
+        OrganPercussionLevel = 84 => (organ_percussion_level),
+        OrganKeyClick = 85 => (organ_key_click),
+        ReverbShimmer = 86 => (reverb_shimmer),
+        StringMachineEnabled = 87 => (string_machine_enabled),
+        StringAttack = 88 => (string_attack),
+        StringRelease = 89 => (string_release),
+        StringBrightness = 90 => (string_brightness),
+        StringEnsemble = 91 => (string_ensemble),
+        FilterEnvAttack = 92 => (filter_env_attack),
+        FilterEnvDecay = 93 => (filter_env_decay),
+        FilterEnvSustain = 94 => (filter_env_sustain),
+        FilterEnvRelease = 95 => (filter_env_release),
+        FilterEnvAmount = 96 => (filter_env_amount),
+        Drive = 97 => (drive),
+        PitchShifterEnabled = 98 => (pitch_shifter_enabled),
+        PitchShifterSemitones = 99 => (pitch_shifter_semitones),
+        PitchShifterMix = 100 => (pitch_shifter_mix),
+        MotionAmount = 102 => (motion_amount),
+        MonoMelody = 103 => (mono_melody),
+        GhostThreshold = 104 => (ghost_threshold),
+        GhostTone = 105 => (ghost_tone),
+        GhostLength = 106 => (ghost_length),
+        GlideMode = 107 => (glide_mode),
+        ReleaseVelocityAmount = 108 => (release_velocity_amount),
+        PressureVibratoCents = 109 => (pressure_vibrato_cents),
+        PressureCutoffOctaves = 110 => (pressure_cutoff_octaves),
+        PressureVolume = 111 => (pressure_volume),
+        EnvRetriggerMode = 112 => (env_retrigger_mode),
+        RandomRate = 113 => (random_rate),
+        RandomCutoffOctaves = 114 => (random_cutoff_octaves),
+        RandomPitchCents = 115 => (random_pitch_cents),
+        RandomFxMix = 116 => (random_fx_mix),
+        VectorX = 117 => (vector_x),
+        VectorY = 118 => (vector_y),
+        VectorSourceA = 119 => (vector_sources[0]),
+        VectorSourceB = 120 => (vector_sources[1]),
+        VectorSourceC = 121 => (vector_sources[2]),
+        VectorSourceD = 122 => (vector_sources[3]),
+        VectorLfoRate = 123 => (vector_lfo_rate),
+        VectorLfoDepth = 124 => (vector_lfo_depth),
+        VectorEnvX = 125 => (vector_env_x),
+        VectorEnvY = 126 => (vector_env_y),
+        LfoMode = 127 => (lfo_mode),
+        DelayLowCut = 128 => (delay_low_cut),
+        DelayHighCut = 129 => (delay_high_cut),
+        ReverbLowCut = 130 => (reverb_low_cut),
+        DelayRightTimeMs = 131 => (delay_right_time_ms),
+        DelayCrossFeedback = 132 => (delay_cross_feedback),
+        DelayModDepthMs = 133 => (delay_mod_depth_ms),
+        DelayModRate = 134 => (delay_mod_rate),
+        VelocityCurve = 135 => (velocity_curve),
+        OutputGainDb = 136 => (output_gain_db),
+        VoiceNormalize = 137 => (voice_normalize),
+        FilterPoles = 138 => (filter_poles),
+        PhaseMode = 139 => (phase_mode),
+        VelocityRandom = 140 => (velocity_random),
+        PitchEnvAttack = 141 => (pitch_env_attack),
+        PitchEnvDecay = 142 => (pitch_env_decay),
+        PitchEnvAmount = 143 => (pitch_env_amount),
+        LfoDelayTime = 144 => (lfo_delay_time),
+        LfoReverbSize = 145 => (lfo_reverb_size),
+        LfoFlangerRate = 146 => (lfo_flanger_rate),
+        LfoTremoloDepth = 147 => (lfo_tremolo_depth),
+        TremoloWaveform = 148 => (tremolo_waveform),
+        TremoloAutoPan = 149 => (tremolo_auto_pan),
+        FrequencyShifterEnabled = 150 => (frequency_shifter_enabled),
+        FrequencyShifterHz = 151 => (frequency_shifter_hz),
+        FrequencyShifterMix = 152 => (frequency_shifter_mix),
+    }
+    track {
+        Volume = TRACK_PARAM_BASE,
+        Pan = TRACK_PARAM_BASE + 1,
+        DelaySend = TRACK_PARAM_BASE + 2,
+        ReverbSend = TRACK_PARAM_BASE + 3,
+    }
+}
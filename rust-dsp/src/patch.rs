@@ -0,0 +1,726 @@
+use crate::effects::{effect_order, DEFAULT_EFFECT_ORDER, EFFECT_COUNT};
+use crate::oscillator::{DEFAULT_DRAWBARS, DEFAULT_VECTOR_SOURCES, DRAWBAR_COUNT, VECTOR_SOURCES};
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Complete parameter set of one engine. The engine keeps this in sync with
+// every setter so the sound can be dumped, reloaded and exchanged.
+// In JSON, fields are named as here and missing ones take their defaults.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Patch {
+    pub waveform: u8,
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub lfo_rate: f32,
+    pub lfo_depth: f32,
+    pub lfo_waveform: u8,
+    pub detune_cents: f32,
+    pub glide_time_ms: f32,
+    pub delay_enabled: bool,
+    pub delay_time_ms: f32,
+    pub delay_feedback: f32,
+    pub delay_mix: f32,
+    pub reverb_enabled: bool,
+    pub reverb_room_size: f32,
+    pub reverb_damping: f32,
+    pub tremolo_enabled: bool,
+    pub tremolo_rate: f32,
+    pub tremolo_depth: f32,
+    pub flanger_enabled: bool,
+    pub flanger_rate: f32,
+    pub flanger_depth: f32,
+    pub flanger_feedback: f32,
+    pub flanger_mix: f32,
+    pub bitcrusher_enabled: bool,
+    pub bitcrusher_bits: f32,
+    pub bitcrusher_downsample: f32,
+    pub bitcrusher_mix: f32,
+    pub eq_enabled: bool,
+    pub eq_low_gain_db: f32,
+    pub eq_low_freq: f32,
+    pub eq_mid_gain_db: f32,
+    pub eq_mid_freq: f32,
+    pub eq_high_gain_db: f32,
+    pub eq_high_freq: f32,
+    pub delay_mode: u8,
+    pub filter_enabled: bool,
+    pub filter_cutoff: f32,
+    pub filter_resonance: f32,
+    pub filter_mode: u8,
+    pub unison_voices: u8,
+    pub unison_detune: f32,
+    pub unison_filter_spread: f32,
+    pub reverb_type: u8,
+    pub reverb_pre_delay_ms: f32,
+    pub reverb_diffusion: f32,
+    pub reverb_modulation: f32,
+    pub rumble_filter_enabled: bool,
+    pub rumble_filter_freq: f32,
+    #[serde(deserialize_with = "deserialize_effect_order")]
+    pub effect_order: [u8; EFFECT_COUNT],
+    pub reverb_mix: f32,
+    pub tremolo_mix: f32,
+    pub protect_lowest_note: bool,
+    pub auto_wah_enabled: bool,
+    pub auto_wah_sensitivity: f32,
+    pub auto_wah_range: f32,
+    pub auto_wah_q: f32,
+    pub auto_wah_mix: f32,
+    pub rotary_enabled: bool,
+    pub rotary_fast: bool,
+    pub rotary_acceleration: f32,
+    pub rotary_balance: f32,
+    pub rotary_spread: f32,
+    pub rotary_mix: f32,
+    pub organ_drawbars: [f32; DRAWBAR_COUNT],
+    pub organ_percussion: bool,
+    pub organ_percussion_harmonic: u8,
+    pub organ_percussion_fast: bool,
+    pub organ_percussion_level: f32,
+    pub organ_key_click: f32,
+    pub reverb_shimmer: f32,
+    pub string_machine_enabled: bool,
+    pub string_attack: f32,
+    pub string_release: f32,
+    pub string_brightness: f32,
+    pub string_ensemble: f32,
+    pub filter_env_attack: f32,
+    pub filter_env_decay: f32,
+    pub filter_env_sustain: f32,
+    pub filter_env_release: f32,
+    pub filter_env_amount: f32,
+    pub drive: f32,
+    pub pitch_shifter_enabled: bool,
+    pub pitch_shifter_semitones: f32,
+    pub pitch_shifter_mix: f32,
+    pub motion_amount: f32,
+    pub mono_melody: bool,
+    pub ghost_threshold: f32,
+    pub ghost_tone: f32,
+    pub ghost_length: f32,
+    // GLIDE_LEGATO | GLIDE_CONSTANT_RATE flags
+    pub glide_mode: u8,
+    // 0-1: how far release velocity shortens (fast) or lengthens (slow)
+    // the release
+    pub release_velocity_amount: f32,
+    // Channel pressure depths: vibrato cents, cutoff octaves, volume 0-1
+    pub pressure_vibrato_cents: f32,
+    pub pressure_cutoff_octaves: f32,
+    pub pressure_volume: f32,
+    // RETRIGGER_FULL, RETRIGGER_SOFT or RETRIGGER_LEGATO
+    pub env_retrigger_mode: u8,
+    // Smooth random modulation: rate in Hz, depths in cutoff octaves, pitch
+    // cents and delay/reverb mix
+    pub random_rate: f32,
+    pub random_cutoff_octaves: f32,
+    pub random_pitch_cents: f32,
+    pub random_fx_mix: f32,
+    // Vector waveform: XY position 0-1, the corner waveforms, a circling LFO
+    // (rate in Hz, radius 0-1) and the filter envelope's push on X and Y
+    pub vector_x: f32,
+    pub vector_y: f32,
+    pub vector_sources: [u8; VECTOR_SOURCES],
+    pub vector_lfo_rate: f32,
+    pub vector_lfo_depth: f32,
+    pub vector_env_x: f32,
+    pub vector_env_y: f32,
+    // LFO mode: 0 free running, 1 one-shot per note, 2 restarted by the
+    // transport
+    pub lfo_mode: u8,
+    // Filters on the delay repeats and the reverb input, in Hz; 20 and
+    // 20000 leave them open
+    pub delay_low_cut: f32,
+    pub delay_high_cut: f32,
+    pub reverb_low_cut: f32,
+    // Stereo delay: the right line's time (0 follows the left), cross
+    // feedback 0-1, and time wobble depth in ms and rate in Hz
+    pub delay_right_time_ms: f32,
+    pub delay_cross_feedback: f32,
+    pub delay_mod_depth_ms: f32,
+    pub delay_mod_rate: f32,
+    // Gain staging: velocity to level curve (-1 to 1, 0 linear), the
+    // engine's gain in dB ahead of its effects, and whether the voices are
+    // turned down as more of them sound so chords sit at a single note's
+    // level
+    pub velocity_curve: f32,
+    pub output_gain_db: f32,
+    pub voice_normalize: bool,
+    // Filter slope: 2 poles (12 dB/octave) or 4 (24 dB/octave)
+    pub filter_poles: u8,
+    // Oscillator start phase on fresh notes (PHASE_RESET, PHASE_FREE or
+    // PHASE_RANDOM) and how far each note's velocity is varied at random,
+    // 0-1 of its value
+    pub phase_mode: u8,
+    pub velocity_random: f32,
+    // Attack-decay pitch envelope: times in seconds, depth in semitones
+    pub pitch_env_attack: f32,
+    pub pitch_env_decay: f32,
+    pub pitch_env_amount: f32,
+    // LFO to effect amounts, 0-1: delay time (up to half either way),
+    // reverb size and tremolo depth (up to 0.5 either way) and flanger rate
+    // (up to two octaves either way)
+    pub lfo_delay_time: f32,
+    pub lfo_reverb_size: f32,
+    pub lfo_flanger_rate: f32,
+    pub lfo_tremolo_depth: f32,
+    // Tremolo shape: sine, triangle or square, and auto-pan (left and right
+    // half a cycle apart)
+    pub tremolo_waveform: u8,
+    pub tremolo_auto_pan: bool,
+    // Frequency shifter: shift in Hz (-2000 to 2000, negative down) and mix
+    pub frequency_shifter_enabled: bool,
+    pub frequency_shifter_hz: f32,
+    pub frequency_shifter_mix: f32,
+}
+
+impl Default for Patch {
+    fn default() -> Self {
+        Patch {
+            waveform: 0,
+            attack: 0.01,
+            decay: 0.3,
+            sustain: 0.7,
+            release: 0.5,
+            lfo_rate: 1.0,
+            lfo_depth: 0.0,
+            lfo_waveform: 0,
+            detune_cents: 0.0,
+            glide_time_ms: 0.0,
+            delay_enabled: false,
+            delay_time_ms: 0.5,
+            delay_feedback: 0.3,
+            delay_mix: 0.3,
+            reverb_enabled: false,
+            reverb_room_size: 0.5,
+            reverb_damping: 0.5,
+            tremolo_enabled: false,
+            tremolo_rate: 5.0,
+            tremolo_depth: 0.5,
+            flanger_enabled: false,
+            flanger_rate: 1.0,
+            flanger_depth: 5.0,
+            flanger_feedback: 0.3,
+            flanger_mix: 0.5,
+            bitcrusher_enabled: false,
+            bitcrusher_bits: 8.0,
+            bitcrusher_downsample: 4.0,
+            bitcrusher_mix: 1.0,
+            eq_enabled: false,
+            eq_low_gain_db: 0.0,
+            eq_low_freq: 200.0,
+            eq_mid_gain_db: 0.0,
+            eq_mid_freq: 1000.0,
+            eq_high_gain_db: 0.0,
+            eq_high_freq: 5000.0,
+            delay_mode: 0,
+            filter_enabled: false,
+            filter_cutoff: 20000.0,
+            filter_resonance: 0.0,
+            filter_mode: 0,
+            unison_voices: 1,
+            unison_detune: 0.0,
+            unison_filter_spread: 0.0,
+            reverb_type: 0,
+            reverb_pre_delay_ms: 20.0,
+            reverb_diffusion: 0.7,
+            reverb_modulation: 0.3,
+            rumble_filter_enabled: false,
+            rumble_filter_freq: 80.0,
+            effect_order: DEFAULT_EFFECT_ORDER,
+            reverb_mix: 0.2,
+            tremolo_mix: 1.0,
+            protect_lowest_note: false,
+            auto_wah_enabled: false,
+            auto_wah_sensitivity: 0.5,
+            auto_wah_range: 0.5,
+            auto_wah_q: 3.0,
+            auto_wah_mix: 1.0,
+            rotary_enabled: false,
+            rotary_fast: false,
+            rotary_acceleration: 1.0,
+            rotary_balance: 0.5,
+            rotary_spread: 0.8,
+            rotary_mix: 1.0,
+            organ_drawbars: DEFAULT_DRAWBARS,
+            organ_percussion: false,
+            organ_percussion_harmonic: 3,
+            organ_percussion_fast: true,
+            organ_percussion_level: 0.5,
+            organ_key_click: 0.3,
+            reverb_shimmer: 0.5,
+            string_machine_enabled: false,
+            string_attack: 0.6,
+            string_release: 1.2,
+            string_brightness: 0.6,
+            string_ensemble: 0.7,
+            filter_env_attack: 0.01,
+            filter_env_decay: 0.3,
+            filter_env_sustain: 0.0,
+            filter_env_release: 0.3,
+            filter_env_amount: 0.0,
+            drive: 0.0,
+            pitch_shifter_enabled: false,
+            pitch_shifter_semitones: 12.0,
+            pitch_shifter_mix: 0.5,
+            motion_amount: 0.0,
+            mono_melody: false,
+            ghost_threshold: 0.0,
+            ghost_tone: 2500.0,
+            ghost_length: 0.5,
+            glide_mode: 0,
+            release_velocity_amount: 0.0,
+            pressure_vibrato_cents: 0.0,
+            pressure_cutoff_octaves: 0.0,
+            pressure_volume: 0.0,
+            env_retrigger_mode: 0,
+            random_rate: 0.5,
+            random_cutoff_octaves: 0.0,
+            random_pitch_cents: 0.0,
+            random_fx_mix: 0.0,
+            vector_x: 0.0,
+            vector_y: 0.0,
+            vector_sources: DEFAULT_VECTOR_SOURCES,
+            vector_lfo_rate: 0.2,
+            vector_lfo_depth: 0.0,
+            vector_env_x: 0.0,
+            vector_env_y: 0.0,
+            lfo_mode: 0,
+            delay_low_cut: 20.0,
+            delay_high_cut: 20000.0,
+            reverb_low_cut: 20.0,
+            delay_right_time_ms: 0.0,
+            delay_cross_feedback: 0.0,
+            delay_mod_depth_ms: 0.0,
+            delay_mod_rate: 0.5,
+            velocity_curve: 0.0,
+            output_gain_db: 0.0,
+            voice_normalize: false,
+            filter_poles: 2,
+            phase_mode: 0,
+            velocity_random: 0.0,
+            pitch_env_attack: 0.001,
+            pitch_env_decay: 0.1,
+            pitch_env_amount: 0.0,
+            lfo_delay_time: 0.0,
+            lfo_reverb_size: 0.0,
+            lfo_flanger_rate: 0.0,
+            lfo_tremolo_depth: 0.0,
+            tremolo_waveform: 0,
+            tremolo_auto_pan: false,
+            frequency_shifter_enabled: false,
+            frequency_shifter_hz: 100.0,
+            frequency_shifter_mix: 0.5,
+        }
+    }
+}
+
+impl Patch {
+    // Flatten to a fixed-order value list. New parameters must only ever be
+    // appended so older dumps stay loadable.
+    pub fn values(&self) -> Vec<f32> {
+        vec![
+            self.waveform as f32,
+            self.attack,
+            self.decay,
+            self.sustain,
+            self.release,
+            self.lfo_rate,
+            self.lfo_depth,
+            self.lfo_waveform as f32,
+            self.detune_cents,
+            self.glide_time_ms,
+            bool_value(self.delay_enabled),
+            self.delay_time_ms,
+            self.delay_feedback,
+            self.delay_mix,
+            bool_value(self.reverb_enabled),
+            self.reverb_room_size,
+            self.reverb_damping,
+            bool_value(self.tremolo_enabled),
+            self.tremolo_rate,
+            self.tremolo_depth,
+            bool_value(self.flanger_enabled),
+            self.flanger_rate,
+            self.flanger_depth,
+            self.flanger_feedback,
+            self.flanger_mix,
+            bool_value(self.bitcrusher_enabled),
+            self.bitcrusher_bits,
+            self.bitcrusher_downsample,
+            self.bitcrusher_mix,
+            bool_value(self.eq_enabled),
+            self.eq_low_gain_db,
+            self.eq_low_freq,
+            self.eq_mid_gain_db,
+            self.eq_mid_freq,
+            self.eq_high_gain_db,
+            self.eq_high_freq,
+            self.delay_mode as f32,
+            bool_value(self.filter_enabled),
+            self.filter_cutoff,
+            self.filter_resonance,
+            self.filter_mode as f32,
+            self.unison_voices as f32,
+            self.unison_detune,
+            self.unison_filter_spread,
+            self.reverb_type as f32,
+            self.reverb_pre_delay_ms,
+            self.reverb_diffusion,
+            self.reverb_modulation,
+            bool_value(self.rumble_filter_enabled),
+            self.rumble_filter_freq,
+            self.effect_order[0] as f32,
+            self.effect_order[1] as f32,
+            self.effect_order[2] as f32,
+            self.effect_order[3] as f32,
+            self.effect_order[4] as f32,
+            self.effect_order[5] as f32,
+            self.reverb_mix,
+            self.tremolo_mix,
+            bool_value(self.protect_lowest_note),
+            bool_value(self.auto_wah_enabled),
+            self.auto_wah_sensitivity,
+            self.auto_wah_range,
+            self.auto_wah_q,
+            self.auto_wah_mix,
+            self.effect_order[6] as f32,
+            bool_value(self.rotary_enabled),
+            bool_value(self.rotary_fast),
+            self.rotary_acceleration,
+            self.rotary_balance,
+            self.rotary_spread,
+            self.rotary_mix,
+            self.effect_order[7] as f32,
+            self.organ_drawbars[0],
+            self.organ_drawbars[1],
+            self.organ_drawbars[2],
+            self.organ_drawbars[3],
+            self.organ_drawbars[4],
+            self.organ_drawbars[5],
+            self.organ_drawbars[6],
+            self.organ_drawbars[7],
+            self.organ_drawbars[8],
+            bool_value(self.organ_percussion),
+            self.organ_percussion_harmonic as f32,
+            bool_value(self.organ_percussion_fast),
+            self.organ_percussion_level,
+            self.organ_key_click,
+            self.reverb_shimmer,
+            bool_value(self.string_machine_enabled),
+            self.string_attack,
+            self.string_release,
+            self.string_brightness,
+            self.string_ensemble,
+            self.filter_env_attack,
+            self.filter_env_decay,
+            self.filter_env_sustain,
+            self.filter_env_release,
+            self.filter_env_amount,
+            self.drive,
+            bool_value(self.pitch_shifter_enabled),
+            self.pitch_shifter_semitones,
+            self.pitch_shifter_mix,
+            self.effect_order[8] as f32,
+            self.motion_amount,
+            bool_value(self.mono_melody),
+            self.ghost_threshold,
+            self.ghost_tone,
+            self.ghost_length,
+            self.glide_mode as f32,
+            self.release_velocity_amount,
+            self.pressure_vibrato_cents,
+            self.pressure_cutoff_octaves,
+            self.pressure_volume,
+            self.env_retrigger_mode as f32,
+            self.random_rate,
+            self.random_cutoff_octaves,
+            self.random_pitch_cents,
+            self.random_fx_mix,
+            self.vector_x,
+            self.vector_y,
+            self.vector_sources[0] as f32,
+            self.vector_sources[1] as f32,
+            self.vector_sources[2] as f32,
+            self.vector_sources[3] as f32,
+            self.vector_lfo_rate,
+            self.vector_lfo_depth,
+            self.vector_env_x,
+            self.vector_env_y,
+            self.lfo_mode as f32,
+            self.delay_low_cut,
+            self.delay_high_cut,
+            self.reverb_low_cut,
+            self.delay_right_time_ms,
+            self.delay_cross_feedback,
+            self.delay_mod_depth_ms,
+            self.delay_mod_rate,
+            self.velocity_curve,
+            self.output_gain_db,
+            bool_value(self.voice_normalize),
+            self.filter_poles as f32,
+            self.phase_mode as f32,
+            self.velocity_random,
+            self.pitch_env_attack,
+            self.pitch_env_decay,
+            self.pitch_env_amount,
+            self.lfo_delay_time,
+            self.lfo_reverb_size,
+            self.lfo_flanger_rate,
+            self.lfo_tremolo_depth,
+            self.tremolo_waveform as f32,
+            bool_value(self.tremolo_auto_pan),
+            bool_value(self.frequency_shifter_enabled),
+            self.frequency_shifter_hz,
+            self.frequency_shifter_mix,
+            self.effect_order[9] as f32,
+        ]
+    }
+
+    // Inverse of `values`. Missing trailing values keep their current
+    // setting, extra values from newer versions are ignored.
+    pub fn set_values(&mut self, values: &[f32]) {
+        let mut values = values.iter().copied();
+        let mut next = |current: f32| values.next().unwrap_or(current);
+
+        self.waveform = next(self.waveform as f32) as u8;
+        self.attack = next(self.attack);
+        self.decay = next(self.decay);
+        self.sustain = next(self.sustain);
+        self.release = next(self.release);
+        self.lfo_rate = next(self.lfo_rate);
+        self.lfo_depth = next(self.lfo_depth);
+        self.lfo_waveform = next(self.lfo_waveform as f32) as u8;
+        self.detune_cents = next(self.detune_cents);
+        self.glide_time_ms = next(self.glide_time_ms);
+        self.delay_enabled = next(bool_value(self.delay_enabled)) >= 0.5;
+        self.delay_time_ms = next(self.delay_time_ms);
+        self.delay_feedback = next(self.delay_feedback);
+        self.delay_mix = next(self.delay_mix);
+        self.reverb_enabled = next(bool_value(self.reverb_enabled)) >= 0.5;
+        self.reverb_room_size = next(self.reverb_room_size);
+        self.reverb_damping = next(self.reverb_damping);
+        self.tremolo_enabled = next(bool_value(self.tremolo_enabled)) >= 0.5;
+        self.tremolo_rate = next(self.tremolo_rate);
+        self.tremolo_depth = next(self.tremolo_depth);
+        self.flanger_enabled = next(bool_value(self.flanger_enabled)) >= 0.5;
+        self.flanger_rate = next(self.flanger_rate);
+        self.flanger_depth = next(self.flanger_depth);
+        self.flanger_feedback = next(self.flanger_feedback);
+        self.flanger_mix = next(self.flanger_mix);
+        self.bitcrusher_enabled = next(bool_value(self.bitcrusher_enabled)) >= 0.5;
+        self.bitcrusher_bits = next(self.bitcrusher_bits);
+        self.bitcrusher_downsample = next(self.bitcrusher_downsample);
+        self.bitcrusher_mix = next(self.bitcrusher_mix);
+        self.eq_enabled = next(bool_value(self.eq_enabled)) >= 0.5;
+        self.eq_low_gain_db = next(self.eq_low_gain_db);
+        self.eq_low_freq = next(self.eq_low_freq);
+        self.eq_mid_gain_db = next(self.eq_mid_gain_db);
+        self.eq_mid_freq = next(self.eq_mid_freq);
+        self.eq_high_gain_db = next(self.eq_high_gain_db);
+        self.eq_high_freq = next(self.eq_high_freq);
+        self.delay_mode = next(self.delay_mode as f32) as u8;
+        self.filter_enabled = next(bool_value(self.filter_enabled)) >= 0.5;
+        self.filter_cutoff = next(self.filter_cutoff);
+        self.filter_resonance = next(self.filter_resonance);
+        self.filter_mode = next(self.filter_mode as f32) as u8;
+        self.unison_voices = next(self.unison_voices as f32) as u8;
+        self.unison_detune = next(self.unison_detune);
+        self.unison_filter_spread = next(self.unison_filter_spread);
+        self.reverb_type = next(self.reverb_type as f32) as u8;
+        self.reverb_pre_delay_ms = next(self.reverb_pre_delay_ms);
+        self.reverb_diffusion = next(self.reverb_diffusion);
+        self.reverb_modulation = next(self.reverb_modulation);
+        self.rumble_filter_enabled = next(bool_value(self.rumble_filter_enabled)) >= 0.5;
+        self.rumble_filter_freq = next(self.rumble_filter_freq);
+        for slot in self.effect_order[..6].iter_mut() {
+            *slot = next(*slot as f32) as u8;
+        }
+        self.reverb_mix = next(self.reverb_mix);
+        self.tremolo_mix = next(self.tremolo_mix);
+        self.protect_lowest_note = next(bool_value(self.protect_lowest_note)) >= 0.5;
+        self.auto_wah_enabled = next(bool_value(self.auto_wah_enabled)) >= 0.5;
+        self.auto_wah_sensitivity = next(self.auto_wah_sensitivity);
+        self.auto_wah_range = next(self.auto_wah_range);
+        self.auto_wah_q = next(self.auto_wah_q);
+        self.auto_wah_mix = next(self.auto_wah_mix);
+        self.effect_order[6] = next(self.effect_order[6] as f32) as u8;
+        self.rotary_enabled = next(bool_value(self.rotary_enabled)) >= 0.5;
+        self.rotary_fast = next(bool_value(self.rotary_fast)) >= 0.5;
+        self.rotary_acceleration = next(self.rotary_acceleration);
+        self.rotary_balance = next(self.rotary_balance);
+        self.rotary_spread = next(self.rotary_spread);
+        self.rotary_mix = next(self.rotary_mix);
+        self.effect_order[7] = next(self.effect_order[7] as f32) as u8;
+        for level in self.organ_drawbars.iter_mut() {
+            *level = next(*level);
+        }
+        self.organ_percussion = next(bool_value(self.organ_percussion)) >= 0.5;
+        self.organ_percussion_harmonic = next(self.organ_percussion_harmonic as f32) as u8;
+        self.organ_percussion_fast = next(bool_value(self.organ_percussion_fast)) >= 0.5;
+        self.organ_percussion_level = next(self.organ_percussion_level);
+        self.organ_key_click = next(self.organ_key_click);
+        self.reverb_shimmer = next(self.reverb_shimmer);
+        self.string_machine_enabled = next(bool_value(self.string_machine_enabled)) >= 0.5;
+        self.string_attack = next(self.string_attack);
+        self.string_release = next(self.string_release);
+        self.string_brightness = next(self.string_brightness);
+        self.string_ensemble = next(self.string_ensemble);
+        self.filter_env_attack = next(self.filter_env_attack);
+        self.filter_env_decay = next(self.filter_env_decay);
+        self.filter_env_sustain = next(self.filter_env_sustain);
+        self.filter_env_release = next(self.filter_env_release);
+        self.filter_env_amount = next(self.filter_env_amount);
+        self.drive = next(self.drive);
+        self.pitch_shifter_enabled = next(bool_value(self.pitch_shifter_enabled)) >= 0.5;
+        self.pitch_shifter_semitones = next(self.pitch_shifter_semitones);
+        self.pitch_shifter_mix = next(self.pitch_shifter_mix);
+        self.effect_order[8] = next(self.effect_order[8] as f32) as u8;
+        self.motion_amount = next(self.motion_amount);
+        self.mono_melody = next(bool_value(self.mono_melody)) >= 0.5;
+        self.ghost_threshold = next(self.ghost_threshold);
+        self.ghost_tone = next(self.ghost_tone);
+        self.ghost_length = next(self.ghost_length);
+        self.glide_mode = next(self.glide_mode as f32) as u8;
+        self.release_velocity_amount = next(self.release_velocity_amount);
+        self.pressure_vibrato_cents = next(self.pressure_vibrato_cents);
+        self.pressure_cutoff_octaves = next(self.pressure_cutoff_octaves);
+        self.pressure_volume = next(self.pressure_volume);
+        self.env_retrigger_mode = next(self.env_retrigger_mode as f32) as u8;
+        self.random_rate = next(self.random_rate);
+        self.random_cutoff_octaves = next(self.random_cutoff_octaves);
+        self.random_pitch_cents = next(self.random_pitch_cents);
+        self.random_fx_mix = next(self.random_fx_mix);
+        self.vector_x = next(self.vector_x);
+        self.vector_y = next(self.vector_y);
+        for source in self.vector_sources.iter_mut() {
+            *source = next(*source as f32) as u8;
+        }
+        self.vector_lfo_rate = next(self.vector_lfo_rate);
+        self.vector_lfo_depth = next(self.vector_lfo_depth);
+        self.vector_env_x = next(self.vector_env_x);
+        self.vector_env_y = next(self.vector_env_y);
+        self.lfo_mode = next(self.lfo_mode as f32) as u8;
+        self.delay_low_cut = next(self.delay_low_cut);
+        self.delay_high_cut = next(self.delay_high_cut);
+        self.reverb_low_cut = next(self.reverb_low_cut);
+        self.delay_right_time_ms = next(self.delay_right_time_ms);
+        self.delay_cross_feedback = next(self.delay_cross_feedback);
+        self.delay_mod_depth_ms = next(self.delay_mod_depth_ms);
+        self.delay_mod_rate = next(self.delay_mod_rate);
+        self.velocity_curve = next(self.velocity_curve);
+        self.output_gain_db = next(self.output_gain_db);
+        self.voice_normalize = next(bool_value(self.voice_normalize)) >= 0.5;
+        self.filter_poles = next(self.filter_poles as f32) as u8;
+        self.phase_mode = next(self.phase_mode as f32) as u8;
+        self.velocity_random = next(self.velocity_random);
+        self.pitch_env_attack = next(self.pitch_env_attack);
+        self.pitch_env_decay = next(self.pitch_env_decay);
+        self.pitch_env_amount = next(self.pitch_env_amount);
+        self.lfo_delay_time = next(self.lfo_delay_time);
+        self.lfo_reverb_size = next(self.lfo_reverb_size);
+        self.lfo_flanger_rate = next(self.lfo_flanger_rate);
+        self.lfo_tremolo_depth = next(self.lfo_tremolo_depth);
+        self.tremolo_waveform = next(self.tremolo_waveform as f32) as u8;
+        self.tremolo_auto_pan = next(bool_value(self.tremolo_auto_pan)) >= 0.5;
+        self.frequency_shifter_enabled = next(bool_value(self.frequency_shifter_enabled)) >= 0.5;
+        self.frequency_shifter_hz = next(self.frequency_shifter_hz);
+        self.frequency_shifter_mix = next(self.frequency_shifter_mix);
+        self.effect_order[9] = next(self.effect_order[9] as f32) as u8;
+
+        // Slots past the first six are appended as effects are added
+        self.effect_order = effect_order(&self.effect_order);
+    }
+
+    // Blend toward `other` by `t` (0-1) for preset morphing. Continuous
+    // parameters interpolate, frequencies on a log scale so sweeps sound
+    // even; switches, modes and the effect order flip at the halfway point.
+    pub fn morph(&self, other: &Patch, t: f32) -> Patch {
+        let t = t.clamp(0.0, 1.0);
+        let mut patch = if t < 0.5 { *self } else { *other };
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let log_lerp = |a: f32, b: f32| if a > 0.0 && b > 0.0 { a * (b / a).powf(t) } else { lerp(a, b) };
+
+        macro_rules! blend {
+            ($blend:ident: $($field:ident),* $(,)?) => {
+                $(patch.$field = $blend(self.$field, other.$field);)*
+            };
+        }
+        blend!(lerp:
+            attack, decay, sustain, release, lfo_rate, lfo_depth, detune_cents, glide_time_ms,
+            delay_time_ms, delay_feedback, delay_mix, reverb_room_size, reverb_damping,
+            tremolo_rate, tremolo_depth, flanger_rate, flanger_depth, flanger_feedback,
+            flanger_mix, bitcrusher_bits, bitcrusher_downsample, bitcrusher_mix, eq_low_gain_db,
+            eq_mid_gain_db, eq_high_gain_db, filter_resonance, unison_detune, unison_filter_spread,
+            reverb_pre_delay_ms, reverb_diffusion, reverb_modulation, reverb_mix, tremolo_mix,
+            auto_wah_sensitivity, auto_wah_range, auto_wah_q, auto_wah_mix, rotary_acceleration,
+            rotary_balance, rotary_spread, rotary_mix, organ_percussion_level, organ_key_click,
+            reverb_shimmer, string_attack, string_release, string_brightness, string_ensemble,
+            filter_env_attack, filter_env_decay, filter_env_sustain, filter_env_release,
+            filter_env_amount, drive, pitch_shifter_semitones, pitch_shifter_mix, motion_amount,
+            ghost_threshold, ghost_length, release_velocity_amount, pressure_vibrato_cents,
+            pressure_cutoff_octaves, pressure_volume, random_rate, random_cutoff_octaves,
+            random_pitch_cents, random_fx_mix, vector_x, vector_y, vector_lfo_rate, vector_lfo_depth,
+            vector_env_x, vector_env_y, delay_right_time_ms, delay_cross_feedback, delay_mod_depth_ms,
+            delay_mod_rate, velocity_curve, output_gain_db, velocity_random,
+            pitch_env_attack, pitch_env_decay, pitch_env_amount, lfo_delay_time, lfo_reverb_size,
+            lfo_flanger_rate, lfo_tremolo_depth, frequency_shifter_hz, frequency_shifter_mix
+        );
+        blend!(log_lerp:
+            filter_cutoff, eq_low_freq, eq_mid_freq, eq_high_freq, rumble_filter_freq, ghost_tone,
+            delay_low_cut, delay_high_cut, reverb_low_cut
+        );
+        for (i, level) in patch.organ_drawbars.iter_mut().enumerate() {
+            *level = lerp(self.organ_drawbars[i], other.organ_drawbars[i]);
+        }
+        patch
+    }
+
+    // This patch with every effect chain setting (the effects, their order
+    // and the LFO's pushes on them) taken from `other`
+    pub fn with_effects_of(&self, other: &Patch) -> Patch {
+        let mut patch = *self;
+        macro_rules! take {
+            ($($field:ident),* $(,)?) => {
+                $(patch.$field = other.$field;)*
+            };
+        }
+        take!(
+            delay_enabled, delay_time_ms, delay_feedback, delay_mix, delay_mode, delay_low_cut,
+            delay_high_cut, delay_right_time_ms, delay_cross_feedback, delay_mod_depth_ms,
+            delay_mod_rate, reverb_enabled, reverb_room_size, reverb_damping, reverb_type,
+            reverb_pre_delay_ms, reverb_diffusion, reverb_modulation, reverb_mix, reverb_shimmer,
+            reverb_low_cut, tremolo_enabled, tremolo_rate, tremolo_depth, tremolo_mix,
+            tremolo_waveform, tremolo_auto_pan, flanger_enabled, flanger_rate, flanger_depth,
+            flanger_feedback, flanger_mix, bitcrusher_enabled, bitcrusher_bits,
+            bitcrusher_downsample, bitcrusher_mix, eq_enabled, eq_low_gain_db, eq_low_freq,
+            eq_mid_gain_db, eq_mid_freq, eq_high_gain_db, eq_high_freq, rumble_filter_enabled,
+            rumble_filter_freq, auto_wah_enabled, auto_wah_sensitivity, auto_wah_range, auto_wah_q,
+            auto_wah_mix, rotary_enabled, rotary_fast, rotary_acceleration, rotary_balance,
+            rotary_spread, rotary_mix, pitch_shifter_enabled, pitch_shifter_semitones,
+            pitch_shifter_mix, frequency_shifter_enabled, frequency_shifter_hz,
+            frequency_shifter_mix, effect_order, lfo_delay_time, lfo_reverb_size, lfo_flanger_rate,
+            lfo_tremolo_depth,
+        );
+        patch
+    }
+
+    pub fn to_json(self) -> String {
+        serde_json::to_string(&self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Option<Patch> {
+        serde_json::from_str(json).ok()
+    }
+}
+
+// Accepts partial or older orders the same way set_values does
+fn deserialize_effect_order<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; EFFECT_COUNT], D::Error> {
+    let order = Vec::<u8>::deserialize(deserializer)?;
+    Ok(effect_order(&order))
+}
+
+fn bool_value(value: bool) -> f32 {
+    if value { 1.0 } else { 0.0 }
+}
@@ -0,0 +1,82 @@
+use std::f32::consts::PI;
+
+// Pressure (aftertouch) as a block-rate modulation source, for a whole
+// engine (channel pressure) or a single voice (polyphonic pressure): leaning
+// into held keys adds vibrato, opens or closes the filter and swells the
+// level, each by its own amount. Incoming values are smoothed so coarse
+// 7-bit steps don't zipper.
+
+const VIBRATO_RATE: f32 = 5.5;
+const SMOOTHING_SECONDS: f32 = 0.02;
+// Below this the pressure counts as released and the source goes idle
+const SILENT: f32 = 1e-4;
+
+#[derive(Clone)]
+pub struct Pressure {
+    sample_rate: f32,
+    target: f32,
+    value: f32,
+    vibrato_phase: f32,
+    vibrato_cents: f32,
+    cutoff_octaves: f32,
+    volume: f32,
+}
+
+impl Pressure {
+    pub fn new(sample_rate: f32) -> Self {
+        Pressure {
+            sample_rate,
+            target: 0.0,
+            value: 0.0,
+            vibrato_phase: 0.0,
+            vibrato_cents: 0.0,
+            cutoff_octaves: 0.0,
+            volume: 0.0,
+        }
+    }
+
+    // 0-1
+    pub fn set_pressure(&mut self, pressure: f32) {
+        self.target = pressure.clamp(0.0, 1.0);
+    }
+
+    // Straight back to no pressure, for a fresh note
+    pub fn reset(&mut self) {
+        self.target = 0.0;
+        self.value = 0.0;
+    }
+
+    // Depths at full pressure: vibrato in cents, cutoff offset in octaves
+    // (negative closes the filter) and volume 0-1 for up to +6 dB
+    pub fn set_amounts(&mut self, vibrato_cents: f32, cutoff_octaves: f32, volume: f32) {
+        self.vibrato_cents = vibrato_cents.clamp(0.0, 100.0);
+        self.cutoff_octaves = cutoff_octaves.clamp(-4.0, 4.0);
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.target > 0.0 || self.value > 0.0
+    }
+
+    // Moves the smoothing and the vibrato on by a block of samples
+    pub fn advance(&mut self, samples: usize) {
+        let coeff = 1.0 - (-(samples as f32) / (SMOOTHING_SECONDS * self.sample_rate)).exp();
+        self.value += (self.target - self.value) * coeff;
+        if (self.value - self.target).abs() < SILENT {
+            self.value = self.target;
+        }
+        self.vibrato_phase = (self.vibrato_phase + VIBRATO_RATE * samples as f32 / self.sample_rate).fract();
+    }
+
+    pub fn vibrato_cents(&self) -> f32 {
+        (self.vibrato_phase * 2.0 * PI).sin() * self.vibrato_cents * self.value
+    }
+
+    pub fn cutoff_octaves(&self) -> f32 {
+        self.cutoff_octaves * self.value
+    }
+
+    pub fn gain(&self) -> f32 {
+        2.0_f32.powf(self.volume * self.value)
+    }
+}
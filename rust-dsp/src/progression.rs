@@ -0,0 +1,120 @@
+// Chord progression generator. Styles are sets of roman-numeral templates
+// (scale degrees, 0 = I); a template is picked at random and repeated to
+// fill the bars, and each degree becomes the diatonic chord built by
+// stacking thirds on that note of the key's scale.
+
+use crate::chords::{AUG, DIM, DOM7, MAJ7, MAJOR, MIN7, MINOR};
+use crate::random::Random;
+use crate::scales;
+
+// Any other style is pop
+pub const STYLE_JAZZ: u8 = 1;
+pub const STYLE_BLUES: u8 = 2;
+pub const STYLE_CLASSICAL: u8 = 3;
+pub const MAX_BARS: usize = 64;
+// Tonic of the generated chords, C3 plus the key's pitch class
+const BASE_NOTE: u8 = 48;
+// Chance each eligible chord is replaced by a secondary dominant
+const SECONDARY_CHANCE: f32 = 0.5;
+
+const POP: &[&[u8]] = &[
+    &[0, 4, 5, 3], // I-V-vi-IV
+    &[5, 3, 0, 4], // vi-IV-I-V
+    &[0, 5, 3, 4], // I-vi-IV-V
+    &[0, 3, 5, 4], // I-IV-vi-V
+];
+const JAZZ: &[&[u8]] = &[
+    &[1, 4, 0, 0], // ii-V-I
+    &[0, 5, 1, 4], // I-vi-ii-V
+    &[2, 5, 1, 4], // iii-vi-ii-V
+    &[3, 2, 1, 4], // IV-iii-ii-V
+];
+const BLUES: &[&[u8]] = &[
+    &[0, 0, 0, 0, 3, 3, 0, 0, 4, 3, 0, 4], // twelve-bar
+    &[0, 3, 0, 0, 3, 3, 0, 0, 4, 3, 0, 4], // quick change
+];
+const CLASSICAL: &[&[u8]] = &[
+    &[0, 3, 4, 0], // I-IV-V-I
+    &[0, 1, 4, 0], // I-ii-V-I
+    &[0, 5, 3, 4], // I-vi-IV-V
+    &[0, 3, 1, 4, 0, 5, 4, 0],
+];
+
+#[derive(Clone, Copy)]
+pub struct ProgressionChord {
+    pub root: u8,
+    pub chord_type: u8,
+}
+
+// `bars` chords, one per bar, in the key of `key_root` (pitch class) and
+// `scale_type` (as scales.rs; pentatonic and blues scales borrow the major
+// or natural minor chords). Jazz voices every chord as a seventh. With
+// `secondary_dominants`, a chord may be swapped for the dominant seventh
+// of the chord after it (V7/x), except before the tonic.
+pub fn generate(
+    key_root: u8,
+    scale_type: u8,
+    style: u8,
+    bars: usize,
+    secondary_dominants: bool,
+    random: &mut Random,
+) -> Vec<ProgressionChord> {
+    let templates = match style {
+        STYLE_JAZZ => JAZZ,
+        STYLE_BLUES => BLUES,
+        STYLE_CLASSICAL => CLASSICAL,
+        _ => POP,
+    };
+    let template = templates[(random.next_u32() as usize) % templates.len()];
+    let scale = diatonic_scale(scale_type);
+    let sevenths = style == STYLE_JAZZ;
+    let tonic = BASE_NOTE + key_root % 12;
+
+    let mut chords: Vec<ProgressionChord> = (0..bars.min(MAX_BARS))
+        .map(|bar| {
+            let degree = template[bar % template.len()] as usize;
+            ProgressionChord { root: tonic + scale[degree], chord_type: diatonic_chord(scale, degree, sevenths) }
+        })
+        .collect();
+
+    if secondary_dominants {
+        // Back to front, so a dominant can itself be approached by one
+        for i in (0..chords.len().saturating_sub(1)).rev() {
+            let target = chords[i + 1];
+            // Only major and minor chords away from the tonic get one
+            let tonicizable = target.root != tonic && !matches!(target.chord_type, DIM | AUG);
+            if tonicizable && target.root != chords[i].root && random.unipolar() < SECONDARY_CHANCE {
+                let root = tonic + (target.root - tonic + 7) % 12;
+                chords[i] = ProgressionChord { root, chord_type: DOM7 };
+            }
+        }
+    }
+    chords
+}
+
+fn diatonic_scale(scale_type: u8) -> &'static [u8] {
+    match scales::scale_intervals(scale_type) {
+        Some(intervals) if intervals.len() == 7 => intervals,
+        // Minor pentatonic and blues
+        _ if scale_type == 10 || scale_type == 11 => scales::scale_intervals(1).unwrap_or_default(),
+        _ => scales::scale_intervals(0).unwrap_or_default(),
+    }
+}
+
+// Chord type of the triad (or seventh chord) stacked on a scale degree
+fn diatonic_chord(scale: &[u8], degree: usize, seventh: bool) -> u8 {
+    let above = |steps: usize| {
+        let note = scale[(degree + steps) % 7] + if degree + steps >= 7 { 12 } else { 0 };
+        note - scale[degree]
+    };
+    let (third, fifth, seventh_interval) = (above(2), above(4), above(6));
+    match (third, fifth, seventh) {
+        (4, 7, true) if seventh_interval == 11 => MAJ7,
+        (4, 7, true) => DOM7,
+        (3, 7, true) if seventh_interval == 10 => MIN7,
+        (4, 7, _) => MAJOR,
+        (3, 7, _) => MINOR,
+        (4, 8, _) => AUG,
+        _ => DIM,
+    }
+}
@@ -0,0 +1,31 @@
+// Xorshift noise for humanization and generative variation: cheap, never
+// allocates, and repeats exactly from a given seed, which golden renders rely
+// on. Not for anything that needs good statistics.
+#[derive(Clone)]
+pub struct Random {
+    state: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Self {
+        // Zero is the one state xorshift never leaves
+        Random { state: seed.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    // -1..1
+    pub fn bipolar(&mut self) -> f32 {
+        self.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    // 0..1
+    pub fn unipolar(&mut self) -> f32 {
+        self.next_u32() as f32 / u32::MAX as f32
+    }
+}
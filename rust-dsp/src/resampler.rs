@@ -0,0 +1,140 @@
+use std::f64::consts::PI;
+
+// Streaming polyphase windowed-sinc resampler from the engine's internal
+// rate to the host's output rate. The Kaiser-windowed sinc is tabulated at
+// PHASES fractional offsets and interpolated between neighbouring phases;
+// when the output rate is lower the cutoff drops with it so nothing aliases.
+
+// Taps either side of the output position
+const HALF_TAPS: usize = 16;
+const TAPS: usize = HALF_TAPS * 2;
+const PHASES: usize = 256;
+const KAISER_BETA: f64 = 9.0;
+// Passband edge as a fraction of the lower Nyquist, leaving room for the
+// transition band
+const CUTOFF: f64 = 0.95;
+// Output block size the input buffers are sized for up front
+const RESERVE: usize = 8192;
+
+pub struct Resampler {
+    output_rate: f32,
+    // Input samples per output sample
+    step: f64,
+    // Read position of the next output sample, in input samples from the
+    // start of `input`
+    position: f64,
+    input: [Vec<f32>; 2],
+    // PHASES + 1 rows of TAPS coefficients; the extra row makes phase
+    // interpolation wrap-free
+    table: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: f32, output_rate: f32) -> Self {
+        let step = input_rate as f64 / output_rate as f64;
+        let cutoff = CUTOFF * (1.0 / step).min(1.0);
+        let mut table = Vec::with_capacity((PHASES + 1) * TAPS);
+        for phase in 0..=PHASES {
+            let fraction = phase as f64 / PHASES as f64;
+            let row: Vec<f64> = (0..TAPS)
+                .map(|tap| {
+                    // Distance of this tap from the output position
+                    let t = tap as f64 - (HALF_TAPS - 1) as f64 - fraction;
+                    cutoff * sinc(cutoff * t) * kaiser(t / HALF_TAPS as f64)
+                })
+                .collect();
+            // Unity gain at DC for every phase
+            let sum: f64 = row.iter().sum();
+            table.extend(row.iter().map(|c| (c / sum) as f32));
+        }
+        let input_len = HALF_TAPS - 1;
+        let buffer = || {
+            let mut buffer = Vec::with_capacity((RESERVE as f64 * step) as usize + TAPS * 2);
+            buffer.resize(input_len, 0.0);
+            buffer
+        };
+        Resampler {
+            output_rate,
+            step,
+            position: input_len as f64,
+            input: [buffer(), buffer()],
+            table,
+        }
+    }
+
+    pub fn output_rate(&self) -> f32 {
+        self.output_rate
+    }
+
+    // Input samples to render and pass to `input_mut` before the next
+    // `output_len` samples can be produced
+    pub fn input_needed(&self, output_len: usize) -> usize {
+        if output_len == 0 {
+            return 0;
+        }
+        let last = (self.position + (output_len - 1) as f64 * self.step).floor() as usize + HALF_TAPS + 1;
+        last.saturating_sub(self.input[0].len())
+    }
+
+    // Appends `len` silent samples per channel and returns them to be
+    // rendered into
+    pub fn input_mut(&mut self, len: usize) -> (&mut [f32], &mut [f32]) {
+        let start = self.input[0].len();
+        for channel in &mut self.input {
+            channel.resize(start + len, 0.0);
+        }
+        let [left, right] = &mut self.input;
+        (&mut left[start..], &mut right[start..])
+    }
+
+    // Fills the output from input already supplied, then drops input that
+    // no later output can reach
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for (out_left, out_right) in left.iter_mut().zip(right.iter_mut()) {
+            let index = self.position.floor();
+            let phase = (self.position - index) * PHASES as f64;
+            let row = phase.floor() as usize;
+            let blend = (phase - row as f64) as f32;
+            let first = index as usize + 1 - HALF_TAPS;
+            let a = &self.table[row * TAPS..(row + 1) * TAPS];
+            let b = &self.table[(row + 1) * TAPS..(row + 2) * TAPS];
+            let (mut sum_left, mut sum_right) = (0.0, 0.0);
+            for tap in 0..TAPS {
+                let coefficient = a[tap] + (b[tap] - a[tap]) * blend;
+                sum_left += self.input[0][first + tap] * coefficient;
+                sum_right += self.input[1][first + tap] * coefficient;
+            }
+            *out_left = sum_left;
+            *out_right = sum_right;
+            self.position += self.step;
+        }
+        let consumed = (self.position.floor() as usize).saturating_sub(HALF_TAPS - 1);
+        for channel in &mut self.input {
+            channel.drain(..consumed.min(channel.len()));
+        }
+        self.position -= consumed as f64;
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+// Kaiser window over -1..1
+fn kaiser(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / bessel_i0(KAISER_BETA)
+}
+
+// Zeroth-order modified Bessel function of the first kind, by its series
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..32 {
+        term *= (x / (2.0 * k as f64)).powi(2);
+        sum += term;
+    }
+    sum
+}
@@ -0,0 +1,45 @@
+// Scales for quantizing live input. Ids 0 and 1 follow the UI's Mode type
+// in src/music/scales.ts (major, natural minor); the rest come after.
+pub const SCALE_TYPE_COUNT: usize = 12;
+
+const INTERVALS: [&[u8]; SCALE_TYPE_COUNT] = [
+    &[0, 2, 4, 5, 7, 9, 11], // major
+    &[0, 2, 3, 5, 7, 8, 10], // natural minor
+    &[0, 2, 3, 5, 7, 9, 10], // dorian
+    &[0, 1, 3, 5, 7, 8, 10], // phrygian
+    &[0, 2, 4, 6, 7, 9, 11], // lydian
+    &[0, 2, 4, 5, 7, 9, 10], // mixolydian
+    &[0, 1, 3, 5, 6, 8, 10], // locrian
+    &[0, 2, 3, 5, 7, 8, 11], // harmonic minor
+    &[0, 2, 3, 5, 7, 9, 11], // melodic minor
+    &[0, 2, 4, 7, 9],        // major pentatonic
+    &[0, 3, 5, 7, 10],       // minor pentatonic
+    &[0, 3, 5, 6, 7, 10],    // blues
+];
+
+// Semitones above the root of each scale note
+pub fn scale_intervals(scale_type: u8) -> Option<&'static [u8]> {
+    INTERVALS.get(scale_type as usize).copied()
+}
+
+// Bit per pitch class (bit 0 = C) of the scale on `root`
+pub fn scale_mask(root: u8, scale_type: u8) -> Option<u16> {
+    let intervals = INTERVALS.get(scale_type as usize)?;
+    Some(intervals.iter().fold(0, |mask, &i| mask | 1 << ((root as usize + i as usize) % 12)))
+}
+
+// Nearest MIDI note whose pitch class is in `mask`. A note exactly between
+// two scale notes goes down.
+pub fn quantize(note: u8, mask: u16) -> u8 {
+    let in_scale = |n: i32| (0..=127).contains(&n) && mask & 1 << (n % 12) != 0;
+    let note = note.min(127) as i32;
+    for distance in 0..=6 {
+        if in_scale(note - distance) {
+            return (note - distance) as u8;
+        }
+        if in_scale(note + distance) {
+            return (note + distance) as u8;
+        }
+    }
+    note as u8
+}
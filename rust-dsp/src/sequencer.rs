@@ -0,0 +1,1009 @@
+// Step sequencer that drives the timeline engine from inside the audio
+// callback. A pattern is a loop of steps, each holding a chord (or a rest),
+// a velocity and a gate length in steps. Event times are kept in fractional
+// samples and every event lands on the first sample at or after its exact
+// time, so timing never drifts however long the loop runs.
+//
+// There is a bank of patterns. One is played and one is edited; playback
+// moves to a queued pattern at the next bar line, and in song mode steps
+// through a list of patterns, each played once through. A sequencer can
+// also fall silent at a bar line and keep time until a pattern is queued.
+// Outside song mode a pattern's follow action can pick what plays after it
+// has repeated a set number of times, so an arrangement can run itself.
+
+use crate::groove::{self, Groove, GROOVE_USER, MAX_GROOVE_STEPS};
+use crate::random::Random;
+
+pub const MAX_STEPS: usize = 64;
+pub const MAX_PATTERNS: usize = 16;
+// Patterns a song can list
+pub const MAX_SONG_LENGTH: usize = 256;
+// Bars are 4/4
+const BEATS_PER_BAR: usize = 4;
+// Notes a single step can hold
+pub const MAX_STEP_NOTES: usize = 8;
+// Latest the off-beat steps can be pushed, as a fraction of a step
+const MAX_SWING: f32 = 0.75;
+// Most hits a ratcheted step can split into
+const MAX_RATCHET: u32 = 4;
+// Furthest humanized timing moves a step either way, as a fraction of a
+// step, so steps never swap order even at full swing
+const MAX_TIMING_JITTER: f64 = 0.125;
+const HUMANIZE_SEED: u32 = 0x9E37_79B9;
+// Furthest a step can be nudged off the grid either way, in steps
+const MAX_NUDGE: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SequencerEvent {
+    NoteOn { note: u8, velocity: f32 },
+    NoteOff { note: u8 },
+    // A held note bends to a new one without a new attack
+    Slide { from: u8, to: u8, velocity: f32 },
+    // A pattern begins playing, ahead of its first notes
+    PatternStart { pattern: u8 },
+    // The playing pattern starts over from its first step
+    PatternLoop { pattern: u8 },
+}
+
+// Anything that generates notes on a sample clock inside the audio callback.
+// The renderer splits each block at `samples_until_next_event` so events
+// land on their exact sample.
+pub trait NoteSource {
+    // Whole samples from now until the next event is due (0 = due now), or
+    // None when nothing is scheduled
+    fn samples_until_next_event(&self) -> Option<usize>;
+    fn advance(&mut self, samples: usize);
+    // Collects every event due on the current sample
+    fn take_due_events(&mut self, events: &mut Vec<SequencerEvent>);
+}
+
+// Two sources driving the same engine
+pub struct Merged<'a, A, B>(pub &'a mut A, pub &'a mut B);
+
+impl<A: NoteSource, B: NoteSource> NoteSource for Merged<'_, A, B> {
+    fn samples_until_next_event(&self) -> Option<usize> {
+        match (self.0.samples_until_next_event(), self.1.samples_until_next_event()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn advance(&mut self, samples: usize) {
+        self.0.advance(samples);
+        self.1.advance(samples);
+    }
+
+    fn take_due_events(&mut self, events: &mut Vec<SequencerEvent>) {
+        self.0.take_due_events(events);
+        self.1.take_due_events(events);
+    }
+}
+
+struct Step {
+    notes: Vec<u8>,
+    velocity: f32,
+    gate: f32,
+    // Chance (0-1) the step plays each time the loop reaches it
+    probability: f32,
+    // Evenly spaced hits within the step, 1 for a single hit
+    ratchet: u32,
+    // Notes this step shares with the step before carry on from it instead
+    // of retriggering
+    tie: bool,
+    // The step glides in from the notes of the step before, 303 style
+    slide: bool,
+    // Start off the grid in steps (-0.5 to 0.5), from a groove or a
+    // triplet quantize
+    nudge: f32,
+}
+
+// What a pattern hands over to once it has played through `repeats` times
+#[derive(Clone, Copy, PartialEq)]
+pub enum FollowAction {
+    // Loops for good
+    None = 0,
+    // The next pattern in the bank with notes
+    Next = 1,
+    // Any other pattern with notes in the same group
+    Random = 2,
+    // Falls silent until a pattern is queued
+    Stop = 3,
+}
+
+impl FollowAction {
+    pub fn from_u8(action: u8) -> Self {
+        match action {
+            1 => FollowAction::Next,
+            2 => FollowAction::Random,
+            3 => FollowAction::Stop,
+            _ => FollowAction::None,
+        }
+    }
+}
+
+struct Pattern {
+    steps: Vec<Step>,
+    length: usize,
+    follow: FollowAction,
+    follow_repeats: u32,
+    group: u8,
+}
+
+impl Pattern {
+    fn new() -> Self {
+        let mut steps = Vec::with_capacity(MAX_STEPS);
+        steps.resize_with(MAX_STEPS, || Step {
+            notes: Vec::with_capacity(MAX_STEP_NOTES),
+            velocity: 0.8,
+            gate: 0.5,
+            probability: 1.0,
+            ratchet: 1,
+            tie: false,
+            slide: false,
+            nudge: 0.0,
+        });
+        Pattern { steps, length: 16, follow: FollowAction::None, follow_repeats: 1, group: 0 }
+    }
+}
+
+// One note of the pattern with times in steps from the pattern start
+pub struct PatternNote {
+    pub start: f64,
+    pub length: f64,
+    pub note: u8,
+    pub velocity: f32,
+}
+
+// `samples` is infinite while a note is held open for a tied step, which
+// sets its real length when it fires
+struct PendingOff {
+    note: u8,
+    samples: f64,
+}
+
+// A live note being recorded: the pattern and step it was written to and
+// when it started, in steps from the pattern start
+struct RecordingNote {
+    note: u8,
+    pattern: usize,
+    step: usize,
+    start: f64,
+}
+
+pub struct Sequencer {
+    patterns: Vec<Pattern>,
+    // Pattern the editing calls act on
+    edit: usize,
+    playing: usize,
+    // Pattern of the step that fired last; the next step may already
+    // belong to another
+    step_pattern: usize,
+    // Pattern to switch to at the next bar line
+    queued: Option<usize>,
+    // Steps run on without playing, and whether they start to at the next
+    // bar line
+    silent: bool,
+    silence_queued: bool,
+    // Song mode: patterns in playing order, empty when off, and the entry
+    // playing (None until playback first reaches the song)
+    song: Vec<usize>,
+    song_position: Option<usize>,
+    // PatternStart is due with the next step
+    pattern_started: bool,
+    // Passes the playing pattern has finished since it started
+    repeats: u32,
+    steps_per_beat: u32,
+    swing: f32,
+    running: bool,
+    current_step: usize,
+    step_samples: f64,
+    // Samples until the next step starts
+    until_step: f64,
+    pending_offs: Vec<PendingOff>,
+    // Ratchet hits of the current step still to come and samples until the
+    // next one
+    ratchet_step: usize,
+    ratchets_left: u32,
+    until_ratchet: f64,
+    // Humanize: largest timing offset in samples and relative velocity
+    // spread, and how far the current step was moved off the swung grid by
+    // humanize and its nudge together
+    timing_jitter: f64,
+    velocity_jitter: f32,
+    jitter: f64,
+    random: Random,
+    // Live recording: on while armed, the grid in steps notes snap to (0
+    // snaps starts to the nearest step and keeps lengths as played) and the
+    // notes still held
+    recording: bool,
+    record_grid: f64,
+    recording_notes: Vec<RecordingNote>,
+    user_groove: Groove,
+}
+
+impl Sequencer {
+    pub fn new(samples_per_beat: f64) -> Self {
+        Sequencer {
+            patterns: (0..MAX_PATTERNS).map(|_| Pattern::new()).collect(),
+            edit: 0,
+            playing: 0,
+            step_pattern: 0,
+            queued: None,
+            silent: false,
+            silence_queued: false,
+            song: Vec::with_capacity(MAX_SONG_LENGTH),
+            song_position: None,
+            pattern_started: false,
+            repeats: 0,
+            steps_per_beat: 4,
+            swing: 0.0,
+            running: false,
+            current_step: 0,
+            step_samples: samples_per_beat / 4.0,
+            until_step: 0.0,
+            pending_offs: Vec::with_capacity(MAX_STEPS * MAX_STEP_NOTES),
+            ratchet_step: 0,
+            ratchets_left: 0,
+            until_ratchet: 0.0,
+            timing_jitter: 0.0,
+            velocity_jitter: 0.0,
+            jitter: 0.0,
+            random: Random::new(HUMANIZE_SEED),
+            recording: false,
+            record_grid: 0.0,
+            recording_notes: Vec::with_capacity(MAX_STEP_NOTES * 4),
+            user_groove: Groove::straight(),
+        }
+    }
+
+    // An empty `notes` makes the step a rest. `gate` is in steps, so values
+    // above 1 hold notes across the following steps.
+    pub fn set_step(&mut self, index: usize, notes: &[u8], velocity: f32, gate: f32) {
+        let Some(step) = self.patterns[self.edit].steps.get_mut(index) else {
+            return;
+        };
+        step.notes.clear();
+        step.notes.extend(notes.iter().take(MAX_STEP_NOTES).map(|&n| n.min(127)));
+        step.velocity = velocity.clamp(0.0, 1.0);
+        step.gate = gate.clamp(0.01, MAX_STEPS as f32);
+    }
+
+    // Replaces a step's notes with `f` of them, e.g. to transpose or invert
+    // it in place; velocity, gate and the rest of the step are kept
+    pub fn transform_step(&mut self, index: usize, f: impl FnOnce(&[u8]) -> Vec<u8>) {
+        let Some(step) = self.patterns[self.edit].steps.get_mut(index) else {
+            return;
+        };
+        let notes = f(&step.notes);
+        step.notes.clear();
+        step.notes.extend(notes.into_iter().filter(|&n| n <= 127).take(MAX_STEP_NOTES));
+    }
+
+    pub fn clear(&mut self) {
+        for step in &mut self.patterns[self.edit].steps {
+            step.notes.clear();
+            step.probability = 1.0;
+            step.ratchet = 1;
+            step.tie = false;
+            step.slide = false;
+            step.nudge = 0.0;
+        }
+    }
+
+    // Below 1 the step is rolled for each pass and plays as a rest when it
+    // misses, so the loop varies from pass to pass
+    pub fn set_step_probability(&mut self, index: usize, probability: f32) {
+        if let Some(step) = self.patterns[self.edit].steps.get_mut(index) {
+            step.probability = probability.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn step_probability(&self, pattern: usize, index: usize) -> f32 {
+        self.pattern_step(pattern, index).map_or(1.0, |step| step.probability)
+    }
+
+    // Splits the step into `count` (1-4) evenly spaced hits, each with its
+    // share of the step's gate
+    pub fn set_step_ratchet(&mut self, index: usize, count: u32) {
+        if let Some(step) = self.patterns[self.edit].steps.get_mut(index) {
+            step.ratchet = count.clamp(1, MAX_RATCHET);
+        }
+    }
+
+    pub fn step_ratchet(&self, pattern: usize, index: usize) -> u32 {
+        self.pattern_step(pattern, index).map_or(1, |step| step.ratchet)
+    }
+
+    // Ties the step to the one before: notes in both are held across the
+    // step boundary without a note-off and note-on, then last for this
+    // step's gate. Other notes play as usual. The first step ties to the
+    // last one when the loop wraps.
+    pub fn set_step_tie(&mut self, index: usize, tie: bool) {
+        if let Some(step) = self.patterns[self.edit].steps.get_mut(index) {
+            step.tie = tie;
+        }
+    }
+
+    pub fn step_tie(&self, pattern: usize, index: usize) -> bool {
+        self.pattern_step(pattern, index).is_some_and(|step| step.tie)
+    }
+
+    // Slides into the step from the one before: its notes are held across
+    // the boundary and each bends to the closest of this step's notes
+    // without a new attack. Notes left over on either side end or start as
+    // usual, and notes the two steps share carry on as for a tie.
+    pub fn set_step_slide(&mut self, index: usize, slide: bool) {
+        if let Some(step) = self.patterns[self.edit].steps.get_mut(index) {
+            step.slide = slide;
+        }
+    }
+
+    pub fn step_slide(&self, pattern: usize, index: usize) -> bool {
+        self.pattern_step(pattern, index).is_some_and(|step| step.slide)
+    }
+
+    // Moves the step's start off the grid by `nudge` steps (-0.5 to 0.5),
+    // on top of swing and humanize
+    pub fn set_step_nudge(&mut self, index: usize, nudge: f32) {
+        if let Some(step) = self.patterns[self.edit].steps.get_mut(index) {
+            step.nudge = nudge.clamp(-MAX_NUDGE, MAX_NUDGE);
+        }
+    }
+
+    pub fn step_nudge(&self, pattern: usize, index: usize) -> f32 {
+        self.pattern_step(pattern, index).map_or(0.0, |step| step.nudge)
+    }
+
+    // Lays groove `id` (see groove::template, or GROOVE_USER) over
+    // `pattern`, cycling it from the first step: `strength` 0-1 moves each
+    // step's nudge that far toward the groove's offset and scales its
+    // velocity that far by the groove's. False for an unknown groove or
+    // pattern.
+    pub fn apply_groove(&mut self, pattern: usize, id: u8, strength: f32) -> bool {
+        let groove = if id == GROOVE_USER { Some(self.user_groove) } else { groove::template(id) };
+        let (Some(groove), Some(pattern)) = (groove, self.patterns.get_mut(pattern)) else {
+            return false;
+        };
+        let strength = strength.clamp(0.0, 1.0);
+        for (i, step) in pattern.steps[..pattern.length].iter_mut().enumerate() {
+            let position = i % groove.length;
+            step.nudge += (groove.offsets[position] - step.nudge) * strength;
+            step.velocity = (step.velocity * (1.0 + (groove.velocities[position] - 1.0) * strength)).clamp(0.0, 1.0);
+        }
+        true
+    }
+
+    // Takes the nudges and relative velocities of the first bar (up to 16
+    // steps) of `pattern` as the user groove. Rests keep their timing and
+    // full velocity.
+    pub fn extract_groove(&mut self, pattern: usize) {
+        let Some(pattern) = self.patterns.get(pattern) else {
+            return;
+        };
+        let steps = &pattern.steps[..pattern.length.min(MAX_GROOVE_STEPS)];
+        let loudest = steps.iter().filter(|s| !s.notes.is_empty()).map(|s| s.velocity).fold(0.0, f32::max);
+        let mut groove = Groove { length: steps.len(), ..Groove::straight() };
+        for (i, step) in steps.iter().enumerate() {
+            groove.offsets[i] = step.nudge;
+            if !step.notes.is_empty() && loudest > 0.0 {
+                groove.velocities[i] = step.velocity / loudest;
+            }
+        }
+        self.user_groove = groove;
+    }
+
+    pub fn set_length(&mut self, steps: usize) {
+        self.patterns[self.edit].length = steps.clamp(1, MAX_STEPS);
+        if self.current_step >= self.patterns[self.playing].length {
+            self.current_step = 0;
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.patterns[self.edit].length
+    }
+
+    pub fn pattern_length(&self, pattern: usize) -> usize {
+        self.patterns.get(pattern).map_or(0, |p| p.length)
+    }
+
+    // Whether any step of `pattern` has notes
+    pub fn pattern_has_notes(&self, pattern: usize) -> bool {
+        self.patterns.get(pattern).is_some_and(|p| p.steps.iter().any(|step| !step.notes.is_empty()))
+    }
+
+    // Steps in a bar at the current resolution
+    pub fn bar_steps(&self) -> usize {
+        self.steps_per_beat as usize * BEATS_PER_BAR
+    }
+
+    // Pattern the editing calls act on, 0-15
+    pub fn set_edit_pattern(&mut self, pattern: usize) {
+        self.edit = pattern.min(MAX_PATTERNS - 1);
+    }
+
+    pub fn edit_pattern(&self) -> usize {
+        self.edit
+    }
+
+    // Pattern being played, or about to be once started
+    pub fn playing_pattern(&self) -> usize {
+        self.playing
+    }
+
+    // Switches playback to `pattern` at the next bar line, from its first
+    // step; straight away while stopped. Leaves song mode.
+    pub fn queue_pattern(&mut self, pattern: usize) {
+        let pattern = pattern.min(MAX_PATTERNS - 1);
+        self.song.clear();
+        self.song_position = None;
+        self.silence_queued = false;
+        if self.running {
+            self.queued = Some(pattern);
+        } else {
+            self.playing = pattern;
+            self.silent = false;
+        }
+    }
+
+    // Stops playing steps at the next bar line, straight away while
+    // stopped, but keeps counting them so a pattern queued later still
+    // comes in on a bar. Leaves song mode.
+    pub fn queue_silence(&mut self) {
+        self.song.clear();
+        self.song_position = None;
+        self.queued = None;
+        if self.running {
+            self.silence_queued = true;
+        } else {
+            self.silent = true;
+        }
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    // Follow action of `pattern`, taken after it has played through
+    // `repeats` (at least 1) times in a row; `group` is the set Random
+    // chooses from
+    pub fn set_follow_action(&mut self, pattern: usize, action: FollowAction, repeats: u32, group: u8) {
+        if let Some(p) = self.patterns.get_mut(pattern) {
+            p.follow = action;
+            p.follow_repeats = repeats.max(1);
+            p.group = group;
+        }
+    }
+
+    pub fn follow_action(&self, pattern: usize) -> Option<(FollowAction, u32, u8)> {
+        self.patterns.get(pattern).map(|p| (p.follow, p.follow_repeats, p.group))
+    }
+
+    // Song mode: plays `patterns` in order, each once through, and loops
+    // back to the first after the last. The current pattern finishes first
+    // when playing. Empty turns song mode off, leaving the current pattern
+    // looping.
+    pub fn set_song(&mut self, patterns: &[usize]) {
+        self.song.clear();
+        self.song.extend(patterns.iter().take(MAX_SONG_LENGTH).map(|&p| p.min(MAX_PATTERNS - 1)));
+        self.song_position = None;
+        self.queued = None;
+        self.silence_queued = false;
+        if !self.running {
+            if let Some(&first) = self.song.first() {
+                self.playing = first;
+                self.song_position = Some(0);
+                self.silent = false;
+            }
+        }
+    }
+
+    pub fn song(&self) -> &[usize] {
+        &self.song
+    }
+
+    // Song entry playing, or None outside song mode
+    pub fn song_position(&self) -> Option<usize> {
+        self.song_position
+    }
+
+    pub fn steps_per_beat(&self) -> u32 {
+        self.steps_per_beat
+    }
+
+    // Every note of one pass through the pattern, in playing order, with
+    // swing applied and every step played whatever its probability. A note cut short by the same note on a later step ends
+    // there, as it does in playback, and a tied note is one long note. A
+    // slide carries on the notes it shares with the step before like a tie;
+    // bends aren't part of the list.
+    pub fn pattern_notes(&self) -> Vec<PatternNote> {
+        let pattern = &self.patterns[self.edit];
+        let mut notes: Vec<PatternNote> = Vec::new();
+        for (index, step) in pattern.steps[..pattern.length].iter().enumerate() {
+            let ratchet = step.ratchet as f64;
+            let previous = index.checked_sub(1).map(|i| &pattern.steps[i]);
+            for hit in 0..step.ratchet {
+                let start =
+                    index as f64 + self.swing_offset(index) / self.step_samples + step.nudge as f64 + hit as f64 / ratchet;
+                let tied = hit == 0 && (step.tie || step.slide) && previous.is_some();
+                for &note in &step.notes {
+                    if let Some(earlier) = notes.iter_mut().rev().find(|n| n.note == note) {
+                        if tied && previous.is_some_and(|p| p.notes.contains(&note)) {
+                            earlier.length = start - earlier.start + step.gate as f64 / ratchet;
+                            continue;
+                        }
+                        earlier.length = earlier.length.min(start - earlier.start);
+                    }
+                    notes.push(PatternNote { start, length: step.gate as f64 / ratchet, note, velocity: step.velocity });
+                }
+            }
+        }
+        notes
+    }
+
+    // Step resolution, e.g. 4 for sixteenths or 2 for eighths
+    pub fn set_steps_per_beat(&mut self, steps_per_beat: u32, samples_per_beat: f64) {
+        self.steps_per_beat = steps_per_beat.clamp(1, 16);
+        self.set_samples_per_beat(samples_per_beat);
+    }
+
+    // Delays every second step by `amount` of a step (0-0.75); 1/3 gives a
+    // triplet shuffle. Takes effect from the next step.
+    pub fn set_swing(&mut self, amount: f32) {
+        self.swing = amount.clamp(0.0, MAX_SWING);
+    }
+
+    pub fn swing(&self) -> f32 {
+        self.swing
+    }
+
+    // Random offsets as steps fire: start times move up to `timing_samples`
+    // either way (at most an eighth of a step), velocities by up to
+    // `velocity_amount` (0-1) of their value. Both 0 plays the grid.
+    pub fn set_humanize(&mut self, timing_samples: f64, velocity_amount: f32) {
+        self.timing_jitter = timing_samples.max(0.0);
+        self.velocity_jitter = velocity_amount.clamp(0.0, 1.0);
+    }
+
+    // (timing in samples, velocity amount)
+    pub fn humanize(&self) -> (f64, f32) {
+        (self.timing_jitter, self.velocity_jitter)
+    }
+
+    // (notes, velocity, gate) of a step of `pattern`
+    pub fn step(&self, pattern: usize, index: usize) -> Option<(&[u8], f32, f32)> {
+        self.pattern_step(pattern, index).map(|step| (step.notes.as_slice(), step.velocity, step.gate))
+    }
+
+    fn pattern_step(&self, pattern: usize, index: usize) -> Option<&Step> {
+        self.patterns.get(pattern)?.steps.get(index)
+    }
+
+    // Samples a step starts after its place on the straight grid
+    fn swing_offset(&self, index: usize) -> f64 {
+        if index % 2 == 1 { self.swing as f64 * self.step_samples } else { 0.0 }
+    }
+
+    // Samples step `index` of `pattern` is nudged off the grid
+    fn nudge_offset(&self, pattern: usize, index: usize) -> f64 {
+        self.pattern_step(pattern, index).map_or(0.0, |step| step.nudge as f64 * self.step_samples)
+    }
+
+    // Humanized offset for the next step to fire
+    fn next_jitter(&mut self) -> f64 {
+        if self.timing_jitter == 0.0 {
+            return 0.0;
+        }
+        let limit = self.timing_jitter.min(MAX_TIMING_JITTER * self.step_samples);
+        self.random.bipolar() as f64 * limit
+    }
+
+    // Tempo changes stretch whatever is already in flight so the current
+    // step and any held gates keep their musical length
+    pub fn set_samples_per_beat(&mut self, samples_per_beat: f64) {
+        let step_samples = samples_per_beat / self.steps_per_beat as f64;
+        if step_samples != self.step_samples {
+            let scale = step_samples / self.step_samples;
+            self.until_step *= scale;
+            self.until_ratchet *= scale;
+            self.jitter *= scale;
+            for off in &mut self.pending_offs {
+                off.samples *= scale;
+            }
+            self.step_samples = step_samples;
+        }
+    }
+
+    // Starts from the first step on the next sample, and from the top of the
+    // song in song mode
+    pub fn start(&mut self) {
+        self.running = true;
+        self.queued = None;
+        self.silent |= std::mem::take(&mut self.silence_queued);
+        if let Some(&first) = self.song.first() {
+            self.playing = first;
+            self.song_position = Some(0);
+            self.silent = false;
+        }
+        self.pattern_started = true;
+        self.repeats = 0;
+        self.step_pattern = self.playing;
+        self.current_step = 0;
+        // A first step nudged early can only start on time
+        self.jitter = self.nudge_offset(self.playing, 0).max(0.0);
+        self.until_step = self.jitter;
+        self.ratchets_left = 0;
+    }
+
+    // Jumps to the step under `beats` of the pattern clock, e.g. when the
+    // transport loops; in song mode the clock runs through the whole song.
+    // Notes already sounding keep their gates.
+    pub fn locate(&mut self, beats: f64) {
+        let steps = beats.max(0.0) * self.steps_per_beat as f64;
+        // A step that began less than a sample ago still fires, so a loop
+        // wrap landing a fraction of a sample late doesn't skip it
+        let next = (steps - 1.0 / self.step_samples).ceil().max(0.0);
+        let mut step = next as usize;
+        let before = self.playing;
+        if !self.song.is_empty() {
+            let song_steps: usize = self.song.iter().map(|&p| self.patterns[p].length).sum();
+            step %= song_steps;
+            for (position, &pattern) in self.song.iter().enumerate() {
+                let length = self.patterns[pattern].length;
+                if step < length {
+                    self.playing = pattern;
+                    self.song_position = Some(position);
+                    self.silent = false;
+                    break;
+                }
+                step -= length;
+            }
+        }
+        self.queued = None;
+        self.pattern_started |= self.playing != before;
+        self.repeats = 0;
+        self.step_pattern = self.playing;
+        self.current_step = step % self.patterns[self.playing].length;
+        self.until_step = (next - steps) * self.step_samples + self.swing_offset(self.current_step);
+        self.ratchets_left = 0;
+        self.jitter = 0.0;
+    }
+
+    // Step after the current one, moving on to the queued pattern at a bar
+    // line, or at the end of this one to the song's next pattern or where
+    // its follow action leads
+    fn next_step(&mut self) -> usize {
+        let next = self.current_step + 1;
+        let length = self.patterns[self.playing].length;
+        let bar_line = next.is_multiple_of(self.bar_steps()) || next == length;
+        if self.silence_queued && bar_line {
+            self.silence_queued = false;
+            self.silent = true;
+        }
+        let pattern = match self.queued {
+            Some(queued) if bar_line => {
+                self.queued = None;
+                self.silent = false;
+                Some(queued)
+            }
+            _ if next < length => None,
+            _ if self.song.is_empty() => self.follow(),
+            _ => {
+                let position = self.song_position.map_or(0, |p| (p + 1) % self.song.len());
+                self.song_position = Some(position);
+                self.silent = false;
+                Some(self.song[position])
+            }
+        };
+        match pattern {
+            Some(pattern) => {
+                self.pattern_started |= pattern != self.playing;
+                self.playing = pattern;
+                self.repeats = 0;
+                0
+            }
+            None => next % length,
+        }
+    }
+
+    // Counts a finished pass of the playing pattern and, once its follow
+    // action is due, the pattern to move to; None to loop it again
+    fn follow(&mut self) -> Option<usize> {
+        if self.silent {
+            return None;
+        }
+        self.repeats += 1;
+        let Pattern { follow, follow_repeats, group, .. } = self.patterns[self.playing];
+        if follow == FollowAction::None || self.repeats < follow_repeats {
+            return None;
+        }
+        self.repeats = 0;
+        let playing = self.playing;
+        match follow {
+            FollowAction::None => None,
+            FollowAction::Next => {
+                (1..MAX_PATTERNS).map(|i| (playing + i) % MAX_PATTERNS).find(|&p| self.pattern_has_notes(p))
+            }
+            FollowAction::Random => {
+                let roll = self.random.unipolar();
+                let candidate =
+                    |p: &usize| *p != playing && self.patterns[*p].group == group && self.pattern_has_notes(*p);
+                let count = (0..MAX_PATTERNS).filter(candidate).count();
+                if count == 0 {
+                    return None;
+                }
+                let pick = ((roll * count as f32) as usize).min(count - 1);
+                (0..MAX_PATTERNS).filter(candidate).nth(pick)
+            }
+            FollowAction::Stop => {
+                self.silent = true;
+                None
+            }
+        }
+    }
+
+    // Ends notes held open for a tie except those step `keep` carries on
+    fn release_held_open(&mut self, keep: usize, events: &mut Vec<SequencerEvent>) {
+        let kept: &[u8] = self.patterns[self.step_pattern].steps.get(keep).map_or(&[], |step| &step.notes);
+        let mut i = 0;
+        while i < self.pending_offs.len() {
+            let off = &self.pending_offs[i];
+            if off.samples.is_infinite() && !kept.contains(&off.note) {
+                let off = self.pending_offs.swap_remove(i);
+                events.push(SequencerEvent::NoteOff { note: off.note });
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    // Arms live recording into the playing pattern. Notes are added to what
+    // the steps already hold, so each pass overdubs the last. `grid` is the
+    // quantize grid in steps, 0 for none. A grid that isn't a whole number
+    // of steps, like triplets over sixteenths, nudges notes off the steps
+    // onto its lines.
+    pub fn start_recording(&mut self, grid: f64) {
+        self.recording = true;
+        self.record_grid = grid.clamp(0.0, MAX_STEPS as f64);
+    }
+
+    // Ends recording; notes still held are written up to now
+    pub fn stop_recording(&mut self) {
+        self.finish_recording_notes();
+        self.recording = false;
+    }
+
+    // Writes notes still held as if let go now
+    pub fn finish_recording_notes(&mut self) {
+        while let Some(held) = self.recording_notes.last() {
+            self.record_note_off(held.note);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    // Writes a live note into the step under the playhead, or the nearest
+    // grid step. Its length is set when it's let go.
+    pub fn record_note_on(&mut self, note: u8, velocity: f32) {
+        if !self.recording || !self.running {
+            return;
+        }
+        let note = note.min(127);
+        let (pattern, position) = self.position_steps();
+        let length = self.patterns[pattern].length;
+        let grid = if self.record_grid > 0.0 { self.record_grid } else { 1.0 };
+        let snapped = (position / grid).round() * grid;
+        let index = snapped.round() as usize % length;
+        let step = &mut self.patterns[pattern].steps[index];
+        if step.notes.is_empty() {
+            step.velocity = velocity.clamp(0.0, 1.0);
+            step.gate = 0.01;
+            step.nudge = (snapped - snapped.round()) as f32;
+        } else if step.notes.contains(&note) || step.notes.len() == MAX_STEP_NOTES {
+            return;
+        } else {
+            step.velocity = step.velocity.max(velocity.clamp(0.0, 1.0));
+        }
+        step.notes.push(note);
+        self.recording_notes.retain(|held| held.note != note);
+        self.recording_notes.push(RecordingNote { note, pattern, step: index, start: position });
+    }
+
+    // Sets the recorded note's step gate to how long it was held, snapped to
+    // the grid when quantizing. A chord's step keeps its longest note.
+    pub fn record_note_off(&mut self, note: u8) {
+        let Some(i) = self.recording_notes.iter().position(|held| held.note == note) else {
+            return;
+        };
+        let held = self.recording_notes.swap_remove(i);
+        let (_, position) = self.position_steps();
+        let pattern = &mut self.patterns[held.pattern];
+        let mut gate = (position - held.start).rem_euclid(pattern.length as f64);
+        if self.record_grid > 0.0 {
+            let grid = self.record_grid;
+            gate = ((gate / grid).round() * grid).max(grid);
+        }
+        let step = &mut pattern.steps[held.step];
+        step.gate = step.gate.max(gate.clamp(0.01, MAX_STEPS as f64) as f32);
+    }
+
+    // Pattern under the playhead and the playhead in steps from its start
+    fn position_steps(&self) -> (usize, f64) {
+        let length = self.patterns[self.step_pattern].length as f64;
+        // Until the next step fires the previous pattern is still playing
+        let next = if self.step_pattern == self.playing { self.current_step as f64 } else { length };
+        (self.step_pattern, (next - self.until_step / self.step_samples).rem_euclid(length))
+    }
+
+    // Pattern under the playhead and the playhead in beats from its start,
+    // None while stopped
+    pub fn position_beats(&self) -> Option<(usize, f64)> {
+        if !self.running {
+            return None;
+        }
+        let (pattern, steps) = self.position_steps();
+        Some((pattern, steps / self.steps_per_beat as f64))
+    }
+
+    // Stops and releases every note still sounding. Recording stays armed
+    // for the next start, but notes held now end here.
+    pub fn stop(&mut self, events: &mut Vec<SequencerEvent>) {
+        self.finish_recording_notes();
+        self.running = false;
+        self.ratchets_left = 0;
+        events.extend(self.pending_offs.drain(..).map(|off| SequencerEvent::NoteOff { note: off.note }));
+    }
+
+    // The step most recently started, or None while stopped
+    pub fn current_step(&self) -> Option<usize> {
+        if !self.running {
+            return None;
+        }
+        let length = self.patterns[self.playing].length;
+        Some((self.current_step + length - 1) % length)
+    }
+
+    // Plays one hit of a step. `offset` is the hit's exact start relative to
+    // now (zero or a fraction of a sample in the past), `gate` its length in
+    // steps. `first` marks the step's first hit, which carries on notes
+    // still held for a tie or slide instead of retriggering them; `last` its
+    // final hit, whose notes are held open if the next step ties or slides.
+    fn play_hit(&mut self, index: usize, offset: f64, gate: f64, first: bool, last: bool, events: &mut Vec<SequencerEvent>) {
+        let pattern = &self.patterns[self.step_pattern];
+        let next = &pattern.steps[(index + 1) % pattern.length];
+        let step = &pattern.steps[index];
+        let (tied, slide) = (first && step.tie, first && step.slide);
+        let gate_samples = gate * self.step_samples + offset;
+        for &note in &step.notes {
+            let held_open = last && ((next.slide && !next.notes.is_empty()) || (next.tie && next.notes.contains(&note)));
+            let samples = if held_open { f64::INFINITY } else { gate_samples };
+            let pending = self.pending_offs.iter().position(|off| off.note == note);
+            if let (true, Some(i)) = (tied || slide, pending) {
+                let off = &mut self.pending_offs[i];
+                off.samples = if off.samples.is_finite() && !held_open { off.samples.max(samples) } else { samples };
+                continue;
+            }
+            let mut velocity = step.velocity;
+            if self.velocity_jitter > 0.0 {
+                velocity = (velocity * (1.0 + self.velocity_jitter * self.random.bipolar())).clamp(0.0, 1.0);
+            }
+            // Bends the closest note held open that this step doesn't play
+            let from = self
+                .pending_offs
+                .iter()
+                .enumerate()
+                .filter(|(_, off)| slide && off.samples.is_infinite() && !step.notes.contains(&off.note))
+                .min_by_key(|(_, off)| off.note.abs_diff(note))
+                .map(|(i, _)| i);
+            if let Some(i) = from {
+                let off = &mut self.pending_offs[i];
+                events.push(SequencerEvent::Slide { from: off.note, to: note, velocity });
+                *off = PendingOff { note, samples };
+                continue;
+            }
+            // A note still held from an earlier step is retriggered
+            if let Some(i) = pending {
+                self.pending_offs.swap_remove(i);
+                events.push(SequencerEvent::NoteOff { note });
+            }
+            events.push(SequencerEvent::NoteOn { note, velocity });
+            self.pending_offs.push(PendingOff { note, samples });
+        }
+        // Held notes with nowhere to slide to end here
+        if slide {
+            self.release_held_open(index, events);
+        }
+    }
+}
+
+impl NoteSource for Sequencer {
+    fn samples_until_next_event(&self) -> Option<usize> {
+        if !self.running && self.pending_offs.is_empty() {
+            return None;
+        }
+        let mut next = if self.running { self.until_step } else { f64::MAX };
+        if self.ratchets_left > 0 {
+            next = next.min(self.until_ratchet);
+        }
+        for off in &self.pending_offs {
+            next = next.min(off.samples);
+        }
+        Some(next.max(0.0).ceil() as usize)
+    }
+
+    fn advance(&mut self, samples: usize) {
+        let samples = samples as f64;
+        if self.running {
+            self.until_step -= samples;
+            self.until_ratchet -= samples;
+        }
+        for off in &mut self.pending_offs {
+            off.samples -= samples;
+        }
+    }
+
+    // Note-offs come first so a note repeated on the next step retriggers
+    // cleanly
+    fn take_due_events(&mut self, events: &mut Vec<SequencerEvent>) {
+        let mut i = 0;
+        while i < self.pending_offs.len() {
+            if self.pending_offs[i].samples <= 0.0 {
+                let off = self.pending_offs.swap_remove(i);
+                events.push(SequencerEvent::NoteOff { note: off.note });
+            } else {
+                i += 1;
+            }
+        }
+
+        if self.running && self.ratchets_left > 0 && self.until_ratchet <= 0.0 {
+            let step = &self.patterns[self.step_pattern].steps[self.ratchet_step];
+            let (gate, ratchet) = (step.gate as f64, step.ratchet as f64);
+            let last = self.ratchets_left == 1;
+            self.play_hit(self.ratchet_step, self.until_ratchet, gate / ratchet, false, last, events);
+            self.until_ratchet += self.step_samples / ratchet;
+            self.ratchets_left -= 1;
+        }
+
+        while self.running && self.until_step <= 0.0 {
+            self.step_pattern = self.playing;
+            let step = &self.patterns[self.playing].steps[self.current_step];
+            let (gate, probability, ratchet, tie, slide) =
+                (step.gate as f64, step.probability, step.ratchet, step.tie, step.slide);
+            // A new step cuts off any hits left from a ratcheted one
+            self.ratchets_left = 0;
+            if self.silent {
+                self.pattern_started = true;
+                self.release_held_open(usize::MAX, events);
+            } else if self.pattern_started {
+                events.push(SequencerEvent::PatternStart { pattern: self.playing as u8 });
+                self.pattern_started = false;
+            } else if self.current_step == 0 {
+                events.push(SequencerEvent::PatternLoop { pattern: self.playing as u8 });
+            }
+            let plays = !self.silent && (probability >= 1.0 || self.random.unipolar() < probability);
+            // Notes held open for this step that it doesn't carry on end here;
+            // a slide sorts them out as it plays
+            if !(plays && slide) {
+                self.release_held_open(if plays && tie { self.current_step } else { usize::MAX }, events);
+            }
+            if plays {
+                // Gates are measured from the step's exact start, which may
+                // lie a fraction of a sample in the past
+                self.play_hit(self.current_step, self.until_step, gate / ratchet as f64, true, ratchet == 1, events);
+                if ratchet > 1 {
+                    self.ratchet_step = self.current_step;
+                    self.ratchets_left = ratchet - 1;
+                    self.until_ratchet = self.until_step + self.step_samples / ratchet as f64;
+                }
+            }
+            let next = self.next_step();
+            let jitter = self.next_jitter() + self.nudge_offset(self.playing, next);
+            self.until_step += self.step_samples + self.swing_offset(next) - self.swing_offset(self.current_step)
+                + jitter - self.jitter;
+            self.jitter = jitter;
+            self.current_step = next;
+        }
+    }
+}
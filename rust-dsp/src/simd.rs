@@ -0,0 +1,189 @@
+// Four-lane f32 vectors for the inner loops the `simd` feature vectorizes.
+// On wasm32 built with simd128 (RUSTFLAGS="-C target-feature=+simd128")
+// they map onto v128 intrinsics; anywhere else they are plain arrays the
+// compiler is free to vectorize itself. Every operation is the IEEE
+// operation the scalar code performs, in the same order, so vector and
+// scalar builds render identical samples.
+
+use std::ops::{Add, Mul, Sub};
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod lanes {
+    use core::arch::wasm32::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(v128);
+
+    impl F32x4 {
+        #[inline]
+        pub fn splat(value: f32) -> Self {
+            F32x4(f32x4_splat(value))
+        }
+
+        #[inline]
+        pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+            F32x4(f32x4(a, b, c, d))
+        }
+
+        // The first four values of `values`
+        #[inline]
+        pub fn load(values: &[f32]) -> Self {
+            let values = &values[..4];
+            // In bounds per the slice above; v128 loads need no alignment
+            F32x4(unsafe { v128_load(values.as_ptr() as *const v128) })
+        }
+
+        #[inline]
+        pub fn store(self, out: &mut [f32]) {
+            let out = &mut out[..4];
+            unsafe { v128_store(out.as_mut_ptr() as *mut v128, self.0) }
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            F32x4(f32x4_add(self.0, other.0))
+        }
+
+        #[inline]
+        pub fn sub(self, other: Self) -> Self {
+            F32x4(f32x4_sub(self.0, other.0))
+        }
+
+        #[inline]
+        pub fn mul(self, other: Self) -> Self {
+            F32x4(f32x4_mul(self.0, other.0))
+        }
+
+        // Zero wherever |value| < threshold, as denormal::flush
+        #[inline]
+        pub fn flush_below(self, threshold: f32) -> Self {
+            let tiny = f32x4_lt(f32x4_abs(self.0), f32x4_splat(threshold));
+            F32x4(v128_andnot(self.0, tiny))
+        }
+
+        // Lanes (a0, a0, a2, a2) and (a1, a1, a3, a3)
+        #[inline]
+        pub fn even_odd(self) -> (Self, Self) {
+            (
+                F32x4(i32x4_shuffle::<0, 0, 2, 2>(self.0, self.0)),
+                F32x4(i32x4_shuffle::<1, 1, 3, 3>(self.0, self.0)),
+            )
+        }
+
+        // Lanes (a0, a1, a0, a1) and (a2, a3, a2, a3)
+        #[inline]
+        pub fn low_high(self) -> (Self, Self) {
+            (
+                F32x4(i32x4_shuffle::<0, 1, 0, 1>(self.0, self.0)),
+                F32x4(i32x4_shuffle::<2, 3, 2, 3>(self.0, self.0)),
+            )
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod lanes {
+    #[derive(Clone, Copy)]
+    pub struct F32x4([f32; 4]);
+
+    impl F32x4 {
+        #[inline]
+        pub fn splat(value: f32) -> Self {
+            F32x4([value; 4])
+        }
+
+        #[inline]
+        pub fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+            F32x4([a, b, c, d])
+        }
+
+        #[inline]
+        pub fn load(values: &[f32]) -> Self {
+            F32x4([values[0], values[1], values[2], values[3]])
+        }
+
+        #[inline]
+        pub fn store(self, out: &mut [f32]) {
+            out[..4].copy_from_slice(&self.0);
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            self.zip(other, |a, b| a + b)
+        }
+
+        #[inline]
+        pub fn sub(self, other: Self) -> Self {
+            self.zip(other, |a, b| a - b)
+        }
+
+        #[inline]
+        pub fn mul(self, other: Self) -> Self {
+            self.zip(other, |a, b| a * b)
+        }
+
+        #[inline]
+        pub fn flush_below(self, threshold: f32) -> Self {
+            F32x4(self.0.map(|a| if a.abs() < threshold { 0.0 } else { a }))
+        }
+
+        #[inline]
+        pub fn even_odd(self) -> (Self, Self) {
+            let [a0, a1, a2, a3] = self.0;
+            (F32x4([a0, a0, a2, a2]), F32x4([a1, a1, a3, a3]))
+        }
+
+        #[inline]
+        pub fn low_high(self) -> (Self, Self) {
+            let [a0, a1, a2, a3] = self.0;
+            (F32x4([a0, a1, a0, a1]), F32x4([a2, a3, a2, a3]))
+        }
+
+        #[inline]
+        fn zip(self, other: Self, op: impl Fn(f32, f32) -> f32) -> Self {
+            let (a, b) = (self.0, other.0);
+            F32x4([op(a[0], b[0]), op(a[1], b[1]), op(a[2], b[2]), op(a[3], b[3])])
+        }
+    }
+}
+
+pub use lanes::F32x4;
+
+impl Add for F32x4 {
+    type Output = F32x4;
+
+    #[inline]
+    fn add(self, other: F32x4) -> F32x4 {
+        F32x4::add(self, other)
+    }
+}
+
+impl Sub for F32x4 {
+    type Output = F32x4;
+
+    #[inline]
+    fn sub(self, other: F32x4) -> F32x4 {
+        F32x4::sub(self, other)
+    }
+}
+
+impl Mul for F32x4 {
+    type Output = F32x4;
+
+    #[inline]
+    fn mul(self, other: F32x4) -> F32x4 {
+        F32x4::mul(self, other)
+    }
+}
+
+impl F32x4 {
+    // Unnormalized 4-point Walsh-Hadamard transform, butterflies in the
+    // same order as the scalar one (adjacent pairs, then pairs of pairs)
+    #[inline]
+    pub fn hadamard(self) -> F32x4 {
+        let (even, odd) = self.even_odd();
+        let x = even + odd * F32x4::new(1.0, -1.0, 1.0, -1.0);
+        let (low, high) = x.low_high();
+        low + high * F32x4::new(1.0, 1.0, -1.0, -1.0)
+    }
+}
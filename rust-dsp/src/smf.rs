@@ -0,0 +1,78 @@
+// Standard MIDI File writer for taking patterns into a DAW: a format 0 file
+// with one track holding the tempo, a 4/4 time signature, the track name
+// and the notes on channel 1.
+
+pub const TICKS_PER_BEAT: u16 = 480;
+
+// Times in ticks
+pub struct SmfNote {
+    pub start: u32,
+    pub length: u32,
+    pub note: u8,
+    pub velocity: u8,
+}
+
+pub fn write(name: &str, bpm: f32, notes: &[SmfNote], end: u32) -> Vec<u8> {
+    // (tick, order, bytes); at the same tick note-offs go before note-ons
+    let mut events: Vec<(u32, u8, [u8; 3])> = Vec::with_capacity(notes.len() * 2);
+    for note in notes {
+        let key = note.note.min(127);
+        events.push((note.start, 1, [0x90, key, note.velocity.clamp(1, 127)]));
+        events.push((note.start + note.length.max(1), 0, [0x80, key, 0x40]));
+    }
+    events.sort_by_key(|&(tick, order, _)| (tick, order));
+
+    let mut track = Vec::new();
+    let name: Vec<u8> = name.bytes().filter(u8::is_ascii).collect();
+    push_meta(&mut track, 0x03, &name);
+    let tempo = (60_000_000.0 / bpm as f64).round() as u32;
+    push_meta(&mut track, 0x51, &tempo.to_be_bytes()[1..]);
+    // 4/4, one click per quarter, 8 thirty-seconds per quarter
+    push_meta(&mut track, 0x58, &[4, 2, 24, 8]);
+
+    let mut last = 0;
+    for (tick, _, bytes) in events {
+        push_varlen(&mut track, tick - last);
+        track.extend_from_slice(&bytes);
+        last = tick;
+    }
+    push_varlen(&mut track, end.saturating_sub(last));
+    track.extend_from_slice(&[0xFF, 0x2F, 0x00]);
+
+    let mut file = Vec::with_capacity(22 + track.len());
+    file.extend_from_slice(b"MThd");
+    file.extend_from_slice(&6u32.to_be_bytes());
+    file.extend_from_slice(&0u16.to_be_bytes());
+    file.extend_from_slice(&1u16.to_be_bytes());
+    file.extend_from_slice(&TICKS_PER_BEAT.to_be_bytes());
+    file.extend_from_slice(b"MTrk");
+    file.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    file.extend_from_slice(&track);
+    file
+}
+
+// Meta event at delta time 0
+fn push_meta(track: &mut Vec<u8>, kind: u8, data: &[u8]) {
+    track.extend_from_slice(&[0x00, 0xFF, kind]);
+    push_varlen(track, data.len() as u32);
+    track.extend_from_slice(data);
+}
+
+// Variable-length quantity: 7 bits per byte, most significant first, with
+// the top bit set on all but the last byte
+fn push_varlen(track: &mut Vec<u8>, value: u32) {
+    let mut bytes = [0u8; 5];
+    let mut count = 0;
+    let mut value = value;
+    loop {
+        bytes[count] = (value & 0x7F) as u8;
+        count += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for i in (0..count).rev() {
+        track.push(if i > 0 { bytes[i] | 0x80 } else { bytes[i] });
+    }
+}
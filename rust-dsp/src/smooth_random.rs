@@ -0,0 +1,97 @@
+// Smooth random modulation for organic drift, as opposed to the LFO's
+// stepped sample-and-hold: value noise (random levels at `rate` points a
+// second, eased from one to the next) with a second octave at twice the rate
+// for a little detail. Cutoff and effect mix each read their own stretch of
+// the noise and every voice's pitch reads another, so nothing moves in step.
+// Block-rate, like the motion macro.
+
+// Where each destination reads the noise, far enough apart to be unrelated
+const CUTOFF_OFFSET: f64 = 1000.5;
+const FX_OFFSET: f64 = 2000.25;
+const PITCH_OFFSET: f64 = 3000.75;
+const VOICE_SPREAD: f64 = 37.3;
+// Level of the second octave relative to the first
+const DETAIL: f32 = 0.35;
+
+#[derive(Clone)]
+pub struct SmoothRandom {
+    sample_rate: f32,
+    rate: f32,
+    position: f64,
+    cutoff_octaves: f32,
+    pitch_cents: f32,
+    fx_mix: f32,
+}
+
+impl SmoothRandom {
+    pub fn new(sample_rate: f32) -> Self {
+        SmoothRandom {
+            sample_rate,
+            rate: 0.5,
+            position: 0.0,
+            cutoff_octaves: 0.0,
+            pitch_cents: 0.0,
+            fx_mix: 0.0,
+        }
+    }
+
+    // New random levels per second, 0.01-20
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.rate = rate_hz.clamp(0.01, 20.0);
+    }
+
+    // Depths: cutoff offset in octaves (0-4), pitch in cents per voice
+    // (0-100) and effect mix, added to the delay and reverb mixes (0-1)
+    pub fn set_amounts(&mut self, cutoff_octaves: f32, pitch_cents: f32, fx_mix: f32) {
+        self.cutoff_octaves = cutoff_octaves.clamp(0.0, 4.0);
+        self.pitch_cents = pitch_cents.clamp(0.0, 100.0);
+        self.fx_mix = fx_mix.clamp(0.0, 1.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.cutoff_octaves > 0.0 || self.pitch_cents > 0.0 || self.fx_mix > 0.0
+    }
+
+    pub fn advance(&mut self, samples: usize) {
+        self.position += (self.rate * samples as f32 / self.sample_rate) as f64;
+    }
+
+    // Cutoff offset in octaves, shared by every voice
+    pub fn cutoff_octaves(&self) -> f32 {
+        self.noise(CUTOFF_OFFSET) * self.cutoff_octaves
+    }
+
+    // Pitch offset in cents for the voice at `index`
+    pub fn pitch_cents(&self, index: usize) -> f32 {
+        self.noise(PITCH_OFFSET + index as f64 * VOICE_SPREAD) * self.pitch_cents
+    }
+
+    // Offset for the delay and reverb mixes
+    pub fn fx_mix(&self) -> f32 {
+        self.noise(FX_OFFSET) * self.fx_mix
+    }
+
+    // -1..1
+    fn noise(&self, offset: f64) -> f32 {
+        let x = self.position + offset;
+        (value_noise(x) + value_noise(x * 2.0 + 0.5) * DETAIL) / (1.0 + DETAIL)
+    }
+}
+
+fn value_noise(x: f64) -> f32 {
+    let cell = x.floor();
+    let t = (x - cell) as f32;
+    let eased = t * t * (3.0 - 2.0 * t);
+    let a = lattice(cell as i64);
+    let b = lattice(cell as i64 + 1);
+    a + (b - a) * eased
+}
+
+// Random level -1..1 for a lattice point, the same every time it's asked for
+fn lattice(index: i64) -> f32 {
+    let mut h = (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    h ^= h >> 32;
+    h = h.wrapping_mul(0xD6E8_FEB8_6659_FD93);
+    h ^= h >> 32;
+    (h >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
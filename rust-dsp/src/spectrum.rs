@@ -0,0 +1,74 @@
+use std::f32::consts::PI;
+use crate::fft::{Complex, Fft};
+
+pub const SPECTRUM_SIZE: usize = 2048;
+pub const SPECTRUM_BINS: usize = SPECTRUM_SIZE / 2;
+
+// Keeps the last SPECTRUM_SIZE samples of a signal (mono, mid of the stereo
+// pair) for the spectrum and scope views. Only the ring write happens on the
+// audio path; the FFT and trigger search run when the UI asks.
+pub struct SpectrumAnalyzer {
+    fft: Fft,
+    window: Vec<f32>,
+    history: Vec<f32>,
+    write: usize,
+    buffer: Vec<Complex>,
+    // Scales bin magnitudes so a full-scale sine reads 1.0 in its bin
+    normalize: f32,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new() -> Self {
+        let window: Vec<f32> = (0..SPECTRUM_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_SIZE as f32).cos())
+            .collect();
+        let normalize = 2.0 / window.iter().sum::<f32>();
+        SpectrumAnalyzer {
+            fft: Fft::new(SPECTRUM_SIZE),
+            window,
+            history: vec![0.0; SPECTRUM_SIZE],
+            write: 0,
+            buffer: vec![Complex::default(); SPECTRUM_SIZE],
+            normalize,
+        }
+    }
+
+    pub fn push_block(&mut self, left: &[f32], right: &[f32]) {
+        for (&l, &r) in left.iter().zip(right) {
+            self.history[self.write] = (l + r) * 0.5;
+            self.write = (self.write + 1) % SPECTRUM_SIZE;
+        }
+    }
+
+    // Linear magnitudes of bins 0..SPECTRUM_BINS (bin k at k * rate / 2048)
+    // into the start of `out`; any extra room is left untouched
+    pub fn magnitudes(&mut self, out: &mut [f32]) {
+        // Oldest sample first, so the window lines up with the ring
+        for (i, value) in self.buffer.iter_mut().enumerate() {
+            let sample = self.history[(self.write + i) % SPECTRUM_SIZE];
+            *value = Complex::new(sample * self.window[i], 0.0);
+        }
+        self.fft.forward(&mut self.buffer);
+        for (magnitude, bin) in out.iter_mut().zip(&self.buffer[..SPECTRUM_BINS]) {
+            *magnitude = (bin.re * bin.re + bin.im * bin.im).sqrt() * self.normalize;
+        }
+    }
+
+    // The most recent out.len() samples (at most SPECTRUM_SIZE), oldest
+    // first. With `trigger` the view starts at the latest rising zero
+    // crossing that still leaves room for it, so periodic waveforms hold
+    // still from call to call; without one it falls back to the latest.
+    pub fn waveform(&self, out: &mut [f32], trigger: bool) {
+        let len = out.len().min(SPECTRUM_SIZE);
+        let sample = |i: usize| self.history[(self.write + i) % SPECTRUM_SIZE];
+        let mut start = SPECTRUM_SIZE - len;
+        if trigger {
+            if let Some(crossing) = (1..=start).rev().find(|&i| sample(i - 1) <= 0.0 && sample(i) > 0.0) {
+                start = crossing;
+            }
+        }
+        for (i, value) in out[..len].iter_mut().enumerate() {
+            *value = sample(start + i);
+        }
+    }
+}
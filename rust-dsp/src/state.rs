@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use crate::automation::AutomationPoint;
+use crate::metadata::Metadata;
+use crate::patch::Patch;
+
+// Full engine state for presets and project files: both tracks' patches
+// and mixer settings, the tempo, the step patterns and the song. Every level takes
+// defaults for missing fields, so files from older versions still load.
+pub const STATE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineState {
+    pub version: u32,
+    pub bpm: f32,
+    pub master_volume: f32,
+    // By engine index: timeline, live
+    pub tracks: Vec<TrackState>,
+    // Pattern 0, with the resolution, swing and humanize every pattern uses
+    pub pattern: PatternState,
+    // Patterns 1 onwards, up to the last with notes; only their length and
+    // steps are used
+    pub patterns: Vec<PatternState>,
+    // Song mode pattern order, empty when off
+    pub song: Vec<u8>,
+}
+
+impl Default for EngineState {
+    fn default() -> Self {
+        EngineState {
+            version: STATE_VERSION,
+            bpm: 120.0,
+            master_volume: 1.0,
+            tracks: Vec::new(),
+            pattern: PatternState::default(),
+            patterns: Vec::new(),
+            song: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackState {
+    pub patch: Patch,
+    pub metadata: Metadata,
+    pub volume: f32,
+    pub pan: f32,
+    pub delay_send: f32,
+    pub reverb_send: f32,
+}
+
+impl Default for TrackState {
+    fn default() -> Self {
+        TrackState {
+            patch: Patch::default(),
+            metadata: Metadata::default(),
+            volume: 0.7,
+            pan: 0.0,
+            delay_send: 0.0,
+            reverb_send: 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternState {
+    pub length: usize,
+    pub steps_per_beat: u32,
+    pub swing: f32,
+    pub humanize_timing_ms: f32,
+    pub humanize_velocity: f32,
+    pub steps: Vec<StepState>,
+    // Timeline patch switched to as the pattern starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<Patch>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub automation: Vec<AutomationLaneState>,
+}
+
+impl Default for PatternState {
+    fn default() -> Self {
+        PatternState {
+            length: 16,
+            steps_per_beat: 4,
+            swing: 0.0,
+            humanize_timing_ms: 0.0,
+            humanize_velocity: 0.0,
+            steps: Vec::new(),
+            preset: None,
+            automation: Vec::new(),
+        }
+    }
+}
+
+// Points of one automated parameter, sorted by beat
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomationLaneState {
+    pub param: u32,
+    pub points: Vec<AutomationPoint>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct StepState {
+    pub notes: Vec<u8>,
+    pub velocity: f32,
+    pub gate: f32,
+    pub probability: f32,
+    pub ratchet: u32,
+    pub tie: bool,
+    pub slide: bool,
+    pub nudge: f32,
+}
+
+impl Default for StepState {
+    fn default() -> Self {
+        StepState {
+            notes: Vec::new(),
+            velocity: 0.8,
+            gate: 0.5,
+            probability: 1.0,
+            ratchet: 1,
+            tie: false,
+            slide: false,
+            nudge: 0.0,
+        }
+    }
+}
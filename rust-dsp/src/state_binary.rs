@@ -0,0 +1,294 @@
+use crate::automation::AutomationPoint;
+use crate::metadata::Metadata;
+use crate::patch::Patch;
+use crate::state::{AutomationLaneState, EngineState, PatternState, StepState, TrackState};
+
+// Compact binary form of EngineState for autosaves and shareable URLs,
+// about a quarter the size of the JSON. Little-endian throughout:
+//
+//   "WCST" <version u8> <header> <track count u16> <track>... <pattern>
+//   <song>
+//
+// Every header, track, pattern and step is a record: a u32 byte length and
+// then its fields. Fields are only ever appended to a record, so a reader
+// skips whatever a newer version added at the end and fields an older file
+// lacks keep their defaults, like the JSON. A patch is its `Patch::values`
+// list, which has the same rule. The version only changes if a field is
+// ever reinterpreted.
+//
+//   header:  bpm f32, master volume f32
+//   track:   patch (count u16, f32s), volume f32, pan f32, delay send f32,
+//            reverb send f32, color u8, name str, tags (count u16, strs)
+//   pattern: length u32, steps per beat u32, swing f32, humanize timing
+//            ms f32, humanize velocity f32, steps (count u32, steps),
+//            preset (count u16 of patch values, 0 for none, f32s),
+//            automation (count u16, lanes)
+//   lane:    param u32, points (count u16; beat f64, value f32, curve u8)
+//   step:    notes (count u8, u8s), velocity f32, gate f32,
+//            probability f32, ratchet u8, tie u8, slide u8,
+//            nudge f32
+//   song:    order (count u16, u8s), further patterns (count u16,
+//            patterns)
+//
+// Strings are a u16 byte length and UTF-8.
+
+const MAGIC: &[u8; 4] = b"WCST";
+const BINARY_VERSION: u8 = 1;
+
+pub fn encode(state: &EngineState) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1024);
+    out.extend_from_slice(MAGIC);
+    out.push(BINARY_VERSION);
+
+    record(&mut out, |out| {
+        put_f32(out, state.bpm);
+        put_f32(out, state.master_volume);
+    });
+
+    out.extend_from_slice(&(state.tracks.len().min(u16::MAX as usize) as u16).to_le_bytes());
+    for track in state.tracks.iter().take(u16::MAX as usize) {
+        record(&mut out, |out| {
+            let values = track.patch.values();
+            out.extend_from_slice(&(values.len() as u16).to_le_bytes());
+            for value in values {
+                put_f32(out, value);
+            }
+            for value in [track.volume, track.pan, track.delay_send, track.reverb_send] {
+                put_f32(out, value);
+            }
+            out.push(track.metadata.color);
+            put_str(out, &track.metadata.name);
+            let tags = &track.metadata.tags[..track.metadata.tags.len().min(u16::MAX as usize)];
+            out.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+            for tag in tags {
+                put_str(out, tag);
+            }
+        });
+    }
+
+    put_pattern(&mut out, &state.pattern);
+    record(&mut out, |out| {
+        let song = &state.song[..state.song.len().min(u16::MAX as usize)];
+        out.extend_from_slice(&(song.len() as u16).to_le_bytes());
+        out.extend_from_slice(song);
+        let patterns = &state.patterns[..state.patterns.len().min(u16::MAX as usize)];
+        out.extend_from_slice(&(patterns.len() as u16).to_le_bytes());
+        for pattern in patterns {
+            put_pattern(out, pattern);
+        }
+    });
+    out
+}
+
+// None if the data isn't a state file or a record is cut short
+pub fn decode(data: &[u8]) -> Option<EngineState> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return None;
+    }
+    // Every version so far shares one layout
+    let _version = reader.u8()?;
+
+    let mut state = EngineState::default();
+    let mut header = reader.record()?;
+    state.bpm = header.f32().unwrap_or(state.bpm);
+    state.master_volume = header.f32().unwrap_or(state.master_volume);
+
+    let track_count = reader.u16()?;
+    for _ in 0..track_count {
+        let mut fields = reader.record()?;
+        let mut track = TrackState::default();
+        let count = fields.u16().unwrap_or(0);
+        let mut values = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            values.push(fields.f32()?);
+        }
+        track.patch.set_values(&values);
+        track.volume = fields.f32().unwrap_or(track.volume);
+        track.pan = fields.f32().unwrap_or(track.pan);
+        track.delay_send = fields.f32().unwrap_or(track.delay_send);
+        track.reverb_send = fields.f32().unwrap_or(track.reverb_send);
+        let mut metadata = Metadata { color: fields.u8().unwrap_or(0), ..Metadata::default() };
+        metadata.name = fields.str().unwrap_or_default();
+        for _ in 0..fields.u16().unwrap_or(0) {
+            metadata.tags.push(fields.str()?);
+        }
+        track.metadata = metadata;
+        state.tracks.push(track);
+    }
+
+    state.pattern = read_pattern(&mut reader)?;
+
+    // Files from before song mode end here
+    if !reader.data.is_empty() {
+        let mut fields = reader.record()?;
+        let count = fields.u16().unwrap_or(0);
+        state.song = fields.bytes(count as usize)?.to_vec();
+        for _ in 0..fields.u16().unwrap_or(0) {
+            state.patterns.push(read_pattern(&mut fields)?);
+        }
+    }
+    Some(state)
+}
+
+fn put_pattern(out: &mut Vec<u8>, pattern: &PatternState) {
+    record(out, |out| {
+        out.extend_from_slice(&(pattern.length as u32).to_le_bytes());
+        out.extend_from_slice(&pattern.steps_per_beat.to_le_bytes());
+        for value in [pattern.swing, pattern.humanize_timing_ms, pattern.humanize_velocity] {
+            put_f32(out, value);
+        }
+        out.extend_from_slice(&(pattern.steps.len() as u32).to_le_bytes());
+        for step in &pattern.steps {
+            record(out, |out| {
+                let notes = &step.notes[..step.notes.len().min(u8::MAX as usize)];
+                out.push(notes.len() as u8);
+                out.extend_from_slice(notes);
+                for value in [step.velocity, step.gate, step.probability] {
+                    put_f32(out, value);
+                }
+                out.push(step.ratchet.min(u8::MAX as u32) as u8);
+                out.push(step.tie as u8);
+                out.push(step.slide as u8);
+                put_f32(out, step.nudge);
+            });
+        }
+        let values = pattern.preset.map(|patch| patch.values()).unwrap_or_default();
+        out.extend_from_slice(&(values.len() as u16).to_le_bytes());
+        for value in values {
+            put_f32(out, value);
+        }
+        let lanes = &pattern.automation[..pattern.automation.len().min(u16::MAX as usize)];
+        out.extend_from_slice(&(lanes.len() as u16).to_le_bytes());
+        for lane in lanes {
+            out.extend_from_slice(&lane.param.to_le_bytes());
+            let points = &lane.points[..lane.points.len().min(u16::MAX as usize)];
+            out.extend_from_slice(&(points.len() as u16).to_le_bytes());
+            for point in points {
+                out.extend_from_slice(&point.beat.to_le_bytes());
+                put_f32(out, point.value);
+                out.push(point.curve);
+            }
+        }
+    });
+}
+
+fn read_pattern(reader: &mut Reader) -> Option<PatternState> {
+    let mut fields = reader.record()?;
+    let mut pattern = PatternState::default();
+    pattern.length = fields.u32().map_or(pattern.length, |length| length as usize);
+    pattern.steps_per_beat = fields.u32().unwrap_or(pattern.steps_per_beat);
+    pattern.swing = fields.f32().unwrap_or(pattern.swing);
+    pattern.humanize_timing_ms = fields.f32().unwrap_or(pattern.humanize_timing_ms);
+    pattern.humanize_velocity = fields.f32().unwrap_or(pattern.humanize_velocity);
+    for _ in 0..fields.u32().unwrap_or(0) {
+        let mut step_fields = fields.record()?;
+        let mut step = StepState::default();
+        let count = step_fields.u8().unwrap_or(0);
+        step.notes = step_fields.bytes(count as usize)?.to_vec();
+        step.velocity = step_fields.f32().unwrap_or(step.velocity);
+        step.gate = step_fields.f32().unwrap_or(step.gate);
+        step.probability = step_fields.f32().unwrap_or(step.probability);
+        step.ratchet = step_fields.u8().map_or(step.ratchet, |ratchet| ratchet as u32);
+        step.tie = step_fields.u8().is_some_and(|tie| tie != 0);
+        step.slide = step_fields.u8().is_some_and(|slide| slide != 0);
+        step.nudge = step_fields.f32().unwrap_or(step.nudge);
+        pattern.steps.push(step);
+    }
+    let count = fields.u16().unwrap_or(0);
+    if count > 0 {
+        let mut values = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            values.push(fields.f32()?);
+        }
+        let mut preset = Patch::default();
+        preset.set_values(&values);
+        pattern.preset = Some(preset);
+    }
+    for _ in 0..fields.u16().unwrap_or(0) {
+        let mut lane = AutomationLaneState { param: fields.u32()?, points: Vec::new() };
+        for _ in 0..fields.u16()? {
+            let (beat, value, curve) = (fields.f64()?, fields.f32()?, fields.u8()?);
+            lane.points.push(AutomationPoint { beat, value, curve });
+        }
+        pattern.automation.push(lane);
+    }
+    Some(pattern)
+}
+
+// Writes the fields `write` adds behind their byte length
+fn record(out: &mut Vec<u8>, write: impl FnOnce(&mut Vec<u8>)) {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    write(out);
+    let len = (out.len() - start - 4) as u32;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+}
+
+fn put_f32(out: &mut Vec<u8>, value: f32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, text: &str) {
+    // Cut back to a character boundary if it's too long
+    let mut len = text.len().min(u16::MAX as usize);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    out.extend_from_slice(&(len as u16).to_le_bytes());
+    out.extend_from_slice(&text.as_bytes()[..len]);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.data.len() {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[byte]| byte)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn str(&mut self) -> Option<String> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).ok()
+    }
+
+    // Reader over the next record's fields
+    fn record(&mut self) -> Option<Reader<'a>> {
+        let len = self.u32()? as usize;
+        self.bytes(len).map(Reader::new)
+    }
+}
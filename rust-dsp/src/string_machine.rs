@@ -0,0 +1,225 @@
+use std::f32::consts::PI;
+
+// String machine: a divide-down sawtooth organ with a built-in ensemble
+// chorus, after the classic 70s string ensembles. Twelve master oscillators
+// (one per pitch class, in the top octave) run all the time; every held
+// note derives its sawtooth by dividing its master down, so a full chord
+// costs barely more than a single note. Notes share the saw bank but each
+// has its own attack/release swell.
+
+const MAX_NOTES: usize = 16;
+// Top octave of the divider bank: MIDI 120-131
+const TOP_NOTE: u8 = 120;
+// Masters wrap after this many cycles, enough to divide down ten octaves
+const MASTER_WRAP: f64 = 1024.0;
+
+const ENSEMBLE_BASE_MS: f32 = 7.0;
+const ENSEMBLE_SLOW_RATE: f32 = 0.6;
+const ENSEMBLE_SLOW_MS: f32 = 2.0;
+const ENSEMBLE_FAST_RATE: f32 = 6.0;
+const ENSEMBLE_FAST_MS: f32 = 0.3;
+
+#[derive(Clone)]
+pub struct StringMachine {
+    sample_rate: f32,
+    masters: [f64; 12],
+    increments: [f64; 12],
+    notes: Vec<StringNote>,
+    attack_step: f32,
+    release_step: f32,
+    brightness_coeff: f32,
+    tone_state: f32,
+    ensemble: Ensemble,
+    ensemble_depth: f32,
+}
+
+#[derive(Clone)]
+struct StringNote {
+    note: u8,
+    level: f32,
+    gate: bool,
+}
+
+#[derive(Clone)]
+struct Ensemble {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    slow_phase: f32,
+    fast_phase: f32,
+}
+
+impl StringMachine {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut increments = [0.0; 12];
+        for (i, increment) in increments.iter_mut().enumerate() {
+            let freq = 440.0 * 2.0_f64.powf((TOP_NOTE as f64 + i as f64 - 69.0) / 12.0);
+            *increment = freq / sample_rate as f64;
+        }
+
+        let mut machine = StringMachine {
+            sample_rate,
+            masters: [0.0; 12],
+            increments,
+            notes: Vec::with_capacity(MAX_NOTES),
+            attack_step: 0.0,
+            release_step: 0.0,
+            brightness_coeff: 0.0,
+            tone_state: 0.0,
+            ensemble: Ensemble::new(sample_rate),
+            ensemble_depth: 0.7,
+        };
+        machine.set_envelope(0.6, 1.2);
+        machine.set_brightness(0.6);
+        machine
+    }
+
+    // Swell times in seconds
+    pub fn set_envelope(&mut self, attack: f32, release: f32) {
+        self.attack_step = 1.0 / (attack.max(0.001) * self.sample_rate);
+        self.release_step = 1.0 / (release.max(0.001) * self.sample_rate);
+    }
+
+    // 0-1 sweeps the tone filter from 800 Hz to 12 kHz
+    pub fn set_brightness(&mut self, brightness: f32) {
+        let cutoff = 800.0 * 15.0_f32.powf(brightness.clamp(0.0, 1.0));
+        self.brightness_coeff = 1.0 - (-2.0 * PI * cutoff / self.sample_rate).exp();
+    }
+
+    pub fn set_ensemble(&mut self, depth: f32) {
+        self.ensemble_depth = depth.clamp(0.0, 1.0);
+    }
+
+    pub fn note_on(&mut self, note: u8) {
+        if note > TOP_NOTE + 11 {
+            return;
+        }
+        if let Some(existing) = self.notes.iter_mut().find(|n| n.note == note) {
+            existing.gate = true;
+            return;
+        }
+        if self.notes.len() == MAX_NOTES {
+            // Drop the quietest note to make room
+            let quietest = self
+                .notes
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.level.total_cmp(&b.1.level))
+                .map(|(i, _)| i);
+            if let Some(i) = quietest {
+                self.notes.swap_remove(i);
+            }
+        }
+        self.notes.push(StringNote { note, level: 0.0, gate: true });
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        for n in self.notes.iter_mut().filter(|n| n.note == note) {
+            n.gate = false;
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        for n in &mut self.notes {
+            n.gate = false;
+        }
+    }
+
+    // Cuts every note and the chorus line at once, without release
+    pub fn silence(&mut self) {
+        self.notes.clear();
+        self.tone_state = 0.0;
+        self.ensemble.buffer.fill(0.0);
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.notes.is_empty()
+    }
+
+    // Adds the string machine into a mono buffer
+    pub fn process(&mut self, output: &mut [f32]) {
+        for sample in output.iter_mut() {
+            for (master, increment) in self.masters.iter_mut().zip(&self.increments) {
+                *master += increment;
+                if *master >= MASTER_WRAP {
+                    *master -= MASTER_WRAP;
+                }
+            }
+
+            let mut mix = 0.0;
+            for n in &mut self.notes {
+                if n.gate {
+                    n.level = (n.level + self.attack_step).min(1.0);
+                } else {
+                    n.level -= self.release_step;
+                }
+                if n.level > 0.0 {
+                    let class = (n.note % 12) as usize;
+                    let divider = (1u32 << ((TOP_NOTE + class as u8 - n.note) / 12)) as f64;
+                    let phase = (self.masters[class] / divider).fract() as f32;
+                    let dt = (self.increments[class] / divider) as f32;
+                    mix += saw(phase, dt) * n.level;
+                }
+            }
+            self.notes.retain(|n| n.gate || n.level > 0.0);
+
+            // Fixed trim keeps a full chord in the same range as the voices
+            self.tone_state += (mix * 0.3 - self.tone_state) * self.brightness_coeff;
+            *sample += self.ensemble.process(self.tone_state, self.ensemble_depth, self.sample_rate);
+        }
+    }
+}
+
+// PolyBLEP sawtooth for a phase in 0..1 advancing by `dt` per sample
+fn saw(phase: f32, dt: f32) -> f32 {
+    let mut output = 2.0 * phase - 1.0;
+    if phase < dt {
+        let t = phase / dt;
+        output -= t + t - t * t - 1.0;
+    } else if phase > 1.0 - dt {
+        let t = (phase - 1.0) / dt;
+        output -= t * t + t + t + 1.0;
+    }
+    output
+}
+
+impl Ensemble {
+    fn new(sample_rate: f32) -> Self {
+        let max_ms = ENSEMBLE_BASE_MS + ENSEMBLE_SLOW_MS + ENSEMBLE_FAST_MS;
+        Ensemble {
+            buffer: vec![0.0; (max_ms * sample_rate / 1000.0) as usize + 4],
+            write_pos: 0,
+            slow_phase: 0.0,
+            fast_phase: 0.0,
+        }
+    }
+
+    // Three taps whose delays are swept by a slow and a fast LFO, each tap
+    // a third of a cycle apart: the thick, moving "ensemble" shimmer
+    fn process(&mut self, input: f32, depth: f32, sample_rate: f32) -> f32 {
+        self.buffer[self.write_pos] = input;
+        self.slow_phase = (self.slow_phase + ENSEMBLE_SLOW_RATE / sample_rate).fract();
+        self.fast_phase = (self.fast_phase + ENSEMBLE_FAST_RATE / sample_rate).fract();
+
+        let mut wet = 0.0;
+        for tap in 0..3 {
+            let offset = tap as f32 / 3.0;
+            let slow = ((self.slow_phase + offset) * 2.0 * PI).sin() * ENSEMBLE_SLOW_MS;
+            let fast = ((self.fast_phase + offset) * 2.0 * PI).sin() * ENSEMBLE_FAST_MS;
+            let delay = (ENSEMBLE_BASE_MS + (slow + fast) * depth) * sample_rate / 1000.0;
+            wet += self.read(delay);
+        }
+        self.write_pos = (self.write_pos + 1) % self.buffer.len();
+
+        input * (1.0 - depth * 0.5) + wet / 3.0 * depth
+    }
+
+    fn read(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = delay.clamp(1.0, (len - 2) as f32);
+        let whole = delay as usize;
+        let frac = delay - whole as f32;
+        let a = self.buffer[(self.write_pos + len - whole) % len];
+        let b = self.buffer[(self.write_pos + len - whole - 1) % len];
+        a + (b - a) * frac
+    }
+}
@@ -0,0 +1,83 @@
+// Note scripts for golden renders. One command per line, times in seconds
+// from the start of the render:
+//
+//   0.0 on 60 0.8    note-on, MIDI note and velocity 0-1
+//   0.5 off 60       note-off
+//   0.0 param 14 1   parameter by id (see params.rs) on the live track,
+//                    e.g. to switch on the effect under test
+//   2.0 end          stop rendering here
+//
+// Blank lines and lines starting with # are skipped. Without an `end` the
+// render runs one second past the last event so release tails are kept.
+
+use crate::params::ParamId;
+
+const DEFAULT_TAIL_SECONDS: f64 = 1.0;
+// Renders longer than this are refused
+pub const MAX_SCRIPT_SECONDS: f64 = 60.0;
+
+#[derive(Clone, Copy)]
+pub enum ScriptCommand {
+    NoteOn { note: u8, velocity: f32 },
+    NoteOff { note: u8 },
+    Param { id: u32, value: f32 },
+}
+
+pub struct NoteScript {
+    // Sorted by time; commands at the same time keep their script order
+    pub events: Vec<(f64, ScriptCommand)>,
+    pub duration: f64,
+}
+
+// None if any line doesn't parse or the render would be too long
+pub fn parse_script(script: &str) -> Option<NoteScript> {
+    let mut events = Vec::new();
+    let mut end = None;
+    for line in script.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let time: f64 = words.next()?.parse().ok().filter(|t: &f64| *t >= 0.0)?;
+        match words.next()? {
+            "on" => {
+                let note: u8 = words.next()?.parse().ok().filter(|&n| n <= 127)?;
+                let velocity: f32 = words.next()?.parse().ok()?;
+                events.push((time, ScriptCommand::NoteOn { note, velocity: velocity.clamp(0.0, 1.0) }));
+            }
+            "off" => {
+                let note: u8 = words.next()?.parse().ok().filter(|&n| n <= 127)?;
+                events.push((time, ScriptCommand::NoteOff { note }));
+            }
+            "param" => {
+                let id: u32 = words.next()?.parse().ok().filter(|&id| ParamId::from_u32(id).is_some())?;
+                let value: f32 = words.next()?.parse().ok().filter(|v: &f32| v.is_finite())?;
+                events.push((time, ScriptCommand::Param { id, value }));
+            }
+            "end" => end = Some(time),
+            _ => return None,
+        }
+        if words.next().is_some() {
+            return None;
+        }
+    }
+
+    events.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let last = events.last().map_or(0.0, |&(time, _)| time);
+    let duration = end.unwrap_or(last + DEFAULT_TAIL_SECONDS);
+    (duration <= MAX_SCRIPT_SECONDS).then_some(NoteScript { events, duration })
+}
+
+// 64-bit FNV-1a over the samples' bit patterns, so a golden render can be
+// checked in as a short string instead of the whole buffer. Any change to
+// any sample, however small, changes it.
+pub fn hash_samples(samples: &[f32]) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for sample in samples {
+        for byte in sample.to_bits().to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+    hash
+}
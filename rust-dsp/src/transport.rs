@@ -0,0 +1,97 @@
+// Sample-accurate musical clock shared by everything tempo-related. The
+// position is kept in beats so tempo changes never move the playhead.
+// With a loop set, playback wraps from the loop end back to its start.
+
+// Shortest loop accepted, in beats
+const MIN_LOOP_BEATS: f64 = 0.25;
+
+pub struct Transport {
+    sample_rate: f32,
+    bpm: f32,
+    playing: bool,
+    position_beats: f64,
+    loop_range: Option<(f64, f64)>,
+}
+
+impl Transport {
+    pub fn new(sample_rate: f32) -> Self {
+        Transport {
+            sample_rate,
+            bpm: 120.0,
+            playing: false,
+            position_beats: 0.0,
+            loop_range: None,
+        }
+    }
+
+    pub fn set_bpm(&mut self, bpm: f32) {
+        self.bpm = bpm.clamp(20.0, 300.0);
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    // Stopping rewinds to the start, like a hardware sequencer
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.position_beats = 0.0;
+    }
+
+    // Stops without rewinding, for MIDI Stop/Continue
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    // An end at or before the start (after the minimum length) clears the loop
+    pub fn set_loop(&mut self, start_beat: f64, end_beat: f64) {
+        let start = start_beat.max(0.0);
+        self.loop_range = (end_beat - start >= MIN_LOOP_BEATS).then_some((start, end_beat));
+    }
+
+    pub fn loop_range(&self) -> Option<(f64, f64)> {
+        self.loop_range
+    }
+
+    // Whole samples until playback reaches the loop end, when it will wrap
+    // while playing. A playhead already past the end runs on unlooped.
+    pub fn samples_until_loop_end(&self) -> Option<usize> {
+        let (_, end) = self.loop_range?;
+        if !self.playing || self.position_beats >= end {
+            return None;
+        }
+        Some(((end - self.position_beats) * self.samples_per_beat()).ceil() as usize)
+    }
+
+    // Moves the playhead on; returns true when it wrapped to the loop start
+    pub fn advance(&mut self, samples: usize) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let before = self.position_beats;
+        self.position_beats += samples as f64 / self.samples_per_beat();
+        match self.loop_range {
+            Some((start, end)) if before < end && self.position_beats >= end => {
+                self.position_beats = start + (self.position_beats - end);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn samples_per_beat(&self) -> f64 {
+        self.sample_rate as f64 * 60.0 / self.bpm as f64
+    }
+
+    pub fn position_beats(&self) -> f64 {
+        self.position_beats
+    }
+}
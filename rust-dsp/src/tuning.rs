@@ -0,0 +1,86 @@
+// Per-note frequency table behind every MIDI note the engines play. The
+// default is 12-tone equal temperament at A4 = 440 Hz; Scala scale files or
+// a host-supplied table (e.g. from MTS messages) replace it for just
+// intonation, EDOs and other microtonal scales.
+
+// Scala's default keyboard mapping: the scale's 1/1 sits on middle C at its
+// equal-tempered pitch
+const SCALA_ROOT_NOTE: i32 = 60;
+const MAX_SCALA_NOTES: usize = 1024;
+
+#[derive(Clone)]
+pub struct Tuning {
+    table: [f32; 128],
+}
+
+impl Tuning {
+    pub fn equal() -> Self {
+        Tuning { table: std::array::from_fn(|note| equal_temperament(note as f32)) }
+    }
+
+    // Exactly 128 positive frequencies in Hz, indexed by MIDI note
+    pub fn from_table(frequencies: &[f32]) -> Option<Tuning> {
+        let table: [f32; 128] = frequencies.try_into().ok()?;
+        table.iter().all(|f| f.is_finite() && *f > 0.0).then_some(Tuning { table })
+    }
+
+    pub fn frequency(&self, note: u8) -> f32 {
+        self.table[note as usize & 127]
+    }
+
+    // Maps the scale's degrees upward and downward from SCALA_ROOT_NOTE,
+    // repeating it at the interval of its last degree (usually 2/1)
+    pub fn from_scala(text: &str) -> Option<Tuning> {
+        let cents = parse_scala(text)?;
+        let period = *cents.last()?;
+        let size = cents.len() as i32;
+        let root = equal_temperament(SCALA_ROOT_NOTE as f32) as f64;
+        let table = std::array::from_fn(|note| {
+            let steps = note as i32 - SCALA_ROOT_NOTE;
+            let (octave, degree) = (steps.div_euclid(size), steps.rem_euclid(size));
+            let offset = if degree == 0 { 0.0 } else { cents[degree as usize - 1] };
+            let cents = octave as f64 * period + offset;
+            (root * 2.0_f64.powf(cents / 1200.0)) as f32
+        });
+        Some(Tuning { table })
+    }
+}
+
+// Pitches of a .scl file in cents above the 1/1, which the file leaves out.
+// Lines starting with '!' are comments; the first other line is the
+// description, then the note count, then one pitch per line: cents if it has
+// a period, otherwise a ratio like 3/2 or a whole number. Anything after the
+// pitch on its line is ignored.
+fn parse_scala(text: &str) -> Option<Vec<f64>> {
+    let mut lines = text.lines().filter(|line| !line.starts_with('!'));
+    lines.next()?;
+    let count: usize = lines.next()?.split_whitespace().next()?.parse().ok()?;
+    if count == 0 || count > MAX_SCALA_NOTES {
+        return None;
+    }
+    let cents: Vec<f64> = lines
+        .take(count)
+        .map(|line| parse_pitch(line.split_whitespace().next()?))
+        .collect::<Option<_>>()?;
+    if cents.len() != count || *cents.last()? <= 0.0 {
+        return None;
+    }
+    Some(cents)
+}
+
+fn parse_pitch(pitch: &str) -> Option<f64> {
+    if pitch.contains('.') {
+        return pitch.parse().ok().filter(|cents: &f64| cents.is_finite());
+    }
+    let (numerator, denominator) = pitch.split_once('/').unwrap_or((pitch, "1"));
+    let numerator: f64 = numerator.parse::<u64>().ok()? as f64;
+    let denominator: f64 = denominator.parse::<u64>().ok()? as f64;
+    if numerator == 0.0 || denominator == 0.0 {
+        return None;
+    }
+    Some(1200.0 * (numerator / denominator).log2())
+}
+
+fn equal_temperament(note: f32) -> f32 {
+    440.0 * 2.0_f32.powf((note - 69.0) / 12.0)
+}
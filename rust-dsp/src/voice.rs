@@ -1,45 +1,350 @@
-use crate::oscillator::Oscillator;
-use crate::envelope::Envelope;
+use crate::oscillator::{Oscillator, Waveform, DRAWBAR_COUNT, VECTOR_SOURCES};
+use crate::envelope::{Envelope, EnvelopeStage, RETRIGGER_FULL, RETRIGGER_LEGATO};
+use crate::filter::{FilterBank, BANK_LANES};
 use crate::effects::glide::Glide;
+use crate::pressure::Pressure;
+use crate::oversampler::Oversampler;
+use crate::random::Random;
 
+pub const MAX_UNISON: usize = 7;
+
+// Where the oscillators start on a fresh note: staggered from a fixed
+// phase, on from wherever they stopped, or at random
+pub const PHASE_RESET: u8 = 0;
+pub const PHASE_FREE: u8 = 1;
+pub const PHASE_RANDOM: u8 = 2;
+
+// Glide, filter envelope and EP modulation update once per block of this
+// many samples rather than every sample
+pub const CONTROL_BLOCK: usize = 32;
+
+// Fixed, uncorrelated cutoff offsets (in units of the spread) so stacked
+// oscillators don't all share the same brightness as their pitch offsets
+const FILTER_SPREAD_OFFSETS: [f32; MAX_UNISON] = [0.0, 0.8, -0.6, 0.3, -0.9, 0.5, -0.2];
+
+// Organ percussion decay times in seconds (fast / slow) and key-click length
+const PERCUSSION_FAST: f32 = 0.2;
+const PERCUSSION_SLOW: f32 = 1.0;
+const KEY_CLICK_TIME: f32 = 0.004;
+
+// Electric piano: the FM bark fades over about half a second to a soft
+// sustained index, the hammer partial within a few tens of milliseconds
+const EP_BARK_TIME: f32 = 0.6;
+const EP_TINE_TIME: f32 = 0.06;
+const EP_MAX_BARK: f32 = 2.5;
+const EP_SUSTAIN_BARK: f32 = 0.2;
+
+// A stolen voice fades its old note out over this long before the new note
+// starts, rather than jumping to it mid-cycle
+const STEAL_FADE_TIME: f32 = 0.003;
+
+// Note waiting on a voice while the note it stole fades out
+#[derive(Clone, Copy)]
+struct PendingNote {
+    frequency: f32,
+    velocity: f32,
+    legato: bool,
+    // Release scale once the note has been let go before it started
+    release: Option<f32>,
+}
+
+#[derive(Clone)]
 pub struct Voice {
-    oscillator: Oscillator,
+    oscillators: Vec<Oscillator>,
+    filters: FilterBank,
     envelope: Envelope,
+    adsr: [f32; 4],
     glide: Glide,
     active: bool,
+    // MIDI note the voice was started for and the engine's running count of
+    // note-ons at the time, which orders voices oldest first
+    note: u8,
+    note_id: u64,
     age: f32,
     velocity: f32,
+    // Velocity shaped by the velocity curve, which sets the level
+    amp_velocity: f32,
+    velocity_exponent: f32,
+    current_freq: f32,
+    detune: f32,
+    unison_voices: usize,
+    unison_detune: f32,
+    filter_enabled: bool,
+    filter_cutoff: f32,
+    filter_spread: f32,
+    cutoff_ratios: [f32; MAX_UNISON],
+    filter_envelope: Envelope,
+    filter_env_amount: f32,
+    // Attack-decay sweep of the pitch in semitones, and the pitch ratio it
+    // set last
+    pitch_envelope: Envelope,
+    pitch_env_amount: f32,
+    pitch_env_ratio: f32,
+    drive: f32,
+    // Runs the drive at 2x/4x so its harmonics don't alias
+    drive_oversampler: Oversampler,
+    cutoff_motion: f32,
+    pitch_motion: f32,
+    ghost_threshold: f32,
+    ghost_tone_coeff: f32,
+    ghost_length: f32,
+    ghost: bool,
+    ghost_tone_state: f32,
+    organ: bool,
+    percussion_enabled: bool,
+    percussion_harmonic: f32,
+    percussion_level: f32,
+    percussion_env: f32,
+    percussion_decay: f32,
+    key_click_level: f32,
+    key_click_env: f32,
+    key_click_decay: f32,
+    noise_state: u32,
+    electric_piano: bool,
+    bark_env: f32,
+    bark_decay: f32,
+    tine_env: f32,
+    tine_decay: f32,
+    // Vector waveform: XY position from the engine (LFO included) and how
+    // far the filter envelope moves it on from there
+    vector: bool,
+    vector_position: [f32; 2],
+    vector_env: [f32; 2],
+    // Polyphonic aftertouch for this voice's note, and the level it sets
+    // at the start of the current block and per sample after that
+    pressure: Pressure,
+    pressure_gain: f32,
+    pressure_gain_step: f32,
+    retrigger_mode: u8,
+    phase_mode: u8,
+    pending: Option<PendingNote>,
+    steal_gain: f32,
+    steal_step: f32,
+    sample_rate: f32,
 }
 
 impl Voice {
     pub fn new(sample_rate: f32) -> Self {
+        let mut oscillators = Vec::with_capacity(MAX_UNISON);
+        for _ in 0..MAX_UNISON {
+            oscillators.push(Oscillator::new(sample_rate));
+        }
+
         Voice {
-            oscillator: Oscillator::new(sample_rate),
+            oscillators,
+            filters: FilterBank::new(sample_rate),
             envelope: Envelope::new(sample_rate),
+            adsr: [0.01, 0.3, 0.7, 0.5],
             glide: Glide::new(sample_rate),
             active: false,
+            note: 0,
+            note_id: 0,
             age: 0.0,
             velocity: 1.0,
+            amp_velocity: 1.0,
+            velocity_exponent: 1.0,
+            current_freq: 0.0,
+            detune: 0.0,
+            unison_voices: 1,
+            unison_detune: 0.0,
+            filter_enabled: false,
+            filter_cutoff: 20000.0,
+            filter_spread: 0.0,
+            cutoff_ratios: [1.0; MAX_UNISON],
+            filter_envelope: Envelope::new(sample_rate),
+            filter_env_amount: 0.0,
+            pitch_envelope: Envelope::new(sample_rate),
+            pitch_env_amount: 0.0,
+            pitch_env_ratio: 1.0,
+            drive: 0.0,
+            drive_oversampler: Oversampler::new(),
+            cutoff_motion: 1.0,
+            pitch_motion: 1.0,
+            ghost_threshold: 0.0,
+            ghost_tone_coeff: 1.0,
+            ghost_length: 1.0,
+            ghost: false,
+            ghost_tone_state: 0.0,
+            organ: false,
+            percussion_enabled: false,
+            percussion_harmonic: 3.0,
+            percussion_level: 0.5,
+            percussion_env: 0.0,
+            percussion_decay: decay_coefficient(PERCUSSION_FAST, sample_rate),
+            key_click_level: 0.3,
+            key_click_env: 0.0,
+            key_click_decay: decay_coefficient(KEY_CLICK_TIME, sample_rate),
+            noise_state: 0x2545_F491,
+            electric_piano: false,
+            bark_env: 0.0,
+            bark_decay: decay_coefficient(EP_BARK_TIME, sample_rate),
+            tine_env: 0.0,
+            tine_decay: decay_coefficient(EP_TINE_TIME, sample_rate),
+            vector: false,
+            vector_position: [0.0; 2],
+            vector_env: [0.0; 2],
+            pressure: Pressure::new(sample_rate),
+            pressure_gain: 1.0,
+            pressure_gain_step: 0.0,
+            retrigger_mode: RETRIGGER_FULL,
+            phase_mode: PHASE_RESET,
+            pending: None,
+            steal_gain: 1.0,
+            steal_step: 1.0 / (STEAL_FADE_TIME * sample_rate),
+            sample_rate,
         }
     }
 
-    pub fn note_on(&mut self, frequency: f32, velocity: f32) {
+    // `legato` is whether the engine already holds another note. On a voice
+    // still sounding, full retrigger fades the old note out over a few
+    // milliseconds and starts the new one from silence after that; soft and
+    // legato retrigger take over its envelope where it is. The voice answers
+    // to `note` and `note_id` from here on, even while the old note fades.
+    pub fn note_on(&mut self, note: u8, note_id: u64, frequency: f32, velocity: f32, legato: bool) {
+        self.note = note;
+        self.note_id = note_id;
+        if self.is_active() && self.retrigger_mode == RETRIGGER_FULL {
+            self.pending = Some(PendingNote { frequency, velocity, legato, release: None });
+            // Counts as the newest note from now, so it isn't stolen again
+            self.age = 0.0;
+            return;
+        }
+        self.start(frequency, velocity, legato);
+    }
+
+    fn start(&mut self, frequency: f32, velocity: f32, legato: bool) {
+        self.steal_gain = 1.0;
         // Use glide for smooth frequency transitions
-        self.glide.set_target(frequency);
-        
+        if self.glide.is_legato_only() && !legato {
+            self.glide.jump_to(frequency);
+        } else {
+            self.glide.set_target(frequency);
+        }
+
         // Only reset phase if this is a new note (not retriggering)
         if !self.active {
-            self.oscillator.reset_phase();
+            let count = self.unison_voices;
+            match self.phase_mode {
+                PHASE_FREE => {}
+                PHASE_RANDOM => {
+                    // Seeded from the note id, so every note differs but a
+                    // render repeats exactly
+                    let mut random = Random::new((self.note_id as u32).wrapping_mul(0x9E37_79B9));
+                    for osc in &mut self.oscillators[..count] {
+                        osc.set_phase(random.unipolar());
+                    }
+                }
+                // Stagger unison phases so the stack doesn't start phase-locked
+                _ => {
+                    for (i, osc) in self.oscillators[..count].iter_mut().enumerate() {
+                        osc.set_phase(i as f32 / count as f32);
+                    }
+                }
+            }
+
+            // Percussion and click fire on fresh notes only, like the
+            // single-trigger percussion of a tonewheel organ
+            if self.organ {
+                self.percussion_env = if self.percussion_enabled { 1.0 } else { 0.0 };
+                self.key_click_env = self.key_click_level;
+            }
         }
-        
+
+        // Pressure belongs to the previous note; drop whatever it still
+        // applies to pitch and cutoff
+        if self.pressure.is_active() {
+            self.pressure.reset();
+            self.pressure_gain = 1.0;
+            self.current_freq = 0.0;
+            self.apply_filter_cutoffs(1.0);
+        }
+
+        // Each strike restarts the bark; harder hits growl more
+        self.bark_env = 1.0;
+        self.tine_env = 1.0;
+
+        // Soft hits switch to the ghost layer: shorter envelope, darker tone
+        self.ghost = velocity < self.ghost_threshold;
+        let [attack, decay, sustain, release] = self.adsr;
+        let length = if self.ghost { self.ghost_length } else { 1.0 };
+        self.envelope.set_adsr(attack, decay * length, sustain, release * length);
+
         self.velocity = velocity;
-        self.envelope.gate_on();
+        self.amp_velocity = velocity.clamp(0.0, 1.0).powf(self.velocity_exponent);
+        // Legato carries a held envelope on; a released one attacks again
+        if self.retrigger_mode != RETRIGGER_LEGATO || !self.envelope.is_held() {
+            self.envelope.gate_on();
+            self.filter_envelope.gate_on();
+            self.pitch_envelope.gate_on();
+        }
         self.active = true;
         self.age = 0.0;
     }
 
+    // Bends the sounding note to a new pitch over `time_ms` without
+    // retriggering anything. It answers to `note` from then on but keeps its
+    // note id, so it's no newer for stealing.
+    pub fn glide_to(&mut self, note: u8, frequency: f32, time_ms: f32) {
+        self.note = note;
+        match &mut self.pending {
+            Some(note) => note.frequency = frequency,
+            None => self.glide.glide_over(frequency, time_ms),
+        }
+    }
+
     pub fn note_off(&mut self) {
-        self.envelope.gate_off();
+        self.note_off_scaled(1.0);
+    }
+
+    // Note off with the release time (ghost-scaled as it is) multiplied by
+    // `scale`, for release velocity
+    pub fn note_off_scaled(&mut self, scale: f32) {
+        if let Some(pending) = &mut self.pending {
+            pending.release = Some(scale);
+            return;
+        }
+        self.envelope.gate_off_scaled(scale);
+        self.filter_envelope.gate_off_scaled(scale);
+    }
+
+    // Note off with a fixed fade in place of the patch release
+    pub fn fade_out(&mut self, seconds: f32) {
+        // A note still waiting on a steal fade never starts
+        self.pending = None;
+        self.envelope.release_over(seconds);
+        self.filter_envelope.gate_off();
+    }
+
+    // Cuts the voice off dead, for panic: no release tail, no pending note,
+    // no pressure, and the next note starts at pitch
+    pub fn silence(&mut self) {
+        self.pending = None;
+        self.steal_gain = 1.0;
+        self.active = false;
+        self.envelope.reset();
+        self.filter_envelope.reset();
+        self.pitch_envelope.reset();
+        self.pressure.reset();
+        self.pressure_gain = 1.0;
+        self.pressure_gain_step = 0.0;
+        self.ghost_tone_state = 0.0;
+        self.glide.reset();
+        self.drive_oversampler.reset();
+    }
+
+    // Called at block boundaries once a steal fade is down to silence
+    fn finish_steal(&mut self) {
+        self.steal_gain = 1.0;
+        self.active = false;
+        self.envelope.reset();
+        self.filter_envelope.reset();
+        self.pitch_envelope.reset();
+        if let Some(note) = self.pending.take() {
+            self.start(note.frequency, note.velocity, note.legato);
+            if let Some(scale) = note.release {
+                self.note_off_scaled(scale);
+            }
+        }
     }
 
     pub fn process(&mut self, output: &mut [f32]) {
@@ -49,17 +354,56 @@ impl Voice {
 
         self.age += 1.0;
 
-        for sample in output.iter_mut() {
-            // Process glide and update oscillator frequency
-            let current_freq = self.glide.process();
-            self.oscillator.set_frequency(current_freq);
-            
-            let osc_out = self.oscillator.process();
-            let env_out = self.envelope.process();
-            *sample += osc_out * env_out * self.velocity;
-
-            if !self.envelope.is_active() {
-                self.active = false;
+        let count = self.unison_voices;
+        let unison_gain = 1.0 / (count as f32).sqrt();
+
+        for block in output.chunks_mut(CONTROL_BLOCK) {
+            if self.steal_gain == 0.0 || (self.pending.is_some() && !self.envelope.is_active()) {
+                self.finish_steal();
+                if !self.active {
+                    return;
+                }
+            }
+            self.update_controls(block.len(), count);
+
+            for sample in block.iter_mut() {
+                let mut osc_out = 0.0;
+                if self.organ {
+                    osc_out += self.organ_transients();
+                }
+                let mut stack = [0.0; BANK_LANES];
+                for (s, osc) in stack.iter_mut().zip(&mut self.oscillators[..count]) {
+                    *s = osc.process();
+                }
+                if self.filter_enabled {
+                    self.filters.process(&mut stack, count);
+                }
+                for s in &stack[..count] {
+                    osc_out += s;
+                }
+
+                let mut voice_out = osc_out * unison_gain;
+                if self.drive > 0.0 {
+                    // Partial make-up: driven voices get somewhat louder, as on hardware
+                    let gain = 1.0 + self.drive * 4.0;
+                    voice_out = self.drive_oversampler.process(voice_out, |x| (x * gain).tanh()) / gain.sqrt();
+                }
+
+                if self.ghost {
+                    self.ghost_tone_state += (voice_out - self.ghost_tone_state) * self.ghost_tone_coeff;
+                    voice_out = self.ghost_tone_state;
+                }
+
+                let env_out = self.envelope.process();
+                *sample += voice_out * env_out * self.amp_velocity * self.pressure_gain * self.steal_gain;
+                self.pressure_gain += self.pressure_gain_step;
+                if self.pending.is_some() || self.steal_gain < 1.0 {
+                    self.steal_gain = (self.steal_gain - self.steal_step).max(0.0);
+                }
+
+                if !self.envelope.is_active() && self.pending.is_none() {
+                    self.active = false;
+                }
             }
         }
     }
@@ -69,31 +413,346 @@ impl Voice {
     }
 
     pub fn set_waveform(&mut self, waveform: u8) {
-        self.oscillator.set_waveform(waveform);
+        self.organ = waveform == Waveform::Organ as u8;
+        self.electric_piano = waveform == Waveform::ElectricPiano as u8;
+        self.vector = waveform == Waveform::Vector as u8;
+        for osc in &mut self.oscillators {
+            osc.set_waveform(waveform);
+        }
+    }
+
+    pub fn set_vector_sources(&mut self, sources: &[u8; VECTOR_SOURCES]) {
+        for osc in &mut self.oscillators {
+            osc.set_vector_sources(sources);
+        }
+    }
+
+    // Block-rate XY position, 0-1 each
+    pub fn set_vector_position(&mut self, x: f32, y: f32) {
+        self.vector_position = [x, y];
+        if self.vector_env == [0.0; 2] {
+            self.apply_vector_position(0.0);
+        }
+    }
+
+    // Offsets the filter envelope adds to the position at its peak, -1 to 1
+    pub fn set_vector_envelope(&mut self, x: f32, y: f32) {
+        self.vector_env = [x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0)];
+        if self.vector_env == [0.0; 2] {
+            self.apply_vector_position(0.0);
+        }
+    }
+
+    fn apply_vector_position(&mut self, filter_env: f32) {
+        let x = self.vector_position[0] + self.vector_env[0] * filter_env;
+        let y = self.vector_position[1] + self.vector_env[1] * filter_env;
+        for osc in &mut self.oscillators {
+            osc.set_vector_position(x, y);
+        }
+    }
+
+    pub fn set_drawbars(&mut self, levels: &[f32; DRAWBAR_COUNT]) {
+        for osc in &mut self.oscillators {
+            osc.set_drawbars(levels);
+        }
+    }
+
+    // Decaying sine at the 2nd or 3rd harmonic added to the start of each note
+    pub fn set_percussion(&mut self, enabled: bool, harmonic: u8, fast: bool, level: f32) {
+        self.percussion_enabled = enabled;
+        self.percussion_harmonic = if harmonic == 2 { 2.0 } else { 3.0 };
+        self.percussion_level = level.clamp(0.0, 1.0);
+        let time = if fast { PERCUSSION_FAST } else { PERCUSSION_SLOW };
+        self.percussion_decay = decay_coefficient(time, self.sample_rate);
+    }
+
+    pub fn set_key_click(&mut self, level: f32) {
+        self.key_click_level = level.clamp(0.0, 1.0);
     }
 
     pub fn set_adsr(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        self.adsr = [attack, decay, sustain, release];
         self.envelope.set_adsr(attack, decay, sustain, release);
     }
 
+    // Velocity layer for ghost notes: hits below `threshold` (0 disables)
+    // play through a one-pole low-pass at `tone_hz` with decay and release
+    // scaled by `length`. Takes effect from the next note.
+    pub fn set_ghost_layer(&mut self, threshold: f32, tone_hz: f32, length: f32) {
+        self.ghost_threshold = threshold.clamp(0.0, 1.0);
+        let tone_hz = tone_hz.clamp(200.0, 20000.0);
+        self.ghost_tone_coeff = 1.0 - (-2.0 * std::f32::consts::PI * tone_hz / self.sample_rate).exp();
+        self.ghost_length = length.clamp(0.1, 1.0);
+    }
+
+    // Velocity to level, -1 to 1: 0 is linear, positive bends it down so
+    // soft notes drop away (velocity to the power of up to 4), negative
+    // bends it up so they stay present (down to the power of 1/4). Takes
+    // effect from the next note.
+    pub fn set_velocity_curve(&mut self, curve: f32) {
+        self.velocity_exponent = 4.0_f32.powf(curve.clamp(-1.0, 1.0));
+    }
+
     pub fn get_frequency(&self) -> f32 {
         self.glide.get_frequency()
     }
 
+    pub fn get_velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    pub fn note(&self) -> u8 {
+        self.note
+    }
+
+    pub fn note_id(&self) -> u64 {
+        self.note_id
+    }
+
     pub fn get_age(&self) -> f32 {
         self.age
     }
 
+    // The voice stays `active` until its envelope finishes, so the release
+    // phase has to come from the envelope itself
     pub fn is_releasing(&self) -> bool {
-        !self.active && self.envelope.is_active()
+        self.pending.is_none() && self.envelope.is_releasing()
+    }
+
+    // Envelope stage of the voice's note; one waiting for a stolen note to
+    // fade hasn't started its attack yet
+    pub fn stage(&self) -> EnvelopeStage {
+        if self.pending.is_some() {
+            EnvelopeStage::Attack
+        } else {
+            self.envelope.stage()
+        }
+    }
+
+    // Envelope level scaled by velocity, 0-1
+    pub fn level(&self) -> f32 {
+        if self.pending.is_some() {
+            0.0
+        } else {
+            self.envelope.level() * self.amp_velocity
+        }
     }
 
     pub fn set_glide_time(&mut self, time_ms: f32) {
         self.glide.set_glide_time(time_ms);
     }
 
+    pub fn set_glide_mode(&mut self, mode: u8) {
+        self.glide.set_mode(mode);
+    }
+
+    pub fn set_retrigger_mode(&mut self, mode: u8) {
+        self.retrigger_mode = mode;
+    }
+
+    pub fn set_phase_mode(&mut self, mode: u8) {
+        self.phase_mode = mode;
+    }
+
     pub fn set_detune(&mut self, cents: f32) {
-        self.oscillator.set_detune(cents);
+        self.detune = cents;
+        self.update_unison_detune();
+    }
+
+    // Stack up to MAX_UNISON oscillators per voice, spread symmetrically
+    // across `detune_cents` with cutoffs spread by up to ±`filter_spread` octaves
+    pub fn set_unison(&mut self, voices: usize, detune_cents: f32, filter_spread: f32) {
+        self.unison_voices = voices.clamp(1, MAX_UNISON);
+        self.unison_detune = detune_cents.clamp(0.0, 100.0);
+        self.filter_spread = filter_spread.clamp(0.0, 1.0);
+        // Force newly enabled oscillators to pick up the current pitch
+        self.current_freq = 0.0;
+        self.update_unison_detune();
+        self.update_filter_cutoffs();
+    }
+
+    pub fn set_filter_enabled(&mut self, enabled: bool) {
+        self.filter_enabled = enabled;
+    }
+
+    pub fn set_filter_cutoff(&mut self, cutoff: f32) {
+        self.filter_cutoff = cutoff;
+        self.update_filter_cutoffs();
+    }
+
+    // Second ADSR that sweeps the cutoff by up to `amount_octaves` (negative
+    // sweeps down) on top of the static cutoff
+    pub fn set_filter_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32, amount_octaves: f32) {
+        self.filter_envelope.set_adsr(attack, decay, sustain, release);
+        self.filter_env_amount = amount_octaves.clamp(-6.0, 6.0);
+        if self.filter_env_amount == 0.0 {
+            self.apply_filter_cutoffs(1.0);
+        }
+    }
+
+    // Attack-decay pitch sweep in seconds, `amount` semitones (-48 to 48)
+    // at its peak; it settles back to the played pitch after the decay
+    pub fn set_pitch_envelope(&mut self, attack: f32, decay: f32, amount_semitones: f32) {
+        self.pitch_envelope.set_adsr(attack, decay, 0.0, decay);
+        self.pitch_env_amount = amount_semitones.clamp(-48.0, 48.0);
+    }
+
+    // Soft saturation after the filter, 0-1
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+    }
+
+    // 1, 2 or 4
+    pub fn set_oversampling(&mut self, factor: u32) {
+        self.drive_oversampler.set_factor(factor);
+    }
+
+    // In samples: oversampling delays the voice while drive is on
+    pub fn latency(&self) -> f32 {
+        if self.drive > 0.0 { self.drive_oversampler.latency() } else { 0.0 }
+    }
+
+    // Block-rate modulation from the engine's motion macro: cutoff offset in
+    // octaves and pitch offset in cents
+    pub fn set_motion(&mut self, cutoff_octaves: f32, pitch_cents: f32) {
+        self.cutoff_motion = 2.0_f32.powf(cutoff_octaves);
+        self.pitch_motion = 2.0_f32.powf(pitch_cents / 1200.0);
+        // Picked up on the next sample
+        self.current_freq = 0.0;
+        if self.filter_env_amount == 0.0 {
+            self.apply_filter_cutoffs(1.0);
+        }
+    }
+
+    // Polyphonic aftertouch, 0-1
+    pub fn set_note_pressure(&mut self, pressure: f32) {
+        self.pressure.set_pressure(pressure);
+    }
+
+    pub fn set_pressure_amounts(&mut self, vibrato_cents: f32, cutoff_octaves: f32, volume: f32) {
+        self.pressure.set_amounts(vibrato_cents, cutoff_octaves, volume);
+    }
+
+    pub fn set_filter_resonance(&mut self, resonance: f32) {
+        self.filters.set_resonance(resonance);
+    }
+
+    pub fn set_filter_mode(&mut self, mode: u8) {
+        self.filters.set_mode(mode);
+    }
+
+    pub fn set_filter_poles(&mut self, poles: u8) {
+        self.filters.set_poles(poles);
+    }
+
+    // Percussion and key click for the organ waveform. The unison stack
+    // shares one percussion tone taken from the centre oscillator's phase.
+    fn organ_transients(&mut self) -> f32 {
+        let mut output = 0.0;
+        if self.percussion_env > 0.0001 {
+            output += self.oscillators[0].harmonic(self.percussion_harmonic)
+                * self.percussion_env
+                * self.percussion_level;
+            self.percussion_env *= self.percussion_decay;
+        }
+        if self.key_click_env > 0.0001 {
+            // Xorshift noise burst standing in for the contact bounce
+            self.noise_state ^= self.noise_state << 13;
+            self.noise_state ^= self.noise_state >> 17;
+            self.noise_state ^= self.noise_state << 5;
+            let noise = self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            output += noise * self.key_click_env;
+            self.key_click_env *= self.key_click_decay;
+        }
+        output
+    }
+
+    // Control-rate updates at the top of each block: glide pitch, pitch and
+    // filter envelope sweeps, pressure, EP modulation and the vector position hold
+    // for the rest of the block (the pressure level ramps across it)
+    fn update_controls(&mut self, samples: usize, count: usize) {
+        // Still applied on the block the pressure settles back to zero, so
+        // the voice returns to its unmodulated pitch and cutoff
+        let pressure = self.pressure.is_active();
+        let mut pressure_pitch = 1.0;
+        let mut pressure_cutoff = 1.0;
+        self.pressure_gain = 1.0;
+        self.pressure_gain_step = 0.0;
+        if pressure {
+            let start_gain = self.pressure.gain();
+            self.pressure.advance(samples);
+            self.pressure_gain = start_gain;
+            self.pressure_gain_step = (self.pressure.gain() - start_gain) / samples as f32;
+            pressure_pitch = 2.0_f32.powf(self.pressure.vibrato_cents() / 1200.0);
+            pressure_cutoff = 2.0_f32.powf(self.pressure.cutoff_octaves());
+        }
+
+        let pitch_env = if self.pitch_env_amount != 0.0 { self.pitch_envelope.advance(samples) } else { 0.0 };
+        let pitch_env_ratio = 2.0_f32.powf(pitch_env * self.pitch_env_amount / 12.0);
+        let freq = self.glide.advance(samples);
+        if freq != self.current_freq || pressure || pitch_env_ratio != self.pitch_env_ratio {
+            self.current_freq = freq;
+            self.pitch_env_ratio = pitch_env_ratio;
+            for osc in &mut self.oscillators[..count] {
+                osc.set_frequency(freq * self.pitch_motion * pressure_pitch * pitch_env_ratio);
+            }
+        }
+
+        if self.electric_piano {
+            self.update_ep_modulation(samples, count);
+        }
+
+        let filter_env = self.filter_envelope.advance(samples);
+        if self.vector && self.vector_env != [0.0; 2] {
+            self.apply_vector_position(filter_env);
+        }
+        if self.filter_enabled && (self.filter_env_amount != 0.0 || pressure) {
+            self.apply_filter_cutoffs(2.0_f32.powf(filter_env * self.filter_env_amount) * pressure_cutoff);
+        }
+    }
+
+    fn update_ep_modulation(&mut self, samples: usize, count: usize) {
+        let bark = self.velocity * self.velocity
+            * (EP_SUSTAIN_BARK + (EP_MAX_BARK - EP_SUSTAIN_BARK) * self.bark_env);
+        let tine = self.velocity * self.tine_env * 0.3;
+        for osc in &mut self.oscillators[..count] {
+            osc.set_ep_modulation(bark, tine);
+        }
+        self.bark_env *= self.bark_decay.powi(samples as i32);
+        self.tine_env *= self.tine_decay.powi(samples as i32);
+    }
+
+    fn update_unison_detune(&mut self) {
+        let count = self.unison_voices;
+        for (i, osc) in self.oscillators.iter_mut().enumerate() {
+            let position = if count > 1 {
+                i as f32 / (count - 1) as f32 * 2.0 - 1.0
+            } else {
+                0.0
+            };
+            osc.set_detune(self.detune + position * self.unison_detune * 0.5);
+        }
+    }
+
+    fn update_filter_cutoffs(&mut self) {
+        let count = self.unison_voices;
+        for (i, ratio) in self.cutoff_ratios.iter_mut().enumerate() {
+            let offset = if count > 1 { FILTER_SPREAD_OFFSETS[i] } else { 0.0 };
+            *ratio = 2.0_f32.powf(offset * self.filter_spread);
+        }
+        self.apply_filter_cutoffs(1.0);
+    }
+
+    // `modulation` multiplies the static cutoff (and each unison spread)
+    fn apply_filter_cutoffs(&mut self, modulation: f32) {
+        let cutoff = self.filter_cutoff * self.cutoff_motion * modulation;
+        for (lane, ratio) in self.cutoff_ratios.iter().enumerate() {
+            self.filters.set_cutoff(lane, cutoff * ratio);
+        }
     }
 }
 
+// Per-sample multiplier that decays to about -60 dB over `seconds`
+fn decay_coefficient(seconds: f32, sample_rate: f32) -> f32 {
+    (-6.9 / (seconds * sample_rate)).exp()
+}
@@ -0,0 +1,63 @@
+// RIFF/WAVE encoder for bounces: 16-bit PCM with TPDF dither, or 32-bit
+// IEEE float (which keeps overs above full scale intact).
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+
+// `samples` are interleaved frames. None for a bit depth other than 16 or 32.
+pub fn encode(samples: &[f32], channels: u16, sample_rate: u32, bit_depth: u16) -> Option<Vec<u8>> {
+    let format = match bit_depth {
+        16 => FORMAT_PCM,
+        32 => FORMAT_FLOAT,
+        _ => return None,
+    };
+    let block_align = channels * bit_depth / 8;
+    let data_len = (samples.len() * (bit_depth / 8) as usize) as u32;
+    let frames = samples.len() as u32 / channels as u32;
+    // Float files carry a fact chunk with the frame count
+    let fact_len = if format == FORMAT_FLOAT { 12 } else { 0 };
+
+    let mut file = Vec::with_capacity(44 + fact_len as usize + data_len as usize);
+    file.extend_from_slice(b"RIFF");
+    file.extend_from_slice(&(36 + fact_len + data_len).to_le_bytes());
+    file.extend_from_slice(b"WAVE");
+
+    file.extend_from_slice(b"fmt ");
+    file.extend_from_slice(&16u32.to_le_bytes());
+    file.extend_from_slice(&format.to_le_bytes());
+    file.extend_from_slice(&channels.to_le_bytes());
+    file.extend_from_slice(&sample_rate.to_le_bytes());
+    file.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    file.extend_from_slice(&block_align.to_le_bytes());
+    file.extend_from_slice(&bit_depth.to_le_bytes());
+
+    if format == FORMAT_FLOAT {
+        file.extend_from_slice(b"fact");
+        file.extend_from_slice(&4u32.to_le_bytes());
+        file.extend_from_slice(&frames.to_le_bytes());
+    }
+
+    file.extend_from_slice(b"data");
+    file.extend_from_slice(&data_len.to_le_bytes());
+    if format == FORMAT_FLOAT {
+        for sample in samples {
+            file.extend_from_slice(&sample.to_le_bytes());
+        }
+    } else {
+        // Triangular dither of one LSB peak, from a fixed seed so the same
+        // bounce always encodes to the same bytes
+        let mut seed: u32 = 0x6D2B_79F5;
+        let mut noise = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32
+        };
+        for sample in samples {
+            let dither = noise() - noise();
+            let value = (sample * 32767.0 + dither).round().clamp(-32768.0, 32767.0) as i16;
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    Some(file)
+}
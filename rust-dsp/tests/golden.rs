@@ -0,0 +1,165 @@
+// Golden renders: each script is rendered and its fingerprint compared
+// with the one stored here. A DSP change that moves any sample changes the
+// hash; when the change is intended, render again and update the string.
+
+use rust_dsp::AudioEngine;
+
+const SAMPLE_RATE: usize = 48000;
+const ENGINE_LIVE: u32 = 1;
+
+// A triad held for half a second, then its release tail
+const CHORD: &str = "
+    0.0 on 60 0.8
+    0.0 on 64 0.8
+    0.0 on 67 0.8
+    0.5 off 60
+    0.5 off 64
+    0.5 off 67
+    1.0 end
+";
+
+fn default_patch() -> String {
+    AudioEngine::new().get_patch_json(ENGINE_LIVE)
+}
+
+// ==== render_test_vector ====
+
+#[test]
+fn test_vector_default_patch() {
+    let samples = AudioEngine::render_test_vector(&default_patch(), CHORD);
+    assert_eq!(samples.len(), SAMPLE_RATE);
+    assert_eq!(AudioEngine::hash_render(&samples), "844ec798b37cc009");
+}
+
+#[test]
+fn test_vector_applies_patch() {
+    let patch = default_patch().replacen("\"waveform\":0", "\"waveform\":1", 1);
+    let samples = AudioEngine::render_test_vector(&patch, CHORD);
+    assert_eq!(AudioEngine::hash_render(&samples), "e8d2f90c01247e00");
+    assert_ne!(samples, AudioEngine::render_test_vector(&default_patch(), CHORD));
+}
+
+#[test]
+fn test_vector_is_repeatable() {
+    let patch = default_patch();
+    assert_eq!(AudioEngine::render_test_vector(&patch, CHORD), AudioEngine::render_test_vector(&patch, CHORD));
+}
+
+#[test]
+fn test_vector_skips_comments_and_tails_last_event() {
+    let script = "# one note\n\n0.0 on 60 0.8\n0.25 off 60\n";
+    let samples = AudioEngine::render_test_vector(&default_patch(), script);
+    assert_eq!(samples.len(), SAMPLE_RATE * 5 / 4);
+    assert_eq!(AudioEngine::hash_render(&samples), "cf12ed8e5531c3e4");
+}
+
+#[test]
+fn test_vector_rejects_bad_patch() {
+    for patch in ["", "{", "null", "{\"waveform\":\"saw\"}"] {
+        assert!(AudioEngine::render_test_vector(patch, CHORD).is_empty(), "patch {:?}", patch);
+    }
+}
+
+#[test]
+fn test_vector_rejects_bad_script() {
+    let scripts = [
+        "0.0 on 60",
+        "0.0 on 128 0.8",
+        "0.0 on 60 0.8 1",
+        "-1.0 on 60 0.8",
+        "soon on 60 0.8",
+        "0.0 play 60",
+        "0.0 off",
+        "0.0 param 9999 1",
+        "0.0 param 14 nan",
+    ];
+    for script in scripts {
+        assert!(AudioEngine::render_test_vector(&default_patch(), script).is_empty(), "script {:?}", script);
+    }
+}
+
+#[test]
+fn test_vector_rejects_overlong_script() {
+    // MAX_SCRIPT_SECONDS is 60, counting the tail added after the last event
+    for script in ["61 end", "59.5 on 60 0.8", "0.0 on 60 0.8\n60.5 end"] {
+        assert!(AudioEngine::render_test_vector(&default_patch(), script).is_empty(), "script {:?}", script);
+    }
+}
+
+// ==== render_deterministic, one module at a time ====
+
+const SEED: u32 = 1164;
+// Every module's render stays under full scale and audible, whatever its hash
+const MAX_PEAK: f32 = 1.0;
+const MIN_PEAK: f32 = 0.01;
+
+// CHORD rendered after `params` are set at time 0, and its expected hash
+struct Module {
+    name: &'static str,
+    params: &'static [(u32, f32)],
+    hash: &'static str,
+}
+
+const MODULES: &[Module] = &[
+    Module { name: "plain", params: &[], hash: "844ec798b37cc009" },
+    Module { name: "envelope", params: &[(1, 0.2), (2, 0.1), (3, 0.4), (4, 0.3)], hash: "69263eb272342539" },
+    Module { name: "filter", params: &[(37, 1.0), (38, 800.0), (39, 0.6)], hash: "d7a1c206dc179b0c" },
+    Module { name: "delay", params: &[(10, 1.0), (11, 150.0)], hash: "49e962fd81746ba5" },
+    Module { name: "reverb", params: &[(14, 1.0)], hash: "4b0f08bf7ec79b79" },
+    Module { name: "tremolo", params: &[(17, 1.0)], hash: "502ddce3bae4fcf0" },
+    Module { name: "tremolo auto-pan", params: &[(17, 1.0), (149, 1.0)], hash: "fb84a687ce21906e" },
+    Module { name: "flanger", params: &[(20, 1.0)], hash: "54d184d6741a14e3" },
+    Module { name: "bitcrusher", params: &[(25, 1.0)], hash: "689f1862b6d17832" },
+    Module { name: "eq", params: &[(29, 1.0), (32, 6.0)], hash: "62261ebdbd9ba212" },
+    Module { name: "rumble filter", params: &[(48, 1.0), (49, 300.0)], hash: "1d643e8a203ba3ff" },
+    Module { name: "auto-wah", params: &[(59, 1.0)], hash: "5bb47aafda1902d1" },
+    Module { name: "rotary", params: &[(65, 1.0), (66, 1.0)], hash: "c67da7a22a7ca80f" },
+    Module { name: "string machine", params: &[(87, 1.0)], hash: "d6380f16c471967f" },
+    Module { name: "pitch shifter", params: &[(98, 1.0), (99, 7.0)], hash: "4a442898affccfe0" },
+    Module { name: "frequency shifter", params: &[(150, 1.0), (151, 30.0)], hash: "d1113b9c836e6a40" },
+];
+
+fn module_script(params: &[(u32, f32)]) -> String {
+    let mut script: String = params.iter().map(|(id, value)| format!("0.0 param {} {}\n", id, value)).collect();
+    script.push_str(CHORD);
+    script
+}
+
+#[test]
+fn golden_modules() {
+    let plain = AudioEngine::render_deterministic(SEED, CHORD);
+    for module in MODULES {
+        let samples = AudioEngine::render_deterministic(SEED, &module_script(module.params));
+        assert_eq!(samples.len(), SAMPLE_RATE, "{}", module.name);
+        if !module.params.is_empty() {
+            assert_ne!(samples, plain, "{} left the render unchanged", module.name);
+        }
+        assert_eq!(AudioEngine::hash_render(&samples), module.hash, "{}", module.name);
+    }
+}
+
+#[test]
+fn modules_render_finite_bounded_sound() {
+    for &Module { name, params, .. } in MODULES {
+        let samples = AudioEngine::render_deterministic(SEED, &module_script(params));
+        assert!(samples.iter().all(|x| x.is_finite()), "{} rendered a non-finite sample", name);
+        let peak = samples.iter().fold(0.0_f32, |peak, x| peak.max(x.abs()));
+        assert!(peak < MAX_PEAK, "{} peaked at {}", name, peak);
+        assert!(peak > MIN_PEAK, "{} is silent (peak {})", name, peak);
+    }
+}
+
+#[test]
+fn deterministic_render_is_repeatable() {
+    // Velocity randomization draws from the seeded generator
+    let script = format!("0.0 param 140 0.5\n{}", CHORD);
+    let first = AudioEngine::render_deterministic(SEED, &script);
+    assert_eq!(first, AudioEngine::render_deterministic(SEED, &script));
+    assert_ne!(first, AudioEngine::render_deterministic(SEED + 1, &script));
+}
+
+#[test]
+fn deterministic_rejects_bad_script() {
+    assert!(AudioEngine::render_deterministic(SEED, "0.0 param 9999 1").is_empty());
+    assert!(AudioEngine::render_deterministic(SEED, "61 end").is_empty());
+}
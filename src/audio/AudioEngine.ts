@@ -1,4 +1,5 @@
 import { loadWorkletProcessor } from './worklet-processor-loader';
+import { ENGINE_LIVE } from './WasmAudioEngine';
 
 export class AudioEngine {
   private wasmEngine: any = null;
@@ -67,8 +68,8 @@ export class AudioEngine {
       
       // Set initial parameters
       this.wasmEngine.set_master_volume(0.7);
-      this.wasmEngine.set_waveform(0); // Sine wave
-      this.wasmEngine.set_adsr(0.08, 0.6, 0.5, 3.0);
+      this.wasmEngine.set_waveform(ENGINE_LIVE, 0); // Sine wave
+      this.wasmEngine.set_adsr(ENGINE_LIVE, 0.08, 0.6, 0.5, 3.0);
       
       // Also send initial parameters to worklet
       this.setMasterVolume(0.7);
@@ -151,7 +152,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_waveform(waveform);
+      this.wasmEngine.set_waveform(ENGINE_LIVE, waveform);
     }
   }
 
@@ -163,7 +164,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_adsr(attack, decay, sustain, release);
+      this.wasmEngine.set_adsr(ENGINE_LIVE, attack, decay, sustain, release);
     }
   }
 
@@ -175,7 +176,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_filter_cutoff(cutoff);
+      this.wasmEngine.set_filter_cutoff(ENGINE_LIVE, cutoff);
     }
   }
 
@@ -187,7 +188,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_filter_resonance(resonance);
+      this.wasmEngine.set_filter_resonance(ENGINE_LIVE, resonance);
     }
   }
 
@@ -199,7 +200,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_lfo_rate(rate);
+      this.wasmEngine.set_lfo_rate(ENGINE_LIVE, rate);
     }
   }
 
@@ -211,7 +212,7 @@ export class AudioEngine {
       });
     }
     if (this.wasmEngine) {
-      this.wasmEngine.set_lfo_depth(depth);
+      this.wasmEngine.set_lfo_depth(ENGINE_LIVE, depth);
     }
   }
 
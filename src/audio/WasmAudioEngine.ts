@@ -2,6 +2,10 @@
 // Runs WASM in main thread, uses ScriptProcessorNode for audio output
 // ALL audio effects are now processed in Rust for maximum performance!
 
+// Engine ids taken by the engine-indexed setters
+export const ENGINE_TIMELINE = 0;
+export const ENGINE_LIVE = 1;
+
 export class WasmAudioEngine {
   public wasmEngine: any = null; // Made public for direct timeline engine access
   private audioContext: AudioContext | null = null;
@@ -9,6 +13,9 @@ export class WasmAudioEngine {
   private isInitialized = false;
   private wasmModule: any = null;
   private bufferSize = 2048; // Larger buffer to reduce glitches
+  // CPU governor actions gathered by the audio callback, logged from a timer
+  private governorActions: number[] = [];
+  private governorLogTimer: ReturnType<typeof setInterval> | null = null;
 
   async initialize(): Promise<void> {
     if (this.isInitialized) return;
@@ -37,10 +44,10 @@ export class WasmAudioEngine {
 
       // Set initial parameters
       this.wasmEngine.set_master_volume(1.0); // Master at 100%
-      this.wasmEngine.set_timeline_volume(0.7); // Timeline at 70%
-      this.wasmEngine.set_live_volume(0.7); // Live at 70%
-      this.wasmEngine.set_waveform(0); // Sine
-      this.wasmEngine.set_adsr(0.01, 0.2, 1.0, 0.3); // Attack, Decay, Sustain, Release
+      this.wasmEngine.set_engine_volume(ENGINE_TIMELINE, 0.7); // Timeline at 70%
+      this.wasmEngine.set_engine_volume(ENGINE_LIVE, 0.7); // Live at 70%
+      this.wasmEngine.set_waveform(ENGINE_LIVE, 0); // Sine
+      this.wasmEngine.set_adsr(ENGINE_LIVE, 0.01, 0.2, 1.0, 0.3); // Attack, Decay, Sustain, Release
 
       // Create ScriptProcessorNode for audio processing
       // Note: ScriptProcessorNode is deprecated but works everywhere
@@ -55,19 +62,18 @@ export class WasmAudioEngine {
       this.scriptNode.onaudioprocess = (event) => {
         const outputL = event.outputBuffer.getChannelData(0);
         const outputR = event.outputBuffer.getChannelData(1);
-        const length = outputL.length;
-
-        // Create buffer for WASM to fill
-        const wasmBuffer = new Float32Array(length);
 
         try {
           // Process audio through WASM (includes all effects!)
-          this.wasmEngine.process(wasmBuffer);
-
-          // Copy to output channels (mono to stereo)
-          for (let i = 0; i < length; i++) {
-            outputL[i] = wasmBuffer[i];
-            outputR[i] = wasmBuffer[i];
+          const start = performance.now();
+          this.wasmEngine.process_stereo(outputL, outputR);
+
+          // Feed the CPU governor with the fraction of the block's time budget we used
+          const budgetMs = (outputL.length / event.outputBuffer.sampleRate) * 1000;
+          this.wasmEngine.report_cpu_load((performance.now() - start) / budgetMs);
+          const actions = this.wasmEngine.take_governor_actions();
+          for (const value of actions) {
+            this.governorActions.push(value);
           }
         } catch (error) {
           console.error('WASM processing error:', error);
@@ -80,6 +86,14 @@ export class WasmAudioEngine {
       // Connect directly to destination (all effects are in Rust now!)
       this.scriptNode.connect(this.audioContext.destination);
 
+      // Report governor actions at most once a second, outside the audio callback
+      this.governorLogTimer = setInterval(() => {
+        if (this.governorActions.length > 0) {
+          console.log('🦀 [RUST] CPU governor actions (code, value):', this.governorActions);
+          this.governorActions = [];
+        }
+      }, 1000);
+
       this.isInitialized = true;
       console.log('✅ WASM Audio Engine initialized successfully');
     } catch (error) {
@@ -113,7 +127,7 @@ export class WasmAudioEngine {
       if (immediate) {
         // Immediate stop - set very short release for clean cutoff
         const currentAdsr = { attack: 0.001, decay: 0.05, sustain: 0.5, release: 0.002 };
-        this.wasmEngine.set_adsr(currentAdsr.attack, currentAdsr.decay, currentAdsr.sustain, 0.002);
+        this.wasmEngine.set_adsr(ENGINE_LIVE, currentAdsr.attack, currentAdsr.decay, currentAdsr.sustain, 0.002);
         this.wasmEngine.note_off(midiNote);
         
         // Restore original ADSR after a short delay
@@ -167,53 +181,53 @@ export class WasmAudioEngine {
   setTimelineVolume(volume: number): void {
     if (this.wasmEngine) {
       console.log('🔊 Setting timeline volume:', volume);
-      this.wasmEngine.set_timeline_volume(volume);
+      this.wasmEngine.set_engine_volume(ENGINE_TIMELINE, volume);
     }
   }
 
   setLiveVolume(volume: number): void {
     if (this.wasmEngine) {
       console.log('🔊 Setting live volume:', volume);
-      this.wasmEngine.set_live_volume(volume);
+      this.wasmEngine.set_engine_volume(ENGINE_LIVE, volume);
     }
   }
 
   setWaveform(waveform: number): void {
     if (this.wasmEngine) {
-      const waveformNames = ['Sine', 'Sawtooth', 'Square', 'Triangle', 'FM', 'Piano'];
+      const waveformNames = ['Sine', 'Sawtooth', 'Square', 'Triangle', 'FM', 'Piano', 'Organ', 'Electric Piano'];
       console.log('🎼 Setting waveform:', waveformNames[waveform] || waveform);
-      this.wasmEngine.set_waveform(waveform);
-      this.wasmEngine.set_timeline_waveform(waveform); // Also apply to timeline engine
+      this.wasmEngine.set_waveform(ENGINE_LIVE, waveform);
+      this.wasmEngine.set_waveform(ENGINE_TIMELINE, waveform); // Also apply to timeline engine
     }
   }
 
   setADSR(attack: number, decay: number, sustain: number, release: number): void {
     if (this.wasmEngine) {
       console.log('📊 Setting ADSR:', { attack, decay, sustain, release });
-      this.wasmEngine.set_adsr(attack, decay, sustain, release);
-      this.wasmEngine.set_timeline_adsr(attack, decay, sustain, release); // Also apply to timeline engine
+      this.wasmEngine.set_adsr(ENGINE_LIVE, attack, decay, sustain, release);
+      this.wasmEngine.set_adsr(ENGINE_TIMELINE, attack, decay, sustain, release); // Also apply to timeline engine
     }
   }
 
 
   setLFORate(rate: number): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_lfo_rate(rate);
-      this.wasmEngine.set_timeline_lfo_rate(rate);
+      this.wasmEngine.set_lfo_rate(ENGINE_LIVE, rate);
+      this.wasmEngine.set_lfo_rate(ENGINE_TIMELINE, rate);
     }
   }
 
   setLFODepth(depth: number): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_lfo_depth(depth);
-      this.wasmEngine.set_timeline_lfo_depth(depth);
+      this.wasmEngine.set_lfo_depth(ENGINE_LIVE, depth);
+      this.wasmEngine.set_lfo_depth(ENGINE_TIMELINE, depth);
     }
   }
 
   setGlideTime(timeMs: number): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_glide_time(timeMs);
-      this.wasmEngine.set_timeline_glide_time(timeMs);
+      this.wasmEngine.set_glide_time(ENGINE_LIVE, timeMs);
+      this.wasmEngine.set_glide_time(ENGINE_TIMELINE, timeMs);
     }
   }
 
@@ -221,27 +235,27 @@ export class WasmAudioEngine {
   // ==== RUST-BASED EFFECTS ====
   // All effects are now processed in Rust WASM for maximum performance!
 
-  setTremolo(enabled: boolean, rate: number, depth: number): void {
+  setTremolo(enabled: boolean, rate: number, depth: number, mix: number = 1): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_tremolo(enabled, rate, depth);
-      this.wasmEngine.set_timeline_tremolo(enabled, rate, depth);
-      console.log('🦀 [RUST] Tremolo:', enabled ? 'ON' : 'OFF', 'rate:', rate, 'depth:', depth);
+      this.wasmEngine.set_tremolo(ENGINE_LIVE, enabled, rate, depth, mix);
+      this.wasmEngine.set_tremolo(ENGINE_TIMELINE, enabled, rate, depth, mix);
+      console.log('🦀 [RUST] Tremolo:', enabled ? 'ON' : 'OFF', 'rate:', rate, 'depth:', depth, 'mix:', mix);
     }
   }
 
-  async setReverb(enabled: boolean, roomSize: number, damping: number): Promise<void> {
+  async setReverb(enabled: boolean, roomSize: number, damping: number, mix: number): Promise<void> {
     if (this.wasmEngine) {
-      this.wasmEngine.set_reverb(enabled, roomSize, damping);
-      this.wasmEngine.set_timeline_reverb(enabled, roomSize, damping);
-      console.log('🦀 [RUST] Reverb:', enabled ? 'ON' : 'OFF', 'room:', roomSize, 'damping:', damping);
+      this.wasmEngine.set_reverb(ENGINE_LIVE, enabled, roomSize, damping, mix);
+      this.wasmEngine.set_reverb(ENGINE_TIMELINE, enabled, roomSize, damping, mix);
+      console.log('🦀 [RUST] Reverb:', enabled ? 'ON' : 'OFF', 'room:', roomSize, 'damping:', damping, 'mix:', mix);
     }
   }
 
   setDelay(enabled: boolean, time: number, feedback: number, mix: number): void {
     if (this.wasmEngine) {
       const timeMs = time * 1000; // Convert seconds to milliseconds
-      this.wasmEngine.set_delay(enabled, timeMs, feedback, mix);
-      this.wasmEngine.set_timeline_delay(enabled, timeMs, feedback, mix);
+      this.wasmEngine.set_delay(ENGINE_LIVE, enabled, timeMs, feedback, mix);
+      this.wasmEngine.set_delay(ENGINE_TIMELINE, enabled, timeMs, feedback, mix);
       console.log('🦀 [RUST] Delay:', enabled ? 'ON' : 'OFF', 'time:', timeMs + 'ms', 'feedback:', feedback, 'mix:', mix);
     }
   }
@@ -253,8 +267,8 @@ export class WasmAudioEngine {
 
   setLFOWaveform(waveform: number): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_lfo_waveform(waveform);
-      this.wasmEngine.set_timeline_lfo_waveform(waveform);
+      this.wasmEngine.set_lfo_waveform(ENGINE_LIVE, waveform);
+      this.wasmEngine.set_lfo_waveform(ENGINE_TIMELINE, waveform);
       console.log('🦀 [RUST] LFO waveform:', waveform);
     }
   }
@@ -262,21 +276,26 @@ export class WasmAudioEngine {
 
   setDetune(cents: number): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_detune(cents);
-      this.wasmEngine.set_timeline_detune(cents);
+      this.wasmEngine.set_detune(ENGINE_LIVE, cents);
+      this.wasmEngine.set_detune(ENGINE_TIMELINE, cents);
       console.log('🦀 [RUST] Detune:', cents, 'cents');
     }
   }
 
   setFlanger(enabled: boolean, rate: number = 0.5, depth: number = 5, feedback: number = 0.5, mix: number = 0.5): void {
     if (this.wasmEngine) {
-      this.wasmEngine.set_flanger(enabled, rate, depth, feedback, mix);
-      this.wasmEngine.set_timeline_flanger(enabled, rate, depth, feedback, mix);
+      this.wasmEngine.set_flanger(ENGINE_LIVE, enabled, rate, depth, feedback, mix);
+      this.wasmEngine.set_flanger(ENGINE_TIMELINE, enabled, rate, depth, feedback, mix);
       console.log('🦀 [RUST] Flanger:', enabled ? 'ON' : 'OFF');
     }
   }
 
   dispose(): void {
+    if (this.governorLogTimer !== null) {
+      clearInterval(this.governorLogTimer);
+      this.governorLogTimer = null;
+    }
+    this.governorActions = [];
     if (this.scriptNode) {
       this.scriptNode.disconnect();
       this.scriptNode.onaudioprocess = null;
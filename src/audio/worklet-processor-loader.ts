@@ -9,6 +9,9 @@ if (typeof AudioWorkletProcessor === 'undefined') {
   throw new Error('AudioWorkletProcessor is not available in this context');
 }
 
+// Engine id the parameter messages address
+const ENGINE_LIVE = 1;
+
 class AudioProcessor extends AudioWorkletProcessor {
   constructor() {
     super();
@@ -32,22 +35,22 @@ class AudioProcessor extends AudioWorkletProcessor {
               this.wasmEngine.set_master_volume(value);
               break;
             case 'waveform':
-              this.wasmEngine.set_waveform(value);
+              this.wasmEngine.set_waveform(ENGINE_LIVE, value);
               break;
             case 'adsr':
-              this.wasmEngine.set_adsr(value.attack, value.decay, value.sustain, value.release);
+              this.wasmEngine.set_adsr(ENGINE_LIVE, value.attack, value.decay, value.sustain, value.release);
               break;
             case 'filterCutoff':
-              this.wasmEngine.set_filter_cutoff(value);
+              this.wasmEngine.set_filter_cutoff(ENGINE_LIVE, value);
               break;
             case 'filterResonance':
-              this.wasmEngine.set_filter_resonance(value);
+              this.wasmEngine.set_filter_resonance(ENGINE_LIVE, value);
               break;
             case 'lfoRate':
-              this.wasmEngine.set_lfo_rate(value);
+              this.wasmEngine.set_lfo_rate(ENGINE_LIVE, value);
               break;
             case 'lfoDepth':
-              this.wasmEngine.set_lfo_depth(value);
+              this.wasmEngine.set_lfo_depth(ENGINE_LIVE, value);
               break;
           }
         }
@@ -4,10 +4,12 @@ if (typeof AudioWorkletProcessor === 'undefined') {
   throw new Error('AudioWorkletProcessor is not available in this context');
 }
 
+// Engine id the parameter messages address
+const ENGINE_LIVE = 1;
+
 class AudioProcessor extends AudioWorkletProcessor {
   constructor() {
     super();
-    this.buffer = new Float32Array(128);
     this.wasmEngine = null;
     this.wasmModule = null;
     this.initializing = false;
@@ -30,22 +32,22 @@ class AudioProcessor extends AudioWorkletProcessor {
               this.wasmEngine.set_master_volume(value);
               break;
             case 'waveform':
-              this.wasmEngine.set_waveform(value);
+              this.wasmEngine.set_waveform(ENGINE_LIVE, value);
               break;
             case 'adsr':
-              this.wasmEngine.set_adsr(value.attack, value.decay, value.sustain, value.release);
+              this.wasmEngine.set_adsr(ENGINE_LIVE, value.attack, value.decay, value.sustain, value.release);
               break;
             case 'filterCutoff':
-              this.wasmEngine.set_filter_cutoff(value);
+              this.wasmEngine.set_filter_cutoff(ENGINE_LIVE, value);
               break;
             case 'filterResonance':
-              this.wasmEngine.set_filter_resonance(value);
+              this.wasmEngine.set_filter_resonance(ENGINE_LIVE, value);
               break;
             case 'lfoRate':
-              this.wasmEngine.set_lfo_rate(value);
+              this.wasmEngine.set_lfo_rate(ENGINE_LIVE, value);
               break;
             case 'lfoDepth':
-              this.wasmEngine.set_lfo_depth(value);
+              this.wasmEngine.set_lfo_depth(ENGINE_LIVE, value);
               break;
           }
         }
@@ -75,18 +77,12 @@ class AudioProcessor extends AudioWorkletProcessor {
     }
 
     // Process audio through WASM engine
-    const bufferLength = output[0].length;
-    this.buffer = new Float32Array(bufferLength);
-    
     try {
-      this.wasmEngine.process(this.buffer);
-      
-      // Copy to output channels (stereo)
-      for (let i = 0; i < bufferLength; i++) {
-        output[0][i] = this.buffer[i];
-        if (output[1]) {
-          output[1][i] = this.buffer[i];
-        }
+      if (output.length >= 2) {
+        this.wasmEngine.process_stereo(output[0], output[1]);
+      } else {
+        // Mono destination: the engine folds its stereo graph down
+        this.wasmEngine.process(output[0]);
       }
     } catch (error) {
       console.error('WASM processing error:', error);
@@ -101,7 +101,8 @@ export default function ArtistPresetSelector({ audioEngine }: ArtistPresetSelect
     audioEngine.setReverb(
       preset.effects.reverb.enabled,
       preset.effects.reverb.size,
-      preset.effects.reverb.damping
+      preset.effects.reverb.damping,
+      preset.effects.reverb.mix
     );
 
     setSelectedPresetId(preset.id);
@@ -22,6 +22,8 @@ export default function ControlPanel({ audioEngine }: ControlPanelProps) {
     triangle: 3,
     fm: 4,
     piano: 5,
+    organ: 6,
+    ep: 7,
   };
 
   const handleKeyChange = (newKey: string) => {
@@ -164,6 +166,8 @@ export default function ControlPanel({ audioEngine }: ControlPanelProps) {
           <option value="triangle">Triangle</option>
           <option value="fm">FM (Wurlitzer)</option>
           <option value="piano">Piano</option>
+          <option value="organ">Organ (Drawbars)</option>
+          <option value="ep">Electric Piano</option>
         </select>
       </div>
 
@@ -35,8 +35,7 @@ export default function EffectsPanel({ audioEngine }: EffectsPanelProps) {
         audioEngine.setTremolo(newEnabled, t.rate, t.depth);
       } else if (effect === 'reverb') {
         const r = effects.reverb;
-        // Map UI params to Rust: size→room_size, mix→damping
-        audioEngine.setReverb(newEnabled, r.size, r.mix);
+        audioEngine.setReverb(newEnabled, r.size, r.damping, r.mix);
       } else if (effect === 'delay') {
         const d = effects.delay;
         audioEngine.setDelay(newEnabled, d.time, d.feedback, d.mix);
@@ -76,9 +75,9 @@ export default function EffectsPanel({ audioEngine }: EffectsPanelProps) {
           param === 'depth' ? value as number : e.depth
         );
       } else if (effect === 'reverb' && e.enabled) {
-        // Map UI params to Rust: size→room_size, mix→damping
         audioEngine.setReverb(true,
           param === 'size' ? value as number : e.size,
+          param === 'damping' ? value as number : e.damping,
           param === 'mix' ? value as number : e.mix
         );
       } else if (effect === 'delay' && e.enabled) {
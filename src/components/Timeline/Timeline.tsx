@@ -1,7 +1,7 @@
 import { useState, useRef, useEffect } from 'react';
 import { useAppStore } from '../../store/useAppStore';
 import { Pattern, TimelineClip, RecordedNote } from '../../store/useAppStore';
-import { WasmAudioEngine } from '../../audio/WasmAudioEngine';
+import { WasmAudioEngine, ENGINE_TIMELINE } from '../../audio/WasmAudioEngine';
 import { generateChord } from '../../music/chords';
 import { generateProceduralProgression, GENERATIVE_PRESETS, GenerationConfig } from '../../utils/proceduralMusicGenerator';
 import { getGenerativePresetParameters } from '../../utils/generativePresetParameters';
@@ -178,25 +178,25 @@ export default function Timeline({ audioEngine }: TimelineProps) {
               
               // Convert waveform string to number
               const waveformMap: Record<string, number> = {
-                sine: 0, sawtooth: 1, square: 2, triangle: 3, fm: 4, piano: 5,
+                sine: 0, sawtooth: 1, square: 2, triangle: 3, fm: 4, piano: 5, organ: 6, ep: 7,
               };
               
               // Apply to TIMELINE engine only - DO NOT touch live engine
               if (audioEngine.wasmEngine) {
                 const wasm = audioEngine.wasmEngine;
-                wasm.set_timeline_waveform(waveformMap[params.waveform]);
-                wasm.set_timeline_adsr(params.adsr.attack, params.adsr.decay, params.adsr.sustain, params.adsr.release);
-                wasm.set_timeline_lfo_rate(params.lfo.rate);
-                wasm.set_timeline_lfo_depth(params.lfo.depth);
-                wasm.set_timeline_lfo_waveform(params.lfo.waveform);
-                wasm.set_timeline_detune(params.detune);
+                wasm.set_waveform(ENGINE_TIMELINE, waveformMap[params.waveform]);
+                wasm.set_adsr(ENGINE_TIMELINE, params.adsr.attack, params.adsr.decay, params.adsr.sustain, params.adsr.release);
+                wasm.set_lfo_rate(ENGINE_TIMELINE, params.lfo.rate);
+                wasm.set_lfo_depth(ENGINE_TIMELINE, params.lfo.depth);
+                wasm.set_lfo_waveform(ENGINE_TIMELINE, params.lfo.waveform);
+                wasm.set_detune(ENGINE_TIMELINE, params.detune);
                 
                 // Effects - timeline only
-                wasm.set_timeline_glide_time(params.effects.glide.enabled ? params.effects.glide.time : 0);
-                wasm.set_timeline_tremolo(params.effects.tremolo.enabled, params.effects.tremolo.rate, params.effects.tremolo.depth);
-                wasm.set_timeline_flanger(params.effects.flanger.enabled, params.effects.flanger.rate, params.effects.flanger.depth, params.effects.flanger.feedback, params.effects.flanger.mix);
-                wasm.set_timeline_delay(params.effects.delay.enabled, params.effects.delay.time, params.effects.delay.feedback, params.effects.delay.mix);
-                wasm.set_timeline_reverb(params.effects.reverb.enabled, params.effects.reverb.size, params.effects.reverb.damping);
+                wasm.set_glide_time(ENGINE_TIMELINE, params.effects.glide.enabled ? params.effects.glide.time : 0);
+                wasm.set_tremolo(ENGINE_TIMELINE, params.effects.tremolo.enabled, params.effects.tremolo.rate, params.effects.tremolo.depth, 1);
+                wasm.set_flanger(ENGINE_TIMELINE, params.effects.flanger.enabled, params.effects.flanger.rate, params.effects.flanger.depth, params.effects.flanger.feedback, params.effects.flanger.mix);
+                wasm.set_delay(ENGINE_TIMELINE, params.effects.delay.enabled, params.effects.delay.time, params.effects.delay.feedback, params.effects.delay.mix);
+                wasm.set_reverb(ENGINE_TIMELINE, params.effects.reverb.enabled, params.effects.reverb.size, params.effects.reverb.damping, params.effects.reverb.mix);
               }
               
               playbackRef.current.currentParametersClipId = clip.id;
@@ -2,7 +2,7 @@ import { create } from 'zustand';
 import { Key, ChordModType } from '../music/chords';
 import { Mode } from '../music/scales';
 
-export type WaveformType = 'sine' | 'sawtooth' | 'square' | 'triangle' | 'fm' | 'piano';
+export type WaveformType = 'sine' | 'sawtooth' | 'square' | 'triangle' | 'fm' | 'piano' | 'organ' | 'ep';
 export type PlaybackMode = 'play' | 'arpeggiator' | 'drum' | 'autodrum';
 export type ArpDirection = 'up' | 'down' | 'updown' | 'downup' | 'random' | 'asplayed';
 export type Subdivision = '1/4' | '1/8' | '1/16' | '1/16T' | '1/32';